@@ -1,20 +1,68 @@
-use std::{env, str::FromStr};
+use std::{env, str::FromStr, sync::Arc};
 
 use fapshi_rs::client::FapshiClient;
 use minio::s3::{creds::StaticProvider, http::BaseUrl, Client};
+use rust_decimal::Decimal;
 
-use crate::services::image::ImageService;
+use crate::services::{
+    image::ImageService,
+    payment_gateway::{FapshiGateway, MockGateway, PaymentGateway},
+    rate_limit::{RateLimit, RateLimitBackend},
+    sms::{LogSmsSender, SmsSender, TwilioSmsSender},
+    user::LoginLockout,
+};
+
+/// Read a requests per minute limit from `var`, 0 disabling it
+fn rate_limit_from_env(var: &str, default: &str) -> Option<RateLimit> {
+    let per_minute = env::var(var)
+        .unwrap_or_else(|_| default.to_string())
+        .parse()
+        .unwrap_or_else(|_| panic!("{} must be a number", var));
+    RateLimit::per_minute(per_minute)
+}
 
 #[derive(Clone, Debug)]
 pub struct Config {
     pub database_url: String,
     pub jwt_secret: String,
+    /// Seconds an access token is valid for
     pub jwt_expires_in: i64,
+    /// Seconds a refresh token is valid for, renewed on every refresh
+    pub refresh_token_expires_in: i64,
     pub server_port: u16,
     pub server_host: String,
     pub cors_origins: Vec<String>,
     pub image_service: ImageService,
-    pub payment_service: FapshiClient,
+    /// Stores vendor ID documents, in a bucket only admins read from
+    pub vendor_document_service: ImageService,
+    pub payment_gateway: Arc<dyn PaymentGateway>,
+    /// Delivers one-time codes by SMS
+    pub sms_sender: Arc<dyn SmsSender>,
+    /// Key one-time codes are hashed with before they are stored
+    pub otp_secret: String,
+    /// Seconds between payment reconciliation runs, 0 disables the job
+    pub payment_reconcile_interval: u64,
+    /// Seconds a payment may stay pending before it is reconciled
+    pub payment_reconcile_after: i64,
+    /// Seconds after which a payment still pending is expired
+    pub payment_expire_after: i64,
+    /// Seconds between wishlist price drop and restock alerts, 0 disables
+    /// the job
+    pub wishlist_alert_interval: u64,
+    /// Share of each vendor sub-total kept by the platform, between 0 and 1
+    pub platform_commission_rate: Decimal,
+    /// Where rate limit buckets are kept
+    pub rate_limit_backend: RateLimitBackend,
+    /// Requests per client IP to the sign-in and account recovery routes
+    pub auth_rate_limit: Option<RateLimit>,
+    /// Login attempts per phone number
+    pub login_rate_limit: Option<RateLimit>,
+    /// Requests per client IP to the authenticated API
+    pub api_rate_limit: Option<RateLimit>,
+    /// Take the client IP from the last `X-Forwarded-For` entry, for
+    /// deployments behind a single reverse proxy
+    pub trust_forwarded_for: bool,
+    pub login_lockout: LoginLockout,
 }
 
 impl Config {
@@ -22,24 +70,90 @@ impl Config {
         let access_key = env::var("MINIO_ROOT_USER").unwrap();
         let secret_key = env::var("MINIO_ROOT_PASSWORD").unwrap();
         let bucket = env::var("MINIO_BUCKET_NAME").unwrap_or_else(|_| "product-images".to_string());
-        let fapshi_api_user = env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER must be set");
-        let fapshi_api_key = env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY must be set");
         let minio_endpoint =
             env::var("MINIO_ENDPOINT").unwrap_or_else(|_| "http://0.0.0:9000".to_string());
 
         let base_url = BaseUrl::from_str(&minio_endpoint).unwrap();
         let credentials = Box::new(StaticProvider::new(&access_key, &secret_key, None));
         let client = Client::new(base_url, Some(credentials), None, None).unwrap();
-        let image_service = ImageService { client, bucket };
-        let payment_service = FapshiClient::new(&fapshi_api_user, &fapshi_api_key, true)
-            .expect("Failed to create FapshiClient");
+        let image_service = ImageService {
+            client: client.clone(),
+            bucket,
+            public: true,
+        };
+        let vendor_document_service = ImageService {
+            client,
+            bucket: env::var("MINIO_VENDOR_DOCUMENTS_BUCKET_NAME")
+                .unwrap_or_else(|_| "vendor-documents".to_string()),
+            public: false,
+        };
+        let payment_gateway: Arc<dyn PaymentGateway> = match env::var("PAYMENT_GATEWAY")
+            .unwrap_or_else(|_| "fapshi".to_string())
+            .as_str()
+        {
+            "fapshi" => {
+                let fapshi_api_user =
+                    env::var("FAPSHI_API_USER").expect("FAPSHI_API_USER must be set");
+                let fapshi_api_key =
+                    env::var("FAPSHI_API_KEY").expect("FAPSHI_API_KEY must be set");
+                let client = FapshiClient::new(&fapshi_api_user, &fapshi_api_key, true)
+                    .expect("Failed to create FapshiClient");
+                Arc::new(FapshiGateway::new(client))
+            }
+            "mock" => Arc::new(MockGateway::default()),
+            other => panic!("PAYMENT_GATEWAY must be fapshi or mock, got {}", other),
+        };
+        let production = match env::var("APP_ENV")
+            .unwrap_or_else(|_| "development".to_string())
+            .as_str()
+        {
+            "development" => false,
+            "production" => true,
+            other => panic!("APP_ENV must be development or production, got {}", other),
+        };
+        let default_sms_sender = if production { "twilio" } else { "log" };
+        let sms_sender: Arc<dyn SmsSender> = match env::var("SMS_SENDER")
+            .unwrap_or_else(|_| default_sms_sender.to_string())
+            .as_str()
+        {
+            "twilio" => Arc::new(TwilioSmsSender::new(
+                env::var("TWILIO_ACCOUNT_SID").expect("TWILIO_ACCOUNT_SID must be set"),
+                env::var("TWILIO_AUTH_TOKEN").expect("TWILIO_AUTH_TOKEN must be set"),
+                env::var("TWILIO_FROM").expect("TWILIO_FROM must be set"),
+            )),
+            "log" if production => {
+                panic!("SMS_SENDER=log writes codes to the log, it cannot be used in production")
+            }
+            "log" => Arc::new(LogSmsSender),
+            other => panic!("SMS_SENDER must be twilio or log, got {}", other),
+        };
+        let platform_commission_rate: Decimal = env::var("PLATFORM_COMMISSION_RATE")
+            .unwrap_or_else(|_| "0.1".to_string())
+            .parse()
+            .expect("PLATFORM_COMMISSION_RATE must be a number");
+        assert!(
+            (Decimal::ZERO..=Decimal::ONE).contains(&platform_commission_rate),
+            "PLATFORM_COMMISSION_RATE must be between 0 and 1"
+        );
+        let rate_limit_backend = match env::var("RATE_LIMIT_STORE")
+            .unwrap_or_else(|_| "memory".to_string())
+            .as_str()
+        {
+            "memory" => RateLimitBackend::Memory,
+            "postgres" => RateLimitBackend::Postgres,
+            other => panic!("RATE_LIMIT_STORE must be memory or postgres, got {}", other),
+        };
         Self {
             database_url: env::var("DATABASE_URL").expect("DATABASE_URL must be set"),
             jwt_secret: env::var("JWT_SECRET").expect("JWT_SECRET must be set"),
             jwt_expires_in: env::var("JWT_EXPIRES_IN")
-                .unwrap_or_else(|_| "86400".to_string())
+                .unwrap_or_else(|_| "900".to_string())
                 .parse()
                 .expect("JWT_EXPIRES_IN must be a number"),
+            refresh_token_expires_in: env::var("REFRESH_TOKEN_EXPIRES_IN")
+                .unwrap_or_else(|_| "2592000".to_string())
+                .parse()
+                .expect("REFRESH_TOKEN_EXPIRES_IN must be a number"),
             server_port: env::var("SERVER_PORT")
                 .unwrap_or_else(|_| "8080".to_string())
                 .parse()
@@ -51,7 +165,74 @@ impl Config {
                 .map(|s| s.trim().to_string())
                 .collect(),
             image_service,
-            payment_service,
+            vendor_document_service,
+            payment_gateway,
+            sms_sender,
+            otp_secret: env::var("OTP_SECRET").expect("OTP_SECRET must be set"),
+            payment_reconcile_interval: env::var("PAYMENT_RECONCILE_INTERVAL_SECS")
+                .unwrap_or_else(|_| "300".to_string())
+                .parse()
+                .expect("PAYMENT_RECONCILE_INTERVAL_SECS must be a number"),
+            payment_reconcile_after: env::var("PAYMENT_RECONCILE_AFTER_SECS")
+                .unwrap_or_else(|_| "900".to_string())
+                .parse()
+                .expect("PAYMENT_RECONCILE_AFTER_SECS must be a number"),
+            payment_expire_after: env::var("PAYMENT_EXPIRE_AFTER_SECS")
+                .unwrap_or_else(|_| "86400".to_string())
+                .parse()
+                .expect("PAYMENT_EXPIRE_AFTER_SECS must be a number"),
+            wishlist_alert_interval: env::var("WISHLIST_ALERT_INTERVAL_SECS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .expect("WISHLIST_ALERT_INTERVAL_SECS must be a number"),
+            platform_commission_rate,
+            rate_limit_backend,
+            auth_rate_limit: rate_limit_from_env("RATE_LIMIT_AUTH_PER_MINUTE", "20"),
+            login_rate_limit: rate_limit_from_env("RATE_LIMIT_LOGIN_PER_PHONE_PER_MINUTE", "5"),
+            api_rate_limit: rate_limit_from_env("RATE_LIMIT_API_PER_MINUTE", "300"),
+            trust_forwarded_for: env::var("TRUST_FORWARDED_FOR")
+                .map(|value| value == "true")
+                .unwrap_or(false),
+            login_lockout: LoginLockout {
+                max_failures: env::var("LOGIN_MAX_FAILURES")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .expect("LOGIN_MAX_FAILURES must be a number"),
+                lock_secs: env::var("LOGIN_LOCKOUT_SECS")
+                    .unwrap_or_else(|_| "900".to_string())
+                    .parse()
+                    .expect("LOGIN_LOCKOUT_SECS must be a number"),
+            },
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            database_url: String::new(),
+            jwt_secret: String::new(),
+            jwt_expires_in: 900,
+            refresh_token_expires_in: 2592000,
+            server_port: 8080,
+            server_host: "0.0.0.0".to_string(),
+            cors_origins: vec![],
+            image_service: ImageService::default(),
+            vendor_document_service: ImageService::default(),
+            payment_gateway: Arc::new(MockGateway::default()),
+            sms_sender: Arc::new(LogSmsSender),
+            otp_secret: String::new(),
+            payment_reconcile_interval: 300,
+            payment_reconcile_after: 900,
+            payment_expire_after: 86400,
+            wishlist_alert_interval: 3600,
+            platform_commission_rate: Decimal::new(1, 1),
+            rate_limit_backend: RateLimitBackend::Memory,
+            auth_rate_limit: RateLimit::per_minute(20),
+            login_rate_limit: RateLimit::per_minute(5),
+            api_rate_limit: RateLimit::per_minute(300),
+            trust_forwarded_for: false,
+            login_lockout: LoginLockout::default(),
         }
     }
 }
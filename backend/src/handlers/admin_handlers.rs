@@ -1,9 +1,15 @@
 use crate::models::{
-    order::Entity as Order,
-    product::{self, Entity as Product},
+    audit_event::{AuditAction, AuditEntity},
+    money::Money,
+    order::{self, Entity as Order},
+    product::{self, Entity as Product, ModerationStatus},
     user::{self, Entity as User, UserRole},
+    vendor_application::{self, Entity as VendorApplication, VendorApplicationStatus},
+    vendor_order::VendorOrderDetails,
 };
+use crate::services::audit::{AuditActor, AuditRecord};
 use crate::state::AppState;
+use crate::utils::policy::OrderScope;
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -13,7 +19,7 @@ use chrono::{Datelike, Utc};
 use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, Set};
 use sea_orm::{QueryOrder, QuerySelect};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::json;
 use uuid::Uuid;
 
 #[derive(Serialize)]
@@ -22,7 +28,7 @@ pub struct DashboardMetrics {
     total_vendors: i64,
     total_products: i64,
     total_orders: i64,
-    total_revenue: f64,
+    total_revenue: Money,
 }
 
 #[derive(Deserialize)]
@@ -30,15 +36,10 @@ pub struct UserStatusUpdate {
     is_active: bool,
 }
 
-#[derive(Deserialize)]
-pub struct ProductStatusUpdate {
-    is_approved: bool,
-}
-
 #[derive(Serialize)]
 pub struct SalesTrend {
     pub month: String,
-    pub sales: f64,
+    pub sales: Money,
 }
 
 #[derive(Serialize)]
@@ -56,12 +57,23 @@ pub struct CategoryStat {
     pub value: usize, // product count or sales count
 }
 
+/// An order with its breakdown into vendor orders
+#[derive(Serialize)]
+pub struct OrderDetails {
+    #[serde(flatten)]
+    pub order: crate::models::order::Model,
+    pub vendor_orders: Vec<VendorOrderDetails>,
+}
+
 #[derive(Serialize)]
 pub struct Activity {
     pub activity_type: String,
     pub name: String,
     pub time: String,
     pub action: String,
+    /// Admin route with the details behind the activity
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
 }
 
 pub async fn get_dashboard_metrics(
@@ -87,12 +99,14 @@ pub async fn get_dashboard_metrics(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     let total_orders = Order::find()
+        .filter(order::Column::DeletedAt.is_null())
         .count(&**db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     // Calculate total revenue from orders
     let total_revenue = Order::find()
+        .filter(order::Column::DeletedAt.is_null())
         .all(&**db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
@@ -126,6 +140,7 @@ pub async fn get_users(
 
 pub async fn update_user_status(
     State(state): State<AppState>,
+    actor: AuditActor,
     Path(user_id): Path<Uuid>,
     Json(status): Json<UserStatusUpdate>,
 ) -> Result<Json<user::Model>, StatusCode> {
@@ -136,6 +151,7 @@ pub async fn update_user_status(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    let was_active = user.is_active;
 
     let mut user: user::ActiveModel = user.into();
     user.is_active = Set(status.is_active);
@@ -144,6 +160,20 @@ pub async fn update_user_status(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    // A disabled account must not keep working through old tokens
+    if !user.is_active {
+        state
+            .session_service
+            .revoke_all_sessions(user.id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
+    let record = AuditRecord::new(AuditAction::UserStatusUpdated, AuditEntity::User, user.id)
+        .before(&json!({ "is_active": was_active }))
+        .after(&json!({ "is_active": user.is_active }));
+    state.audit_service.record_or_log(&actor, record).await;
+
     Ok(Json(user))
 }
 
@@ -160,35 +190,13 @@ pub async fn get_products(
     Ok(Json(products))
 }
 
-pub async fn update_product_status(
-    State(state): State<AppState>,
-    Path(product_id): Path<Uuid>,
-    Json(status): Json<ProductStatusUpdate>,
-) -> Result<Json<crate::models::product::Model>, StatusCode> {
-    let db = &state.db;
-
-    let product = Product::find_by_id(product_id)
-        .one(&**db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
-
-    let mut product: product::ActiveModel = product.into();
-    product.is_approved = Set(status.is_approved);
-    let product = product
-        .update(&**db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Ok(Json(product))
-}
-
 pub async fn get_orders(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<crate::models::order::Model>>, StatusCode> {
     let db = &state.db;
 
     let orders = Order::find()
+        .filter(order::Column::DeletedAt.is_null())
         .all(&**db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -199,7 +207,7 @@ pub async fn get_orders(
 pub async fn get_order_details(
     State(state): State<AppState>,
     Path(order_id): Path<Uuid>,
-) -> Result<Json<crate::models::order::Model>, StatusCode> {
+) -> Result<Json<OrderDetails>, StatusCode> {
     let db = &state.db;
 
     let order = Order::find_by_id(order_id)
@@ -208,7 +216,16 @@ pub async fn get_order_details(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    Ok(Json(order))
+    let vendor_orders = state
+        .order_service
+        .get_vendor_orders(order_id, OrderScope::All)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(OrderDetails {
+        order,
+        vendor_orders,
+    }))
 }
 
 pub async fn get_sales_trends(
@@ -217,10 +234,11 @@ pub async fn get_sales_trends(
     let db = &state.db;
     let current_year = Utc::now().year();
     let orders = Order::find()
+        .filter(order::Column::DeletedAt.is_null())
         .all(&**db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let mut monthly_sales = [0.0; 12];
+    let mut monthly_sales = [Money::ZERO; 12];
     for order in orders {
         let month = order.created_at.month0() as usize;
         if order.created_at.year() == current_year {
@@ -252,6 +270,7 @@ pub async fn get_buyer_conversion(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let orders = Order::find()
+        .filter(order::Column::DeletedAt.is_null())
         .all(&**db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -263,7 +282,7 @@ pub async fn get_buyer_conversion(
             monthly_registered[month] += 1;
         }
     }
-    for i in 0..12 {
+    for (i, converted) in monthly_converted.iter_mut().enumerate() {
         let buyers_this_month: Vec<_> = buyers
             .iter()
             .filter(|b| b.created_at.year() == current_year && b.created_at.month0() as usize == i)
@@ -278,7 +297,7 @@ pub async fn get_buyer_conversion(
                 }
             }
         }
-        monthly_converted[i] = buyers_with_orders.len();
+        *converted = buyers_with_orders.len();
     }
     let month_names = [
         "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
@@ -304,36 +323,30 @@ pub async fn get_buyer_conversion(
     Ok(Json(conversions))
 }
 
+/// Top-level categories with the most approved products, counting their
+/// subcategories
 pub async fn get_top_categories(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<CategoryStat>>, StatusCode> {
-    let db = &state.db;
-    let products = Product::find()
-        .all(&**db)
+    let categories = state
+        .category_service
+        .tree()
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let mut category_counts: HashMap<String, usize> = HashMap::new();
-    for product in &products {
-        let category = product
-            .category
-            .clone()
-            .unwrap_or_else(|| "Uncategorized".to_string());
-        *category_counts.entry(category).or_insert(0) += 1;
-    }
-    let total: usize = category_counts.values().sum();
-    let mut stats: Vec<CategoryStat> = category_counts
+    let total: u64 = categories.iter().map(|node| node.product_count).sum();
+    let mut stats: Vec<CategoryStat> = categories
         .into_iter()
-        .map(|(category, value)| CategoryStat {
+        .map(|node| CategoryStat {
             percentage: if total > 0 {
-                (value as f64 / total as f64) * 100.0
+                (node.product_count as f64 / total as f64) * 100.0
             } else {
                 0.0
             },
-            category,
-            value,
+            category: node.category.name_en,
+            value: node.product_count as usize,
         })
         .collect();
-    stats.sort_by(|a, b| b.value.cmp(&a.value));
+    stats.sort_by_key(|s| std::cmp::Reverse(s.value));
     stats.truncate(5);
     Ok(Json(stats))
 }
@@ -342,9 +355,9 @@ pub async fn get_recent_activities(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<Activity>>, StatusCode> {
     let db = &state.db;
-    let recent_producers = User::find()
-        .filter(user::Column::Role.eq(UserRole::Vendor))
-        .order_by_desc(user::Column::CreatedAt)
+    let recent_applications = VendorApplication::find()
+        .find_also_related(User)
+        .order_by_desc(vendor_application::Column::CreatedAt)
         .limit(5)
         .all(&**db)
         .await
@@ -356,18 +369,27 @@ pub async fn get_recent_activities(
         .await
         .unwrap_or_default();
     let recent_orders = Order::find()
-        .order_by_desc(crate::models::order::Column::CreatedAt)
+        .filter(order::Column::DeletedAt.is_null())
+        .order_by_desc(order::Column::CreatedAt)
         .limit(5)
         .all(&**db)
         .await
         .unwrap_or_default();
     let mut activities: Vec<Activity> = vec![];
-    for producer in recent_producers {
+    for (application, producer) in recent_applications {
         activities.push(Activity {
             activity_type: "New Producer".to_string(),
-            name: producer.full_name,
-            time: producer.created_at.format("%Y-%m-%d %H:%M").to_string(),
-            action: "Registration submitted".to_string(),
+            name: producer
+                .map(|producer| producer.full_name)
+                .unwrap_or(application.business_name),
+            time: application.created_at.format("%Y-%m-%d %H:%M").to_string(),
+            action: match application.status {
+                VendorApplicationStatus::Pending => "Application pending review",
+                VendorApplicationStatus::Approved => "Approved by admin",
+                VendorApplicationStatus::Rejected => "Rejected by admin",
+            }
+            .to_string(),
+            link: Some(format!("/api/admins/vendor-applications/{}", application.id)),
         });
     }
     for product in recent_products {
@@ -375,12 +397,18 @@ pub async fn get_recent_activities(
             activity_type: "Product Approval".to_string(),
             name: product.title,
             time: product.created_at.format("%Y-%m-%d %H:%M").to_string(),
-            action: if product.is_approved {
-                "Approved by admin"
-            } else {
-                "Pending approval"
+            action: match product.moderation_status {
+                ModerationStatus::Draft => "Draft",
+                ModerationStatus::PendingReview => "Pending approval",
+                ModerationStatus::Approved => "Approved by admin",
+                ModerationStatus::Rejected => "Rejected by admin",
+                ModerationStatus::Suspended => "Suspended by admin",
             }
             .to_string(),
+            link: Some(format!(
+                "/api/admins/products/{}/moderation-history",
+                product.id
+            )),
         });
     }
     for order in recent_orders {
@@ -388,64 +416,11 @@ pub async fn get_recent_activities(
             activity_type: "New Order".to_string(),
             name: format!("Order #{}", order.id),
             time: order.created_at.format("%Y-%m-%d %H:%M").to_string(),
-            action: format!("Placed for {:.0} FCFA", order.total),
+            action: format!("Placed for {}", order.total),
+            link: None,
         });
     }
     activities.sort_by(|a, b| b.time.cmp(&a.time));
     activities.truncate(10);
     Ok(Json(activities))
 }
-
-pub async fn get_pending_products(
-    State(state): State<AppState>,
-) -> Result<Json<Vec<crate::models::product::Model>>, StatusCode> {
-    let db = &state.db;
-    let products = Product::find()
-        .filter(product::Column::IsApproved.eq(false))
-        .order_by_desc(product::Column::CreatedAt)
-        .all(&**db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(Json(products))
-}
-
-pub async fn reject_product(
-    State(state): State<AppState>,
-    Path(product_id): Path<Uuid>,
-) -> Result<Json<crate::models::product::Model>, StatusCode> {
-    let db = &state.db;
-    let product = Product::find_by_id(product_id)
-        .one(&**db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
-    let mut product: product::ActiveModel = product.into();
-    product.is_approved = Set(false);
-    product.is_rejected = Set(true);
-    let product = product
-        .update(&**db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(Json(product))
-}
-
-pub async fn approve_product(
-    State(state): State<AppState>,
-    Path(product_id): Path<Uuid>,
-) -> Result<Json<crate::models::product::Model>, StatusCode> {
-    let db = &state.db;
-    let product = Product::find_by_id(product_id)
-        .one(&**db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
-    let mut product: product::ActiveModel = product.into();
-    product.is_approved = Set(true);
-    product.is_rejected = Set(false);
-    let product = product
-        .update(&**db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(Json(product))
-}
- 
\ No newline at end of file
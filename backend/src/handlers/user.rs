@@ -1,14 +1,33 @@
-use axum::{extract::State, Extension, Json};
-use tracing::info;
+use axum::{
+    extract::{Path, State},
+    http::{header::USER_AGENT, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Deserialize;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::{
-    middleware::auth::AuthUser,
-    models::user::{Model, UserRole},
-    services::user::{CreateUser, LoginRequest, LoginResponse, UserService},
+    middleware::{auth::AuthUser, rate_limit::too_many_requests},
+    models::{
+        otp_code::OtpPurpose,
+        phone::PhoneNumber,
+        role_permission::Permission,
+        user::{Model, UserRole},
+    },
+    services::{
+        errors::ServiceError,
+        rate_limit::Decision,
+        user::{
+            ChangePasswordRequest, CreateUser, LoginRequest, LoginResponse, RefreshRequest,
+            UpdateUser, UserService,
+        },
+    },
     state::AppState,
-    utils::rbac::require_role,
-    utils::shared::ApiResponse,
+    utils::policy::user_id,
+    utils::rbac::{require_permission, require_role},
+    utils::shared::{error_response, ApiResponse},
 };
 
 /// Register a new user
@@ -34,46 +53,385 @@ pub async fn register(
             }
         }
     }
+    // Staff accounts are created through /api/admins/staff
+    if user_data.role == UserRole::Staff {
+        return Json(ApiResponse::error(
+            "Staff accounts are created by an admin.",
+        ));
+    }
 
     let user_service = UserService::new(state.db);
     match user_service.create_user(user_data).await {
         Ok(user) => {
             info!("User Successfully created");
-            Json(ApiResponse::success(user, "User created successfully"))
+            // The user can ask for another code if this one does not arrive
+            if let Err(e) = state
+                .otp_service
+                .send_code(&user.phone, OtpPurpose::PhoneVerification)
+                .await
+            {
+                warn!("Could not send verification code: {}", e);
+            }
+            Json(ApiResponse::success(
+                user,
+                "User created successfully, a verification code was sent to their phone",
+            ))
         }
         Err(e) => Json(ApiResponse::error(&e.to_string())),
     }
 }
 
+#[derive(Deserialize)]
+pub struct SendCodeRequest {
+    pub phone: PhoneNumber,
+}
+
+#[derive(Deserialize)]
+pub struct VerifyPhoneRequest {
+    pub phone: PhoneNumber,
+    pub code: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub phone: PhoneNumber,
+    pub code: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmChangeRequest {
+    pub code: String,
+}
+
+/// Send another phone verification code. The response is the same whether
+/// or not an unverified account uses the number, so it cannot be used to
+/// discover accounts.
+#[axum::debug_handler]
+pub async fn request_phone_verification(
+    State(state): State<AppState>,
+    Json(payload): Json<SendCodeRequest>,
+) -> impl IntoResponse {
+    let user_service = UserService::new(state.db.clone());
+    match user_service.get_user_by_phone(&payload.phone).await {
+        Ok(Some(user)) if user.phone_verified_at.is_none() => {
+            if let Err(e) = state
+                .otp_service
+                .send_code(&payload.phone, OtpPurpose::PhoneVerification)
+                .await
+            {
+                return error_response(e, "Could not send verification code");
+            }
+        }
+        Ok(_) => {}
+        Err(e) => return error_response(e, "Could not send verification code"),
+    }
+
+    Json(ApiResponse::success(
+        (),
+        "If an unverified account uses this number, a verification code was sent to it",
+    ))
+    .into_response()
+}
+
+/// Verify a phone number with the code texted to it
+#[axum::debug_handler]
+pub async fn verify_phone(
+    State(state): State<AppState>,
+    Json(payload): Json<VerifyPhoneRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = state
+        .otp_service
+        .verify_code(&payload.phone, OtpPurpose::PhoneVerification, &payload.code)
+        .await
+    {
+        return error_response(e, "Could not verify phone number");
+    }
+
+    let user_service = UserService::new(state.db);
+    match user_service.mark_phone_verified(&payload.phone).await {
+        Ok(user) => Json(ApiResponse::success(user, "Phone number verified")).into_response(),
+        Err(e) => error_response(e, "Could not verify phone number"),
+    }
+}
+
+/// Text a password reset code. The response is the same whether or not an
+/// account uses the number, so it cannot be used to discover accounts.
+#[axum::debug_handler]
+pub async fn request_password_reset(
+    State(state): State<AppState>,
+    Json(payload): Json<SendCodeRequest>,
+) -> impl IntoResponse {
+    let user_service = UserService::new(state.db.clone());
+    match user_service.get_user_by_phone(&payload.phone).await {
+        Ok(Some(_)) => {
+            if let Err(e) = state
+                .otp_service
+                .send_code(&payload.phone, OtpPurpose::PasswordReset)
+                .await
+            {
+                return error_response(e, "Could not send reset code");
+            }
+        }
+        Ok(None) => {}
+        Err(e) => return error_response(e, "Could not send reset code"),
+    }
+
+    Json(ApiResponse::success(
+        (),
+        "If an account uses this number, a reset code was sent to it",
+    ))
+    .into_response()
+}
+
+/// Choose a new password with a reset code. Every session of the account is
+/// logged out.
+#[axum::debug_handler]
+pub async fn reset_password(
+    State(state): State<AppState>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> impl IntoResponse {
+    if let Err(e) = state
+        .otp_service
+        .verify_code(&payload.phone, OtpPurpose::PasswordReset, &payload.code)
+        .await
+    {
+        return error_response(e, "Could not reset password");
+    }
+
+    let user_service = UserService::new(state.db.clone());
+    let user = match user_service
+        .reset_password(&payload.phone, &payload.new_password)
+        .await
+    {
+        Ok(user) => user,
+        Err(e) => return error_response(e, "Could not reset password"),
+    };
+    if let Err(e) = state.session_service.revoke_all_sessions(user.id).await {
+        return error_response(e, "Could not reset password");
+    }
+
+    Json(ApiResponse::success(
+        (),
+        "Password reset, sign in with the new password",
+    ))
+    .into_response()
+}
+
 /// Login a user
 #[axum::debug_handler]
 pub async fn login(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(login_data): Json<LoginRequest>,
-) -> Json<ApiResponse<LoginResponse>> {
-    let user_service = UserService::new(state.db);
-    let config = state.config.as_ref();
+) -> Response {
+    // Slow down guessing against one account from many addresses
+    if let Some(limit) = state.config.login_rate_limit {
+        let key = format!("login:phone:{}", login_data.phone);
+        match state.rate_limiter.acquire(&key, limit).await {
+            Ok(Decision::Allowed) => {}
+            Ok(Decision::Limited { retry_after_secs }) => {
+                warn!("Login rate limited for {}", login_data.phone);
+                return too_many_requests(retry_after_secs);
+            }
+            Err(e) => error!("Rate limiter failed, letting login through: {}", e),
+        }
+    }
+
+    let user_service = UserService::new(state.db.clone());
     let expected_role = login_data.role;
-    match user_service
-        .login(login_data.phone, login_data.password, config, expected_role)
+    let user = match user_service
+        .login(
+            login_data.phone,
+            login_data.password,
+            expected_role,
+            state.config.login_lockout,
+        )
+        .await
+    {
+        Ok(user) => user,
+        Err(ServiceError::Unauthorized(msg)) => {
+            warn!("Login refused: {}", msg);
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(ApiResponse::<()>::error(&msg)),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            error!("Login failed: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("Login failed")),
+            )
+                .into_response();
+        }
+    };
+
+    let user_agent = headers
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    match state.session_service.start_session(&user, user_agent).await {
+        Ok(tokens) => Json(ApiResponse::success(
+            LoginResponse {
+                role: user.role,
+                user_id: user.id,
+                token: tokens.token,
+                refresh_token: tokens.refresh_token,
+                expires_in: tokens.expires_in,
+                full_name: user.full_name,
+            },
+            "Login successful",
+        ))
+        .into_response(),
+        Err(e) => {
+            error!("Could not start session: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("Login failed")),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Exchange a refresh token for a new pair of tokens
+#[axum::debug_handler]
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(payload): Json<RefreshRequest>,
+) -> impl IntoResponse {
+    match state.session_service.refresh(&payload.refresh_token).await {
+        Ok((user, tokens)) => Json(ApiResponse::success(
+            LoginResponse {
+                role: user.role,
+                user_id: user.id,
+                token: tokens.token,
+                refresh_token: tokens.refresh_token,
+                expires_in: tokens.expires_in,
+                full_name: user.full_name,
+            },
+            "Token refreshed",
+        ))
+        .into_response(),
+        Err(ServiceError::Unauthorized(msg)) => {
+            tracing::warn!("Refresh failed: {}", msg);
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ApiResponse::<()>::error("Invalid refresh token")),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            error!("Refresh failed: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("Could not refresh token")),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Log out the session the request was made with
+#[axum::debug_handler]
+pub async fn logout(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    revoke_session(state, auth_user.clone(), auth_user.session_id).await
+}
+
+/// Log out one of the caller's other sessions
+#[axum::debug_handler]
+pub async fn revoke_user_session(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(session_id): Path<Uuid>,
+) -> impl IntoResponse {
+    revoke_session(state, auth_user, session_id).await
+}
+
+async fn revoke_session(
+    state: AppState,
+    auth_user: AuthUser,
+    session_id: Uuid,
+) -> axum::response::Response {
+    let user_id = match user_id(&auth_user) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .session_service
+        .revoke_session(session_id, user_id)
         .await
     {
-        Ok((user, token)) => {
-            let response = ApiResponse::success(
-                LoginResponse {
-                    role: user.role,
-                    user_id: user.id,
-                    token,
-                    full_name: user.full_name,
-                },
-                "Login successful",
-            );
-
-            Json(response)
+        Ok(()) => Json(ApiResponse::success((), "Logged out")).into_response(),
+        Err(ServiceError::NotFound(msg)) => {
+            (StatusCode::NOT_FOUND, Json(ApiResponse::<()>::error(&msg))).into_response()
         }
         Err(e) => {
-            tracing::warn!("Login failed: {}", e);
-            Json(ApiResponse::error("Login failed:"))
+            error!("Could not revoke session: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("Could not log out")),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Log out every session of the caller
+#[axum::debug_handler]
+pub async fn logout_all(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth_user) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state.session_service.revoke_all_sessions(user_id).await {
+        Ok(count) => {
+            Json(ApiResponse::success(count, "Logged out of every session")).into_response()
+        }
+        Err(e) => {
+            error!("Could not revoke sessions: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("Could not log out")),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// List the caller's active sessions
+#[axum::debug_handler]
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth_user) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state.session_service.list_sessions(user_id).await {
+        Ok(sessions) => Json(ApiResponse::success(sessions, "Sessions retrieved")).into_response(),
+        Err(e) => {
+            error!("Could not list sessions: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("Could not retrieve sessions")),
+            )
+                .into_response()
         }
     }
 }
@@ -84,10 +442,15 @@ pub async fn get_me(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> Json<ApiResponse<Option<Model>>> {
-    // RBAC: Only allow Buyer, Vendor, or Admin (all roles)
-    if let Err((status, msg)) = require_role(
+    // RBAC: Only allow Buyer, Vendor, Admin or Staff (all roles)
+    if let Err((_status, msg)) = require_role(
         &auth_user,
-        &[UserRole::Buyer, UserRole::Vendor, UserRole::Admin],
+        &[
+            UserRole::Buyer,
+            UserRole::Vendor,
+            UserRole::Admin,
+            UserRole::Staff,
+        ],
     ) {
         return Json(ApiResponse::error(msg));
     }
@@ -115,14 +478,199 @@ pub async fn get_me(
     }
 }
 
+/// Update the caller's profile. A new phone number gets a code texted to it,
+/// a new email a code texted to the current phone; neither replaces the old
+/// one until the code is entered.
+#[axum::debug_handler]
+pub async fn update_me(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<UpdateUser>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth_user) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    let email_requested = payload.email.is_some();
+    let phone_requested = payload.phone.is_some();
+
+    let user_service = UserService::new(state.db.clone());
+    let user = match user_service.update_user(user_id, payload).await {
+        Ok(user) => user,
+        Err(e) => return error_response(e, "Could not update profile"),
+    };
+
+    // The user can send the same change again if a code does not arrive
+    let mut message = "Profile updated";
+    if let Some(pending_phone) = user.pending_phone.as_ref().filter(|_| phone_requested) {
+        match state
+            .otp_service
+            .send_code(pending_phone, OtpPurpose::PhoneChange)
+            .await
+        {
+            Ok(()) => {
+                message = "Profile updated, confirm the new phone number with the code sent to it"
+            }
+            Err(e) => warn!("Could not send phone change code: {}", e),
+        }
+    }
+    if user.pending_email.is_some() && email_requested {
+        match state
+            .otp_service
+            .send_code(&user.phone, OtpPurpose::EmailChange)
+            .await
+        {
+            Ok(()) => {
+                message = "Profile updated, confirm the new email with the code sent to your phone"
+            }
+            Err(e) => warn!("Could not send email change code: {}", e),
+        }
+    }
+
+    Json(ApiResponse::success(user, message)).into_response()
+}
+
+/// Change the caller's password. Every other session is logged out.
+#[axum::debug_handler]
+pub async fn change_password(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth_user) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+
+    let user_service = UserService::new(state.db.clone());
+    if let Err(e) = user_service
+        .change_password(user_id, &payload.current_password, &payload.new_password)
+        .await
+    {
+        return error_response(e, "Could not change password");
+    }
+    if let Err(e) = state
+        .session_service
+        .revoke_other_sessions(user_id, auth_user.session_id)
+        .await
+    {
+        return error_response(e, "Could not change password");
+    }
+
+    Json(ApiResponse::success((), "Password changed")).into_response()
+}
+
+/// Confirm a pending email change with the code texted to the caller's phone
+#[axum::debug_handler]
+pub async fn confirm_email_change(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<ConfirmChangeRequest>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth_user) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+
+    let user_service = UserService::new(state.db.clone());
+    let user = match user_service.get_user_by_id(user_id).await {
+        Ok(Some(user)) if user.pending_email.is_some() => user,
+        Ok(Some(_)) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::<()>::error("No email change is pending")),
+            )
+                .into_response();
+        }
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ApiResponse::<()>::error("User not found")),
+            )
+                .into_response();
+        }
+        Err(e) => return error_response(e, "Could not change email"),
+    };
+    if let Err(e) = state
+        .otp_service
+        .verify_code(&user.phone, OtpPurpose::EmailChange, &payload.code)
+        .await
+    {
+        return error_response(e, "Could not change email");
+    }
+
+    match user_service.confirm_email_change(user_id).await {
+        Ok(user) => Json(ApiResponse::success(user, "Email changed")).into_response(),
+        Err(e) => error_response(e, "Could not change email"),
+    }
+}
+
+/// Confirm a pending phone number change with the code texted to the new
+/// number
+#[axum::debug_handler]
+pub async fn confirm_phone_change(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(payload): Json<ConfirmChangeRequest>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth_user) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+
+    let user_service = UserService::new(state.db.clone());
+    let pending_phone = match user_service.get_user_by_id(user_id).await {
+        Ok(Some(user)) => match user.pending_phone {
+            Some(phone) => phone,
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiResponse::<()>::error(
+                        "No phone number change is pending",
+                    )),
+                )
+                    .into_response();
+            }
+        },
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ApiResponse::<()>::error("User not found")),
+            )
+                .into_response();
+        }
+        Err(e) => return error_response(e, "Could not change phone number"),
+    };
+    if let Err(e) = state
+        .otp_service
+        .verify_code(&pending_phone, OtpPurpose::PhoneChange, &payload.code)
+        .await
+    {
+        return error_response(e, "Could not change phone number");
+    }
+
+    match user_service.confirm_phone_change(user_id).await {
+        Ok(user) => Json(ApiResponse::success(user, "Phone number changed")).into_response(),
+        Err(e) => error_response(e, "Could not change phone number"),
+    }
+}
+
 /// Get all users
 #[axum::debug_handler]
 pub async fn get_all_users(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
 ) -> Json<ApiResponse<Vec<Model>>> {
-    // Only admins can access
-    if let Err((_status, msg)) = require_role(&auth_user, &[UserRole::Admin]) {
+    // Only admins and staff who manage users can access
+    if let Err((_status, msg)) = require_permission(&auth_user, Permission::ManageUsers) {
         return Json(ApiResponse::error(msg));
     }
     let user_service = UserService::new(state.db);
@@ -0,0 +1,2 @@
+pub mod payment_reconciliation;
+pub mod wishlist_alerts;
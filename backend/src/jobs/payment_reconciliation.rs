@@ -0,0 +1,48 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tracing::{error, info};
+
+use crate::{config::Config, services::payment::PaymentService};
+
+/// Periodically settle payments stuck in pending, see
+/// [`PaymentService::reconcile_pending_payments`], and refunds stuck in
+/// refunding, see [`PaymentService::reconcile_refunds`], and post completed
+/// payments missing from the vendor ledger. Returns `None` when the
+/// job is disabled by a zero interval.
+pub fn spawn(payment_service: Arc<PaymentService>, config: &Config) -> Option<JoinHandle<()>> {
+    if config.payment_reconcile_interval == 0 {
+        info!("Payment reconciliation is disabled");
+        return None;
+    }
+
+    let every = Duration::from_secs(config.payment_reconcile_interval);
+    let pending_for = chrono::Duration::seconds(config.payment_reconcile_after);
+    let abandon_after = chrono::Duration::seconds(config.payment_expire_after);
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            match payment_service
+                .reconcile_pending_payments(pending_for, abandon_after)
+                .await
+            {
+                Ok(report) if report.checked == 0 => {}
+                Ok(report) => info!("Payment reconciliation: {}", report),
+                Err(e) => error!("Payment reconciliation failed: {}", e),
+            }
+            match payment_service.reconcile_refunds(pending_for).await {
+                Ok(report) if report.checked == 0 => {}
+                Ok(report) => info!("Refund reconciliation: {}", report),
+                Err(e) => error!("Refund reconciliation failed: {}", e),
+            }
+            match payment_service.ledger.post_unposted_payments().await {
+                Ok(0) => {}
+                Ok(posted) => info!("Posted {} payments to the ledger", posted),
+                Err(e) => error!("Posting payments to the ledger failed: {}", e),
+            }
+        }
+    }))
+}
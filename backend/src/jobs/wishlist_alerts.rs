@@ -0,0 +1,31 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tracing::{error, info};
+
+use crate::{config::Config, services::wishlist::WishlistService};
+
+/// Periodically text users about wishlisted items that dropped in price or
+/// came back in stock, see [`WishlistService::send_alerts`]. Returns `None`
+/// when the job is disabled by a zero interval.
+pub fn spawn(wishlist_service: Arc<WishlistService>, config: &Config) -> Option<JoinHandle<()>> {
+    if config.wishlist_alert_interval == 0 {
+        info!("Wishlist alerts are disabled");
+        return None;
+    }
+
+    let every = Duration::from_secs(config.wishlist_alert_interval);
+
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            interval.tick().await;
+            match wishlist_service.send_alerts().await {
+                Ok(report) if report.price_drops == 0 && report.restocks == 0 => {}
+                Ok(report) => info!("Wishlist alerts: {}", report),
+                Err(e) => error!("Wishlist alerts failed: {}", e),
+            }
+        }
+    }))
+}
@@ -1,5 +1,6 @@
 pub mod config;
 pub mod handlers;
+pub mod jobs;
 pub mod middleware;
 pub mod migration;
 pub mod models;
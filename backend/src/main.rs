@@ -1,13 +1,16 @@
 use axum::routing::{get, post};
 use axum::{http, middleware, Extension, Router};
-use cameroon_made_market::handlers::user::{login, register};
-use cameroon_made_market::middleware::auth::{auth, generate_token};
-use cameroon_made_market::models::user::UserRole;
+use cameroon_made_market::handlers::user::{login, refresh, register};
+use cameroon_made_market::jobs::{payment_reconciliation, wishlist_alerts};
+use cameroon_made_market::middleware::auth::auth;
+use cameroon_made_market::middleware::rate_limit::{rate_limit, RateLimitGroup};
+use cameroon_made_market::middleware::request_id::request_id;
 use cameroon_made_market::routes;
 use cameroon_made_market::routes::admin::admin_routes;
 
 use cameroon_made_market::routes::product::list_products;
 use cameroon_made_market::state::setup;
+use std::net::SocketAddr;
 use tokio::net::TcpListener;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
@@ -21,13 +24,8 @@ async fn main() {
 
     // Get configuration
     let app_state = setup().await;
-    let token = generate_token(
-        "ed9bac6c-1714-4002-939d-0e328af7a2b8",
-        UserRole::Buyer,
-        &app_state.config,
-    )
-    .unwrap();
-    println!("{}", token);
+    payment_reconciliation::spawn(app_state.payment_service.clone(), &app_state.config);
+    wishlist_alerts::spawn(app_state.wishlist_service.clone(), &app_state.config);
     // Configure CORS
     let cors = CorsLayer::new()
         .allow_origin(
@@ -46,26 +44,49 @@ async fn main() {
         .merge(routes::user::config())
         .merge(routes::product::config())
         .merge(routes::cart::config())
+        .merge(routes::wishlist::config())
         .merge(routes::order::config())
         .merge(routes::payment::config())
+        .merge(routes::ledger::config())
+        .merge(routes::vendor_application::config())
+        .merge(routes::moderation::config())
+        .merge(routes::variant::config())
+        .merge(routes::review::config())
+        .merge(routes::store::config())
+        .merge(routes::role::config())
+        .merge(routes::audit::config())
+        .merge(routes::category::config())
         .merge(admin_routes())
         .layer(middleware::from_fn({
             move |req: http::Request<axum::body::Body>, next| auth(req, next)
         }))
-   
-        .route("/api/users", post(register))
-        .route("/api/users/login", post(login))
+        .layer(middleware::from_fn(|req, next| {
+            rate_limit(RateLimitGroup::Api, req, next)
+        }))
+        .merge(
+            Router::new()
+                .route("/api/users", post(register))
+                .route("/api/users/login", post(login))
+                .route("/api/users/refresh", post(refresh))
+                .merge(routes::user::public_config())
+                .layer(middleware::from_fn(|req, next| {
+                    rate_limit(RateLimitGroup::Auth, req, next)
+                })),
+        )
+        .merge(routes::payment::webhook_config())
+        .merge(routes::store::public_config())
+        .merge(routes::search::config())
+        .merge(routes::category::public_config())
+        .merge(routes::review::public_config())
+        .merge(routes::wishlist::public_config())
         .route("/products", get(list_products))
         .route("/api", get(welcome))
-        // .merge(routes::category::config())
         // .merge(routes::address::config())
         // .merge(routes::notification::config())
-        // .merge(routes::review::config())
-        // .merge(routes::wishlist::config())
         // .merge(routes::shipping::config())
-        // .merge(routes::search::config())
         // .merge(routes::admin::config())
         .layer(Extension(app_state.clone()))
+        .layer(middleware::from_fn(request_id))
         .layer(cors)
         .layer(TraceLayer::new_for_http())
         .with_state(app_state.clone());
@@ -76,9 +97,12 @@ async fn main() {
         app_state.config.server_host, app_state.config.server_port
     );
     tracing::info!("listening on {}", addr);
-    axum::serve(TcpListener::bind(addr).await.unwrap(), app)
-        .await
-        .unwrap();
+    axum::serve(
+        TcpListener::bind(addr).await.unwrap(),
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
 
 async fn welcome() -> &'static str {
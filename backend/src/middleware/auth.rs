@@ -1,13 +1,15 @@
-use crate::{config::Config, models::user::UserRole, state::AppState};
-use axum::{
-    extract::Request,
-    http::StatusCode,
-    middleware::Next,
-    response::Response,
+use crate::{
+    config::Config,
+    models::{role_permission::Permission, user::UserRole},
+    services::errors::ServiceError,
+    state::AppState,
 };
+use axum::{extract::Request, http::StatusCode, middleware::Next, response::Response};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
 
 /// JWT claims structure
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,16 +17,30 @@ pub struct Claims {
     pub sub: String,
     pub exp: usize,
     pub role: UserRole,
+    /// Session the token was issued for
+    pub sid: Uuid,
 }
 
 /// Authenticated user structure
 #[derive(Debug, Clone)]
 pub struct AuthUser {
     pub id: String,
+    /// Role of the user as stored, loaded with their permissions
     pub role: UserRole,
+    pub session_id: Uuid,
+    /// What the user may do beyond their own account, loaded on each
+    /// request so role changes apply at once
+    pub permissions: HashSet<Permission>,
 }
 
-/// Middleware to validate JWT token
+impl AuthUser {
+    pub fn can(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+}
+
+/// Middleware to validate JWT token. The session it was issued for must
+/// still be active and its user must not have been deactivated.
 pub async fn auth(mut req: Request, next: Next) -> Result<Response, StatusCode> {
     // Get state from request extensions
     let state = req
@@ -45,9 +61,41 @@ pub async fn auth(mut req: Request, next: Next) -> Result<Response, StatusCode>
         &Validation::default(),
     ) {
         Ok(claims) => {
+            let user_id =
+                Uuid::parse_str(&claims.claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
+            match state
+                .session_service
+                .authenticate(claims.claims.sid, user_id)
+                .await
+            {
+                Ok(()) => {}
+                Err(ServiceError::Unauthorized(reason)) => {
+                    tracing::warn!("rejected token for user {}: {}", user_id, reason);
+                    return Err(StatusCode::UNAUTHORIZED);
+                }
+                Err(e) => {
+                    tracing::error!("error checking session: {}", e);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            }
+            // The role claim is ignored, it is only as fresh as the token
+            let (role, permissions) = match state.permission_service.permissions_for(user_id).await
+            {
+                Ok(grants) => grants,
+                Err(ServiceError::Unauthorized(reason)) => {
+                    tracing::warn!("rejected token for user {}: {}", user_id, reason);
+                    return Err(StatusCode::UNAUTHORIZED);
+                }
+                Err(e) => {
+                    tracing::error!("error loading permissions: {}", e);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            };
             req.extensions_mut().insert(AuthUser {
                 id: claims.claims.sub,
-                role: claims.claims.role,
+                role,
+                session_id: claims.claims.sid,
+                permissions,
             });
             Ok(next.run(req).await)
         }
@@ -58,10 +106,11 @@ pub async fn auth(mut req: Request, next: Next) -> Result<Response, StatusCode>
     }
 }
 
-/// Generate JWT token for a user
+/// Generate a short-lived JWT access token for a session of a user
 pub fn generate_token(
     user_id: &str,
     role: UserRole,
+    session_id: Uuid,
     config: &Config,
 ) -> Result<String, StatusCode> {
     let now = SystemTime::now()
@@ -73,6 +122,7 @@ pub fn generate_token(
         sub: user_id.to_string(),
         exp: now + config.jwt_expires_in as usize,
         role,
+        sid: session_id,
     };
 
     encode(
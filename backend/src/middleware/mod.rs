@@ -1,3 +1,5 @@
 pub mod auth;
 pub mod error;
-pub mod admin_auth;
+pub mod permission;
+pub mod rate_limit;
+pub mod request_id;
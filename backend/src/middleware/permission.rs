@@ -0,0 +1,19 @@
+use axum::{extract::Request, http::StatusCode, middleware::Next, response::Response};
+
+use crate::middleware::auth::AuthUser;
+use crate::models::role_permission::Permission;
+
+/// Middleware that lets a request through only when the authenticated user
+/// holds `permission`. Mount it on a group of routes with
+/// `route_layer(from_fn(|req, next| require_permission(permission, req, next)))`.
+pub async fn require_permission(
+    permission: Permission,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    match req.extensions().get::<AuthUser>() {
+        Some(user) if user.can(permission) => Ok(next.run(req).await),
+        Some(_) => Err(StatusCode::FORBIDDEN),
+        None => Err(StatusCode::UNAUTHORIZED),
+    }
+}
@@ -0,0 +1,141 @@
+use std::net::{IpAddr, SocketAddr};
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{header::RETRY_AFTER, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::{
+    config::Config,
+    services::rate_limit::{Decision, RateLimit},
+    state::AppState,
+    utils::shared::ApiResponse,
+};
+
+/// Groups of routes sharing a rate limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimitGroup {
+    /// Sign-in and account recovery, which are open to anyone
+    Auth,
+    /// Routes that need an access token
+    Api,
+}
+
+impl RateLimitGroup {
+    fn name(self) -> &'static str {
+        match self {
+            RateLimitGroup::Auth => "auth",
+            RateLimitGroup::Api => "api",
+        }
+    }
+
+    fn limit(self, config: &Config) -> Option<RateLimit> {
+        match self {
+            RateLimitGroup::Auth => config.auth_rate_limit,
+            RateLimitGroup::Api => config.api_rate_limit,
+        }
+    }
+}
+
+/// Address of the client. Behind a reverse proxy the connection comes from
+/// the proxy, which passes the client on in `X-Forwarded-For`. Only the
+/// right-most entry, the one our proxy appended, is used: anything left of
+/// it was sent by the client and can be made up.
+pub fn client_ip(
+    headers: &HeaderMap,
+    connect_info: Option<&ConnectInfo<SocketAddr>>,
+    trust_forwarded_for: bool,
+) -> Option<IpAddr> {
+    let forwarded = trust_forwarded_for
+        .then(|| headers.get("X-Forwarded-For")?.to_str().ok())
+        .flatten()
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok());
+    forwarded.or_else(|| connect_info.map(|ConnectInfo(addr)| addr.ip()))
+}
+
+/// Response for a client that ran out of requests
+pub fn too_many_requests(retry_after_secs: u64) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(RETRY_AFTER, retry_after_secs.to_string())],
+        Json(ApiResponse::<()>::error(
+            "Too many requests, try again later",
+        )),
+    )
+        .into_response()
+}
+
+/// Middleware limiting the requests each client IP makes to `group`
+pub async fn rate_limit(group: RateLimitGroup, req: Request, next: Next) -> Response {
+    let Some(state) = req.extensions().get::<AppState>().cloned() else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let Some(limit) = group.limit(&state.config) else {
+        return next.run(req).await;
+    };
+
+    let ip = client_ip(
+        req.headers(),
+        req.extensions().get::<ConnectInfo<SocketAddr>>(),
+        state.config.trust_forwarded_for,
+    );
+    let key = match ip {
+        Some(ip) => format!("{}:ip:{}", group.name(), ip),
+        None => format!("{}:ip:unknown", group.name()),
+    };
+    match state.rate_limiter.acquire(&key, limit).await {
+        Ok(Decision::Allowed) => next.run(req).await,
+        Ok(Decision::Limited { retry_after_secs }) => {
+            tracing::warn!("rate limited {}", key);
+            too_many_requests(retry_after_secs)
+        }
+        Err(e) => {
+            // An unavailable limiter must not take the API down with it
+            tracing::error!("rate limiter failed, letting request through: {}", e);
+            next.run(req).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_ip_only_trusts_forwarded_for_when_told() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Forwarded-For", "203.0.113.7".parse().unwrap());
+        let connect_info = ConnectInfo(SocketAddr::from(([10, 0, 0, 2], 4000)));
+
+        assert_eq!(
+            client_ip(&headers, Some(&connect_info), false),
+            Some(IpAddr::from([10, 0, 0, 2]))
+        );
+        assert_eq!(
+            client_ip(&headers, Some(&connect_info), true),
+            Some(IpAddr::from([203, 0, 113, 7]))
+        );
+        assert_eq!(
+            client_ip(&HeaderMap::new(), Some(&connect_info), true),
+            Some(IpAddr::from([10, 0, 0, 2]))
+        );
+    }
+    #[test]
+    fn test_client_ip_ignores_spoofed_forwarded_for_entries() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "X-Forwarded-For",
+            "198.51.100.1, 203.0.113.7".parse().unwrap(),
+        );
+        let connect_info = ConnectInfo(SocketAddr::from(([10, 0, 0, 2], 4000)));
+
+        assert_eq!(
+            client_ip(&headers, Some(&connect_info), true),
+            Some(IpAddr::from([203, 0, 113, 7]))
+        );
+    }
+}
@@ -0,0 +1,62 @@
+use axum::{
+    extract::Request,
+    http::{HeaderMap, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest request id taken from a client or proxy
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+/// Identifier of the request being handled, used to tie log lines and audit
+/// events back to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// The `X-Request-Id` sent by a proxy, if it is one we can keep
+fn forwarded_request_id(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(&REQUEST_ID_HEADER)?.to_str().ok()?.trim();
+    let usable = !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    usable.then(|| value.to_string())
+}
+
+/// Middleware giving every request an id. An `X-Request-Id` set by a proxy is
+/// kept, otherwise a new one is generated. The id is echoed in the response.
+pub async fn request_id(mut req: Request, next: Next) -> Response {
+    let id = forwarded_request_id(req.headers()).unwrap_or_else(|| Uuid::new_v4().to_string());
+    req.extensions_mut().insert(RequestId(id.clone()));
+
+    let mut response = next.run(req).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER.clone(), value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forwarded_request_id_is_checked() {
+        let request_id = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(&REQUEST_ID_HEADER, value.parse().unwrap());
+            forwarded_request_id(&headers)
+        };
+
+        assert_eq!(request_id(" abc-123 "), Some("abc-123".to_string()));
+        assert_eq!(request_id("not valid!"), None);
+        assert_eq!(request_id(&"a".repeat(65)), None);
+        assert_eq!(forwarded_request_id(&HeaderMap::new()), None);
+    }
+}
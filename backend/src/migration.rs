@@ -1,11 +1,60 @@
 use sea_orm_migration::prelude::*;
 
+pub mod m20261017_000001_create_order_status_history;
+pub mod m20261017_000002_add_orders_deleted_at;
+pub mod m20261017_000003_add_payments_transaction_id;
+pub mod m20261017_000004_create_vendor_orders;
+pub mod m20261017_000005_create_vendor_ledger;
+pub mod m20261017_000006_convert_money_to_bigint;
+pub mod m20261017_000007_create_sessions;
+pub mod m20261017_000008_normalize_phone_numbers;
+pub mod m20261017_000009_create_otp_codes;
+pub mod m20261017_000010_add_login_protection;
+pub mod m20261017_000011_add_user_profile;
+pub mod m20261017_000012_create_vendor_applications;
+pub mod m20261017_000013_create_stores;
+pub mod m20261017_000014_create_staff_roles;
+pub mod m20261017_000015_create_audit_events;
+pub mod m20261017_000016_add_product_moderation;
+pub mod m20261017_000017_add_product_search;
+pub mod m20261017_000018_create_categories;
+pub mod m20261017_000019_create_product_variants;
+pub mod m20261017_000020_create_reviews;
+pub mod m20261017_000021_create_wishlists;
+pub mod m20261017_000022_add_payments_pending_index;
+pub mod m20261017_000023_add_ledger_cancellations;
+
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(tables::Migration)]
+        vec![
+            Box::new(tables::Migration),
+            Box::new(m20261017_000001_create_order_status_history::Migration),
+            Box::new(m20261017_000002_add_orders_deleted_at::Migration),
+            Box::new(m20261017_000003_add_payments_transaction_id::Migration),
+            Box::new(m20261017_000004_create_vendor_orders::Migration),
+            Box::new(m20261017_000005_create_vendor_ledger::Migration),
+            Box::new(m20261017_000006_convert_money_to_bigint::Migration),
+            Box::new(m20261017_000007_create_sessions::Migration),
+            Box::new(m20261017_000008_normalize_phone_numbers::Migration),
+            Box::new(m20261017_000009_create_otp_codes::Migration),
+            Box::new(m20261017_000010_add_login_protection::Migration),
+            Box::new(m20261017_000011_add_user_profile::Migration),
+            Box::new(m20261017_000012_create_vendor_applications::Migration),
+            Box::new(m20261017_000013_create_stores::Migration),
+            Box::new(m20261017_000014_create_staff_roles::Migration),
+            Box::new(m20261017_000015_create_audit_events::Migration),
+            Box::new(m20261017_000016_add_product_moderation::Migration),
+            Box::new(m20261017_000017_add_product_search::Migration),
+            Box::new(m20261017_000018_create_categories::Migration),
+            Box::new(m20261017_000019_create_product_variants::Migration),
+            Box::new(m20261017_000020_create_reviews::Migration),
+            Box::new(m20261017_000021_create_wishlists::Migration),
+            Box::new(m20261017_000022_add_payments_pending_index::Migration),
+            Box::new(m20261017_000023_add_ledger_cancellations::Migration),
+        ]
     }
 }
 
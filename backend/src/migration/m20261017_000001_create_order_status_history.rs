@@ -0,0 +1,149 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Statuses are parsed strictly from now on, so normalise the
+        // capitalised or padded values written by older code
+        db.execute_unprepared(
+            "UPDATE orders SET status = CASE lower(btrim(status))
+                WHEN 'canceled' THEN 'cancelled'
+                ELSE lower(btrim(status))
+            END",
+        )
+        .await?;
+
+        // Anything else cannot be mapped safely, so stop and name the
+        // orders to fix by hand rather than guess
+        db.execute_unprepared(
+            "DO $$
+            DECLARE unknown text;
+            BEGIN
+                SELECT string_agg(id || ' (' || coalesce(quote_literal(status), 'NULL') || ')', ', ')
+                INTO unknown FROM orders
+                WHERE status IS NULL
+                    OR status NOT IN ('pending', 'processing', 'shipped', 'delivered', 'cancelled');
+                IF unknown IS NOT NULL THEN
+                    RAISE EXCEPTION 'Orders with an unknown status: %', unknown;
+                END IF;
+            END $$",
+        )
+        .await?;
+
+        // Keep unknown statuses from being written again
+        db.execute_unprepared(
+            "ALTER TABLE orders ADD CONSTRAINT chk_orders_status
+                CHECK (status IN ('pending', 'processing', 'shipped', 'delivered', 'cancelled'))",
+        )
+        .await?;
+
+        // Create order_status_history table
+        manager
+            .create_table(
+                Table::create()
+                    .table(OrderStatusHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(OrderStatusHistory::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(OrderStatusHistory::OrderId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(OrderStatusHistory::FromStatus).text())
+                    .col(
+                        ColumnDef::new(OrderStatusHistory::ToStatus)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(OrderStatusHistory::ChangedBy).uuid())
+                    .col(
+                        ColumnDef::new(OrderStatusHistory::ChangedByRole)
+                            .enumeration(Alias::new("user_role"), vec!["Admin", "Vendor", "Buyer"]),
+                    )
+                    .col(ColumnDef::new(OrderStatusHistory::Reason).text())
+                    .col(
+                        ColumnDef::new(OrderStatusHistory::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_order_status_history_order_id")
+                            .from(OrderStatusHistory::Table, OrderStatusHistory::OrderId)
+                            .to(Orders::Table, Orders::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_order_status_history_changed_by")
+                            .from(OrderStatusHistory::Table, OrderStatusHistory::ChangedBy)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_order_status_history_order_id")
+                    .table(OrderStatusHistory::Table)
+                    .col(OrderStatusHistory::OrderId)
+                    .col(OrderStatusHistory::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(OrderStatusHistory::Table).to_owned())
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared("ALTER TABLE orders DROP CONSTRAINT IF EXISTS chk_orders_status")
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum OrderStatusHistory {
+    Table,
+    Id,
+    OrderId,
+    FromStatus,
+    ToStatus,
+    ChangedBy,
+    ChangedByRole,
+    Reason,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Orders {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
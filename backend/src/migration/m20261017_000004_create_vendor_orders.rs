@@ -0,0 +1,177 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create vendor_orders table, one fulfilment group per seller and order
+        manager
+            .create_table(
+                Table::create()
+                    .table(VendorOrders::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(VendorOrders::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(VendorOrders::OrderId).uuid().not_null())
+                    .col(ColumnDef::new(VendorOrders::VendorId).uuid().not_null())
+                    .col(ColumnDef::new(VendorOrders::Status).text().not_null())
+                    .col(ColumnDef::new(VendorOrders::Subtotal).double().not_null())
+                    .col(ColumnDef::new(VendorOrders::Carrier).string())
+                    .col(ColumnDef::new(VendorOrders::TrackingNumber).string())
+                    .col(ColumnDef::new(VendorOrders::ShippedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(VendorOrders::DeliveredAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(VendorOrders::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(VendorOrders::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_vendor_orders_order_id")
+                            .from(VendorOrders::Table, VendorOrders::OrderId)
+                            .to(Orders::Table, Orders::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_vendor_orders_vendor_id")
+                            .from(VendorOrders::Table, VendorOrders::VendorId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::NoAction)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_vendor_orders_order_id_vendor_id")
+                    .table(VendorOrders::Table)
+                    .col(VendorOrders::OrderId)
+                    .col(VendorOrders::VendorId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_vendor_orders_vendor_id")
+                    .table(VendorOrders::Table)
+                    .col(VendorOrders::VendorId)
+                    .col(VendorOrders::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrderItems::Table)
+                    .add_column(ColumnDef::new(OrderItems::VendorOrderId).uuid())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_order_items_vendor_order_id")
+                            .from_tbl(OrderItems::Table)
+                            .from_col(OrderItems::VendorOrderId)
+                            .to_tbl(VendorOrders::Table)
+                            .to_col(VendorOrders::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Split existing orders by seller
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "INSERT INTO vendor_orders (id, order_id, vendor_id, status, subtotal, created_at, updated_at)
+             SELECT gen_random_uuid(), oi.order_id, p.seller_id, o.status,
+                    SUM(oi.price * oi.quantity), o.created_at, o.created_at
+             FROM order_items oi
+             JOIN products p ON p.id = oi.product_id
+             JOIN orders o ON o.id = oi.order_id
+             GROUP BY oi.order_id, p.seller_id, o.status, o.created_at",
+        )
+        .await?;
+        db.execute_unprepared(
+            "UPDATE order_items oi
+             SET vendor_order_id = vo.id
+             FROM products p, vendor_orders vo
+             WHERE p.id = oi.product_id
+               AND vo.order_id = oi.order_id
+               AND vo.vendor_id = p.seller_id",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrderItems::Table)
+                    .drop_foreign_key(Alias::new("fk_order_items_vendor_order_id"))
+                    .drop_column(OrderItems::VendorOrderId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(VendorOrders::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum VendorOrders {
+    Table,
+    Id,
+    OrderId,
+    VendorId,
+    Status,
+    Subtotal,
+    Carrier,
+    TrackingNumber,
+    ShippedAt,
+    DeliveredAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum OrderItems {
+    Table,
+    VendorOrderId,
+}
+
+#[derive(Iden)]
+enum Orders {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
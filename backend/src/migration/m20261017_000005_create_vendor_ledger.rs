@@ -0,0 +1,254 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create payouts table
+        manager
+            .create_table(
+                Table::create()
+                    .table(Payouts::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Payouts::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Payouts::VendorId).uuid().not_null())
+                    .col(ColumnDef::new(Payouts::Amount).double().not_null())
+                    .col(ColumnDef::new(Payouts::Status).text().not_null())
+                    .col(ColumnDef::new(Payouts::Phone).string().not_null())
+                    .col(ColumnDef::new(Payouts::Note).text())
+                    .col(ColumnDef::new(Payouts::ReviewedBy).uuid())
+                    .col(ColumnDef::new(Payouts::ReviewedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(Payouts::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Payouts::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_payouts_vendor_id")
+                            .from(Payouts::Table, Payouts::VendorId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::NoAction)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_payouts_reviewed_by")
+                            .from(Payouts::Table, Payouts::ReviewedBy)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_payouts_vendor_id")
+                    .table(Payouts::Table)
+                    .col(Payouts::VendorId)
+                    .col(Payouts::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create ledger_transactions table, one balanced journal entry each
+        manager
+            .create_table(
+                Table::create()
+                    .table(LedgerTransactions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LedgerTransactions::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LedgerTransactions::Kind).text().not_null())
+                    .col(ColumnDef::new(LedgerTransactions::PaymentId).uuid())
+                    .col(ColumnDef::new(LedgerTransactions::PayoutId).uuid())
+                    .col(
+                        ColumnDef::new(LedgerTransactions::Description)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(LedgerTransactions::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_ledger_transactions_payment_id")
+                            .from(LedgerTransactions::Table, LedgerTransactions::PaymentId)
+                            .to(Payments::Table, Payments::Id)
+                            .on_delete(ForeignKeyAction::NoAction)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_ledger_transactions_payout_id")
+                            .from(LedgerTransactions::Table, LedgerTransactions::PayoutId)
+                            .to(Payouts::Table, Payouts::Id)
+                            .on_delete(ForeignKeyAction::NoAction)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A payment or payout is posted at most once per kind
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ledger_transactions_payment_id_kind")
+                    .table(LedgerTransactions::Table)
+                    .col(LedgerTransactions::PaymentId)
+                    .col(LedgerTransactions::Kind)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ledger_transactions_payout_id")
+                    .table(LedgerTransactions::Table)
+                    .col(LedgerTransactions::PayoutId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create ledger_entries table, the debit and credit lines
+        manager
+            .create_table(
+                Table::create()
+                    .table(LedgerEntries::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LedgerEntries::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(LedgerEntries::TransactionId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(LedgerEntries::Account).text().not_null())
+                    .col(ColumnDef::new(LedgerEntries::VendorId).uuid())
+                    .col(ColumnDef::new(LedgerEntries::Amount).double().not_null())
+                    .col(
+                        ColumnDef::new(LedgerEntries::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_ledger_entries_transaction_id")
+                            .from(LedgerEntries::Table, LedgerEntries::TransactionId)
+                            .to(LedgerTransactions::Table, LedgerTransactions::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_ledger_entries_vendor_id")
+                            .from(LedgerEntries::Table, LedgerEntries::VendorId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::NoAction)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ledger_entries_account_vendor_id")
+                    .table(LedgerEntries::Table)
+                    .col(LedgerEntries::Account)
+                    .col(LedgerEntries::VendorId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LedgerEntries::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(LedgerTransactions::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Payouts::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Payouts {
+    Table,
+    Id,
+    VendorId,
+    Amount,
+    Status,
+    Phone,
+    Note,
+    ReviewedBy,
+    ReviewedAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum LedgerTransactions {
+    Table,
+    Id,
+    Kind,
+    PaymentId,
+    PayoutId,
+    Description,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum LedgerEntries {
+    Table,
+    Id,
+    TransactionId,
+    Account,
+    VendorId,
+    Amount,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Payments {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
@@ -0,0 +1,44 @@
+use sea_orm_migration::prelude::*;
+
+/// Columns holding amounts of money, as (table, column)
+const MONEY_COLUMNS: [(&str, &str); 7] = [
+    ("products", "price"),
+    ("orders", "total"),
+    ("order_items", "price"),
+    ("payments", "amount"),
+    ("vendor_orders", "subtotal"),
+    ("payouts", "amount"),
+    ("ledger_entries", "amount"),
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Amounts are whole francs; round explicitly rather than relying on
+        // the default cast, which rounds halves to even
+        let db = manager.get_connection();
+        for (table, column) in MONEY_COLUMNS {
+            db.execute_unprepared(&format!(
+                "ALTER TABLE {table} ALTER COLUMN {column} TYPE BIGINT USING ROUND({column})::BIGINT"
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        for (table, column) in MONEY_COLUMNS {
+            db.execute_unprepared(&format!(
+                "ALTER TABLE {table} ALTER COLUMN {column} TYPE DOUBLE PRECISION"
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+/// Text columns holding phone numbers as typed, as (table, column)
+const TEXT_PHONE_COLUMNS: [(&str, &str); 2] = [("orders", "customer_phone"), ("payouts", "phone")];
+
+/// Digits of a number written in any format, matching a Cameroonian
+/// number with or without the country code
+const CAMEROON_DIGITS: &str = "'^(00)?(237)?[26][0-9]{8}$'";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Integer phone numbers are nine digit national numbers. The unique
+        // constraint on the column carries over, and now holds across every
+        // way a number can be written since all of them normalise the same.
+        db.execute_unprepared(
+            "ALTER TABLE users ALTER COLUMN phone TYPE VARCHAR(16) USING CASE \
+             WHEN phone BETWEEN 200000000 AND 699999999 THEN '+237' || phone::TEXT \
+             ELSE phone::TEXT END",
+        )
+        .await?;
+
+        // Free text numbers are normalised where they are recognisable and
+        // left untouched otherwise
+        for (table, column) in TEXT_PHONE_COLUMNS {
+            let digits = format!("regexp_replace({column}, '[^0-9]', '', 'g')");
+            db.execute_unprepared(&format!(
+                "UPDATE {table} SET {column} = '+237' || right({digits}, 9) \
+                 WHERE {digits} ~ {CAMEROON_DIGITS}"
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Normalised text numbers stay valid, only the users column changes
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE users ALTER COLUMN phone TYPE INTEGER \
+                 USING right(regexp_replace(phone, '[^0-9]', '', 'g'), 9)::INTEGER",
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create otp_codes table, one row per code texted to a phone
+        manager
+            .create_table(
+                Table::create()
+                    .table(OtpCodes::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(OtpCodes::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(OtpCodes::Phone).string_len(16).not_null())
+                    .col(ColumnDef::new(OtpCodes::Purpose).text().not_null())
+                    .col(ColumnDef::new(OtpCodes::CodeHash).string().not_null())
+                    .col(
+                        ColumnDef::new(OtpCodes::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(OtpCodes::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(OtpCodes::ConsumedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(OtpCodes::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_otp_codes_phone_purpose")
+                    .table(OtpCodes::Table)
+                    .col(OtpCodes::Phone)
+                    .col(OtpCodes::Purpose)
+                    .col(OtpCodes::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Accounts that existed before phone verification keep working
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(Users::PhoneVerifiedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE users SET phone_verified_at = created_at")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::PhoneVerifiedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(OtpCodes::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum OtpCodes {
+    Table,
+    Id,
+    Phone,
+    Purpose,
+    CodeHash,
+    Attempts,
+    ExpiresAt,
+    ConsumedAt,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    PhoneVerifiedAt,
+}
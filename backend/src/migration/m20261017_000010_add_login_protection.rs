@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Track failed logins to lock accounts under attack
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(
+                        ColumnDef::new(Users::FailedLoginAttempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .add_column(ColumnDef::new(Users::LockedUntil).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create rate_limit_buckets table, shared by every instance when
+        // rate limits are kept in Postgres
+        manager
+            .create_table(
+                Table::create()
+                    .table(RateLimitBuckets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RateLimitBuckets::Key)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RateLimitBuckets::Tokens).double().not_null())
+                    .col(
+                        ColumnDef::new(RateLimitBuckets::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RateLimitBuckets::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::FailedLoginAttempts)
+                    .drop_column(Users::LockedUntil)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    FailedLoginAttempts,
+    LockedUntil,
+}
+
+#[derive(Iden)]
+enum RateLimitBuckets {
+    Table,
+    Key,
+    Tokens,
+    UpdatedAt,
+}
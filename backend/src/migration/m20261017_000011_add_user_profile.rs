@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Profile fields users can edit themselves, and the email or phone
+        // waiting to be confirmed with a code
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(Users::AvatarUrl).text())
+                    .add_column(
+                        ColumnDef::new(Users::PreferredLanguage)
+                            .text()
+                            .not_null()
+                            .default("fr"),
+                    )
+                    .add_column(ColumnDef::new(Users::PendingEmail).string())
+                    .add_column(ColumnDef::new(Users::PendingPhone).string_len(16))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::AvatarUrl)
+                    .drop_column(Users::PreferredLanguage)
+                    .drop_column(Users::PendingEmail)
+                    .drop_column(Users::PendingPhone)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    AvatarUrl,
+    PreferredLanguage,
+    PendingEmail,
+    PendingPhone,
+}
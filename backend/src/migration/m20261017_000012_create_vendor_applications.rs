@@ -0,0 +1,156 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create vendor_applications table, one row per application a
+        // vendor submits for review
+        manager
+            .create_table(
+                Table::create()
+                    .table(VendorApplications::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(VendorApplications::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(VendorApplications::UserId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(VendorApplications::BusinessName)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(VendorApplications::TradeRegistryNumber)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(VendorApplications::TaxpayerNumber)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(VendorApplications::IdDocument)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(VendorApplications::Status).text().not_null())
+                    .col(ColumnDef::new(VendorApplications::RejectionReason).text())
+                    .col(ColumnDef::new(VendorApplications::ReviewedBy).uuid())
+                    .col(ColumnDef::new(VendorApplications::ReviewedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(VendorApplications::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(VendorApplications::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_vendor_applications_user_id")
+                            .from(VendorApplications::Table, VendorApplications::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_vendor_applications_reviewed_by")
+                            .from(VendorApplications::Table, VendorApplications::ReviewedBy)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_vendor_applications_user_id")
+                    .table(VendorApplications::Table)
+                    .col(VendorApplications::UserId)
+                    .col(VendorApplications::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_vendor_applications_status")
+                    .table(VendorApplications::Table)
+                    .col(VendorApplications::Status)
+                    .col(VendorApplications::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Only verified vendors may list products. Vendors already selling
+        // keep doing so.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(ColumnDef::new(Users::VendorVerifiedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE users SET vendor_verified_at = created_at WHERE role = 'Vendor'",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Users::VendorVerifiedAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(VendorApplications::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum VendorApplications {
+    Table,
+    Id,
+    UserId,
+    BusinessName,
+    TradeRegistryNumber,
+    TaxpayerNumber,
+    IdDocument,
+    Status,
+    RejectionReason,
+    ReviewedBy,
+    ReviewedAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+    VendorVerifiedAt,
+}
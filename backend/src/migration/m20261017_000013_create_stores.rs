@@ -0,0 +1,97 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create stores table, one public shop per vendor
+        manager
+            .create_table(
+                Table::create()
+                    .table(Stores::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Stores::Id).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(Stores::OwnerId)
+                            .uuid()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Stores::Name).string().not_null())
+                    .col(
+                        ColumnDef::new(Stores::Slug)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Stores::LogoUrl).text())
+                    .col(ColumnDef::new(Stores::BannerUrl).text())
+                    .col(ColumnDef::new(Stores::Description).text())
+                    .col(ColumnDef::new(Stores::Region).text())
+                    .col(ColumnDef::new(Stores::City).string())
+                    .col(ColumnDef::new(Stores::ArtisanStory).text())
+                    .col(
+                        ColumnDef::new(Stores::ContactPreferences)
+                            .json_binary()
+                            .not_null()
+                            .default(Expr::cust("'{}'::jsonb")),
+                    )
+                    .col(
+                        ColumnDef::new(Stores::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Stores::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_stores_owner_id")
+                            .from(Stores::Table, Stores::OwnerId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Stores::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Stores {
+    Table,
+    Id,
+    OwnerId,
+    Name,
+    Slug,
+    LogoUrl,
+    BannerUrl,
+    Description,
+    Region,
+    City,
+    ArtisanStory,
+    ContactPreferences,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
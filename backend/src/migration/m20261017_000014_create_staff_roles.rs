@@ -0,0 +1,183 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Staff accounts get their permissions from the roles assigned to them
+        manager
+            .get_connection()
+            .execute_unprepared("ALTER TYPE user_role ADD VALUE IF NOT EXISTS 'Staff'")
+            .await?;
+
+        // Create roles table, named sets of permissions
+        manager
+            .create_table(
+                Table::create()
+                    .table(Roles::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Roles::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Roles::Name).string().not_null().unique_key())
+                    .col(ColumnDef::new(Roles::Description).text())
+                    .col(
+                        ColumnDef::new(Roles::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create role_permissions table, one row per permission of a role
+        manager
+            .create_table(
+                Table::create()
+                    .table(RolePermissions::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(RolePermissions::RoleId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(RolePermissions::Permission)
+                            .text()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(RolePermissions::RoleId)
+                            .col(RolePermissions::Permission),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_role_permissions_role_id")
+                            .from(RolePermissions::Table, RolePermissions::RoleId)
+                            .to(Roles::Table, Roles::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create role_assignments table, the roles held by each staff account
+        manager
+            .create_table(
+                Table::create()
+                    .table(RoleAssignments::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(RoleAssignments::UserId).uuid().not_null())
+                    .col(ColumnDef::new(RoleAssignments::RoleId).uuid().not_null())
+                    .col(ColumnDef::new(RoleAssignments::AssignedBy).uuid())
+                    .col(
+                        ColumnDef::new(RoleAssignments::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(RoleAssignments::UserId)
+                            .col(RoleAssignments::RoleId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_role_assignments_user_id")
+                            .from(RoleAssignments::Table, RoleAssignments::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_role_assignments_role_id")
+                            .from(RoleAssignments::Table, RoleAssignments::RoleId)
+                            .to(Roles::Table, Roles::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_role_assignments_assigned_by")
+                            .from(RoleAssignments::Table, RoleAssignments::AssignedBy)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Seed the staff roles we start with
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT INTO roles (id, name, description, created_at) VALUES
+                    (gen_random_uuid(), 'moderator', 'Reviews products and vendor applications', now()),
+                    (gen_random_uuid(), 'support', 'Looks up orders and refunds payments', now())
+                ON CONFLICT (name) DO NOTHING;
+                INSERT INTO role_permissions (role_id, permission)
+                SELECT roles.id, permissions.permission
+                FROM roles
+                JOIN (VALUES
+                    ('moderator', 'moderate_products'),
+                    ('moderator', 'review_vendors'),
+                    ('support', 'view_orders'),
+                    ('support', 'issue_refunds')
+                ) AS permissions (role, permission) ON permissions.role = roles.name
+                ON CONFLICT DO NOTHING",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RoleAssignments::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(RolePermissions::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Roles::Table).to_owned())
+            .await?;
+        // Postgres cannot drop a value from an enum type, so staff accounts
+        // are turned back into buyers and 'Staff' stays unused
+        manager
+            .get_connection()
+            .execute_unprepared("UPDATE users SET role = 'Buyer' WHERE role = 'Staff'")
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Roles {
+    Table,
+    Id,
+    Name,
+    Description,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum RolePermissions {
+    Table,
+    RoleId,
+    Permission,
+}
+
+#[derive(Iden)]
+enum RoleAssignments {
+    Table,
+    UserId,
+    RoleId,
+    AssignedBy,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
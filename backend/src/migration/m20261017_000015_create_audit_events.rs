@@ -0,0 +1,115 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create audit_events table. The actor has no foreign key: events
+        // are never updated, so they must outlive the accounts they name.
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditEvents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditEvents::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditEvents::ActorId).uuid())
+                    .col(ColumnDef::new(AuditEvents::ActorRole).enumeration(
+                        Alias::new("user_role"),
+                        vec!["Admin", "Vendor", "Buyer", "Staff"],
+                    ))
+                    .col(ColumnDef::new(AuditEvents::Action).text().not_null())
+                    .col(ColumnDef::new(AuditEvents::EntityType).text().not_null())
+                    .col(ColumnDef::new(AuditEvents::EntityId).text().not_null())
+                    .col(ColumnDef::new(AuditEvents::Before).json_binary())
+                    .col(ColumnDef::new(AuditEvents::After).json_binary())
+                    .col(ColumnDef::new(AuditEvents::RequestId).text())
+                    .col(
+                        ColumnDef::new(AuditEvents::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_events_entity")
+                    .table(AuditEvents::Table)
+                    .col(AuditEvents::EntityType)
+                    .col(AuditEvents::EntityId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_events_actor_id")
+                    .table(AuditEvents::Table)
+                    .col(AuditEvents::ActorId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_audit_events_created_at")
+                    .table(AuditEvents::Table)
+                    .col(AuditEvents::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // The log is append-only, even for code that forgets it is
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE OR REPLACE FUNCTION audit_events_append_only() RETURNS trigger AS $$
+                BEGIN
+                    RAISE EXCEPTION 'audit_events is append-only';
+                END;
+                $$ LANGUAGE plpgsql;
+                CREATE TRIGGER audit_events_append_only
+                    BEFORE UPDATE OR DELETE ON audit_events
+                    FOR EACH ROW EXECUTE FUNCTION audit_events_append_only()",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditEvents::Table).to_owned())
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared("DROP FUNCTION IF EXISTS audit_events_append_only()")
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum AuditEvents {
+    Table,
+    Id,
+    ActorId,
+    ActorRole,
+    Action,
+    EntityType,
+    EntityId,
+    Before,
+    After,
+    RequestId,
+    CreatedAt,
+}
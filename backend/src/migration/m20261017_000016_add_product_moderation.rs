@@ -0,0 +1,218 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One moderation status replaces the is_approved / is_rejected pair,
+        // which could contradict each other
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Products::Table)
+                    .add_column(
+                        ColumnDef::new(Products::ModerationStatus)
+                            .text()
+                            .not_null()
+                            .default("pending_review"),
+                    )
+                    .add_column(ColumnDef::new(Products::RejectionReason).text())
+                    .add_column(ColumnDef::new(Products::ModeratorNote).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        // A rejection wins over an approval when both flags were set
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE products SET moderation_status = CASE
+                    WHEN is_rejected THEN 'rejected'
+                    WHEN is_approved THEN 'approved'
+                    ELSE 'pending_review'
+                END",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Products::Table)
+                    .drop_column(Products::IsApproved)
+                    .drop_column(Products::IsRejected)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_products_moderation_status")
+                    .table(Products::Table)
+                    .col(Products::ModerationStatus)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create product_moderation_history table
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProductModerationHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ProductModerationHistory::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ProductModerationHistory::ProductId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ProductModerationHistory::FromStatus).text())
+                    .col(
+                        ColumnDef::new(ProductModerationHistory::ToStatus)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(ProductModerationHistory::ChangedBy).uuid())
+                    .col(
+                        ColumnDef::new(ProductModerationHistory::ChangedByRole).enumeration(
+                            Alias::new("user_role"),
+                            vec!["Admin", "Vendor", "Buyer", "Staff"],
+                        ),
+                    )
+                    .col(ColumnDef::new(ProductModerationHistory::Reason).text())
+                    .col(ColumnDef::new(ProductModerationHistory::Note).text())
+                    .col(
+                        ColumnDef::new(ProductModerationHistory::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_product_moderation_history_product_id")
+                            .from(
+                                ProductModerationHistory::Table,
+                                ProductModerationHistory::ProductId,
+                            )
+                            .to(Products::Table, Products::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_product_moderation_history_changed_by")
+                            .from(
+                                ProductModerationHistory::Table,
+                                ProductModerationHistory::ChangedBy,
+                            )
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_product_moderation_history_product_id")
+                    .table(ProductModerationHistory::Table)
+                    .col(ProductModerationHistory::ProductId)
+                    .col(ProductModerationHistory::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(ProductModerationHistory::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Products::Table)
+                    .add_column(
+                        ColumnDef::new(Products::IsApproved)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column(
+                        ColumnDef::new(Products::IsRejected)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "UPDATE products SET
+                    is_approved = moderation_status = 'approved',
+                    is_rejected = moderation_status = 'rejected'",
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Products::Table)
+                    .drop_column(Products::ModerationStatus)
+                    .drop_column(Products::RejectionReason)
+                    .drop_column(Products::ModeratorNote)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Products {
+    Table,
+    Id,
+    IsApproved,
+    IsRejected,
+    ModerationStatus,
+    RejectionReason,
+    ModeratorNote,
+}
+
+#[derive(Iden)]
+enum ProductModerationHistory {
+    Table,
+    Id,
+    ProductId,
+    FromStatus,
+    ToStatus,
+    ChangedBy,
+    ChangedByRole,
+    Reason,
+    Note,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
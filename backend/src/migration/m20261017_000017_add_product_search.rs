@@ -0,0 +1,83 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Buyers search in French and in English, so the title, category
+        // and description are indexed under both configurations
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE products ADD COLUMN search_vector tsvector
+                    GENERATED ALWAYS AS (
+                        to_tsvector('english', coalesce(title, '') || ' ' || coalesce(category, '') || ' ' || coalesce(description, ''))
+                        || to_tsvector('french', coalesce(title, '') || ' ' || coalesce(category, '') || ' ' || coalesce(description, ''))
+                    ) STORED;
+                CREATE INDEX idx_products_search_vector ON products USING GIN (search_vector)",
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_products_price")
+                    .table(Products::Table)
+                    .col(Products::Price)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_products_created_at")
+                    .table(Products::Table)
+                    .col(Products::CreatedAt)
+                    .col(Products::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_products_created_at")
+                    .table(Products::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_products_price")
+                    .table(Products::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Products::Table)
+                    .drop_column(Products::SearchVector)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Products {
+    Table,
+    Id,
+    Price,
+    CreatedAt,
+    SearchVector,
+}
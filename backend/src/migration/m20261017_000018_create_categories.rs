@@ -0,0 +1,197 @@
+use sea_orm_migration::prelude::*;
+
+/// Slug of a free-text category, the same as `services::store::slugify`
+const SLUG_OF_CATEGORY: &str = "trim(both '-' from regexp_replace(
+    translate(lower(trim(category)), 'àâäçéèêëîïôöùûü', 'aaaceeeeiioouuu'),
+    '[^a-z0-9]+', '-', 'g'))";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create categories table
+        manager
+            .create_table(
+                Table::create()
+                    .table(Categories::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Categories::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Categories::ParentId).uuid())
+                    .col(
+                        ColumnDef::new(Categories::Slug)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Categories::NameEn).text().not_null())
+                    .col(ColumnDef::new(Categories::NameFr).text().not_null())
+                    .col(ColumnDef::new(Categories::Icon).text())
+                    .col(
+                        ColumnDef::new(Categories::SortOrder)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(Categories::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Categories::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_categories_parent_id")
+                            .from(Categories::Table, Categories::ParentId)
+                            .to(Categories::Table, Categories::Id)
+                            .on_delete(ForeignKeyAction::Restrict)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_categories_parent_id")
+                    .table(Categories::Table)
+                    .col(Categories::ParentId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Products::Table)
+                    .add_column(ColumnDef::new(Products::CategoryId).uuid())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_products_category_id")
+                            .from_tbl(Products::Table)
+                            .from_col(Products::CategoryId)
+                            .to_tbl(Categories::Table)
+                            .to_col(Categories::Id)
+                            .on_delete(ForeignKeyAction::Restrict)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Every spelling of a free-text category that gives the same slug
+        // ("Crafts", "crafts ") becomes one top-level category, named after
+        // its oldest product. Translations such as "Artisanat" stay apart
+        // until an admin merges them.
+        let db = manager.get_connection();
+        db.execute_unprepared(&format!(
+            "INSERT INTO categories (id, slug, name_en, name_fr, sort_order, created_at, updated_at)
+            SELECT gen_random_uuid(), slug, name, name, 0, now(), now()
+            FROM (
+                SELECT DISTINCT ON (slug) {SLUG_OF_CATEGORY} AS slug, trim(category) AS name
+                FROM products
+                WHERE category IS NOT NULL
+                ORDER BY slug, created_at
+            ) spellings
+            WHERE slug <> ''"
+        ))
+        .await?;
+        db.execute_unprepared(&format!(
+            "UPDATE products SET category_id = categories.id
+            FROM categories
+            WHERE products.category IS NOT NULL AND categories.slug = {SLUG_OF_CATEGORY}"
+        ))
+        .await?;
+
+        // The search vector reads the category text, so it is rebuilt over
+        // the title and description; search matches category names through
+        // the categories table
+        db.execute_unprepared(
+            "ALTER TABLE products DROP COLUMN search_vector;
+            ALTER TABLE products DROP COLUMN category;
+            ALTER TABLE products ADD COLUMN search_vector tsvector
+                GENERATED ALWAYS AS (
+                    to_tsvector('english', coalesce(title, '') || ' ' || coalesce(description, ''))
+                    || to_tsvector('french', coalesce(title, '') || ' ' || coalesce(description, ''))
+                ) STORED;
+            CREATE INDEX idx_products_search_vector ON products USING GIN (search_vector)",
+        )
+        .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_products_category_id")
+                    .table(Products::Table)
+                    .col(Products::CategoryId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "ALTER TABLE products ADD COLUMN category text;
+            UPDATE products SET category = categories.name_en
+                FROM categories WHERE categories.id = products.category_id;
+            ALTER TABLE products DROP COLUMN search_vector;
+            ALTER TABLE products ADD COLUMN search_vector tsvector
+                GENERATED ALWAYS AS (
+                    to_tsvector('english', coalesce(title, '') || ' ' || coalesce(category, '') || ' ' || coalesce(description, ''))
+                    || to_tsvector('french', coalesce(title, '') || ' ' || coalesce(category, '') || ' ' || coalesce(description, ''))
+                ) STORED;
+            CREATE INDEX idx_products_search_vector ON products USING GIN (search_vector)",
+        )
+        .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Products::Table)
+                    .drop_foreign_key(Alias::new("fk_products_category_id"))
+                    .drop_column(Products::CategoryId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Categories::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Categories {
+    Table,
+    Id,
+    ParentId,
+    Slug,
+    NameEn,
+    NameFr,
+    Icon,
+    SortOrder,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Products {
+    Table,
+    CategoryId,
+}
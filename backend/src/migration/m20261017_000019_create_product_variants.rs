@@ -0,0 +1,254 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create product_options table
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProductOptions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ProductOptions::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ProductOptions::ProductId).uuid().not_null())
+                    .col(ColumnDef::new(ProductOptions::Name).text().not_null())
+                    .col(
+                        ColumnDef::new(ProductOptions::Values)
+                            .array(ColumnType::Text)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ProductOptions::Position)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_product_options_product_id")
+                            .from(ProductOptions::Table, ProductOptions::ProductId)
+                            .to(Products::Table, Products::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_product_options_product_id_name")
+                    .table(ProductOptions::Table)
+                    .col(ProductOptions::ProductId)
+                    .col(ProductOptions::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create product_variants table
+        manager
+            .create_table(
+                Table::create()
+                    .table(ProductVariants::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ProductVariants::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ProductVariants::ProductId).uuid().not_null())
+                    .col(ColumnDef::new(ProductVariants::Sku).text().not_null())
+                    .col(ColumnDef::new(ProductVariants::Options).json_binary().not_null())
+                    .col(ColumnDef::new(ProductVariants::Price).big_integer().not_null())
+                    .col(
+                        ColumnDef::new(ProductVariants::Quantity)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ProductVariants::ImageUrls)
+                            .array(ColumnType::Text)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ProductVariants::Position)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(ProductVariants::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(ProductVariants::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_product_variants_product_id")
+                            .from(ProductVariants::Table, ProductVariants::ProductId)
+                            .to(Products::Table, Products::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_product_variants_product_id_sku")
+                    .table(ProductVariants::Table)
+                    .col(ProductVariants::ProductId)
+                    .col(ProductVariants::Sku)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Stock can never go negative, whatever the application does
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE product_variants
+                    ADD CONSTRAINT chk_product_variants_quantity CHECK (quantity >= 0)",
+            )
+            .await?;
+
+        // Cart and order lines point at the variant bought, if any. Order
+        // lines keep the SKU in case the variant is deleted later.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CartItems::Table)
+                    .add_column(ColumnDef::new(CartItems::VariantId).uuid())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_cart_items_variant_id")
+                            .from_tbl(CartItems::Table)
+                            .from_col(CartItems::VariantId)
+                            .to_tbl(ProductVariants::Table)
+                            .to_col(ProductVariants::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrderItems::Table)
+                    .add_column(ColumnDef::new(OrderItems::VariantId).uuid())
+                    .add_column(ColumnDef::new(OrderItems::Sku).text())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_order_items_variant_id")
+                            .from_tbl(OrderItems::Table)
+                            .from_col(OrderItems::VariantId)
+                            .to_tbl(ProductVariants::Table)
+                            .to_col(ProductVariants::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(OrderItems::Table)
+                    .drop_foreign_key(Alias::new("fk_order_items_variant_id"))
+                    .drop_column(OrderItems::VariantId)
+                    .drop_column(OrderItems::Sku)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(CartItems::Table)
+                    .drop_foreign_key(Alias::new("fk_cart_items_variant_id"))
+                    .drop_column(CartItems::VariantId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ProductVariants::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ProductOptions::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum ProductOptions {
+    Table,
+    Id,
+    ProductId,
+    Name,
+    Values,
+    Position,
+}
+
+#[derive(Iden)]
+enum ProductVariants {
+    Table,
+    Id,
+    ProductId,
+    Sku,
+    Options,
+    Price,
+    Quantity,
+    ImageUrls,
+    Position,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Products {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum CartItems {
+    Table,
+    VariantId,
+}
+
+#[derive(Iden)]
+enum OrderItems {
+    Table,
+    VariantId,
+    Sku,
+}
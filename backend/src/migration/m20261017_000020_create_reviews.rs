@@ -0,0 +1,226 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create reviews table
+        manager
+            .create_table(
+                Table::create()
+                    .table(Reviews::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Reviews::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Reviews::ProductId).uuid().not_null())
+                    .col(ColumnDef::new(Reviews::BuyerId).uuid().not_null())
+                    .col(ColumnDef::new(Reviews::OrderId).uuid())
+                    .col(ColumnDef::new(Reviews::Rating).integer().not_null())
+                    .col(ColumnDef::new(Reviews::Body).text().not_null())
+                    .col(
+                        ColumnDef::new(Reviews::ImageUrls)
+                            .array(ColumnType::Text)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Reviews::Status)
+                            .text()
+                            .not_null()
+                            .default("published"),
+                    )
+                    .col(ColumnDef::new(Reviews::HiddenReason).text())
+                    .col(ColumnDef::new(Reviews::VendorReply).text())
+                    .col(ColumnDef::new(Reviews::VendorRepliedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(Reviews::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Reviews::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_reviews_product_id")
+                            .from(Reviews::Table, Reviews::ProductId)
+                            .to(Products::Table, Products::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_reviews_buyer_id")
+                            .from(Reviews::Table, Reviews::BuyerId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_reviews_order_id")
+                            .from(Reviews::Table, Reviews::OrderId)
+                            .to(Orders::Table, Orders::Id)
+                            .on_delete(ForeignKeyAction::SetNull)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // One review per buyer and product
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_reviews_product_id_buyer_id")
+                    .table(Reviews::Table)
+                    .col(Reviews::ProductId)
+                    .col(Reviews::BuyerId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE reviews
+                    ADD CONSTRAINT chk_reviews_rating CHECK (rating BETWEEN 1 AND 5)",
+            )
+            .await?;
+
+        // Create review_reports table
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReviewReports::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ReviewReports::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ReviewReports::ReviewId).uuid().not_null())
+                    .col(ColumnDef::new(ReviewReports::ReporterId).uuid().not_null())
+                    .col(ColumnDef::new(ReviewReports::Reason).text().not_null())
+                    .col(ColumnDef::new(ReviewReports::ResolvedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(ReviewReports::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_review_reports_review_id")
+                            .from(ReviewReports::Table, ReviewReports::ReviewId)
+                            .to(Reviews::Table, Reviews::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_review_reports_reporter_id")
+                            .from(ReviewReports::Table, ReviewReports::ReporterId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // One report per user and review
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_review_reports_review_id_reporter_id")
+                    .table(ReviewReports::Table)
+                    .col(ReviewReports::ReviewId)
+                    .col(ReviewReports::ReporterId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Moderators look after reviews
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "INSERT INTO role_permissions (role_id, permission)
+                SELECT id, 'moderate_reviews' FROM roles WHERE name = 'moderator'
+                ON CONFLICT DO NOTHING",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DELETE FROM role_permissions WHERE permission = 'moderate_reviews'",
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(ReviewReports::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Reviews::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Reviews {
+    Table,
+    Id,
+    ProductId,
+    BuyerId,
+    OrderId,
+    Rating,
+    Body,
+    ImageUrls,
+    Status,
+    HiddenReason,
+    VendorReply,
+    VendorRepliedAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum ReviewReports {
+    Table,
+    Id,
+    ReviewId,
+    ReporterId,
+    Reason,
+    ResolvedAt,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Products {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Orders {
+    Table,
+    Id,
+}
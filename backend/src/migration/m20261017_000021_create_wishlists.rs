@@ -0,0 +1,198 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create wishlists table
+        manager
+            .create_table(
+                Table::create()
+                    .table(Wishlists::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Wishlists::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Wishlists::UserId).uuid().not_null())
+                    .col(ColumnDef::new(Wishlists::Name).text().not_null())
+                    .col(ColumnDef::new(Wishlists::ShareToken).text().unique_key())
+                    .col(
+                        ColumnDef::new(Wishlists::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Wishlists::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_wishlists_user_id")
+                            .from(Wishlists::Table, Wishlists::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // List names are unique per user
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wishlists_user_id_name")
+                    .table(Wishlists::Table)
+                    .col(Wishlists::UserId)
+                    .col(Wishlists::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create wishlist_items table
+        manager
+            .create_table(
+                Table::create()
+                    .table(WishlistItems::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WishlistItems::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(WishlistItems::WishlistId).uuid().not_null())
+                    .col(ColumnDef::new(WishlistItems::ProductId).uuid().not_null())
+                    .col(ColumnDef::new(WishlistItems::VariantId).uuid())
+                    .col(
+                        ColumnDef::new(WishlistItems::Quantity)
+                            .integer()
+                            .not_null()
+                            .default(1),
+                    )
+                    .col(
+                        ColumnDef::new(WishlistItems::NotifiedPrice)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WishlistItems::NotifiedInStock)
+                            .boolean()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(WishlistItems::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_wishlist_items_wishlist_id")
+                            .from(WishlistItems::Table, WishlistItems::WishlistId)
+                            .to(Wishlists::Table, Wishlists::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_wishlist_items_product_id")
+                            .from(WishlistItems::Table, WishlistItems::ProductId)
+                            .to(Products::Table, Products::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_wishlist_items_variant_id")
+                            .from(WishlistItems::Table, WishlistItems::VariantId)
+                            .to(ProductVariants::Table, ProductVariants::Id)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A product, or one of its variants, appears once per list
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE UNIQUE INDEX IF NOT EXISTS idx_wishlist_items_wishlist_id_product_id_variant_id
+                    ON wishlist_items (wishlist_id, product_id, COALESCE(variant_id, '00000000-0000-0000-0000-000000000000'))",
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wishlist_items_product_id")
+                    .table(WishlistItems::Table)
+                    .col(WishlistItems::ProductId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WishlistItems::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Wishlists::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Wishlists {
+    Table,
+    Id,
+    UserId,
+    Name,
+    ShareToken,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum WishlistItems {
+    Table,
+    Id,
+    WishlistId,
+    ProductId,
+    VariantId,
+    Quantity,
+    NotifiedPrice,
+    NotifiedInStock,
+    CreatedAt,
+}
+
+#[derive(Iden)]
+enum Users {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum Products {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum ProductVariants {
+    Table,
+    Id,
+}
@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Only the newest pending payment of an order can still be waited
+        // on, older ones would have been expired by reconciliation
+        db.execute_unprepared(
+            "UPDATE payments SET status = 'expired', updated_at = now()
+            WHERE status = 'pending' AND id NOT IN (
+                SELECT DISTINCT ON (order_id) id FROM payments
+                WHERE status = 'pending'
+                ORDER BY order_id, created_at DESC
+            )",
+        )
+        .await?;
+
+        // At most one payment of an order waits for the gateway at a time
+        db.execute_unprepared(
+            "CREATE UNIQUE INDEX idx_payments_order_id_pending
+                ON payments (order_id) WHERE status = 'pending'",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_payments_order_id_pending")
+                    .table(Payments::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Payments {
+    Table,
+}
@@ -0,0 +1,122 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Vendor order whose cancellation a posting reverses
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LedgerTransactions::Table)
+                    .add_column(ColumnDef::new(LedgerTransactions::VendorOrderId).uuid())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_ledger_transactions_vendor_order_id")
+                            .from_tbl(LedgerTransactions::Table)
+                            .from_col(LedgerTransactions::VendorOrderId)
+                            .to_tbl(VendorOrders::Table)
+                            .to_col(VendorOrders::Id)
+                            .on_delete(ForeignKeyAction::NoAction)
+                            .on_update(ForeignKeyAction::NoAction),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A payment has one cancellation posting per vendor order, so only
+        // the other kinds stay unique per payment
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_ledger_transactions_payment_id_kind")
+                    .table(LedgerTransactions::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE UNIQUE INDEX idx_ledger_transactions_payment_id_kind
+                    ON ledger_transactions (payment_id, kind) WHERE vendor_order_id IS NULL",
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ledger_transactions_vendor_order_id")
+                    .table(LedgerTransactions::Table)
+                    .col(LedgerTransactions::VendorOrderId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DELETE FROM ledger_transactions WHERE vendor_order_id IS NOT NULL")
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_ledger_transactions_vendor_order_id")
+                    .table(LedgerTransactions::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_ledger_transactions_payment_id_kind")
+                    .table(LedgerTransactions::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_ledger_transactions_payment_id_kind")
+                    .table(LedgerTransactions::Table)
+                    .col(LedgerTransactions::PaymentId)
+                    .col(LedgerTransactions::Kind)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(LedgerTransactions::Table)
+                    .drop_foreign_key(Alias::new("fk_ledger_transactions_vendor_order_id"))
+                    .drop_column(LedgerTransactions::VendorOrderId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum LedgerTransactions {
+    Table,
+    PaymentId,
+    Kind,
+    VendorOrderId,
+}
+
+#[derive(Iden)]
+enum VendorOrders {
+    Table,
+    Id,
+}
@@ -0,0 +1,135 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+use super::user::UserRole;
+
+/// Audit event model recording a privileged action. Rows are only ever
+/// inserted, a database trigger rejects updates and deletes.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "audit_events")]
+pub struct Model {
+    /// Unique identifier for the event
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// User who acted, empty for actions the platform took on its own
+    pub actor_id: Option<Uuid>,
+    /// Role of the user when they acted
+    pub actor_role: Option<UserRole>,
+    /// What was done
+    pub action: AuditAction,
+    /// Kind of record acted on
+    pub entity_type: AuditEntity,
+    /// Id of the record acted on
+    pub entity_id: String,
+    /// Snapshot of the record before the action
+    pub before: Option<Json>,
+    /// Snapshot of the record after the action
+    pub after: Option<Json>,
+    /// Id of the request that made the change
+    pub request_id: Option<String>,
+    /// Timestamp when the action happened
+    pub created_at: DateTime<Utc>,
+}
+
+/// Audited actions, stored as snake_case text in `audit_events.action`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    #[sea_orm(string_value = "user_status_updated")]
+    UserStatusUpdated,
+    #[sea_orm(string_value = "product_approved")]
+    ProductApproved,
+    #[sea_orm(string_value = "product_rejected")]
+    ProductRejected,
+    #[sea_orm(string_value = "product_suspended")]
+    ProductSuspended,
+    #[sea_orm(string_value = "product_status_updated")]
+    ProductStatusUpdated,
+    #[sea_orm(string_value = "review_hidden")]
+    ReviewHidden,
+    #[sea_orm(string_value = "review_restored")]
+    ReviewRestored,
+    #[sea_orm(string_value = "category_created")]
+    CategoryCreated,
+    #[sea_orm(string_value = "category_updated")]
+    CategoryUpdated,
+    #[sea_orm(string_value = "category_merged")]
+    CategoryMerged,
+    #[sea_orm(string_value = "category_deleted")]
+    CategoryDeleted,
+    #[sea_orm(string_value = "vendor_application_approved")]
+    VendorApplicationApproved,
+    #[sea_orm(string_value = "vendor_application_rejected")]
+    VendorApplicationRejected,
+    #[sea_orm(string_value = "payout_approved")]
+    PayoutApproved,
+    #[sea_orm(string_value = "payout_rejected")]
+    PayoutRejected,
+    #[sea_orm(string_value = "payment_refunded")]
+    PaymentRefunded,
+    #[sea_orm(string_value = "order_deleted")]
+    OrderDeleted,
+    #[sea_orm(string_value = "role_created")]
+    RoleCreated,
+    #[sea_orm(string_value = "role_permissions_updated")]
+    RolePermissionsUpdated,
+    #[sea_orm(string_value = "role_assigned")]
+    RoleAssigned,
+    #[sea_orm(string_value = "role_revoked")]
+    RoleRevoked,
+    #[sea_orm(string_value = "staff_created")]
+    StaffCreated,
+}
+
+/// Kinds of records audit events point at, stored as snake_case text in
+/// `audit_events.entity_type`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEntity {
+    #[sea_orm(string_value = "user")]
+    User,
+    #[sea_orm(string_value = "product")]
+    Product,
+    #[sea_orm(string_value = "review")]
+    Review,
+    #[sea_orm(string_value = "category")]
+    Category,
+    #[sea_orm(string_value = "vendor_application")]
+    VendorApplication,
+    #[sea_orm(string_value = "payout")]
+    Payout,
+    #[sea_orm(string_value = "payment")]
+    Payment,
+    #[sea_orm(string_value = "order")]
+    Order,
+    #[sea_orm(string_value = "role")]
+    Role,
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
+impl fmt::Display for AuditEntity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
+/// Audit events stand alone, they keep no foreign keys
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
@@ -14,6 +14,8 @@ pub struct Model {
     pub cart_id: Uuid,
     /// Reference to the product in the cart
     pub product_id: Uuid,
+    /// Reference to the chosen variant, for products that have variants
+    pub variant_id: Option<Uuid>,
     /// Quantity of the product in the cart
     pub quantity: i32,
 }
@@ -41,6 +43,16 @@ pub enum Relation {
         on_delete = "Cascade"
     )]
     Product,
+    /// Relationship with the chosen ProductVariant
+    /// If the variant is deleted, the cart item is also deleted
+    #[sea_orm(
+        belongs_to = "super::product_variant::Entity",
+        from = "Column::VariantId",
+        to = "super::product_variant::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Variant,
 }
 
 /// Implements the relationship with Cart entity
@@ -60,6 +72,7 @@ impl Related<super::product::Entity> for Entity {
 pub struct CartItem {
     pub cart_id: Uuid,
     pub product_id: Uuid,
+    pub variant_id: Option<Uuid>,
     pub quantity: i32,
 }
 /// Implements default behavior for active model operations
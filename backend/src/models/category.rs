@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Category model, one node of the product taxonomy
+/// Categories form a tree: top-level categories have no parent
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "categories")]
+pub struct Model {
+    /// Unique identifier for the category
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the category this one belongs to, empty at the top level
+    pub parent_id: Option<Uuid>,
+    /// Unique lowercase name used in the category's URL
+    #[sea_orm(unique)]
+    pub slug: String,
+    /// Name shown to English speakers
+    pub name_en: String,
+    /// Name shown to French speakers
+    pub name_fr: String,
+    /// Icon name or URL shown next to the category
+    pub icon: Option<String>,
+    /// Position among its siblings, lowest first
+    pub sort_order: i32,
+    /// Timestamp when the category was created
+    pub created_at: DateTime<Utc>,
+    /// Timestamp when the category was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Defines the relationships between Category and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the parent category
+    /// A category with subcategories cannot be deleted
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ParentId",
+        to = "Column::Id",
+        on_update = "NoAction",
+        on_delete = "Restrict"
+    )]
+    Parent,
+    /// Relationship with the Products in the category
+    #[sea_orm(has_many = "super::product::Entity")]
+    Product,
+}
+
+/// Implements the relationship with Product entity
+impl Related<super::product::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Product.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::money::Money;
+
+/// LedgerEntry model representing one debit or credit line of a ledger transaction
+/// Debits are stored as positive amounts and credits as negative amounts
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "ledger_entries")]
+pub struct Model {
+    /// Unique identifier for the entry
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the ledger transaction this entry belongs to
+    pub transaction_id: Uuid,
+    /// Account the entry is posted to
+    pub account: LedgerAccount,
+    /// Vendor owning the account, only set for vendor payable entries
+    pub vendor_id: Option<Uuid>,
+    /// Signed amount, positive for a debit and negative for a credit
+    pub amount: Money,
+    /// Timestamp when the entry was posted
+    pub created_at: DateTime<Utc>,
+}
+
+/// Ledger accounts, stored as lowercase text in `ledger_entries.account`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerAccount {
+    /// Funds collected through the payment gateway and held by the platform
+    #[sea_orm(string_value = "gateway_clearing")]
+    GatewayClearing,
+    /// Amount owed to a vendor for their sales
+    #[sea_orm(string_value = "vendor_payable")]
+    VendorPayable,
+    /// Commission earned by the platform
+    #[sea_orm(string_value = "platform_commission")]
+    PlatformCommission,
+    /// Amount owed back to buyers for vendor orders cancelled after payment
+    #[sea_orm(string_value = "refunds_payable")]
+    RefundsPayable,
+}
+
+/// Defines the relationships between LedgerEntry and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the LedgerTransaction this entry belongs to
+    /// If the transaction is deleted, its entries are also deleted
+    #[sea_orm(
+        belongs_to = "super::ledger_transaction::Entity",
+        from = "Column::TransactionId",
+        to = "super::ledger_transaction::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    LedgerTransaction,
+    /// Relationship with the vendor (User) owning the account
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::VendorId",
+        to = "super::user::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Vendor,
+}
+
+/// Implements the relationship with LedgerTransaction entity
+impl Related<super::ledger_transaction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LedgerTransaction.def()
+    }
+}
+
+/// Implements the relationship with User entity
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Vendor.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// LedgerTransaction model grouping the entries of one balanced posting
+/// The amounts of its entries always add up to zero
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "ledger_transactions")]
+pub struct Model {
+    /// Unique identifier for the ledger transaction
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// What caused the posting
+    pub kind: LedgerTransactionKind,
+    /// Payment that was collected or refunded, if any
+    pub payment_id: Option<Uuid>,
+    /// Payout that was approved, if any
+    pub payout_id: Option<Uuid>,
+    /// Vendor order whose cancellation is posted, if any
+    pub vendor_order_id: Option<Uuid>,
+    /// Human readable description of the posting
+    pub description: String,
+    /// Timestamp when the posting was made
+    pub created_at: DateTime<Utc>,
+}
+
+/// Kind of ledger transaction, stored as lowercase text
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerTransactionKind {
+    /// A completed payment, split between vendors and the platform
+    #[sea_orm(string_value = "payment")]
+    Payment,
+    /// A refunded payment, reversing its payment posting
+    #[sea_orm(string_value = "refund")]
+    Refund,
+    /// Funds paid out to a vendor
+    #[sea_orm(string_value = "payout")]
+    Payout,
+    /// A vendor order cancelled after payment, its share now owed back to
+    /// the buyer
+    #[sea_orm(string_value = "cancellation")]
+    Cancellation,
+}
+
+/// Defines the relationships between LedgerTransaction and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the entries of this transaction
+    #[sea_orm(has_many = "super::ledger_entry::Entity")]
+    LedgerEntry,
+    /// Relationship with the Payment this transaction posts
+    #[sea_orm(
+        belongs_to = "super::payment::Entity",
+        from = "Column::PaymentId",
+        to = "super::payment::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Payment,
+    /// Relationship with the Payout this transaction posts
+    #[sea_orm(
+        belongs_to = "super::payout::Entity",
+        from = "Column::PayoutId",
+        to = "super::payout::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Payout,
+    /// Relationship with the VendorOrder whose cancellation this transaction posts
+    #[sea_orm(
+        belongs_to = "super::vendor_order::Entity",
+        from = "Column::VendorOrderId",
+        to = "super::vendor_order::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    VendorOrder,
+}
+
+/// Implements the relationship with LedgerEntry entity
+impl Related<super::ledger_entry::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::LedgerEntry.def()
+    }
+}
+
+/// Implements the relationship with Payment entity
+impl Related<super::payment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Payment.def()
+    }
+}
+
+/// Implements the relationship with Payout entity
+impl Related<super::payout::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Payout.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,7 +1,31 @@
+pub mod audit_event;
 pub mod cart;
 pub mod cart_item;
+pub mod category;
+pub mod ledger_entry;
+pub mod ledger_transaction;
+pub mod money;
 pub mod order;
 pub mod order_item;
+pub mod order_status_history;
+pub mod otp_code;
 pub mod payment;
+pub mod payout;
+pub mod phone;
 pub mod product;
+pub mod product_moderation_history;
+pub mod product_option;
+pub mod product_variant;
+pub mod rate_limit_bucket;
+pub mod review;
+pub mod review_report;
+pub mod role;
+pub mod role_assignment;
+pub mod role_permission;
+pub mod session;
+pub mod store;
 pub mod user;
+pub mod vendor_application;
+pub mod vendor_order;
+pub mod wishlist;
+pub mod wishlist_item;
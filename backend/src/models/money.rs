@@ -0,0 +1,256 @@
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+};
+
+use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
+use sea_orm::DeriveValueType;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::services::errors::ServiceError;
+
+/// An amount of Central African CFA francs (XAF).
+///
+/// The franc has no minor unit in circulation, so amounts are whole francs
+/// stored as `BIGINT`. Arithmetic is exact; the only rounding happens when
+/// taking a share of an amount with [`Money::share`]. Amounts built from
+/// request input are combined with the `checked_` methods, which refuse
+/// to overflow.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, DeriveValueType,
+)]
+#[serde(transparent)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    /// An amount of whole francs
+    pub const fn xaf(amount: i64) -> Self {
+        Money(amount)
+    }
+
+    /// The amount in whole francs
+    pub const fn as_xaf(self) -> i64 {
+        self.0
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    /// The sum of two amounts, or a validation error if it overflows
+    pub fn checked_add(self, rhs: Money) -> Result<Money, ServiceError> {
+        self.0
+            .checked_add(rhs.0)
+            .map(Money)
+            .ok_or_else(|| ServiceError::Validation("Amount is too large".to_string()))
+    }
+
+    /// Price times quantity, or a validation error if it overflows
+    pub fn checked_mul(self, quantity: i64) -> Result<Money, ServiceError> {
+        self.0
+            .checked_mul(quantity)
+            .map(Money)
+            .ok_or_else(|| ServiceError::Validation("Amount is too large".to_string()))
+    }
+
+    /// `rate` of this amount, rounded half away from zero to a whole franc
+    pub fn share(self, rate: Decimal) -> Money {
+        let share = (Decimal::from(self.0) * rate)
+            .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero);
+        Money(share.to_i64().unwrap_or_default())
+    }
+
+    /// The amount as a float, for payment providers whose APIs take one
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64
+    }
+
+    /// Convert a float amount reported by a payment provider, rounding to
+    /// the nearest franc
+    pub fn from_f64(amount: f64) -> Money {
+        Money(amount.round() as i64)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} FCFA", self.0)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(iter: I) -> Money {
+        iter.copied().sum()
+    }
+}
+
+/// Amounts are read from JSON numbers. Whole floats such as `5000.0` are
+/// accepted for older clients, fractions of a franc are rejected.
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MoneyVisitor;
+
+        impl de::Visitor<'_> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a whole number of francs")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Money, E> {
+                Ok(Money(v))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Money, E> {
+                i64::try_from(v)
+                    .map(Money)
+                    .map_err(|_| E::custom("amount is too large"))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Money, E> {
+                if v.is_finite() && v.fract() == 0.0 && v.abs() < i64::MAX as f64 {
+                    Ok(Money(v as i64))
+                } else {
+                    Err(E::custom(format!("{} is not a whole number of francs", v)))
+                }
+            }
+        }
+
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+/// Deserialize an amount sent in a request, refusing negative ones. Use
+/// with `#[serde(deserialize_with = "money::non_negative")]`.
+pub fn non_negative<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Money, D::Error> {
+    let amount = Money::deserialize(deserializer)?;
+    if amount.0 < 0 {
+        return Err(de::Error::custom("amount cannot be negative"));
+    }
+    Ok(amount)
+}
+
+/// [`non_negative`] for optional fields, which also need `#[serde(default)]`
+pub fn non_negative_opt<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Money>, D::Error> {
+    #[derive(Deserialize)]
+    struct NonNegative(#[serde(deserialize_with = "non_negative")] Money);
+
+    Ok(Option::<NonNegative>::deserialize(deserializer)?.map(|NonNegative(amount)| amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_share_rounds_half_away_from_zero() {
+        let rate = Decimal::from_str("0.1").unwrap();
+        assert_eq!(Money::xaf(2500).share(rate), Money::xaf(250));
+        assert_eq!(Money::xaf(1235).share(rate), Money::xaf(124));
+        assert_eq!(Money::xaf(1234).share(rate), Money::xaf(123));
+        assert_eq!(Money::xaf(1000).share(Decimal::ZERO), Money::ZERO);
+    }
+
+    #[test]
+    fn test_arithmetic_is_exact() {
+        // 0.1 + 0.2 style drift cannot happen with whole francs
+        let total: Money = [Money::xaf(1999), Money::xaf(2999), Money::xaf(1)]
+            .iter()
+            .sum();
+        assert_eq!(total, Money::xaf(4999));
+        assert_eq!(Money::xaf(1500).checked_mul(3).unwrap(), Money::xaf(4500));
+        assert_eq!(Money::xaf(100) - Money::xaf(250), -Money::xaf(150));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        assert_eq!(serde_json::to_string(&Money::xaf(5000)).unwrap(), "5000");
+        assert_eq!(
+            serde_json::from_str::<Money>("5000").unwrap(),
+            Money::xaf(5000)
+        );
+        assert_eq!(
+            serde_json::from_str::<Money>("5000.0").unwrap(),
+            Money::xaf(5000)
+        );
+        assert!(serde_json::from_str::<Money>("5000.5").is_err());
+        assert!(serde_json::from_str::<Money>("\"5000\"").is_err());
+    }
+
+    #[test]
+    fn test_checked_arithmetic_refuses_overflow() {
+        assert!(matches!(
+            Money::xaf(i64::MAX).checked_add(Money::xaf(1)),
+            Err(ServiceError::Validation(_))
+        ));
+        assert!(matches!(
+            Money::xaf(i64::MAX / 2).checked_mul(3),
+            Err(ServiceError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn test_request_amounts_cannot_be_negative() {
+        #[derive(Deserialize)]
+        struct Request {
+            #[serde(deserialize_with = "non_negative")]
+            price: Money,
+            #[serde(default, deserialize_with = "non_negative_opt")]
+            discount: Option<Money>,
+        }
+
+        let request: Request = serde_json::from_str(r#"{"price": 0}"#).unwrap();
+        assert_eq!(request.price, Money::ZERO);
+        assert_eq!(request.discount, None);
+        assert!(serde_json::from_str::<Request>(r#"{"price": -5000}"#).is_err());
+        assert!(serde_json::from_str::<Request>(r#"{"price": 1, "discount": -1}"#).is_err());
+    }
+}
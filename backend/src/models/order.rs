@@ -1,9 +1,11 @@
 use chrono::{DateTime, Utc};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{fmt, str::FromStr};
 use uuid::Uuid;
 
+use super::{money::Money, phone::PhoneNumber};
+
 use crate::routes::order::OrderItemRequest;
 
 /// Order model representing customer purchases in the marketplace
@@ -22,7 +24,7 @@ pub struct Model {
     /// Email address of the customer
     pub customer_email: Option<String>,
     /// Phone number of the customer
-    pub customer_phone: String,
+    pub customer_phone: PhoneNumber,
     /// Delivery address for the order
     pub delivery_address: String,
     /// region where the order is to be delivered
@@ -30,62 +32,89 @@ pub struct Model {
     /// City where the order is to be delivered
     pub city: String,
     /// Current status of the order
-    #[sea_orm(column_type = "Text")]
-    pub status: String,
+    pub status: Status,
     /// Total amount of the order including all items and fees
-    pub total: f64,
+    pub total: Money,
     /// Timestamp when the order was created
     pub created_at: DateTime<Utc>,
+    /// Timestamp when an admin deleted the order, hidden from listings once set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
-/// Order status enum
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+/// Order status enum, stored as lowercase text in `orders.status`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "lowercase")]
 pub enum Status {
     /// Order is pending and awaiting payment
+    #[sea_orm(string_value = "pending")]
+    #[serde(alias = "Pending")]
     Pending,
     /// Order has been paid and is being processed
+    #[sea_orm(string_value = "processing")]
+    #[serde(alias = "Processing")]
     Processing,
     /// Order has been shipped to the customer
+    #[sea_orm(string_value = "shipped")]
+    #[serde(alias = "Shipped")]
     Shipped,
     /// Order has been delivered to the customer
+    #[sea_orm(string_value = "delivered")]
+    #[serde(alias = "Delivered")]
     Delivered,
     /// Order has been cancelled
+    #[sea_orm(string_value = "cancelled")]
+    #[serde(alias = "Cancelled")]
     Cancelled,
 }
 
+impl Status {
+    /// Whether `self -> next` is a step of the normal order lifecycle:
+    /// pending -> processing -> shipped -> delivered, with cancellation
+    /// possible until the order has shipped.
+    pub fn can_transition_to(&self, next: Status) -> bool {
+        matches!(
+            (self, next),
+            (Status::Pending, Status::Processing)
+                | (Status::Pending, Status::Cancelled)
+                | (Status::Processing, Status::Shipped)
+                | (Status::Processing, Status::Cancelled)
+                | (Status::Shipped, Status::Delivered)
+        )
+    }
+
+    /// Whether the order can no longer move through the lifecycle
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Status::Delivered | Status::Cancelled)
+    }
+}
+
 impl fmt::Display for Status {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Status::Pending => write!(f, "Pending"),
-            Status::Processing => write!(f, "Processing"),
-            Status::Shipped => write!(f, "Shipped"),
-            Status::Delivered => write!(f, "Delivered"),
-            Status::Cancelled => write!(f, "Cancelled"),
-        }
+        write!(f, "{}", self.to_value())
     }
 }
 
 impl From<Status> for String {
     fn from(status: Status) -> String {
-        match status {
-            Status::Pending => "pending".to_string(),
-            Status::Processing => "processing".to_string(),
-            Status::Shipped => "shipped".to_string(),
-            Status::Delivered => "delivered".to_string(),
-            Status::Cancelled => "cancelled".to_string(),
-        }
+        status.to_value()
     }
 }
 
-impl From<String> for Status {
-    fn from(status: String) -> Status {
-        match status.as_str() {
-            "pending" => Status::Pending,
-            "processing" => Status::Processing,
-            "shipped" => Status::Shipped,
-            "delivered" => Status::Delivered,
-            "cancelled" => Status::Cancelled,
-            _ => Status::Pending,
+impl FromStr for Status {
+    type Err = String;
+
+    fn from_str(status: &str) -> Result<Status, Self::Err> {
+        match status.to_lowercase().as_str() {
+            "pending" => Ok(Status::Pending),
+            "processing" => Ok(Status::Processing),
+            "shipped" => Ok(Status::Shipped),
+            "delivered" => Ok(Status::Delivered),
+            "cancelled" => Ok(Status::Cancelled),
+            other => Err(format!("Unknown order status: {}", other)),
         }
     }
 }
@@ -109,6 +138,12 @@ pub enum Relation {
     /// Relationship with Payments associated with this order
     #[sea_orm(has_many = "super::payment::Entity")]
     Payment,
+    /// Relationship with the status changes recorded for this order
+    #[sea_orm(has_many = "super::order_status_history::Entity")]
+    StatusHistory,
+    /// Relationship with the per-vendor parts of this order
+    #[sea_orm(has_many = "super::vendor_order::Entity")]
+    VendorOrder,
 }
 
 /// Implements the relationship with User entity
@@ -131,6 +166,20 @@ impl Related<super::payment::Entity> for Entity {
         Relation::Payment.def()
     }
 }
+
+/// Implements the relationship with VendorOrder entity
+impl Related<super::vendor_order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::VendorOrder.def()
+    }
+}
+
+/// Implements the relationship with OrderStatusHistory entity
+impl Related<super::order_status_history::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::StatusHistory.def()
+    }
+}
 #[derive(Serialize, Deserialize)]
 pub struct CreateOrder {
     /// Unique identifier for the order
@@ -143,13 +192,13 @@ pub struct CreateOrder {
     /// Email address of the customer
     pub customer_email: Option<String>,
     /// Phone number of the customer
-    pub customer_phone: String,
+    pub customer_phone: PhoneNumber,
     /// Delivery address for the order
     pub delivery_address: String,
     /// Current status of the ord
     pub status: String,
     /// Total amount of the order including all items and fees
-    pub total: f64,
+    pub total: Money,
     /// region where the order is to be delivered
     pub region: String,
     /// City where the order is to be delivered
@@ -162,7 +211,7 @@ pub struct CreateOrder {
 pub struct Items {
     pub product_id: Uuid,
     pub quantity: u32,
-    pub price: f64,
+    pub price: Money,
 }
 
 #[derive(Debug)]
@@ -170,13 +219,12 @@ pub struct NewOrder {
     pub user_id: Uuid,
     pub customer_name: String,
     pub customer_email: Option<String>,
-    pub customer_phone: String,
+    pub customer_phone: PhoneNumber,
     pub delivery_address: String,
-    pub status: String,
-    pub total: f64,
+    pub status: Status,
     pub items: Vec<OrderItemRequest>,
     pub city: String,
-    pub region: String
+    pub region: String,
 }
 
 /// Implements default behavior for active model operations
@@ -1,8 +1,9 @@
-use rust_decimal::Decimal;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::money::Money;
+
 /// OrderItem model representing individual items within an order
 /// This model tracks the quantity and price of each product in the order
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
@@ -15,10 +16,19 @@ pub struct Model {
     pub order_id: Uuid,
     /// Reference to the product being ordered
     pub product_id: Uuid,
+    /// Reference to the variant ordered, for products that have variants
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant_id: Option<Uuid>,
+    /// SKU of the variant at the time of order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sku: Option<String>,
     /// Price of the product at the time of order
-    pub price: f64,
+    pub price: Money,
     /// Quantity of the product ordered
     pub quantity: i32,
+    /// Vendor order fulfilling this line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vendor_order_id: Option<Uuid>,
 }
 
 /// Defines the relationships between OrderItem and other entities
@@ -44,6 +54,25 @@ pub enum Relation {
         on_delete = "NoAction"
     )]
     Product,
+    /// Relationship with the VendorOrder fulfilling this line
+    #[sea_orm(
+        belongs_to = "super::vendor_order::Entity",
+        from = "Column::VendorOrderId",
+        to = "super::vendor_order::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    VendorOrder,
+    /// Relationship with the ProductVariant being ordered
+    /// If the variant is deleted, the order item keeps its SKU
+    #[sea_orm(
+        belongs_to = "super::product_variant::Entity",
+        from = "Column::VariantId",
+        to = "super::product_variant::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Variant,
 }
 
 /// Implements the relationship with Order entity
@@ -60,6 +89,13 @@ impl Related<super::product::Entity> for Entity {
     }
 }
 
+/// Implements the relationship with VendorOrder entity
+impl Related<super::vendor_order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::VendorOrder.def()
+    }
+}
+
 pub struct OrderItem {
     /// Unique identifier for the order item
     pub id: Uuid,
@@ -70,7 +106,7 @@ pub struct OrderItem {
     /// Quantity of the product ordered
     pub quantity: i32,
     /// Price of the product at the time of order
-    pub price: Decimal,
+    pub price: Money,
 }
 /// Implements default behavior for active model operations
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{order::Status, user::UserRole};
+
+/// OrderStatusHistory model recording every status change of an order
+/// Rows are only ever appended, giving the full timeline of the order
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "order_status_history")]
+pub struct Model {
+    /// Unique identifier for the status change
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the order whose status changed
+    pub order_id: Uuid,
+    /// Status before the change, empty for the initial status of the order
+    pub from_status: Option<Status>,
+    /// Status after the change
+    pub to_status: Status,
+    /// User who made the change, empty for changes made by the system
+    pub changed_by: Option<Uuid>,
+    /// Role the change was made under, empty for changes made by the system
+    pub changed_by_role: Option<UserRole>,
+    /// Why the status was changed
+    pub reason: Option<String>,
+    /// Timestamp when the change happened
+    pub created_at: DateTime<Utc>,
+}
+
+/// Defines the relationships between OrderStatusHistory and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the Order this change belongs to
+    /// If the order is deleted, its history is also deleted
+    #[sea_orm(
+        belongs_to = "super::order::Entity",
+        from = "Column::OrderId",
+        to = "super::order::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Order,
+}
+
+/// Implements the relationship with Order entity
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+use super::phone::PhoneNumber;
+
+/// One-time code model representing a code texted to a phone
+/// A code proves its holder controls the phone for a single purpose
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "otp_codes")]
+pub struct Model {
+    /// Unique identifier for the code
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Phone number the code was sent to
+    pub phone: PhoneNumber,
+    /// What the code may be used for
+    pub purpose: OtpPurpose,
+    /// SHA-256 hash of the code
+    #[serde(skip_serializing)]
+    pub code_hash: String,
+    /// Number of wrong codes entered against this one
+    pub attempts: i32,
+    /// Timestamp after which the code can no longer be used
+    pub expires_at: DateTime<Utc>,
+    /// Timestamp when the code was used or replaced, empty while it is usable
+    pub consumed_at: Option<DateTime<Utc>>,
+    /// Timestamp when the code was sent
+    pub created_at: DateTime<Utc>,
+}
+
+/// Purpose of a one-time code, stored as lowercase text in `otp_codes.purpose`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum OtpPurpose {
+    /// Prove a newly registered user owns their phone
+    #[sea_orm(string_value = "phone_verification")]
+    PhoneVerification,
+    /// Allow a user who forgot their password to choose a new one
+    #[sea_orm(string_value = "password_reset")]
+    PasswordReset,
+    /// Confirm a signed in user owns the new phone number they asked for
+    #[sea_orm(string_value = "phone_change")]
+    PhoneChange,
+    /// Confirm a signed in user asked to change their email. The code goes
+    /// to their current phone, since no email is sent yet.
+    #[sea_orm(string_value = "email_change")]
+    EmailChange,
+}
+
+impl fmt::Display for OtpPurpose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
+/// One-time codes are not related to other entities; they are looked up by
+/// phone number so that they work before an account is verified
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
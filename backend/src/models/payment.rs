@@ -1,9 +1,11 @@
 use chrono::{DateTime, Utc};
-use rust_decimal::Decimal;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use uuid::Uuid;
 
+use super::money::Money;
+
 /// Payment model representing financial transactions in the marketplace
 /// This model tracks payment information and status for orders
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
@@ -15,20 +17,75 @@ pub struct Model {
     /// Reference to the order this payment is associated with
     pub order_id: Uuid,
     /// Amount of the payment
-    pub amount: f64,
-    /// Current status of the payment (e.g., "pending", "completed", "failed", "refunded")
-    pub status: String,
+    pub amount: Money,
+    /// Current status of the payment
+    pub status: PaymentStatus,
     /// Method used for payment (e.g., "credit_card", "mobile_money", "bank_transfer")
     pub payment_method: String,
     /// Additional payment details stored as JSON
     /// This can include transaction IDs, payment provider details, etc.
     pub payment_details: Option<Json>,
+    /// Transaction id assigned by the payment provider, used to match callbacks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_id: Option<String>,
     /// Timestamp when the payment was created
     pub created_at: DateTime<Utc>,
     /// Timestamp when the payment was last updated
     pub updated_at: DateTime<Utc>,
 }
 
+/// Payment status enum, stored as lowercase text in `payments.status`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "lowercase")]
+pub enum PaymentStatus {
+    /// Payment has been initiated and is awaiting confirmation from the provider
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    /// Provider confirmed that the funds were collected
+    #[sea_orm(string_value = "completed")]
+    Completed,
+    /// Provider reported that the payment failed
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    /// Payment was abandoned and can no longer be completed
+    #[sea_orm(string_value = "expired")]
+    Expired,
+    /// A refund was sent to the gateway and awaits its answer
+    #[sea_orm(string_value = "refunding")]
+    Refunding,
+    /// Collected funds were returned to the buyer
+    #[sea_orm(string_value = "refunded")]
+    Refunded,
+}
+
+impl Model {
+    /// Why a completed payment was not applied to its order, for instance
+    /// because the order was cancelled before the funds arrived. Such
+    /// payments are not posted to the ledger and are refunded.
+    pub fn unapplied_reason(&self) -> Option<&str> {
+        self.payment_details
+            .as_ref()?
+            .get("unapplied_reason")?
+            .as_str()
+    }
+}
+
+impl PaymentStatus {
+    /// Whether the payment has reached a final state
+    pub fn is_final(&self) -> bool {
+        !matches!(self, PaymentStatus::Pending)
+    }
+}
+
+impl fmt::Display for PaymentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
 /// Defines the relationships between Payment and other entities
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
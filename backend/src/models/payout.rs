@@ -0,0 +1,83 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+use super::{money::Money, phone::PhoneNumber};
+
+/// Payout model representing a vendor's request to withdraw their balance
+/// Approved payouts are posted to the ledger and debit the vendor's balance
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "payouts")]
+pub struct Model {
+    /// Unique identifier for the payout
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the vendor being paid
+    pub vendor_id: Uuid,
+    /// Amount requested by the vendor
+    pub amount: Money,
+    /// Current status of the payout
+    pub status: PayoutStatus,
+    /// Mobile money number the funds are sent to
+    pub phone: PhoneNumber,
+    /// Note left by the admin who reviewed the payout
+    pub note: Option<String>,
+    /// Admin who approved or rejected the payout
+    pub reviewed_by: Option<Uuid>,
+    /// Timestamp when the payout was reviewed
+    pub reviewed_at: Option<DateTime<Utc>>,
+    /// Timestamp when the payout was requested
+    pub created_at: DateTime<Utc>,
+    /// Timestamp when the payout was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Payout status enum, stored as lowercase text in `payouts.status`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "lowercase")]
+pub enum PayoutStatus {
+    /// Requested by the vendor and waiting for an admin
+    #[sea_orm(string_value = "requested")]
+    Requested,
+    /// Approved by an admin and posted to the ledger
+    #[sea_orm(string_value = "approved")]
+    Approved,
+    /// Rejected by an admin, the amount stays in the vendor's balance
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+}
+
+impl fmt::Display for PayoutStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
+/// Defines the relationships between Payout and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the vendor (User) being paid
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::VendorId",
+        to = "super::user::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Vendor,
+}
+
+/// Implements the relationship with User entity
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Vendor.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
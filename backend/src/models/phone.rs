@@ -0,0 +1,186 @@
+use std::{fmt, str::FromStr};
+
+use sea_orm::DeriveValueType;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+/// Country calling code of Cameroon
+const COUNTRY_CODE: &str = "237";
+
+/// A Cameroonian phone number, kept in E.164 form (`+2376XXXXXXXX`).
+///
+/// Numbers are parsed from the formats people actually type: with or
+/// without the `+237` or `00237` prefix, with spaces, dashes, dots or
+/// parentheses between the digits. JSON integers are accepted too, since
+/// older clients send the national number as one.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, DeriveValueType)]
+#[serde(transparent)]
+pub struct PhoneNumber(String);
+
+/// Mobile network operators whose numbers can pay with mobile money
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Operator {
+    Mtn,
+    Orange,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{0} is not a valid Cameroonian phone number")]
+pub struct InvalidPhoneNumber(String);
+
+impl PhoneNumber {
+    /// Normalise `input` to E.164
+    pub fn parse(input: &str) -> Result<Self, InvalidPhoneNumber> {
+        let invalid = || InvalidPhoneNumber(input.to_string());
+        let mut digits = String::with_capacity(input.len());
+        for c in input.chars() {
+            match c {
+                '0'..='9' => digits.push(c),
+                // A plus sign may only introduce the country code
+                '+' if digits.is_empty() => {}
+                ' ' | '-' | '.' | '(' | ')' => {}
+                _ => return Err(invalid()),
+            }
+        }
+        let digits = digits.strip_prefix("00").unwrap_or(&digits);
+        let national = match digits.len() {
+            9 => digits,
+            12 => digits.strip_prefix(COUNTRY_CODE).ok_or_else(invalid)?,
+            _ => return Err(invalid()),
+        };
+        // Mobile numbers start with 6, fixed lines with 2
+        if !national.starts_with(['2', '6']) {
+            return Err(invalid());
+        }
+
+        Ok(PhoneNumber(format!("+{}{}", COUNTRY_CODE, national)))
+    }
+
+    /// The number in E.164 form
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The nine digit national number, as payment providers expect it
+    pub fn national(&self) -> &str {
+        &self.0[1 + COUNTRY_CODE.len()..]
+    }
+
+    /// The operator the number was allocated to, if it can pay with
+    /// mobile money
+    pub fn operator(&self) -> Option<Operator> {
+        let prefix: u16 = self.national()[..3].parse().ok()?;
+        match prefix {
+            650..=654 | 670..=679 | 680..=684 => Some(Operator::Mtn),
+            655..=659 | 686..=689 | 690..=699 => Some(Operator::Orange),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PhoneNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for PhoneNumber {
+    type Err = InvalidPhoneNumber;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PhoneNumber::parse(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for PhoneNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PhoneNumberVisitor;
+
+        impl de::Visitor<'_> for PhoneNumberVisitor {
+            type Value = PhoneNumber;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a Cameroonian phone number")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<PhoneNumber, E> {
+                PhoneNumber::parse(v).map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<PhoneNumber, E> {
+                self.visit_str(&v.to_string())
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<PhoneNumber, E> {
+                self.visit_str(&v.to_string())
+            }
+        }
+
+        deserializer.deserialize_any(PhoneNumberVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_formats_normalise_to_e164() {
+        for input in [
+            "677777777",
+            "677 77 77 77",
+            "677-77-77-77",
+            "+237 677 77 77 77",
+            "(+237) 677.77.77.77",
+            "237677777777",
+            "00237677777777",
+        ] {
+            assert_eq!(
+                PhoneNumber::parse(input).unwrap().as_str(),
+                "+237677777777",
+                "{}",
+                input
+            );
+        }
+        assert_eq!(
+            PhoneNumber::parse("222 23 45 67").unwrap().national(),
+            "222234567"
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_numbers() {
+        for input in [
+            "",
+            "67777777",
+            "6777777777",
+            "+33 677777777",
+            "577777777",
+            "677a77777",
+            "677+777777",
+        ] {
+            assert!(PhoneNumber::parse(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_detects_operator() {
+        let operator = |input: &str| PhoneNumber::parse(input).unwrap().operator();
+        assert_eq!(operator("677777777"), Some(Operator::Mtn));
+        assert_eq!(operator("651234567"), Some(Operator::Mtn));
+        assert_eq!(operator("699999999"), Some(Operator::Orange));
+        assert_eq!(operator("655123456"), Some(Operator::Orange));
+        assert_eq!(operator("662345678"), None);
+        assert_eq!(operator("222234567"), None);
+    }
+
+    #[test]
+    fn test_json_accepts_strings_and_integers() {
+        let phone: PhoneNumber = serde_json::from_str("\"+237 677 77 77 77\"").unwrap();
+        assert_eq!(serde_json::to_string(&phone).unwrap(), "\"+237677777777\"");
+        assert_eq!(
+            serde_json::from_str::<PhoneNumber>("677777777").unwrap(),
+            phone
+        );
+        assert!(serde_json::from_str::<PhoneNumber>("\"12\"").is_err());
+    }
+}
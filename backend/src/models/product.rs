@@ -1,8 +1,11 @@
 use chrono::{DateTime, Utc};
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use uuid::Uuid;
 
+use super::money::Money;
+
 /// Product model representing items that can be sold in the marketplace
 /// This model includes all necessary fields for product information and tracking
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
@@ -18,26 +21,94 @@ pub struct Model {
     pub title: String,
     /// Detailed description of the product
     pub description: Option<String>,
-    /// Price of the product in the specified currency
-    pub price: f64,
-    /// Category the product belongs to (e.g., "Electronics", "Clothing")
-    pub category: Option<String>,
-    /// Quantity of the product available for sale
+    /// Price of the product in the specified currency; for a product with
+    /// variants, the price of its cheapest variant
+    pub price: Money,
+    /// Reference to the Category the product is listed under
+    pub category_id: Option<Uuid>,
+    /// Quantity of the product available for sale; for a product with
+    /// variants, the stock of every variant together
     pub quantity: i32,
     /// List of URLs to product images
     pub image_urls: Vec<String>,
-    /// Indicates if the product is currently active and available for sale
-    pub is_approved: bool,
+    /// Where the product stands in moderation; only approved products are
+    /// listed and sold
+    pub moderation_status: ModerationStatus,
+    /// Why moderators rejected or suspended the product
+    pub rejection_reason: Option<String>,
+    /// Note from the moderator to the vendor, such as what to fix
+    pub moderator_note: Option<String>,
     /// Refund policy for the product
     pub return_policy: Option<String>,
-    /// Indicates if the product is rejected
-    pub is_rejected: bool,
     /// Timestamp when the product was created
     pub created_at: DateTime<Utc>,
     /// Timestamp when the product was last updated
     pub updated_at: DateTime<Utc>,
 }
 
+/// Moderation status of a product, stored as snake_case text in
+/// `products.moderation_status`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationStatus {
+    /// Saved by the vendor but not submitted for review yet
+    #[sea_orm(string_value = "draft")]
+    Draft,
+    /// Waiting for a moderator
+    #[sea_orm(string_value = "pending_review")]
+    PendingReview,
+    /// Listed and available for sale
+    #[sea_orm(string_value = "approved")]
+    Approved,
+    /// Turned down by a moderator, the vendor may fix it and resubmit
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+    /// Taken off sale by a moderator after it was approved
+    #[sea_orm(string_value = "suspended")]
+    Suspended,
+}
+
+impl ModerationStatus {
+    /// Whether `self -> next` is a step of the moderation workflow:
+    /// draft -> pending_review -> approved or rejected. Rejected products
+    /// go back to review when resubmitted, approved products when their
+    /// content is edited. Moderators may suspend approved products and
+    /// reinstate suspended ones, or reverse a rejection.
+    pub fn can_transition_to(&self, next: ModerationStatus) -> bool {
+        use ModerationStatus::*;
+        matches!(
+            (self, next),
+            (Draft, PendingReview)
+                | (PendingReview, Approved)
+                | (PendingReview, Rejected)
+                | (Rejected, PendingReview)
+                | (Rejected, Approved)
+                | (Approved, PendingReview)
+                | (Approved, Suspended)
+                | (Suspended, PendingReview)
+                | (Suspended, Approved)
+        )
+    }
+
+    /// Whether the move to this status is a moderator's decision rather
+    /// than something the vendor does
+    pub fn is_decision(&self) -> bool {
+        matches!(
+            self,
+            ModerationStatus::Approved | ModerationStatus::Rejected | ModerationStatus::Suspended
+        )
+    }
+}
+
+impl fmt::Display for ModerationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
 /// Defines the relationships between Product and other entities
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
@@ -51,12 +122,34 @@ pub enum Relation {
         on_delete = "SetNull"
     )]
     User,
+    /// Relationship with the Category the product is listed under
+    /// A category that still holds products cannot be deleted
+    #[sea_orm(
+        belongs_to = "super::category::Entity",
+        from = "Column::CategoryId",
+        to = "super::category::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Restrict"
+    )]
+    Category,
     /// Relationship with CartItems that contain this product
     #[sea_orm(has_many = "super::cart_item::Entity")]
     CartItem,
     /// Relationship with OrderItems that contain this product
     #[sea_orm(has_many = "super::order_item::Entity")]
     OrderItem,
+    /// Relationship with the moderation history of the product
+    #[sea_orm(has_many = "super::product_moderation_history::Entity")]
+    ModerationHistory,
+    /// Relationship with the options the product comes in
+    #[sea_orm(has_many = "super::product_option::Entity")]
+    ProductOption,
+    /// Relationship with the variants of the product
+    #[sea_orm(has_many = "super::product_variant::Entity")]
+    Variant,
+    /// Relationship with the reviews of the product
+    #[sea_orm(has_many = "super::review::Entity")]
+    Review,
 }
 
 /// Implements the relationship with User entity
@@ -66,6 +159,13 @@ impl Related<super::user::Entity> for Entity {
     }
 }
 
+/// Implements the relationship with Category entity
+impl Related<super::category::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Category.def()
+    }
+}
+
 /// Implements the relationship with CartItem entity
 impl Related<super::cart_item::Entity> for Entity {
     fn to() -> RelationDef {
@@ -80,5 +180,33 @@ impl Related<super::order_item::Entity> for Entity {
     }
 }
 
+/// Implements the relationship with ProductModerationHistory entity
+impl Related<super::product_moderation_history::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ModerationHistory.def()
+    }
+}
+
+/// Implements the relationship with ProductOption entity
+impl Related<super::product_option::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ProductOption.def()
+    }
+}
+
+/// Implements the relationship with ProductVariant entity
+impl Related<super::product_variant::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Variant.def()
+    }
+}
+
+/// Implements the relationship with Review entity
+impl Related<super::review::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Review.def()
+    }
+}
+
 /// Implements default behavior for active model operations
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{product::ModerationStatus, user::UserRole};
+
+/// ProductModerationHistory model recording every moderation status change
+/// of a product. Rows are only ever appended, giving the full review
+/// timeline of the product.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "product_moderation_history")]
+pub struct Model {
+    /// Unique identifier for the status change
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the product whose status changed
+    pub product_id: Uuid,
+    /// Status before the change, empty for the initial status of the product
+    pub from_status: Option<ModerationStatus>,
+    /// Status after the change
+    pub to_status: ModerationStatus,
+    /// User who made the change
+    pub changed_by: Option<Uuid>,
+    /// Role the change was made under
+    pub changed_by_role: Option<UserRole>,
+    /// Why the status was changed, required for rejections and suspensions
+    pub reason: Option<String>,
+    /// Note from the moderator to the vendor
+    pub note: Option<String>,
+    /// Timestamp when the change happened
+    pub created_at: DateTime<Utc>,
+}
+
+/// Defines the relationships between ProductModerationHistory and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the Product this change belongs to
+    /// If the product is deleted, its history is also deleted
+    #[sea_orm(
+        belongs_to = "super::product::Entity",
+        from = "Column::ProductId",
+        to = "super::product::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Product,
+}
+
+/// Implements the relationship with Product entity
+impl Related<super::product::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Product.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
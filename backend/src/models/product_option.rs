@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// ProductOption model, one way a product comes in (e.g. "Size", "Color")
+/// Each variant of the product picks one of the option's values
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "product_options")]
+pub struct Model {
+    /// Unique identifier for the option
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the product the option belongs to
+    pub product_id: Uuid,
+    /// Name of the option, unique within the product
+    pub name: String,
+    /// Values the option can take, in display order (e.g. "S", "M", "L")
+    pub values: Vec<String>,
+    /// Position among the product's options, lowest first
+    pub position: i32,
+}
+
+/// Defines the relationships between ProductOption and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the Product the option belongs to
+    /// If the product is deleted, its options are also deleted
+    #[sea_orm(
+        belongs_to = "super::product::Entity",
+        from = "Column::ProductId",
+        to = "super::product::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Product,
+}
+
+/// Implements the relationship with Product entity
+impl Related<super::product::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Product.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
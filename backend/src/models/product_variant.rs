@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::prelude::*, FromJsonQueryResult};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::money::Money;
+
+/// ProductVariant model, one purchasable version of a product such as a
+/// size and color, with its own SKU, price, stock and images
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "product_variants")]
+pub struct Model {
+    /// Unique identifier for the variant
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the product the variant belongs to
+    pub product_id: Uuid,
+    /// Stock keeping unit chosen by the vendor, unique within the product
+    pub sku: String,
+    /// Value of each of the product's options for this variant
+    pub options: VariantOptions,
+    /// Price of the variant
+    pub price: Money,
+    /// Quantity of the variant available for sale
+    pub quantity: i32,
+    /// List of URLs to images of the variant
+    pub image_urls: Vec<String>,
+    /// Position among the product's variants, lowest first
+    pub position: i32,
+    /// Timestamp when the variant was created
+    pub created_at: DateTime<Utc>,
+    /// Timestamp when the variant was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Option values of a variant keyed by option name, stored as JSON in
+/// `product_variants.options`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+#[serde(transparent)]
+pub struct VariantOptions(pub BTreeMap<String, String>);
+
+/// Defines the relationships between ProductVariant and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the Product the variant belongs to
+    /// If the product is deleted, its variants are also deleted
+    #[sea_orm(
+        belongs_to = "super::product::Entity",
+        from = "Column::ProductId",
+        to = "super::product::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Product,
+}
+
+/// Implements the relationship with Product entity
+impl Related<super::product::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Product.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
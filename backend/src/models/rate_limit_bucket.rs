@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Rate limit bucket model, used when several instances share limits
+/// through the database
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "rate_limit_buckets")]
+pub struct Model {
+    /// What is limited, such as `auth:ip:203.0.113.7`
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub key: String,
+    /// Requests left in the bucket when it was last updated
+    pub tokens: f64,
+    /// Timestamp when the bucket was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Rate limit buckets are not related to other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,112 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// Review model, a buyer's rating of a product they received
+/// Each buyer reviews a product at most once; the vendor may reply once
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "reviews")]
+pub struct Model {
+    /// Unique identifier for the review
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the product reviewed
+    pub product_id: Uuid,
+    /// Reference to the buyer (User) who wrote the review
+    pub buyer_id: Uuid,
+    /// Reference to the delivered order the product was bought in
+    pub order_id: Option<Uuid>,
+    /// Number of stars, from 1 to 5
+    pub rating: i32,
+    /// What the buyer thought of the product
+    pub body: String,
+    /// List of URLs to photos taken by the buyer
+    pub image_urls: Vec<String>,
+    /// Whether the review is shown on the product
+    pub status: ReviewStatus,
+    /// Why a moderator hid the review
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden_reason: Option<String>,
+    /// The vendor's answer to the review
+    pub vendor_reply: Option<String>,
+    /// Timestamp when the vendor replied
+    pub vendor_replied_at: Option<DateTime<Utc>>,
+    /// Timestamp when the review was written
+    pub created_at: DateTime<Utc>,
+    /// Timestamp when the review was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Review status enum, stored as lowercase text in `reviews.status`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewStatus {
+    /// Shown on the product and counted in its rating
+    #[sea_orm(string_value = "published")]
+    Published,
+    /// Taken down by a moderator
+    #[sea_orm(string_value = "hidden")]
+    Hidden,
+}
+
+impl fmt::Display for ReviewStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
+/// Defines the relationships between Review and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the Product reviewed
+    /// If the product is deleted, its reviews are also deleted
+    #[sea_orm(
+        belongs_to = "super::product::Entity",
+        from = "Column::ProductId",
+        to = "super::product::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Product,
+    /// Relationship with the buyer (User) who wrote the review
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::BuyerId",
+        to = "super::user::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Buyer,
+    /// Relationship with the reports made about the review
+    #[sea_orm(has_many = "super::review_report::Entity")]
+    Report,
+}
+
+/// Implements the relationship with Product entity
+impl Related<super::product::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Product.def()
+    }
+}
+
+/// Implements the relationship with User entity
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Buyer.def()
+    }
+}
+
+/// Implements the relationship with ReviewReport entity
+impl Related<super::review_report::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Report.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// ReviewReport model, a user flagging a review for moderators
+/// A report stays open until a moderator acts on the review
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "review_reports")]
+pub struct Model {
+    /// Unique identifier for the report
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the review reported
+    pub review_id: Uuid,
+    /// Reference to the user who reported the review
+    pub reporter_id: Uuid,
+    /// What is wrong with the review
+    pub reason: String,
+    /// Timestamp when a moderator dealt with the review, empty while open
+    pub resolved_at: Option<DateTime<Utc>>,
+    /// Timestamp when the review was reported
+    pub created_at: DateTime<Utc>,
+}
+
+/// Defines the relationships between ReviewReport and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the Review reported
+    /// If the review is deleted, its reports are also deleted
+    #[sea_orm(
+        belongs_to = "super::review::Entity",
+        from = "Column::ReviewId",
+        to = "super::review::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Review,
+}
+
+/// Implements the relationship with Review entity
+impl Related<super::review::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Review.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Role model representing a named set of permissions given to staff
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "roles")]
+pub struct Model {
+    /// Unique identifier for the role
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Unique name of the role, such as `moderator` or `support`
+    pub name: String,
+    /// What staff holding the role are expected to do
+    pub description: Option<String>,
+    /// Timestamp when the role was created
+    pub created_at: DateTime<Utc>,
+}
+
+/// Defines the relationships between Role and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the permissions of the role
+    #[sea_orm(has_many = "super::role_permission::Entity")]
+    RolePermission,
+    /// Relationship with the staff holding the role
+    #[sea_orm(has_many = "super::role_assignment::Entity")]
+    RoleAssignment,
+}
+
+/// Implements the relationship with RolePermission entity
+impl Related<super::role_permission::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RolePermission.def()
+    }
+}
+
+/// Implements the relationship with RoleAssignment entity
+impl Related<super::role_assignment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RoleAssignment.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
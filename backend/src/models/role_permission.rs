@@ -0,0 +1,89 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// One permission granted by a role
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "role_permissions")]
+pub struct Model {
+    /// Reference to the role granting the permission
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub role_id: Uuid,
+    /// The permission granted
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub permission: Permission,
+}
+
+/// Something staff may be allowed to do, stored as snake_case text in
+/// `role_permissions.permission`. Admins hold every permission.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// List users and activate or deactivate their accounts
+    #[sea_orm(string_value = "manage_users")]
+    ManageUsers,
+    /// Approve and reject product listings
+    #[sea_orm(string_value = "moderate_products")]
+    ModerateProducts,
+    /// Hide and restore reported reviews
+    #[sea_orm(string_value = "moderate_reviews")]
+    ModerateReviews,
+    /// Create, edit, merge and delete product categories
+    #[sea_orm(string_value = "manage_categories")]
+    ManageCategories,
+    /// Review vendor applications and their ID documents
+    #[sea_orm(string_value = "review_vendors")]
+    ReviewVendors,
+    /// Look up any order
+    #[sea_orm(string_value = "view_orders")]
+    ViewOrders,
+    /// See sales metrics, payments and payouts
+    #[sea_orm(string_value = "view_finance")]
+    ViewFinance,
+    /// Approve or reject payouts and reconcile the ledger
+    #[sea_orm(string_value = "manage_payouts")]
+    ManagePayouts,
+    /// Refund payments
+    #[sea_orm(string_value = "issue_refunds")]
+    IssueRefunds,
+    /// Create roles, create staff accounts and assign roles to them
+    #[sea_orm(string_value = "manage_roles")]
+    ManageRoles,
+    /// Browse the audit log
+    #[sea_orm(string_value = "view_audit_log")]
+    ViewAuditLog,
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
+/// Defines the relationships between RolePermission and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the role granting the permission
+    #[sea_orm(
+        belongs_to = "super::role::Entity",
+        from = "Column::RoleId",
+        to = "super::role::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Role,
+}
+
+/// Implements the relationship with Role entity
+impl Related<super::role::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Role.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
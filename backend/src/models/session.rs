@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Session model representing a signed-in device of a user
+/// Each session holds one refresh token, replaced every time it is used
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "sessions")]
+pub struct Model {
+    /// Unique identifier for the session, carried by its access tokens
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the user who signed in
+    pub user_id: Uuid,
+    /// SHA-256 hash of the current refresh token
+    #[serde(skip_serializing)]
+    pub refresh_token_hash: String,
+    /// SHA-256 hash of the refresh token it replaced, to detect reuse
+    #[serde(skip_serializing)]
+    pub previous_token_hash: Option<String>,
+    /// User agent of the device that signed in
+    pub user_agent: Option<String>,
+    /// Timestamp after which the refresh token can no longer be used
+    pub expires_at: DateTime<Utc>,
+    /// Timestamp when the session was revoked, empty while it is active
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// Timestamp when the refresh token was last used
+    pub last_used_at: DateTime<Utc>,
+    /// Timestamp when the user signed in
+    pub created_at: DateTime<Utc>,
+}
+
+impl Model {
+    /// Whether the session can still be used at `now`
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
+
+/// Defines the relationships between Session and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the User who owns the session
+    /// If the user is deleted, their sessions are also deleted
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+/// Implements the relationship with User entity
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
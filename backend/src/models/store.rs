@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{entity::prelude::*, FromJsonQueryResult};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::phone::PhoneNumber;
+
+/// Store model representing a vendor's public shop
+/// Each vendor owns at most one store, which presents them and their catalogue
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "stores")]
+pub struct Model {
+    /// Unique identifier for the store
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the vendor (User) who owns the store
+    #[sea_orm(unique)]
+    pub owner_id: Uuid,
+    /// Name the store is shown under
+    pub name: String,
+    /// Unique lowercase name used in the store's public URL
+    #[sea_orm(unique)]
+    pub slug: String,
+    /// Uploaded logo image
+    pub logo_url: Option<String>,
+    /// Uploaded banner image shown at the top of the shop page
+    pub banner_url: Option<String>,
+    /// Short presentation of what the store sells
+    pub description: Option<String>,
+    /// Region of Cameroon the store is based in
+    pub region: Option<Region>,
+    /// City or town the store is based in
+    pub city: Option<String>,
+    /// The maker's story, how and where their products are made
+    pub artisan_story: Option<String>,
+    /// How buyers may contact the store
+    pub contact_preferences: ContactPreferences,
+    /// Timestamp when the store was created
+    pub created_at: DateTime<Utc>,
+    /// Timestamp when the store was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The ten regions of Cameroon, stored as snake_case text in `stores.region`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum Region {
+    #[sea_orm(string_value = "adamawa")]
+    Adamawa,
+    #[sea_orm(string_value = "centre")]
+    Centre,
+    #[sea_orm(string_value = "east")]
+    East,
+    #[sea_orm(string_value = "far_north")]
+    FarNorth,
+    #[sea_orm(string_value = "littoral")]
+    Littoral,
+    #[sea_orm(string_value = "north")]
+    North,
+    #[sea_orm(string_value = "northwest")]
+    Northwest,
+    #[sea_orm(string_value = "south")]
+    South,
+    #[sea_orm(string_value = "southwest")]
+    Southwest,
+    #[sea_orm(string_value = "west")]
+    West,
+}
+
+/// Which of the owner's details appear on the public shop page, stored as
+/// JSON in `stores.contact_preferences`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+pub struct ContactPreferences {
+    /// Show the owner's phone number
+    #[serde(default)]
+    pub show_phone: bool,
+    /// Show the owner's email address
+    #[serde(default)]
+    pub show_email: bool,
+    /// WhatsApp number buyers can message, if the store uses one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub whatsapp: Option<PhoneNumber>,
+}
+
+/// Defines the relationships between Store and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the vendor (User) who owns the store
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::OwnerId",
+        to = "super::user::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Owner,
+}
+
+/// Implements the relationship with User entity
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Owner.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
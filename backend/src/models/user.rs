@@ -3,6 +3,8 @@ use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::phone::PhoneNumber;
+
 // Define the UserRole enum
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "user_role")]
@@ -13,6 +15,24 @@ pub enum UserRole {
     Vendor,
     #[sea_orm(string_value = "Buyer")]
     Buyer,
+    /// Platform staff, allowed whatever their assigned roles permit
+    #[sea_orm(string_value = "Staff")]
+    Staff,
+}
+
+/// Language the user wants the app and their messages in, stored as
+/// lowercase text in `users.preferred_language`
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    #[sea_orm(string_value = "fr")]
+    Fr,
+    #[sea_orm(string_value = "en")]
+    En,
 }
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
@@ -26,7 +46,28 @@ pub struct Model {
     pub role: UserRole,
     pub full_name: String,
     pub is_active: bool,
-    pub phone: i32,
+    pub phone: PhoneNumber,
+    /// Timestamp when the user proved they own the phone, empty until then
+    pub phone_verified_at: Option<DateTime<Utc>>,
+    /// Failed logins since the last successful one or the last lockout
+    #[serde(skip_serializing)]
+    pub failed_login_attempts: i32,
+    /// Timestamp until which logins are refused after repeated failures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked_until: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avatar_url: Option<String>,
+    pub preferred_language: Language,
+    /// Email the user asked to change to, until they confirm it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_email: Option<String>,
+    /// Phone number the user asked to change to, until they confirm it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_phone: Option<PhoneNumber>,
+    /// Timestamp when an admin approved the vendor's application, empty
+    /// for buyers and vendors who cannot list products yet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vendor_verified_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
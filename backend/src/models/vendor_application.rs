@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// Vendor application model representing a vendor's request to sell
+/// An admin checks the business details and ID document before approving it
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "vendor_applications")]
+pub struct Model {
+    /// Unique identifier for the application
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the vendor (User) applying
+    pub user_id: Uuid,
+    /// Name the business trades under
+    pub business_name: String,
+    /// Trade and personal property credit registry (RCCM) number
+    pub trade_registry_number: String,
+    /// Taxpayer identification number (NIU)
+    pub taxpayer_number: String,
+    /// Object name of the ID document in the private documents bucket
+    pub id_document: String,
+    /// Current status of the application
+    pub status: VendorApplicationStatus,
+    /// Reason given to the vendor when the application is rejected
+    pub rejection_reason: Option<String>,
+    /// Admin who approved or rejected the application
+    pub reviewed_by: Option<Uuid>,
+    /// Timestamp when the application was reviewed
+    pub reviewed_at: Option<DateTime<Utc>>,
+    /// Timestamp when the application was submitted
+    pub created_at: DateTime<Utc>,
+    /// Timestamp when the application was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Vendor application status enum, stored as lowercase text in
+/// `vendor_applications.status`
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "lowercase")]
+pub enum VendorApplicationStatus {
+    /// Submitted by the vendor and waiting for an admin
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    /// Approved by an admin, the vendor can list products
+    #[sea_orm(string_value = "approved")]
+    Approved,
+    /// Rejected by an admin, the vendor may apply again
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+}
+
+impl fmt::Display for VendorApplicationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
+/// Defines the relationships between VendorApplication and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the vendor (User) applying
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    User,
+}
+
+/// Implements the relationship with User entity
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,117 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{money::Money, order::Status, order_item, phone::PhoneNumber};
+
+/// VendorOrder model representing the part of an order fulfilled by one seller
+/// The buyer pays for the whole order once; each vendor ships their own part
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "vendor_orders")]
+pub struct Model {
+    /// Unique identifier for the vendor order
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the parent order paid by the buyer
+    pub order_id: Uuid,
+    /// Reference to the seller fulfilling this part of the order
+    pub vendor_id: Uuid,
+    /// Fulfilment status of this part of the order
+    pub status: Status,
+    /// Sum of the lines fulfilled by this vendor
+    pub subtotal: Money,
+    /// Company carrying the parcel, once shipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub carrier: Option<String>,
+    /// Carrier tracking number, once shipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracking_number: Option<String>,
+    /// Timestamp when the vendor shipped the parcel
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shipped_at: Option<DateTime<Utc>>,
+    /// Timestamp when the parcel was delivered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivered_at: Option<DateTime<Utc>>,
+    /// Timestamp when the vendor order was created
+    pub created_at: DateTime<Utc>,
+    /// Timestamp when the vendor order was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Defines the relationships between VendorOrder and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the parent Order
+    /// If the order is deleted, its vendor orders are also deleted
+    #[sea_orm(
+        belongs_to = "super::order::Entity",
+        from = "Column::OrderId",
+        to = "super::order::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Order,
+    /// Relationship with the User selling these items
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::VendorId",
+        to = "super::user::Column::Id",
+        on_update = "NoAction",
+        on_delete = "NoAction"
+    )]
+    Vendor,
+    /// Relationship with the OrderItems fulfilled by this vendor
+    #[sea_orm(has_many = "super::order_item::Entity")]
+    OrderItem,
+}
+
+/// Implements the relationship with Order entity
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+
+/// Implements the relationship with OrderItem entity
+impl Related<super::order_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::OrderItem.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
+
+/// Where the parent order must be delivered
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ShippingAddress {
+    pub customer_name: String,
+    pub customer_phone: PhoneNumber,
+    pub delivery_address: String,
+    pub city: String,
+    pub region: String,
+}
+
+impl From<&super::order::Model> for ShippingAddress {
+    fn from(order: &super::order::Model) -> Self {
+        Self {
+            customer_name: order.customer_name.clone(),
+            customer_phone: order.customer_phone.clone(),
+            delivery_address: order.delivery_address.clone(),
+            city: order.city.clone(),
+            region: order.region.clone(),
+        }
+    }
+}
+
+/// A vendor order with the lines it fulfils
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct VendorOrderDetails {
+    #[serde(flatten)]
+    pub vendor_order: Model,
+    pub items: Vec<order_item::Model>,
+    /// Delivery details, included in the vendor's fulfilment view
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shipping: Option<ShippingAddress>,
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Wishlist model, a named list of products a user wants or saved for later
+/// A list may be shared with anyone holding its link
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "wishlists")]
+pub struct Model {
+    /// Unique identifier for the wishlist
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the user who owns the list
+    pub user_id: Uuid,
+    /// Name of the list, unique among the user's lists
+    pub name: String,
+    /// Token of the list's share link, empty while the list is private
+    #[sea_orm(unique)]
+    pub share_token: Option<String>,
+    /// Timestamp when the list was created
+    pub created_at: DateTime<Utc>,
+    /// Timestamp when the list was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Defines the relationships between Wishlist and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the User who owns the list
+    /// If the user is deleted, their lists are also deleted
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    User,
+    /// Relationship with the items on the list
+    #[sea_orm(has_many = "super::wishlist_item::Entity")]
+    WishlistItem,
+}
+
+/// Implements the relationship with User entity
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+/// Implements the relationship with WishlistItem entity
+impl Related<super::wishlist_item::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WishlistItem.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
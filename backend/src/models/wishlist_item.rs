@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::money::Money;
+
+/// WishlistItem model, a product or one of its variants on a wishlist
+/// The price and stock last told to the owner are kept so that a price drop
+/// or a restock is only announced once
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "wishlist_items")]
+pub struct Model {
+    /// Unique identifier for the wishlist item
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    /// Reference to the parent wishlist
+    pub wishlist_id: Uuid,
+    /// Reference to the product on the list
+    pub product_id: Uuid,
+    /// Reference to the chosen variant, for products that have variants
+    pub variant_id: Option<Uuid>,
+    /// Quantity to put in the cart when the item is moved there
+    pub quantity: i32,
+    /// Price of the product or variant when the owner last heard of it
+    pub notified_price: Money,
+    /// Whether the product or variant was in stock when the owner last
+    /// heard of it
+    pub notified_in_stock: bool,
+    /// Timestamp when the item was added to the list
+    pub created_at: DateTime<Utc>,
+}
+
+/// Defines the relationships between WishlistItem and other entities
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Relationship with the parent Wishlist
+    /// If the list is deleted, its items are also deleted
+    #[sea_orm(
+        belongs_to = "super::wishlist::Entity",
+        from = "Column::WishlistId",
+        to = "super::wishlist::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Wishlist,
+    /// Relationship with the Product on the list
+    /// If the product is deleted, the item is also deleted
+    #[sea_orm(
+        belongs_to = "super::product::Entity",
+        from = "Column::ProductId",
+        to = "super::product::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Product,
+    /// Relationship with the chosen ProductVariant
+    /// If the variant is deleted, the item is also deleted
+    #[sea_orm(
+        belongs_to = "super::product_variant::Entity",
+        from = "Column::VariantId",
+        to = "super::product_variant::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Variant,
+}
+
+/// Implements the relationship with Wishlist entity
+impl Related<super::wishlist::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Wishlist.def()
+    }
+}
+
+/// Implements the relationship with Product entity
+impl Related<super::product::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Product.def()
+    }
+}
+
+/// Implements the relationship with ProductVariant entity
+impl Related<super::product_variant::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Variant.def()
+    }
+}
+
+/// Implements default behavior for active model operations
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,40 @@
+use crate::{
+    middleware::permission::require_permission, models::role_permission::Permission,
+    services::audit::AuditEventFilter, state::AppState, utils::shared::ApiResponse,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    middleware::from_fn,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use tracing::error;
+
+pub fn config() -> Router<AppState> {
+    Router::new()
+        .route("/api/admins/audit-events", get(list_audit_events))
+        .route_layer(from_fn(|req, next| {
+            require_permission(Permission::ViewAuditLog, req, next)
+        }))
+}
+
+/// Audit events, newest first, filtered by the query parameters
+#[axum::debug_handler]
+async fn list_audit_events(
+    State(state): State<AppState>,
+    Query(filter): Query<AuditEventFilter>,
+) -> impl IntoResponse {
+    match state.audit_service.list_events(filter).await {
+        Ok(events) => Json(ApiResponse::success(events, "Audit events retrieved")).into_response(),
+        Err(e) => {
+            error!("Could not retrieve audit events: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("Could not retrieve audit events")),
+            )
+                .into_response()
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    middleware::auth::AuthUser, models::cart_item::CartItem, state::AppState,
-    utils::shared::ApiResponse,
+    middleware::auth::AuthUser, models::cart_item::CartItem, services::errors::ServiceError,
+    state::AppState, utils::shared::ApiResponse,
 };
 use axum::{
     extract::{Path, State},
@@ -38,6 +38,9 @@ async fn get_cart(
 #[derive(Deserialize)]
 pub struct AddToCartRequest {
     product_id: Uuid,
+    /// Required for products that have variants
+    #[serde(default)]
+    variant_id: Option<Uuid>,
     quantity: i32,
 }
 
@@ -55,6 +58,7 @@ async fn add_to_cart(
                 let cart_item = CartItem {
                     cart_id: cart.id,
                     product_id: payload.product_id,
+                    variant_id: payload.variant_id,
                     quantity: payload.quantity,
                 };
                 match state
@@ -63,6 +67,11 @@ async fn add_to_cart(
                     .await
                 {
                     Ok(_) => Json(ApiResponse::success((), "Item added to cart")).into_response(),
+                    Err(ServiceError::Validation(msg)) => (
+                        StatusCode::BAD_REQUEST,
+                        Json(ApiResponse::<()>::error(&msg)),
+                    )
+                        .into_response(),
                     Err(e) => {
                         tracing::error!("could not add item to cart: {}", e.to_string());
                         (
@@ -75,7 +84,7 @@ async fn add_to_cart(
             } else {
                 // Cart does not exist, create a new one
                 let new_cart = state.cart_service.get_or_create_cart(auth.id.clone()).await;
-                return match new_cart {
+                match new_cart {
                     Ok(_) => Json(ApiResponse::success((), "New cart created")).into_response(),
                     Err(e) => {
                         tracing::error!("could not create new cart: {}", e.to_string());
@@ -85,7 +94,7 @@ async fn add_to_cart(
                         )
                             .into_response()
                     }
-                };
+                }
             }
         }
         Err(e) => {
@@ -99,11 +108,6 @@ async fn add_to_cart(
     }
 }
 
-#[derive(Deserialize)]
-pub struct UpdateCartRequest {
-    quantity: i32,
-}
-
 #[axum::debug_handler]
 async fn remove_from_cart(
     State(state): State<AppState>,
@@ -0,0 +1,186 @@
+use crate::{
+    middleware::{
+        permission::require_permission,
+        rate_limit::{rate_limit, RateLimitGroup},
+    },
+    models::{
+        audit_event::{AuditAction, AuditEntity},
+        role_permission::Permission,
+    },
+    services::{
+        audit::{AuditActor, AuditRecord},
+        category::{CreateCategory, UpdateCategory},
+    },
+    state::AppState,
+    utils::shared::{error_response, ApiResponse},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    middleware::from_fn,
+    response::IntoResponse,
+    routing::{get, patch, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Taxonomy upkeep for staff who may manage categories
+pub fn config() -> Router<AppState> {
+    Router::new()
+        .route("/api/admins/categories", post(create_category))
+        .route(
+            "/api/admins/categories/:id",
+            patch(update_category).delete(delete_category),
+        )
+        .route("/api/admins/categories/:id/merge", post(merge_category))
+        .route_layer(from_fn(|req, next| {
+            require_permission(Permission::ManageCategories, req, next)
+        }))
+}
+
+/// Category browsing, open to buyers who are not signed in. It is mounted
+/// outside the auth layer, so it is rate limited here.
+pub fn public_config() -> Router<AppState> {
+    Router::new()
+        .route("/api/categories", get(list_categories))
+        .route("/api/categories/:slug", get(get_category_page))
+        .route_layer(from_fn(|req, next| {
+            rate_limit(RateLimitGroup::Api, req, next)
+        }))
+}
+
+#[derive(Deserialize)]
+pub struct MergeCategoryRequest {
+    /// Category that takes over the products and subcategories
+    into: Uuid,
+}
+
+/// The category tree with the number of approved products in each subtree
+#[axum::debug_handler]
+async fn list_categories(State(state): State<AppState>) -> impl IntoResponse {
+    match state.category_service.tree().await {
+        Ok(categories) => {
+            Json(ApiResponse::success(categories, "Categories retrieved")).into_response()
+        }
+        Err(e) => error_response(e, "Could not retrieve categories"),
+    }
+}
+
+/// One category with its subcategories and the categories above it
+#[axum::debug_handler]
+async fn get_category_page(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> impl IntoResponse {
+    match state.category_service.get_page(&slug).await {
+        Ok(page) => Json(ApiResponse::success(page, "Category retrieved")).into_response(),
+        Err(e) => error_response(e, "Could not retrieve category"),
+    }
+}
+
+#[axum::debug_handler]
+async fn create_category(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Json(data): Json<CreateCategory>,
+) -> impl IntoResponse {
+    match state.category_service.create_category(data).await {
+        Ok(category) => {
+            let record = AuditRecord::new(
+                AuditAction::CategoryCreated,
+                AuditEntity::Category,
+                category.id,
+            )
+            .after(&category);
+            state.audit_service.record_or_log(&actor, record).await;
+            (
+                StatusCode::CREATED,
+                Json(ApiResponse::success(category, "Category created")),
+            )
+                .into_response()
+        }
+        Err(e) => error_response(e, "Could not create category"),
+    }
+}
+
+#[axum::debug_handler]
+async fn update_category(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(category_id): Path<Uuid>,
+    Json(data): Json<UpdateCategory>,
+) -> impl IntoResponse {
+    let before = match state.category_service.get_category(category_id).await {
+        Ok(category) => category,
+        Err(e) => return error_response(e, "Could not update category"),
+    };
+    match state
+        .category_service
+        .update_category(category_id, data)
+        .await
+    {
+        Ok(category) => {
+            let record = AuditRecord::new(
+                AuditAction::CategoryUpdated,
+                AuditEntity::Category,
+                category.id,
+            )
+            .before(&before)
+            .after(&category);
+            state.audit_service.record_or_log(&actor, record).await;
+            Json(ApiResponse::success(category, "Category updated")).into_response()
+        }
+        Err(e) => error_response(e, "Could not update category"),
+    }
+}
+
+/// Delete a category that holds no products or subcategories
+#[axum::debug_handler]
+async fn delete_category(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(category_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match state.category_service.delete_category(category_id).await {
+        Ok(category) => {
+            let record = AuditRecord::new(
+                AuditAction::CategoryDeleted,
+                AuditEntity::Category,
+                category.id,
+            )
+            .before(&category);
+            state.audit_service.record_or_log(&actor, record).await;
+            Json(ApiResponse::<()>::success((), "Category deleted")).into_response()
+        }
+        Err(e) => error_response(e, "Could not delete category"),
+    }
+}
+
+/// Fold a category into another, moving its products and subcategories
+#[axum::debug_handler]
+async fn merge_category(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(category_id): Path<Uuid>,
+    Json(request): Json<MergeCategoryRequest>,
+) -> impl IntoResponse {
+    match state
+        .category_service
+        .merge_category(category_id, request.into)
+        .await
+    {
+        Ok((merged, target)) => {
+            let record = AuditRecord::new(
+                AuditAction::CategoryMerged,
+                AuditEntity::Category,
+                merged.id,
+            )
+            .before(&merged)
+            .after(&target);
+            state.audit_service.record_or_log(&actor, record).await;
+            Json(ApiResponse::success(target, "Category merged")).into_response()
+        }
+        Err(e) => error_response(e, "Could not merge category"),
+    }
+}
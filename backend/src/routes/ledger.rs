@@ -0,0 +1,232 @@
+use crate::{
+    middleware::auth::AuthUser,
+    models::{
+        audit_event::{AuditAction, AuditEntity},
+        money::{self, Money},
+        payout::PayoutStatus,
+        phone::PhoneNumber,
+        role_permission::Permission,
+        user::UserRole,
+    },
+    services::audit::{AuditActor, AuditRecord},
+    state::AppState,
+    utils::{
+        policy::user_id,
+        rbac::{require_permission, require_role},
+        shared::{error_response, ApiResponse},
+    },
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+pub fn config() -> Router<AppState> {
+    Router::new()
+        .route("/api/vendor/balance", get(get_balance))
+        .route("/api/vendor/statement", get(get_statement))
+        .route("/api/payouts", get(list_payouts))
+        .route("/api/payouts", post(request_payout))
+        .route("/api/payouts/:id/approve", post(approve_payout))
+        .route("/api/payouts/:id/reject", post(reject_payout))
+        .route("/api/ledger/reconciliation", get(reconcile_ledger))
+}
+
+#[derive(Deserialize)]
+pub struct ListPayoutsQuery {
+    vendor_id: Option<Uuid>,
+    status: Option<PayoutStatus>,
+}
+
+#[derive(Deserialize)]
+pub struct RequestPayoutRequest {
+    #[serde(deserialize_with = "money::non_negative")]
+    amount: Money,
+    phone: PhoneNumber,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ReviewPayoutRequest {
+    note: Option<String>,
+}
+
+/// Check the caller has one of `roles` and return their id
+fn authorize(auth: &AuthUser, roles: &[UserRole]) -> Result<Uuid, (StatusCode, &'static str)> {
+    require_role(auth, roles).and_then(|_| user_id(auth))
+}
+
+/// Check the caller holds `permission` and return their id
+fn authorize_staff(
+    auth: &AuthUser,
+    permission: Permission,
+) -> Result<Uuid, (StatusCode, &'static str)> {
+    require_permission(auth, permission).and_then(|_| user_id(auth))
+}
+
+#[axum::debug_handler]
+async fn get_balance(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let vendor_id = match authorize(&auth, &[UserRole::Vendor]) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state.ledger_service.get_vendor_balance(vendor_id).await {
+        Ok(balance) => Json(ApiResponse::success(balance, "Balance retrieved")).into_response(),
+        Err(e) => error_response(e, "Could not retrieve balance"),
+    }
+}
+
+#[axum::debug_handler]
+async fn get_statement(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let vendor_id = match authorize(&auth, &[UserRole::Vendor]) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state.ledger_service.get_vendor_statement(vendor_id).await {
+        Ok(lines) => Json(ApiResponse::success(lines, "Statement retrieved")).into_response(),
+        Err(e) => error_response(e, "Could not retrieve statement"),
+    }
+}
+
+/// Vendors see their own payouts, staff who may view finance see every payout
+#[axum::debug_handler]
+async fn list_payouts(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Query(params): Query<ListPayoutsQuery>,
+) -> impl IntoResponse {
+    let vendor_id = if auth.can(Permission::ViewFinance) {
+        params.vendor_id
+    } else {
+        match authorize(&auth, &[UserRole::Vendor]) {
+            Ok(id) => Some(id),
+            Err((status, msg)) => {
+                return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+            }
+        }
+    };
+    match state
+        .ledger_service
+        .list_payouts(vendor_id, params.status)
+        .await
+    {
+        Ok(payouts) => Json(ApiResponse::success(payouts, "Payouts retrieved")).into_response(),
+        Err(e) => error_response(e, "Could not retrieve payouts"),
+    }
+}
+
+#[axum::debug_handler]
+async fn request_payout(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Json(payload): Json<RequestPayoutRequest>,
+) -> impl IntoResponse {
+    let vendor_id = match authorize(&auth, &[UserRole::Vendor]) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .ledger_service
+        .request_payout(vendor_id, payload.amount, payload.phone)
+        .await
+    {
+        Ok(payout) => (
+            StatusCode::CREATED,
+            Json(ApiResponse::success(payout, "Payout requested")),
+        )
+            .into_response(),
+        Err(e) => error_response(e, "Could not request payout"),
+    }
+}
+
+#[axum::debug_handler]
+async fn approve_payout(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    actor: AuditActor,
+    Path(payout_id): Path<Uuid>,
+    payload: Option<Json<ReviewPayoutRequest>>,
+) -> impl IntoResponse {
+    review_payout(state, auth, actor, payout_id, true, payload).await
+}
+
+#[axum::debug_handler]
+async fn reject_payout(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    actor: AuditActor,
+    Path(payout_id): Path<Uuid>,
+    payload: Option<Json<ReviewPayoutRequest>>,
+) -> impl IntoResponse {
+    review_payout(state, auth, actor, payout_id, false, payload).await
+}
+
+async fn review_payout(
+    state: AppState,
+    auth: AuthUser,
+    actor: AuditActor,
+    payout_id: Uuid,
+    approve: bool,
+    payload: Option<Json<ReviewPayoutRequest>>,
+) -> Response {
+    let admin_id = match authorize_staff(&auth, Permission::ManagePayouts) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    let before = match state.ledger_service.get_payout(payout_id).await {
+        Ok(payout) => payout,
+        Err(e) => return error_response(e, "Could not review payout"),
+    };
+    let note = payload.unwrap_or_default().0.note;
+    match state
+        .ledger_service
+        .review_payout(payout_id, admin_id, approve, note)
+        .await
+    {
+        Ok(payout) => {
+            let action = if approve {
+                AuditAction::PayoutApproved
+            } else {
+                AuditAction::PayoutRejected
+            };
+            let record = AuditRecord::new(action, AuditEntity::Payout, payout.id)
+                .before(&before)
+                .after(&payout);
+            state.audit_service.record_or_log(&actor, record).await;
+            Json(ApiResponse::success(payout, "Payout reviewed")).into_response()
+        }
+        Err(e) => error_response(e, "Could not review payout"),
+    }
+}
+
+#[axum::debug_handler]
+async fn reconcile_ledger(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+) -> impl IntoResponse {
+    if let Err((status, msg)) = authorize_staff(&auth, Permission::ManagePayouts) {
+        return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+    }
+    match state.ledger_service.reconcile().await {
+        Ok(report) => Json(ApiResponse::success(report, "Ledger reconciled")).into_response(),
+        Err(e) => error_response(e, "Could not reconcile ledger"),
+    }
+}
@@ -1,7 +1,18 @@
+pub mod audit;
 pub mod cart;
+pub mod category;
+pub mod ledger;
+pub mod moderation;
 pub mod payment;
 pub mod product;
+pub mod review;
+pub mod role;
+pub mod search;
+pub mod store;
 pub mod user;
+pub mod variant;
+pub mod vendor_application;
+pub mod wishlist;
 
 pub mod admin;
 pub(crate) mod error;
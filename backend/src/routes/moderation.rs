@@ -0,0 +1,275 @@
+use crate::{
+    middleware::{auth::AuthUser, permission::require_permission},
+    models::{
+        audit_event::{AuditAction, AuditEntity},
+        product::ModerationStatus,
+        role_permission::Permission,
+        user::UserRole,
+    },
+    services::{
+        audit::{AuditActor, AuditRecord},
+        errors::ServiceError,
+        moderation::{ModerationActor, ModerationDecision},
+    },
+    state::AppState,
+    utils::{
+        policy::user_id,
+        rbac::require_role,
+        shared::{error_response, ApiResponse},
+    },
+};
+use axum::{
+    extract::{Path, State},
+    middleware::from_fn,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Product moderation: the review queue and decisions for moderators, and
+/// resubmission and history for the vendor who makes the product
+pub fn config() -> Router<AppState> {
+    let review = Router::new()
+        .route("/api/admins/products/pending", get(list_pending_products))
+        .route("/api/admins/products/:id", put(moderate_product))
+        .route("/api/admins/products/:id/approve", post(approve_product))
+        .route("/api/admins/products/:id/reject", put(reject_product))
+        .route("/api/admins/products/:id/suspend", post(suspend_product))
+        .route(
+            "/api/admins/products/:id/moderation-history",
+            get(get_moderation_history),
+        )
+        .route_layer(from_fn(|req, next| {
+            require_permission(Permission::ModerateProducts, req, next)
+        }));
+
+    Router::new()
+        .route("/api/products/:id/submit", post(submit_product))
+        .route(
+            "/api/products/:id/moderation-history",
+            get(get_my_moderation_history),
+        )
+        .merge(review)
+}
+
+/// Optional explanation sent with an approval, rejection or suspension
+#[derive(Deserialize, Default)]
+pub struct ModerationRequest {
+    reason: Option<String>,
+    note: Option<String>,
+}
+
+/// Products waiting for a moderator, oldest first
+#[axum::debug_handler]
+async fn list_pending_products(State(state): State<AppState>) -> impl IntoResponse {
+    match state.moderation_service.list_pending().await {
+        Ok(products) => Json(ApiResponse::success(
+            products,
+            "Pending products retrieved successfully",
+        ))
+        .into_response(),
+        Err(e) => error_response(e, "Could not retrieve pending products"),
+    }
+}
+
+/// Approve, reject or suspend a product, with the status in the body
+#[axum::debug_handler]
+async fn moderate_product(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    actor: AuditActor,
+    Path(product_id): Path<Uuid>,
+    Json(decision): Json<ModerationDecision>,
+) -> impl IntoResponse {
+    decide(state, auth, actor, product_id, decision).await
+}
+
+#[axum::debug_handler]
+async fn approve_product(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    actor: AuditActor,
+    Path(product_id): Path<Uuid>,
+    payload: Option<Json<ModerationRequest>>,
+) -> impl IntoResponse {
+    let request = payload.unwrap_or_default().0;
+    let decision = ModerationDecision {
+        status: ModerationStatus::Approved,
+        reason: request.reason,
+        note: request.note,
+    };
+    decide(state, auth, actor, product_id, decision).await
+}
+
+#[axum::debug_handler]
+async fn reject_product(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    actor: AuditActor,
+    Path(product_id): Path<Uuid>,
+    payload: Option<Json<ModerationRequest>>,
+) -> impl IntoResponse {
+    let request = payload.unwrap_or_default().0;
+    let decision = ModerationDecision {
+        status: ModerationStatus::Rejected,
+        reason: request.reason,
+        note: request.note,
+    };
+    decide(state, auth, actor, product_id, decision).await
+}
+
+#[axum::debug_handler]
+async fn suspend_product(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    actor: AuditActor,
+    Path(product_id): Path<Uuid>,
+    payload: Option<Json<ModerationRequest>>,
+) -> impl IntoResponse {
+    let request = payload.unwrap_or_default().0;
+    let decision = ModerationDecision {
+        status: ModerationStatus::Suspended,
+        reason: request.reason,
+        note: request.note,
+    };
+    decide(state, auth, actor, product_id, decision).await
+}
+
+async fn decide(
+    state: AppState,
+    auth: AuthUser,
+    actor: AuditActor,
+    product_id: Uuid,
+    decision: ModerationDecision,
+) -> Response {
+    let moderator = match user_id(&auth) {
+        Ok(id) => ModerationActor {
+            id,
+            role: auth.role.clone(),
+        },
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    let before = match state.product_service.get_product_by_id(product_id).await {
+        Ok(Some(product)) => product.product,
+        Ok(None) => {
+            return error_response(
+                ServiceError::NotFound("Product not found".to_string()),
+                "Could not moderate product",
+            )
+        }
+        Err(e) => return error_response(e, "Could not moderate product"),
+    };
+    match state
+        .moderation_service
+        .moderate(product_id, &moderator, decision)
+        .await
+    {
+        Ok(product) => {
+            let action = match product.moderation_status {
+                ModerationStatus::Approved => AuditAction::ProductApproved,
+                ModerationStatus::Rejected => AuditAction::ProductRejected,
+                ModerationStatus::Suspended => AuditAction::ProductSuspended,
+                ModerationStatus::Draft | ModerationStatus::PendingReview => {
+                    AuditAction::ProductStatusUpdated
+                }
+            };
+            let record = AuditRecord::new(action, AuditEntity::Product, product.id)
+                .before(&before)
+                .after(&product);
+            state.audit_service.record_or_log(&actor, record).await;
+            Json(ApiResponse::success(
+                product,
+                "Product moderated successfully",
+            ))
+            .into_response()
+        }
+        Err(e) => error_response(e, "Could not moderate product"),
+    }
+}
+
+#[axum::debug_handler]
+async fn get_moderation_history(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match state.moderation_service.get_history(product_id).await {
+        Ok(history) => Json(ApiResponse::success(
+            history,
+            "Moderation history retrieved",
+        ))
+        .into_response(),
+        Err(e) => error_response(e, "Could not retrieve moderation history"),
+    }
+}
+
+/// Send a draft, rejected or suspended product back to the review queue
+#[axum::debug_handler]
+async fn submit_product(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(product_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let vendor_id = match require_role(&auth, &[UserRole::Vendor]).and_then(|_| user_id(&auth)) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .moderation_service
+        .submit_for_review(product_id, vendor_id)
+        .await
+    {
+        Ok(product) => Json(ApiResponse::success(
+            product,
+            "Product submitted for review",
+        ))
+        .into_response(),
+        Err(e) => error_response(e, "Could not submit product"),
+    }
+}
+
+/// Moderation history of one of the caller's products
+#[axum::debug_handler]
+async fn get_my_moderation_history(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(product_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let vendor_id = match require_role(&auth, &[UserRole::Vendor]).and_then(|_| user_id(&auth)) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state.product_service.get_product_by_id(product_id).await {
+        Ok(Some(product)) if product.product.seller_id == vendor_id => {}
+        Ok(Some(_)) => {
+            return error_response(
+                ServiceError::Forbidden(
+                    "You don't have permission to view this product's history".to_string(),
+                ),
+                "Could not retrieve moderation history",
+            )
+        }
+        Ok(None) => {
+            return error_response(
+                ServiceError::NotFound("Product not found".to_string()),
+                "Could not retrieve moderation history",
+            )
+        }
+        Err(e) => return error_response(e, "Could not retrieve moderation history"),
+    }
+    match state.moderation_service.get_history(product_id).await {
+        Ok(history) => Json(ApiResponse::success(
+            history,
+            "Moderation history retrieved",
+        ))
+        .into_response(),
+        Err(e) => error_response(e, "Could not retrieve moderation history"),
+    }
+}
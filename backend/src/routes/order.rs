@@ -1,18 +1,29 @@
 use crate::{
     middleware::auth::AuthUser,
-    models::order::{NewOrder, Status},
+    models::{
+        audit_event::{AuditAction, AuditEntity},
+        order::{self, NewOrder, Status},
+        phone::PhoneNumber,
+    },
+    services::{
+        audit::{AuditActor, AuditRecord},
+        order::{Actor, VendorOrderUpdate},
+    },
     state::AppState,
-    utils::shared::ApiResponse,
+    utils::{
+        policy::{can_delete_order, order_scope, user_id, OrderScope},
+        shared::{error_response, ApiResponse},
+    },
 };
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::{delete, get, post, put},
     Extension, Json, Router,
 };
 use serde::Deserialize;
-use tracing::{error, info};
+use std::str::FromStr;
 use uuid::Uuid;
 
 use super::error::ErrorResponse;
@@ -24,7 +35,14 @@ pub fn config() -> Router<AppState> {
         .route("/api/orders/:id", get(get_order))
         .route("/api/orders/:id/status", put(update_order_status))
         .route("/api/orders/:id/items", get(get_order_items))
+        .route("/api/orders/:id/history", get(get_order_history))
+        .route("/api/orders/:id/vendor-orders", get(get_vendor_orders))
         .route("/api/orders/:id", delete(delete_order))
+        .route("/api/vendor-orders", get(list_vendor_orders))
+        .route(
+            "/api/vendor-orders/:id/status",
+            put(update_vendor_order_status),
+        )
 }
 
 #[derive(Deserialize)]
@@ -37,33 +55,99 @@ pub struct ListOrdersQuery {
 pub struct CreateOrderRequest {
     customer_name: String,
     customer_email: Option<String>,
-    customer_phone: String,
+    customer_phone: PhoneNumber,
     delivery_address: String,
     city: String,
     region: String,
     items: Vec<OrderItemRequest>,
 }
 
+/// A line of a new order. Prices are never taken from the client; they
+/// are looked up from the product catalog when the order is created.
 #[derive(Deserialize, Debug)]
 pub struct OrderItemRequest {
     pub product_id: String,
+    /// Required for products that have variants
+    #[serde(default)]
+    pub variant_id: Option<Uuid>,
     pub quantity: u32,
-    pub price: f64,
 }
 
 #[derive(Deserialize)]
 pub struct UpdateOrderStatusRequest {
     status: Status,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ListVendorOrdersQuery {
+    status: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateVendorOrderStatusRequest {
+    status: Status,
+    carrier: Option<String>,
+    tracking_number: Option<String>,
+    reason: Option<String>,
+}
+
+/// Resolve the caller's order scope and fetch the order if it is visible
+/// within it. Orders outside the scope are reported as not found.
+async fn authorize_order(
+    state: &AppState,
+    auth: &AuthUser,
+    order_id: Uuid,
+) -> Result<(OrderScope, order::Model), Response> {
+    let scope = order_scope(auth)
+        .map_err(|(status, msg)| (status, Json(ApiResponse::<()>::error(msg))).into_response())?;
+
+    match state
+        .order_service
+        .get_order_in_scope(order_id, scope)
+        .await
+    {
+        Ok(Some(order)) => Ok((scope, order)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("Order not found")),
+        )
+            .into_response()),
+        Err(e) => Err(error_response(e, "Could not retrieve order")),
+    }
 }
 
 #[axum::debug_handler]
 async fn list_orders(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
     axum::extract::Query(params): axum::extract::Query<ListOrdersQuery>,
 ) -> impl IntoResponse {
+    let scope = match order_scope(&auth) {
+        Ok(scope) => scope,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    // Only admins may look at another buyer's orders
+    if scope != OrderScope::All && params.user_id.is_some() {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::<()>::error(
+                "You do not have permission to access this resource.",
+            )),
+        )
+            .into_response();
+    }
+    let status = match params.status.as_deref().map(Status::from_str).transpose() {
+        Ok(status) => status,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(&e))).into_response()
+        }
+    };
     match state
         .order_service
-        .list_orders(params.user_id, params.status.map(|s| s.into()))
+        .list_orders(scope, params.user_id, status)
         .await
     {
         Ok(orders) => Json(ApiResponse::success(
@@ -71,33 +155,21 @@ async fn list_orders(
             "Orders retrieved successfully",
         ))
         .into_response(),
-        Err(e) => {
-            error!("Error retrieving orders: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiResponse::<()>::error("Could not retrieve orders")),
-            )
-                .into_response()
-        }
+        Err(e) => error_response(e, "Could not retrieve orders"),
     }
 }
 
 #[axum::debug_handler]
-async fn get_order(State(state): State<AppState>, Path(order_id): Path<Uuid>) -> impl IntoResponse {
-    match state.order_service.get_order_by_id(order_id).await {
-        Ok(Some(order)) => {
+async fn get_order(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(order_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match authorize_order(&state, &auth, order_id).await {
+        Ok((_, order)) => {
             Json(ApiResponse::success(order, "Order retrieved successfully")).into_response()
         }
-        Ok(None) => (
-            StatusCode::NOT_FOUND,
-            Json(ApiResponse::<()>::error("Order not found")),
-        )
-            .into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse::<()>::error(&e.to_string())),
-        )
-            .into_response(),
+        Err(response) => response,
     }
 }
 
@@ -107,12 +179,6 @@ async fn create_order(
     Extension(auth): Extension<AuthUser>, // require auth if needed
     Json(payload): Json<CreateOrderRequest>,
 ) -> impl IntoResponse {
-    let total: f64 = payload
-        .items
-        .iter()
-        .map(|item| item.quantity as f64 * item.price)
-        .sum();
-
     let id = match Uuid::parse_str(&auth.id) {
         Ok(uuid) => uuid,
         Err(e) => {
@@ -130,8 +196,7 @@ async fn create_order(
         customer_email: payload.customer_email,
         customer_phone: payload.customer_phone,
         delivery_address: payload.delivery_address,
-        status: "pending".to_string(),
-        total,
+        status: Status::Pending,
         items: payload.items,
         city: payload.city,
         region: payload.region,
@@ -143,68 +208,192 @@ async fn create_order(
             Json(ApiResponse::success(order, "Order created successfully")),
         )
             .into_response(),
-        Err(e) => {
-            error!("Error creating order: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiResponse::<()>::error("Could not create order")),
-            )
-                .into_response()
-        }
+        Err(e) => error_response(e, "Could not create order"),
     }
 }
 
 #[axum::debug_handler]
 async fn update_order_status(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
     Path(order_id): Path<Uuid>,
     Json(payload): Json<UpdateOrderStatusRequest>,
 ) -> impl IntoResponse {
+    if let Err(response) = authorize_order(&state, &auth, order_id).await {
+        return response;
+    }
+    let id = match Uuid::parse_str(&auth.id) {
+        Ok(uuid) => uuid,
+        Err(e) => {
+            return ErrorResponse::ConversionFailed(format!(
+                "Invalid UUID in auth.id: {} - Error: {}",
+                auth.id, e
+            ))
+            .into_response();
+        }
+    };
+    let actor = Actor::User {
+        id,
+        role: auth.role,
+    };
+
     match state
         .order_service
-        .update_order_status(order_id, payload.status.into())
+        .update_order_status(order_id, payload.status, actor, payload.reason)
         .await
     {
         Ok(order) => Json(ApiResponse::success(order, "Order status updated")).into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse::<()>::error(&e.to_string())),
-        )
-            .into_response(),
+        Err(e) => error_response(e, "Could not update order status"),
     }
 }
 
 #[axum::debug_handler]
 async fn get_order_items(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
     Path(order_id): Path<Uuid>,
 ) -> impl IntoResponse {
-    match state.order_service.get_order_items(order_id).await {
+    let scope = match authorize_order(&state, &auth, order_id).await {
+        Ok((scope, _)) => scope,
+        Err(response) => return response,
+    };
+    match state.order_service.get_order_items(order_id, scope).await {
         Ok(items) => Json(ApiResponse::success(items, "Order items retrieved")).into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            error!("Error retrieving order items: {}", e),
-            Json(ApiResponse::<()>::error("Could not retrieve order items")),
-        )
-            .into_response(),
+        Err(e) => error_response(e, "Could not retrieve order items"),
+    }
+}
+
+#[axum::debug_handler]
+async fn get_order_history(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(order_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err(response) = authorize_order(&state, &auth, order_id).await {
+        return response;
+    }
+    match state.order_service.get_order_history(order_id).await {
+        Ok(history) => {
+            Json(ApiResponse::success(history, "Order history retrieved")).into_response()
+        }
+        Err(e) => error_response(e, "Could not retrieve order history"),
+    }
+}
+
+#[axum::debug_handler]
+async fn get_vendor_orders(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(order_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let scope = match authorize_order(&state, &auth, order_id).await {
+        Ok((scope, _)) => scope,
+        Err(response) => return response,
+    };
+    match state.order_service.get_vendor_orders(order_id, scope).await {
+        Ok(vendor_orders) => Json(ApiResponse::success(
+            vendor_orders,
+            "Vendor orders retrieved",
+        ))
+        .into_response(),
+        Err(e) => error_response(e, "Could not retrieve vendor orders"),
+    }
+}
+
+#[axum::debug_handler]
+async fn list_vendor_orders(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    axum::extract::Query(params): axum::extract::Query<ListVendorOrdersQuery>,
+) -> impl IntoResponse {
+    let scope = match order_scope(&auth) {
+        Ok(scope) => scope,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    let status = match params.status.as_deref().map(Status::from_str).transpose() {
+        Ok(status) => status,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(&e))).into_response()
+        }
+    };
+    match state.order_service.list_vendor_orders(scope, status).await {
+        Ok(vendor_orders) => Json(ApiResponse::success(
+            vendor_orders,
+            "Vendor orders retrieved",
+        ))
+        .into_response(),
+        Err(e) => error_response(e, "Could not retrieve vendor orders"),
+    }
+}
+
+#[axum::debug_handler]
+async fn update_vendor_order_status(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(vendor_order_id): Path<Uuid>,
+    Json(payload): Json<UpdateVendorOrderStatusRequest>,
+) -> impl IntoResponse {
+    let id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    let actor = Actor::User {
+        id,
+        role: auth.role,
+    };
+    let update = VendorOrderUpdate {
+        status: payload.status,
+        carrier: payload.carrier,
+        tracking_number: payload.tracking_number,
+        reason: payload.reason,
+    };
+
+    match state
+        .order_service
+        .update_vendor_order_status(vendor_order_id, update, actor)
+        .await
+    {
+        Ok(vendor_order) => {
+            Json(ApiResponse::success(vendor_order, "Vendor order updated")).into_response()
+        }
+        Err(e) => error_response(e, "Could not update vendor order"),
     }
 }
 
 #[axum::debug_handler]
 async fn delete_order(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    actor: AuditActor,
     Path(order_id): Path<Uuid>,
 ) -> impl IntoResponse {
+    if let Err((status, msg)) = can_delete_order(&auth) {
+        return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+    }
+    let before = state
+        .order_service
+        .get_order_by_id(order_id)
+        .await
+        .ok()
+        .flatten();
     match state.order_service.delete_order(order_id).await {
-        Ok(_) => (
-            StatusCode::NO_CONTENT,
-            Json(ApiResponse::success((), "Order deleted successfully")),
-        )
-            .into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse::<()>::error(&e.to_string())),
-        )
-            .into_response(),
+        Ok(_) => {
+            let mut record =
+                AuditRecord::new(AuditAction::OrderDeleted, AuditEntity::Order, order_id);
+            if let Some(before) = &before {
+                record = record.before(before);
+            }
+            state.audit_service.record_or_log(&actor, record).await;
+            (
+                StatusCode::NO_CONTENT,
+                Json(ApiResponse::success((), "Order deleted successfully")),
+            )
+                .into_response()
+        }
+        Err(e) => error_response(e, "Could not delete order"),
     }
 }
@@ -1,17 +1,38 @@
 use crate::{
-    middleware::auth::AuthUser, models::user::UserRole, state::AppState, utils::shared::ApiResponse,
+    middleware::{
+        auth::AuthUser,
+        rate_limit::{rate_limit, RateLimitGroup},
+    },
+    models::{
+        audit_event::{AuditAction, AuditEntity},
+        order::Status,
+        payment,
+        phone::PhoneNumber,
+        role_permission::Permission,
+    },
+    services::{
+        audit::{AuditActor, AuditRecord},
+        errors::ServiceError,
+        payment::CreatePayment,
+        payment_gateway::{DirectPaymentRequest, PaymentLinkRequest},
+    },
+    state::AppState,
+    utils::{
+        policy::OrderScope,
+        rbac::require_permission,
+        shared::{error_response, ApiResponse},
+    },
 };
 use axum::{
     extract::{Path, State},
     http::StatusCode,
-    response::IntoResponse,
+    middleware::from_fn,
+    response::{IntoResponse, Response},
     routing::{get, post},
     Extension, Json, Router,
 };
-use fapshi_rs::api::{payment::PaymentApi, transaction::TransactionApi};
-use fapshi_rs::models::DirectPaymentRequest;
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 pub fn config() -> Router<AppState> {
@@ -20,6 +41,18 @@ pub fn config() -> Router<AppState> {
         .route("/api/payments", post(create_payment))
         .route("/api/indirect_payment", post(create_indirect_payment))
         .route("/api/payments/:id", get(get_transation_status))
+        .route("/api/payments/:id/refund", post(refund_payment))
+}
+
+/// Routes called by the payment provider. They are mounted outside the
+/// auth layer, so every callback is verified with the provider instead and
+/// the routes are rate limited here.
+pub fn webhook_config() -> Router<AppState> {
+    Router::new()
+        .route("/api/webhooks/fapshi", post(fapshi_webhook))
+        .route_layer(from_fn(|req, next| {
+            rate_limit(RateLimitGroup::Api, req, next)
+        }))
 }
 
 #[axum::debug_handler]
@@ -38,7 +71,10 @@ async fn list_payments(
         }
     };
 
-    match TransactionApi::get_transactions_by_user_id(&state.config.payment_service, &user_id.to_string())
+    match state
+        .payment_service
+        .gateway
+        .user_transactions(&user_id.to_string())
         .await
     {
         Ok(payments) => Json(ApiResponse::success(
@@ -46,14 +82,7 @@ async fn list_payments(
             "Payments retrieved successfully",
         ))
         .into_response(),
-        Err(e) => {
-            tracing::error!("error: {}", e.to_string());
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ApiResponse::<()>::error("could not get transactions")),
-            )
-                .into_response()
-        }
+        Err(e) => error_response(e, "could not get transactions"),
     }
 }
 
@@ -63,7 +92,7 @@ async fn get_transation_status(
     Extension(auth_user): Extension<AuthUser>,
     Path(transaction_id): Path<String>,
 ) -> impl IntoResponse {
-    if auth_user.role == UserRole::Admin {
+    if auth_user.can(Permission::ViewFinance) {
         let _user_id = match Uuid::parse_str(&auth_user.id) {
             Ok(id) => id,
             Err(e) => {
@@ -75,21 +104,17 @@ async fn get_transation_status(
             }
         };
 
-        match TransactionApi::get_status(&state.config.payment_service, &transaction_id).await {
+        match state
+            .payment_service
+            .verify_transaction(&transaction_id)
+            .await
+        {
             Ok(status) => Json(ApiResponse::success(
                 status,
                 "Payment status retrieved successfully",
             ))
             .into_response(),
-
-            Err(e) => {
-                error!("error {}", e.to_string());
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ApiResponse::<()>::error("could not get transaction status")),
-                )
-                    .into_response()
-            }
+            Err(e) => error_response(e, "could not get transaction status"),
         }
     } else {
         (
@@ -100,6 +125,29 @@ async fn get_transation_status(
     }
 }
 
+/// A conflict response when the order already has a payment waiting for
+/// the gateway, so the buyer is not charged twice. This only saves the call
+/// to the gateway, saving the payment checks again.
+async fn payment_in_progress(state: &AppState, order_id: Uuid) -> Option<Response> {
+    match state
+        .payment_service
+        .pending_payment_for_order(order_id)
+        .await
+    {
+        Ok(None) => None,
+        Ok(Some(_)) => Some(
+            (
+                StatusCode::CONFLICT,
+                Json(ApiResponse::<()>::error(
+                    "A payment for this order is already in progress",
+                )),
+            )
+                .into_response(),
+        ),
+        Err(e) => Some(error_response(e, "Failed to check existing payments")),
+    }
+}
+
 #[axum::debug_handler]
 async fn create_payment(
     State(state): State<AppState>,
@@ -117,16 +165,33 @@ async fn create_payment(
         }
     };
 
-    // Validate order_id exists and belongs to the user
+    if payment_data.phone.operator().is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error(
+                "Only MTN and Orange numbers can pay with mobile money",
+            )),
+        )
+            .into_response();
+    }
+
+    // Validate order_id exists, belongs to the user and still awaits payment
     match state
         .order_service
-        .get_order_by_id(payment_data.order_id)
+        .get_order_in_scope(payment_data.order_id, OrderScope::Buyer(user_id))
         .await
     {
+        Ok(Some(order)) if order.status != Status::Pending => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error("Order is not awaiting payment")),
+        )
+            .into_response(),
         Ok(Some(order)) => {
+            if let Some(response) = payment_in_progress(&state, order.id).await {
+                return response;
+            }
             let payment_request = DirectPaymentRequest {
-                amount: order.total as f32,
-                medium: None,
+                amount: order.total,
                 name: Some(payment_data.name.clone()),
                 email: None,
                 phone: payment_data.phone.clone(),
@@ -137,34 +202,36 @@ async fn create_payment(
 
             info!("Initiating payment for order: {:?}", payment_request);
 
-            match PaymentApi::initiate_direct_payment(&state.config.payment_service, &payment_request)
+            match state
+                .payment_service
+                .gateway
+                .initiate_direct_payment(&payment_request)
                 .await
             {
-                Ok(fapshi_response) => {
-                    let payment = Payment {
-                        id: Uuid::new_v4(),
-                        user_id,
+                Ok(initiated) => {
+                    let payment = CreatePayment {
                         order_id: order.id,
                         amount: order.total,
-                        status: "pending".to_string(),
-                        transaction_id: fapshi_response.transaction_id,
-                        created_at: chrono::Utc::now(),
+                        payment_method: "mobile_money".to_string(),
+                        payment_details: Some(serde_json::json!({
+                            "name": payment_data.name,
+                            "phone": payment_data.phone,
+                        })),
+                        transaction_id: Some(initiated.transaction_id),
                     };
-                    (
-                        StatusCode::CREATED,
-                        Json(ApiResponse::success(
-                            payment,
-                            "Payment initiated successfully",
-                        )),
-                    )
-                        .into_response()
+                    match state.payment_service.create_payment(payment).await {
+                        Ok(payment) => (
+                            StatusCode::CREATED,
+                            Json(ApiResponse::success(
+                                payment,
+                                "Payment initiated successfully",
+                            )),
+                        )
+                            .into_response(),
+                        Err(e) => error_response(e, "Could not save payment"),
+                    }
                 }
-                Err(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    error!("Error initiating payment: {}", e.to_string()),
-                    Json(ApiResponse::<()>::error("Payment initiation failed")),
-                )
-                    .into_response(),
+                Err(e) => error_response(e, "Payment initiation failed"),
             }
         }
         Ok(None) => (
@@ -172,14 +239,7 @@ async fn create_payment(
             Json(ApiResponse::<()>::error("Order not found")),
         )
             .into_response(),
-        Err(e) => (
-            StatusCode::BAD_GATEWAY,
-            Json(ApiResponse::<()>::error(&format!(
-                "Payment gateway error: {}",
-                e
-            ))),
-        )
-            .into_response(),
+        Err(e) => error_response(e, "Could not retrieve order"),
     }
 }
 
@@ -199,53 +259,68 @@ async fn create_indirect_payment(
         }
     };
 
-    // Validate order_id exists and belongs to the user
+    // Validate order_id exists, belongs to the user and still awaits payment
     match state
         .order_service
-        .get_order_by_id(payment_data.order_id)
+        .get_order_in_scope(payment_data.order_id, OrderScope::Buyer(user_id))
         .await
     {
+        Ok(Some(order)) if order.status != Status::Pending => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error("Order is not awaiting payment")),
+        )
+            .into_response(),
         Ok(Some(order)) => {
+            if let Some(response) = payment_in_progress(&state, order.id).await {
+                return response;
+            }
             let message = format!("payment for order {}", order.id);
-            let payment_request = fapshi_rs::models::PaymentRequest {
+            let payment_request = PaymentLinkRequest {
                 amount: order.total,
                 email: None,
                 user_id: Some(user_id.to_string()),
                 external_id: Some(order.id.to_string()),
                 message,
                 redirect_url: Some(payment_data.redirect_url.clone()),
-                card_only: None,
             };
 
             info!("Initiating payment for order: {:?}", payment_request);
 
-            match PaymentApi::create_payment(&state.config.payment_service, &payment_request).await {
-                Ok(fapshi_response) => {
-                    let payment = IndirectPayment {
-                        id: Uuid::new_v4(),
-                        user_id,
+            match state
+                .payment_service
+                .gateway
+                .create_payment_link(&payment_request)
+                .await
+            {
+                Ok(initiated) => {
+                    let payment_link = initiated.payment_link.unwrap_or_default();
+                    let payment = CreatePayment {
                         order_id: order.id,
                         amount: order.total,
-                        status: "pending".to_string(),
-                        transaction_id: fapshi_response.transaction_id,
-                        created_at: chrono::Utc::now(),
-                        payment_link: fapshi_response.payment_link,
+                        payment_method: "payment_link".to_string(),
+                        payment_details: Some(serde_json::json!({
+                            "name": payment_data.name,
+                            "phone": payment_data.phone,
+                            "payment_link": payment_link,
+                        })),
+                        transaction_id: Some(initiated.transaction_id),
                     };
-                    (
-                        StatusCode::CREATED,
-                        Json(ApiResponse::success(
-                            payment,
-                            "Payment initiated successfully",
-                        )),
-                    )
-                        .into_response()
+                    match state.payment_service.create_payment(payment).await {
+                        Ok(payment) => (
+                            StatusCode::CREATED,
+                            Json(ApiResponse::success(
+                                IndirectPayment {
+                                    payment,
+                                    payment_link,
+                                },
+                                "Payment initiated successfully",
+                            )),
+                        )
+                            .into_response(),
+                        Err(e) => error_response(e, "Could not save payment"),
+                    }
                 }
-                Err(e) => (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    error!("Error initiating payment: {}", e.to_string()),
-                    Json(ApiResponse::<()>::error("Payment initiation failed")),
-                )
-                    .into_response(),
+                Err(e) => error_response(e, "Payment initiation failed"),
             }
         }
         Ok(None) => (
@@ -253,14 +328,55 @@ async fn create_indirect_payment(
             Json(ApiResponse::<()>::error("Order not found")),
         )
             .into_response(),
-        Err(e) => (
-            StatusCode::BAD_GATEWAY,
-            Json(ApiResponse::<()>::error(&format!(
-                "Payment gateway error: {}",
-                e
-            ))),
-        )
-            .into_response(),
+        Err(e) => error_response(e, "Could not retrieve order"),
+    }
+}
+
+#[axum::debug_handler]
+async fn refund_payment(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    actor: AuditActor,
+    Path(payment_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err((status, msg)) = require_permission(&auth_user, Permission::IssueRefunds) {
+        return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+    }
+
+    let before = match state.payment_service.get_payment_by_id(payment_id).await {
+        Ok(Some(payment)) => payment,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ApiResponse::<()>::error("Payment not found")),
+            )
+                .into_response();
+        }
+        Err(e) => return error_response(e, "Could not refund payment"),
+    };
+    match state.payment_service.refund_payment(payment_id).await {
+        Ok(payment) => {
+            let record = AuditRecord::new(
+                AuditAction::PaymentRefunded,
+                AuditEntity::Payment,
+                payment.id,
+            )
+            .before(&before)
+            .after(&payment);
+            state.audit_service.record_or_log(&actor, record).await;
+            Json(ApiResponse::success(payment, "Payment refunded")).into_response()
+        }
+        Err(ServiceError::PaymentGateway(msg)) => {
+            error!("Error refunding payment {}: {}", payment_id, msg);
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(ApiResponse::<()>::error(
+                    "Refund could not be confirmed, it will be checked with the provider",
+                )),
+            )
+                .into_response()
+        }
+        Err(e) => error_response(e, "Could not refund payment"),
     }
 }
 
@@ -268,7 +384,7 @@ async fn create_indirect_payment(
 pub struct CreateDirectPaymentRequest {
     order_id: Uuid,
     name: String,
-    phone: String,
+    phone: PhoneNumber,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -276,7 +392,7 @@ pub struct CreateInDirectPaymentRequest {
     order_id: Uuid,
     name: String,
     redirect_url: String,
-    phone: String,
+    phone: PhoneNumber,
 }
 pub struct CreatePaymentResponse {
     pub payment_link: String,
@@ -284,25 +400,59 @@ pub struct CreatePaymentResponse {
     pub date_initiated: String,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct Payment {
-    pub id: Uuid,
-    pub user_id: Uuid,
-    pub order_id: Uuid,
-    pub amount: f64,
-    pub status: String,
-    pub transaction_id: String,
-    pub created_at: chrono::DateTime<chrono::Utc>,
-}
-
-#[derive(Serialize, Deserialize)]
+/// A payment made through a hosted payment link
+#[derive(Serialize)]
 pub struct IndirectPayment {
-    pub id: Uuid,
-    pub user_id: Uuid,
-    pub order_id: Uuid,
-    pub amount: f64,
-    pub status: String,
-    pub transaction_id: String,
-    pub created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(flatten)]
+    pub payment: payment::Model,
     pub payment_link: String,
 }
+
+/// Body of a Fapshi webhook callback. Only the transaction id is used; the
+/// status itself is always fetched back from Fapshi before it is applied.
+#[derive(Deserialize)]
+pub struct FapshiWebhook {
+    #[serde(rename = "transId")]
+    transaction_id: String,
+}
+
+#[axum::debug_handler]
+async fn fapshi_webhook(
+    State(state): State<AppState>,
+    Json(payload): Json<FapshiWebhook>,
+) -> impl IntoResponse {
+    let transaction = match state
+        .payment_service
+        .verify_transaction(&payload.transaction_id)
+        .await
+    {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            error!(
+                "Could not verify transaction {}: {}",
+                payload.transaction_id, e
+            );
+            return (
+                StatusCode::BAD_GATEWAY,
+                Json(ApiResponse::<()>::error("Could not verify transaction")),
+            )
+                .into_response();
+        }
+    };
+
+    match state
+        .payment_service
+        .apply_transaction_status(&transaction)
+        .await
+    {
+        // Anyone can call this, so nothing about the payment is echoed back
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => {
+            warn!(
+                "Could not apply transaction {}: {}",
+                transaction.transaction_id, e
+            );
+            error_response(e, "Could not update payment")
+        }
+    }
+}
@@ -1,12 +1,13 @@
 use crate::{
     middleware::auth::AuthUser,
+    models::money::{self, Money},
     services::{
+        errors::ServiceError,
         image::handle_image_upload,
         product::{CreateProduct, UpdateProduct},
     },
     state::AppState,
     utils::shared::ApiResponse,
-    models::user::UserRole,
 };
 use axum::{
     extract::{Path, State},
@@ -33,12 +34,6 @@ pub fn config() -> Router<AppState> {
             "/api/vendor",
             Router::new().route("/products", get(list_products_by)),
         )
-        .nest(
-            "/api/admin",
-            Router::new()
-                .route("/products/:id/approve", post(approve_product))
-                .route("/products/pending", get(list_pending_products)),
-        )
 }
 pub async fn list_products_by(
     State(state): State<AppState>,
@@ -133,15 +128,35 @@ async fn create_product(
                 .into_response();
         }
     };
+    // Vendors list products once an admin approved their application
+    match state
+        .vendor_application_service
+        .ensure_verified_vendor(seller_id)
+        .await
+    {
+        Ok(()) => {}
+        Err(ServiceError::Forbidden(msg)) => {
+            return (StatusCode::FORBIDDEN, Json(ApiResponse::<()>::error(&msg))).into_response();
+        }
+        Err(e) => {
+            tracing::error!("could not check vendor verification: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("could not store product")),
+            )
+                .into_response();
+        }
+    }
     let create_product = CreateProduct {
         seller_id,
         title: product_data.title,
         description: Some(product_data.description),
         price: product_data.price,
-        category: Some(product_data.category),
+        category_id: product_data.category_id,
         image_urls: product_data.image_urls,
         quantity: product_data.quantity,
         return_policy: Some(product_data.return_policy),
+        draft: product_data.draft,
     };
     info!("Creating product: {:?}", create_product);
     match state.product_service.create_product(create_product).await {
@@ -153,6 +168,11 @@ async fn create_product(
             )),
         )
             .into_response(),
+        Err(ServiceError::Validation(msg)) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error(&msg)),
+        )
+            .into_response(),
         Err(e) => {
             tracing::error!("could not store products: {}", e.to_string());
             (
@@ -187,7 +207,7 @@ async fn update_product(
                 title: product_data.title,
                 description: product_data.description,
                 price: product_data.price,
-                category: product_data.category,
+                category_id: product_data.category_id,
                 image_urls: product_data.image_urls,
                 quantity: product_data.quantity,
                 return_policy: product_data.return_policy,
@@ -203,6 +223,11 @@ async fn update_product(
                     "Product updated successfully",
                 ))
                 .into_response(),
+                Err(ServiceError::Validation(msg)) => (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiResponse::<()>::error(&msg)),
+                )
+                    .into_response(),
                 Err(e) => (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(ApiResponse::<()>::error(&e.to_string())),
@@ -274,74 +299,19 @@ async fn delete_product(
     }
 }
 
-async fn approve_product(
-    State(state): State<AppState>,
-    Path(product_id): Path<Uuid>,
-    Extension(auth_user): Extension<AuthUser>,
-) -> impl IntoResponse {
-    // Check if user is admin
-    if auth_user.role != UserRole::Admin {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(ApiResponse::<()>::error("Only admins can approve products")),
-        )
-            .into_response();
-    }
-    match state.product_service.approve_product(product_id).await {
-        Ok(product) => (
-            StatusCode::OK,
-            Json(ApiResponse::success(product, "Product approved successfully")),
-        )
-            .into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse::<()>::error(&e.to_string())),
-        )
-            .into_response(),
-    }
-}
-
-async fn list_pending_products(
-    State(state): State<AppState>,
-    Extension(auth_user): Extension<AuthUser>,
-) -> impl IntoResponse {
-    // Check if user is admin
-    if auth_user.role != UserRole::Admin {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(ApiResponse::<()>::error("Only admins can view pending products")),
-        )
-            .into_response();
-    }
-    match state.product_service.list_pending_products().await {
-        Ok(products) => (
-            StatusCode::OK,
-            Json(ApiResponse::success(products, "Pending products retrieved successfully")),
-        )
-            .into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse::<()>::error(&e.to_string())),
-        )
-            .into_response(),
-    }
-}
-
-#[derive(serde::Deserialize)]
-pub struct ListProductsQuery {
-    category: Option<String>,
-    seller_id: Option<Uuid>,
-}
-
 #[derive(serde::Deserialize, Debug)]
 pub struct CreateProductRequest {
     title: String,
     description: String,
-    price: f64,
-    category: String,
+    #[serde(deserialize_with = "money::non_negative")]
+    price: Money,
+    category_id: Option<Uuid>,
     image_urls: Vec<String>,
     quantity: i32,
     return_policy: String,
+    /// Save the product without submitting it for review
+    #[serde(default)]
+    draft: bool,
 }
 
 #[derive(serde::Deserialize)]
@@ -349,8 +319,9 @@ pub struct UpdateProductRequest {
     title: Option<String>,
     description: Option<String>,
     quantity: Option<i32>,
-    price: Option<f64>,
-    category: Option<String>,
+    #[serde(default, deserialize_with = "money::non_negative_opt")]
+    price: Option<Money>,
+    category_id: Option<Uuid>,
     image_urls: Option<Vec<String>>,
     return_policy: Option<String>,
 }
@@ -0,0 +1,200 @@
+use crate::{
+    middleware::{
+        auth::AuthUser,
+        permission::require_permission,
+        rate_limit::{rate_limit, RateLimitGroup},
+    },
+    models::{
+        audit_event::{AuditAction, AuditEntity},
+        review::ReviewStatus,
+        role_permission::Permission,
+        user::UserRole,
+    },
+    services::{
+        audit::{AuditActor, AuditRecord},
+        review::{NewReview, ReviewDecision},
+    },
+    state::AppState,
+    utils::{
+        policy::user_id,
+        rbac::require_role,
+        shared::{error_response, ApiResponse},
+    },
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    middleware::from_fn,
+    response::IntoResponse,
+    routing::{get, post, put},
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Writing, answering and reporting reviews, and review moderation for
+/// staff who may moderate reviews
+pub fn config() -> Router<AppState> {
+    let moderation = Router::new()
+        .route("/api/admins/reviews/reported", get(list_reported_reviews))
+        .route("/api/admins/reviews/:id", put(moderate_review))
+        .route_layer(from_fn(|req, next| {
+            require_permission(Permission::ModerateReviews, req, next)
+        }));
+
+    Router::new()
+        .route("/api/products/:id/reviews", post(create_review))
+        .route("/api/reviews/:id/reply", post(reply_to_review))
+        .route("/api/reviews/:id/report", post(report_review))
+        .merge(moderation)
+}
+
+/// Product reviews, open to buyers who are not signed in. It is mounted
+/// outside the auth layer, so it is rate limited here.
+pub fn public_config() -> Router<AppState> {
+    Router::new()
+        .route("/api/products/:id/reviews", get(list_product_reviews))
+        .route_layer(from_fn(|req, next| {
+            rate_limit(RateLimitGroup::Api, req, next)
+        }))
+}
+
+#[derive(Deserialize)]
+pub struct ReplyRequest {
+    reply: String,
+}
+
+#[derive(Deserialize)]
+pub struct ReportRequest {
+    reason: String,
+}
+
+/// Published reviews of a product with its average rating
+#[axum::debug_handler]
+async fn list_product_reviews(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match state.review_service.list_product_reviews(product_id).await {
+        Ok(reviews) => Json(ApiResponse::success(reviews, "Reviews retrieved")).into_response(),
+        Err(e) => error_response(e, "Could not retrieve reviews"),
+    }
+}
+
+/// Review a product the caller received
+#[axum::debug_handler]
+async fn create_review(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(product_id): Path<Uuid>,
+    Json(data): Json<NewReview>,
+) -> impl IntoResponse {
+    let buyer_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .review_service
+        .create_review(product_id, buyer_id, data)
+        .await
+    {
+        Ok(review) => (
+            StatusCode::CREATED,
+            Json(ApiResponse::success(review, "Review created")),
+        )
+            .into_response(),
+        Err(e) => error_response(e, "Could not create review"),
+    }
+}
+
+/// Answer a review of one of the caller's products
+#[axum::debug_handler]
+async fn reply_to_review(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(review_id): Path<Uuid>,
+    Json(request): Json<ReplyRequest>,
+) -> impl IntoResponse {
+    let vendor_id = match require_role(&auth, &[UserRole::Vendor]).and_then(|_| user_id(&auth)) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .review_service
+        .reply(review_id, vendor_id, &request.reply)
+        .await
+    {
+        Ok(review) => Json(ApiResponse::success(review, "Reply posted")).into_response(),
+        Err(e) => error_response(e, "Could not reply to review"),
+    }
+}
+
+#[axum::debug_handler]
+async fn report_review(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(review_id): Path<Uuid>,
+    Json(request): Json<ReportRequest>,
+) -> impl IntoResponse {
+    let reporter_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .review_service
+        .report(review_id, reporter_id, &request.reason)
+        .await
+    {
+        Ok(report) => (
+            StatusCode::CREATED,
+            Json(ApiResponse::success(report, "Review reported")),
+        )
+            .into_response(),
+        Err(e) => error_response(e, "Could not report review"),
+    }
+}
+
+/// Reviews with open reports, the longest waiting first
+#[axum::debug_handler]
+async fn list_reported_reviews(State(state): State<AppState>) -> impl IntoResponse {
+    match state.review_service.list_reported().await {
+        Ok(reviews) => {
+            Json(ApiResponse::success(reviews, "Reported reviews retrieved")).into_response()
+        }
+        Err(e) => error_response(e, "Could not retrieve reported reviews"),
+    }
+}
+
+/// Hide or restore a review, with the `status` and `reason` in the body
+#[axum::debug_handler]
+async fn moderate_review(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(review_id): Path<Uuid>,
+    Json(decision): Json<ReviewDecision>,
+) -> impl IntoResponse {
+    let before = match state.review_service.get_review(review_id).await {
+        Ok(review) => review,
+        Err(e) => return error_response(e, "Could not moderate review"),
+    };
+    match state.review_service.moderate(review_id, decision).await {
+        Ok(review) => {
+            let action = match review.status {
+                ReviewStatus::Hidden => AuditAction::ReviewHidden,
+                ReviewStatus::Published => AuditAction::ReviewRestored,
+            };
+            let record = AuditRecord::new(action, AuditEntity::Review, review.id)
+                .before(&before)
+                .after(&review);
+            state.audit_service.record_or_log(&actor, record).await;
+            Json(ApiResponse::success(review, "Review moderated")).into_response()
+        }
+        Err(e) => error_response(e, "Could not moderate review"),
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{
+    middleware::{auth::AuthUser, permission::require_permission},
+    models::{
+        audit_event::{AuditAction, AuditEntity},
+        phone::PhoneNumber,
+        role_permission::Permission,
+        user::UserRole,
+    },
+    services::{
+        audit::{AuditActor, AuditRecord},
+        permission::NewRole,
+        user::{CreateUser, UserService},
+    },
+    state::AppState,
+    utils::{
+        policy::user_id,
+        shared::{error_response, ApiResponse},
+    },
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    middleware::from_fn,
+    response::IntoResponse,
+    routing::{delete, get, post, put},
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+pub fn config() -> Router<AppState> {
+    let manage = Router::new()
+        .route("/api/admins/roles", get(list_roles).post(create_role))
+        .route(
+            "/api/admins/roles/:id/permissions",
+            put(set_role_permissions),
+        )
+        .route("/api/admins/staff", post(create_staff))
+        .route(
+            "/api/admins/staff/:id/roles",
+            get(list_staff_roles).post(assign_role),
+        )
+        .route("/api/admins/staff/:id/roles/:role_id", delete(revoke_role))
+        .route_layer(from_fn(|req, next| {
+            require_permission(Permission::ManageRoles, req, next)
+        }));
+
+    Router::new()
+        .route("/api/users/me/permissions", get(get_my_permissions))
+        .merge(manage)
+}
+
+#[derive(Deserialize)]
+pub struct SetPermissionsRequest {
+    permissions: Vec<Permission>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateStaffRequest {
+    full_name: String,
+    email: Option<String>,
+    phone: PhoneNumber,
+    password: String,
+    #[serde(default)]
+    roles: Vec<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct AssignRoleRequest {
+    role_id: Uuid,
+}
+
+/// What the caller may do, so clients can hide what they cannot
+#[axum::debug_handler]
+async fn get_my_permissions(Extension(auth): Extension<AuthUser>) -> impl IntoResponse {
+    let mut permissions: Vec<Permission> = auth.permissions.into_iter().collect();
+    permissions.sort_by_key(|permission| permission.to_string());
+    Json(ApiResponse::success(permissions, "Permissions retrieved"))
+}
+
+#[axum::debug_handler]
+async fn list_roles(State(state): State<AppState>) -> impl IntoResponse {
+    match state.permission_service.list_roles().await {
+        Ok(roles) => Json(ApiResponse::success(roles, "Roles retrieved")).into_response(),
+        Err(e) => error_response(e, "Could not retrieve roles"),
+    }
+}
+
+#[axum::debug_handler]
+async fn create_role(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Json(payload): Json<NewRole>,
+) -> impl IntoResponse {
+    match state.permission_service.create_role(payload).await {
+        Ok(role) => {
+            let record =
+                AuditRecord::new(AuditAction::RoleCreated, AuditEntity::Role, role.role.id)
+                    .after(&role);
+            state.audit_service.record_or_log(&actor, record).await;
+            (
+                StatusCode::CREATED,
+                Json(ApiResponse::success(role, "Role created")),
+            )
+                .into_response()
+        }
+        Err(e) => error_response(e, "Could not create role"),
+    }
+}
+
+#[axum::debug_handler]
+async fn set_role_permissions(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path(role_id): Path<Uuid>,
+    Json(payload): Json<SetPermissionsRequest>,
+) -> impl IntoResponse {
+    let before = match state.permission_service.get_role(role_id).await {
+        Ok(role) => role,
+        Err(e) => return error_response(e, "Could not update role"),
+    };
+    match state
+        .permission_service
+        .set_role_permissions(role_id, payload.permissions)
+        .await
+    {
+        Ok(role) => {
+            let record = AuditRecord::new(
+                AuditAction::RolePermissionsUpdated,
+                AuditEntity::Role,
+                role.role.id,
+            )
+            .before(&before)
+            .after(&role);
+            state.audit_service.record_or_log(&actor, record).await;
+            Json(ApiResponse::success(role, "Role updated")).into_response()
+        }
+        Err(e) => error_response(e, "Could not update role"),
+    }
+}
+
+/// Create a staff account holding `roles`
+#[axum::debug_handler]
+async fn create_staff(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    actor: AuditActor,
+    Json(payload): Json<CreateStaffRequest>,
+) -> impl IntoResponse {
+    let admin_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    // Check the roles first so a typo does not leave a staff account
+    // without them
+    if let Err(e) = state.permission_service.find_roles(&payload.roles).await {
+        return error_response(e, "Could not create staff account");
+    }
+
+    let user_service = UserService::new(state.db.clone());
+    let staff = match user_service
+        .create_user(CreateUser {
+            full_name: payload.full_name,
+            email: payload.email,
+            phone: payload.phone,
+            password: payload.password,
+            role: UserRole::Staff,
+        })
+        .await
+    {
+        Ok(staff) => staff,
+        Err(e) => return error_response(e, "Could not create staff account"),
+    };
+    for role_id in &payload.roles {
+        if let Err(e) = state
+            .permission_service
+            .assign_role(staff.id, *role_id, admin_id)
+            .await
+        {
+            return error_response(e, "Could not assign role");
+        }
+    }
+    // The snapshot leaves out the password hash
+    let record =
+        AuditRecord::new(AuditAction::StaffCreated, AuditEntity::User, staff.id).after(&json!({
+            "full_name": staff.full_name,
+            "email": staff.email,
+            "phone": staff.phone,
+            "roles": payload.roles,
+        }));
+    state.audit_service.record_or_log(&actor, record).await;
+
+    (
+        StatusCode::CREATED,
+        Json(ApiResponse::success(staff, "Staff account created")),
+    )
+        .into_response()
+}
+
+#[axum::debug_handler]
+async fn list_staff_roles(
+    State(state): State<AppState>,
+    Path(staff_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match state.permission_service.roles_of(staff_id).await {
+        Ok(roles) => Json(ApiResponse::success(roles, "Roles retrieved")).into_response(),
+        Err(e) => error_response(e, "Could not retrieve roles"),
+    }
+}
+
+#[axum::debug_handler]
+async fn assign_role(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    actor: AuditActor,
+    Path(staff_id): Path<Uuid>,
+    Json(payload): Json<AssignRoleRequest>,
+) -> impl IntoResponse {
+    let admin_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .permission_service
+        .assign_role(staff_id, payload.role_id, admin_id)
+        .await
+    {
+        Ok(()) => {
+            let record = AuditRecord::new(AuditAction::RoleAssigned, AuditEntity::User, staff_id)
+                .after(&json!({ "role_id": payload.role_id }));
+            state.audit_service.record_or_log(&actor, record).await;
+            Json(ApiResponse::success((), "Role assigned")).into_response()
+        }
+        Err(e) => error_response(e, "Could not assign role"),
+    }
+}
+
+#[axum::debug_handler]
+async fn revoke_role(
+    State(state): State<AppState>,
+    actor: AuditActor,
+    Path((staff_id, role_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    match state
+        .permission_service
+        .revoke_role(staff_id, role_id)
+        .await
+    {
+        Ok(()) => {
+            let record = AuditRecord::new(AuditAction::RoleRevoked, AuditEntity::User, staff_id)
+                .before(&json!({ "role_id": role_id }));
+            state.audit_service.record_or_log(&actor, record).await;
+            Json(ApiResponse::success((), "Role revoked")).into_response()
+        }
+        Err(e) => error_response(e, "Could not revoke role"),
+    }
+}
@@ -0,0 +1,49 @@
+use crate::{
+    middleware::rate_limit::{rate_limit, RateLimitGroup},
+    services::{errors::ServiceError, search::ProductSearch},
+    state::AppState,
+    utils::shared::ApiResponse,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    middleware::from_fn,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use tracing::error;
+
+/// Catalogue search, open to buyers who are not signed in. It is mounted
+/// outside the auth layer, so it is rate limited here.
+pub fn config() -> Router<AppState> {
+    Router::new()
+        .route("/api/search/products", get(search_products))
+        .route_layer(from_fn(|req, next| {
+            rate_limit(RateLimitGroup::Api, req, next)
+        }))
+}
+
+/// Approved products matching the query parameters, one page at a time
+#[axum::debug_handler]
+async fn search_products(
+    State(state): State<AppState>,
+    Query(search): Query<ProductSearch>,
+) -> impl IntoResponse {
+    match state.search_service.search_products(search).await {
+        Ok(results) => Json(ApiResponse::success(results, "Products retrieved")).into_response(),
+        Err(ServiceError::Validation(msg)) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error(&msg)),
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Could not search products: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("Could not search products")),
+            )
+                .into_response()
+        }
+    }
+}
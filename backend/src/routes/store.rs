@@ -0,0 +1,119 @@
+use crate::{
+    middleware::{
+        auth::AuthUser,
+        rate_limit::{rate_limit, RateLimitGroup},
+    },
+    models::user::UserRole,
+    services::store::{CreateStore, UpdateStore},
+    state::AppState,
+    utils::{
+        policy::user_id,
+        rbac::require_role,
+        shared::{error_response, ApiResponse},
+    },
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    middleware::from_fn,
+    response::IntoResponse,
+    routing::get,
+    Extension, Json, Router,
+};
+use uuid::Uuid;
+
+pub fn config() -> Router<AppState> {
+    Router::new().route(
+        "/api/vendor/store",
+        get(get_my_store).post(create_store).patch(update_store),
+    )
+}
+
+/// Shop pages buyers can browse without signing in. They are mounted
+/// outside the auth layer, so they are rate limited here.
+pub fn public_config() -> Router<AppState> {
+    Router::new()
+        .route("/api/stores/:slug", get(get_store_page))
+        .route_layer(from_fn(|req, next| {
+            rate_limit(RateLimitGroup::Api, req, next)
+        }))
+}
+
+/// Check the caller is a vendor and return their id
+fn authorize_vendor(auth: &AuthUser) -> Result<Uuid, (StatusCode, &'static str)> {
+    require_role(auth, &[UserRole::Vendor]).and_then(|_| user_id(auth))
+}
+
+#[axum::debug_handler]
+async fn get_my_store(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let vendor_id = match authorize_vendor(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state.store_service.get_store_by_owner(vendor_id).await {
+        Ok(Some(store)) => Json(ApiResponse::success(store, "Store retrieved")).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::<()>::error("Store not found")),
+        )
+            .into_response(),
+        Err(e) => error_response(e, "Could not retrieve store"),
+    }
+}
+
+#[axum::debug_handler]
+async fn create_store(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Json(payload): Json<CreateStore>,
+) -> impl IntoResponse {
+    let vendor_id = match authorize_vendor(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state.store_service.create_store(vendor_id, payload).await {
+        Ok(store) => (
+            StatusCode::CREATED,
+            Json(ApiResponse::success(store, "Store created")),
+        )
+            .into_response(),
+        Err(e) => error_response(e, "Could not create store"),
+    }
+}
+
+#[axum::debug_handler]
+async fn update_store(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Json(payload): Json<UpdateStore>,
+) -> impl IntoResponse {
+    let vendor_id = match authorize_vendor(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state.store_service.update_store(vendor_id, payload).await {
+        Ok(store) => Json(ApiResponse::success(store, "Store updated")).into_response(),
+        Err(e) => error_response(e, "Could not update store"),
+    }
+}
+
+/// A store and its approved products
+#[axum::debug_handler]
+async fn get_store_page(
+    State(state): State<AppState>,
+    Path(slug): Path<String>,
+) -> impl IntoResponse {
+    match state.store_service.get_store_page(&slug).await {
+        Ok(page) => Json(ApiResponse::success(page, "Store retrieved")).into_response(),
+        Err(e) => error_response(e, "Could not retrieve store"),
+    }
+}
@@ -1,15 +1,42 @@
 use axum::{
-    routing::get,
+    routing::{delete, get, post},
     Router,
 };
 
 use crate::{
-    handlers::user::{get_all_users, get_me},
+    handlers::user::{
+        change_password, confirm_email_change, confirm_phone_change, get_all_users, get_me,
+        list_sessions, logout, logout_all, request_password_reset, request_phone_verification,
+        reset_password, revoke_user_session, update_me, verify_phone,
+    },
     state::AppState,
 };
 
 pub fn config() -> Router<AppState> {
     Router::new()
-        .route("/api/users/me", get(get_me))
+        .route("/api/users/me", get(get_me).patch(update_me))
+        .route("/api/users/me/password", post(change_password))
+        .route("/api/users/me/email/verify", post(confirm_email_change))
+        .route("/api/users/me/phone/verify", post(confirm_phone_change))
         .route("/api/users/all", get(get_all_users))
+        .route("/api/users/logout", post(logout))
+        .route("/api/users/logout-all", post(logout_all))
+        .route("/api/users/sessions", get(list_sessions))
+        .route("/api/users/sessions/:id", delete(revoke_user_session))
+}
+
+/// Account recovery routes, used before the user can sign in. They are
+/// mounted outside the auth layer.
+pub fn public_config() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/users/verify-phone/request",
+            post(request_phone_verification),
+        )
+        .route("/api/users/verify-phone", post(verify_phone))
+        .route(
+            "/api/users/password-reset/request",
+            post(request_password_reset),
+        )
+        .route("/api/users/password-reset", post(reset_password))
 }
@@ -0,0 +1,153 @@
+use crate::{
+    middleware::auth::AuthUser,
+    models::user::UserRole,
+    services::variant::{CreateVariant, OptionInput, UpdateVariant},
+    state::AppState,
+    utils::{
+        policy::user_id,
+        rbac::require_role,
+        shared::{error_response, ApiResponse},
+    },
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, patch, put},
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Product options and variants: anyone signed in may read them, the
+/// vendor who makes the product manages them
+pub fn config() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/products/:id/variants",
+            get(get_variants).post(create_variant),
+        )
+        .route("/api/products/:id/options", put(set_options))
+        .route(
+            "/api/products/:id/variants/:variant_id",
+            patch(update_variant).delete(delete_variant),
+        )
+}
+
+#[derive(Deserialize)]
+pub struct SetOptionsRequest {
+    options: Vec<OptionInput>,
+}
+
+/// Check the caller is a vendor and return their id
+fn authorize_vendor(auth: &AuthUser) -> Result<Uuid, (StatusCode, &'static str)> {
+    require_role(auth, &[UserRole::Vendor]).and_then(|_| user_id(auth))
+}
+
+/// The options of a product and its variants
+#[axum::debug_handler]
+async fn get_variants(
+    State(state): State<AppState>,
+    Path(product_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match state.variant_service.get_variants(product_id).await {
+        Ok(variants) => Json(ApiResponse::success(variants, "Variants retrieved")).into_response(),
+        Err(e) => error_response(e, "Could not retrieve variants"),
+    }
+}
+
+/// Replace the options of one of the caller's products
+#[axum::debug_handler]
+async fn set_options(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(product_id): Path<Uuid>,
+    Json(request): Json<SetOptionsRequest>,
+) -> impl IntoResponse {
+    let vendor_id = match authorize_vendor(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .variant_service
+        .set_options(product_id, vendor_id, request.options)
+        .await
+    {
+        Ok(options) => Json(ApiResponse::success(options, "Options updated")).into_response(),
+        Err(e) => error_response(e, "Could not update options"),
+    }
+}
+
+#[axum::debug_handler]
+async fn create_variant(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(product_id): Path<Uuid>,
+    Json(data): Json<CreateVariant>,
+) -> impl IntoResponse {
+    let vendor_id = match authorize_vendor(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .variant_service
+        .create_variant(product_id, vendor_id, data)
+        .await
+    {
+        Ok(variant) => (
+            StatusCode::CREATED,
+            Json(ApiResponse::success(variant, "Variant created")),
+        )
+            .into_response(),
+        Err(e) => error_response(e, "Could not create variant"),
+    }
+}
+
+#[axum::debug_handler]
+async fn update_variant(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path((product_id, variant_id)): Path<(Uuid, Uuid)>,
+    Json(data): Json<UpdateVariant>,
+) -> impl IntoResponse {
+    let vendor_id = match authorize_vendor(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .variant_service
+        .update_variant(product_id, variant_id, vendor_id, data)
+        .await
+    {
+        Ok(variant) => Json(ApiResponse::success(variant, "Variant updated")).into_response(),
+        Err(e) => error_response(e, "Could not update variant"),
+    }
+}
+
+#[axum::debug_handler]
+async fn delete_variant(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path((product_id, variant_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let vendor_id = match authorize_vendor(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .variant_service
+        .delete_variant(product_id, variant_id, vendor_id)
+        .await
+    {
+        Ok(()) => Json(ApiResponse::<()>::success((), "Variant deleted")).into_response(),
+        Err(e) => error_response(e, "Could not delete variant"),
+    }
+}
@@ -0,0 +1,353 @@
+use crate::{
+    middleware::{auth::AuthUser, permission::require_permission},
+    models::{
+        audit_event::{AuditAction, AuditEntity},
+        role_permission::Permission,
+        user::UserRole,
+        vendor_application::{self, VendorApplicationStatus},
+    },
+    services::{
+        audit::{AuditActor, AuditRecord},
+        errors::ServiceError,
+        image::{ALLOWED_MIME_TYPES, MAX_FILE_SIZE},
+        vendor_application::NewVendorApplication,
+    },
+    state::AppState,
+    utils::{
+        policy::user_id,
+        rbac::require_role,
+        shared::{error_response, ApiResponse},
+    },
+};
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::{header::CONTENT_TYPE, StatusCode},
+    middleware::from_fn,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use uuid::Uuid;
+
+pub fn config() -> Router<AppState> {
+    let review = Router::new()
+        .route("/api/admins/vendor-applications", get(list_applications))
+        .route("/api/admins/vendor-applications/:id", get(get_application))
+        .route(
+            "/api/admins/vendor-applications/:id/document",
+            get(get_application_document),
+        )
+        .route(
+            "/api/admins/vendor-applications/:id/approve",
+            post(approve_application),
+        )
+        .route(
+            "/api/admins/vendor-applications/:id/reject",
+            post(reject_application),
+        )
+        .route_layer(from_fn(|req, next| {
+            require_permission(Permission::ReviewVendors, req, next)
+        }));
+
+    Router::new()
+        .route("/api/vendor/application", get(get_my_application))
+        .route("/api/vendor/application", post(submit_application))
+        .merge(review)
+}
+
+#[derive(Deserialize)]
+pub struct ListApplicationsQuery {
+    status: Option<VendorApplicationStatus>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ReviewApplicationRequest {
+    reason: Option<String>,
+}
+
+/// The caller's verification status and latest application
+#[derive(Serialize)]
+pub struct VendorStatus {
+    pub verified: bool,
+    pub application: Option<vendor_application::Model>,
+}
+
+/// Check the caller has one of `roles` and return their id
+fn authorize(auth: &AuthUser, roles: &[UserRole]) -> Result<Uuid, (StatusCode, &'static str)> {
+    require_role(auth, roles).and_then(|_| user_id(auth))
+}
+
+fn bad_request(msg: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(ApiResponse::<()>::error(msg))).into_response()
+}
+
+#[axum::debug_handler]
+async fn get_my_application(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let vendor_id = match authorize(&auth, &[UserRole::Vendor]) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    let verified = match state
+        .vendor_application_service
+        .ensure_verified_vendor(vendor_id)
+        .await
+    {
+        Ok(()) => true,
+        Err(ServiceError::Forbidden(_)) => false,
+        Err(e) => return error_response(e, "Could not retrieve application"),
+    };
+    match state
+        .vendor_application_service
+        .latest_application(vendor_id)
+        .await
+    {
+        Ok(application) => Json(ApiResponse::success(
+            VendorStatus {
+                verified,
+                application,
+            },
+            "Application retrieved",
+        ))
+        .into_response(),
+        Err(e) => error_response(e, "Could not retrieve application"),
+    }
+}
+
+/// Apply to sell, as a multipart form with `business_name`,
+/// `trade_registry_number`, `taxpayer_number` and an `id_document` image
+#[axum::debug_handler]
+async fn submit_application(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let vendor_id = match authorize(&auth, &[UserRole::Vendor]) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+
+    let mut business_name = String::new();
+    let mut trade_registry_number = String::new();
+    let mut taxpayer_number = String::new();
+    let mut id_document = None;
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return bad_request(&format!("Failed to process multipart form: {}", e)),
+        };
+        let name = field.name().unwrap_or_default().to_string();
+        if name == "id_document" {
+            let content_type = field.content_type().unwrap_or_default().to_string();
+            if !ALLOWED_MIME_TYPES.contains(&content_type.as_str()) {
+                return bad_request(&format!(
+                    "Invalid file type. Allowed types: {}",
+                    ALLOWED_MIME_TYPES.join(", ")
+                ));
+            }
+            match field.bytes().await {
+                Ok(data) if data.len() > MAX_FILE_SIZE => {
+                    return bad_request(&format!(
+                        "File size exceeds the maximum limit of {}MB",
+                        MAX_FILE_SIZE / 1024 / 1024
+                    ));
+                }
+                Ok(data) => id_document = Some((data.to_vec(), content_type)),
+                Err(e) => return bad_request(&format!("Failed to read file data: {}", e)),
+            }
+            continue;
+        }
+        let value = match field.text().await {
+            Ok(value) => value,
+            Err(e) => return bad_request(&format!("Failed to read {}: {}", name, e)),
+        };
+        match name.as_str() {
+            "business_name" => business_name = value,
+            "trade_registry_number" => trade_registry_number = value,
+            "taxpayer_number" => taxpayer_number = value,
+            _ => {}
+        }
+    }
+    let Some((data, content_type)) = id_document else {
+        return bad_request("An ID document is required");
+    };
+
+    let id_document = match state
+        .config
+        .vendor_document_service
+        .upload_image(data, &content_type)
+        .await
+    {
+        Ok(object_name) => object_name,
+        Err(e) => {
+            error!("Could not store ID document: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("Could not store ID document")),
+            )
+                .into_response();
+        }
+    };
+    let application = NewVendorApplication {
+        business_name,
+        trade_registry_number,
+        taxpayer_number,
+        id_document,
+    };
+    match state
+        .vendor_application_service
+        .submit(vendor_id, application)
+        .await
+    {
+        Ok(application) => (
+            StatusCode::CREATED,
+            Json(ApiResponse::success(
+                application,
+                "Application submitted for review",
+            )),
+        )
+            .into_response(),
+        Err(e) => error_response(e, "Could not submit application"),
+    }
+}
+
+#[axum::debug_handler]
+async fn list_applications(
+    State(state): State<AppState>,
+    Query(params): Query<ListApplicationsQuery>,
+) -> impl IntoResponse {
+    match state
+        .vendor_application_service
+        .list_applications(params.status)
+        .await
+    {
+        Ok(applications) => {
+            Json(ApiResponse::success(applications, "Applications retrieved")).into_response()
+        }
+        Err(e) => error_response(e, "Could not retrieve applications"),
+    }
+}
+
+#[axum::debug_handler]
+async fn get_application(
+    State(state): State<AppState>,
+    Path(application_id): Path<Uuid>,
+) -> impl IntoResponse {
+    match state
+        .vendor_application_service
+        .get_application(application_id)
+        .await
+    {
+        Ok(application) => {
+            Json(ApiResponse::success(application, "Application retrieved")).into_response()
+        }
+        Err(e) => error_response(e, "Could not retrieve application"),
+    }
+}
+
+/// The ID document of an application. Documents are kept in a private
+/// bucket and only served through this route.
+#[axum::debug_handler]
+async fn get_application_document(
+    State(state): State<AppState>,
+    Path(application_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let application = match state
+        .vendor_application_service
+        .get_application(application_id)
+        .await
+    {
+        Ok(application) => application,
+        Err(e) => return error_response(e, "Could not retrieve document"),
+    };
+    match state
+        .config
+        .vendor_document_service
+        .get_image(&application.id_document)
+        .await
+    {
+        Ok((data, content_type)) => ([(CONTENT_TYPE, content_type)], data).into_response(),
+        Err(e) => {
+            error!("Could not read ID document: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("Could not retrieve document")),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[axum::debug_handler]
+async fn approve_application(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    actor: AuditActor,
+    Path(application_id): Path<Uuid>,
+) -> impl IntoResponse {
+    review_application(state, auth, actor, application_id, true, None).await
+}
+
+#[axum::debug_handler]
+async fn reject_application(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    actor: AuditActor,
+    Path(application_id): Path<Uuid>,
+    payload: Option<Json<ReviewApplicationRequest>>,
+) -> impl IntoResponse {
+    let reason = payload.unwrap_or_default().0.reason;
+    review_application(state, auth, actor, application_id, false, reason).await
+}
+
+async fn review_application(
+    state: AppState,
+    auth: AuthUser,
+    actor: AuditActor,
+    application_id: Uuid,
+    approve: bool,
+    reason: Option<String>,
+) -> Response {
+    let reviewer_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    let before = match state
+        .vendor_application_service
+        .get_application(application_id)
+        .await
+    {
+        Ok(application) => application,
+        Err(e) => return error_response(e, "Could not review application"),
+    };
+    match state
+        .vendor_application_service
+        .review_application(application_id, reviewer_id, approve, reason)
+        .await
+    {
+        Ok(application) => {
+            let action = if approve {
+                AuditAction::VendorApplicationApproved
+            } else {
+                AuditAction::VendorApplicationRejected
+            };
+            let record = AuditRecord::new(action, AuditEntity::VendorApplication, application.id)
+                .before(&before)
+                .after(&application);
+            state.audit_service.record_or_log(&actor, record).await;
+            Json(ApiResponse::success(application, "Application reviewed")).into_response()
+        }
+        Err(e) => error_response(e, "Could not review application"),
+    }
+}
@@ -0,0 +1,323 @@
+use crate::{
+    middleware::{
+        auth::AuthUser,
+        rate_limit::{rate_limit, RateLimitGroup},
+    },
+    services::wishlist::NewWishlistItem,
+    state::AppState,
+    utils::{
+        policy::user_id,
+        shared::{error_response, ApiResponse},
+    },
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    middleware::from_fn,
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// The current user's wishlists, their items, share links and moves
+/// between wishlists and the cart
+pub fn config() -> Router<AppState> {
+    Router::new()
+        .route("/api/wishlists", get(list_wishlists).post(create_wishlist))
+        .route(
+            "/api/wishlists/:id",
+            get(get_wishlist)
+                .put(rename_wishlist)
+                .delete(delete_wishlist),
+        )
+        .route(
+            "/api/wishlists/:id/share",
+            post(share_wishlist).delete(unshare_wishlist),
+        )
+        .route("/api/wishlists/:id/items", post(add_item))
+        .route("/api/wishlists/:id/items/:item_id", delete(remove_item))
+        .route(
+            "/api/wishlists/:id/items/:item_id/move-to-cart",
+            post(move_to_cart),
+        )
+        .route(
+            "/api/cart/items/:item_id/save-for-later",
+            post(save_for_later),
+        )
+}
+
+/// Wishlists shared by link, open to visitors. It is mounted outside the
+/// auth layer, so it is rate limited here.
+pub fn public_config() -> Router<AppState> {
+    Router::new()
+        .route("/api/wishlists/shared/:token", get(get_shared_wishlist))
+        .route_layer(from_fn(|req, next| {
+            rate_limit(RateLimitGroup::Api, req, next)
+        }))
+}
+
+#[derive(Deserialize)]
+pub struct WishlistRequest {
+    name: String,
+}
+
+#[axum::debug_handler]
+async fn list_wishlists(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state.wishlist_service.list_wishlists(user_id).await {
+        Ok(wishlists) => {
+            Json(ApiResponse::success(wishlists, "Wishlists retrieved")).into_response()
+        }
+        Err(e) => error_response(e, "Could not retrieve wishlists"),
+    }
+}
+
+#[axum::debug_handler]
+async fn create_wishlist(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Json(request): Json<WishlistRequest>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .wishlist_service
+        .create_wishlist(user_id, &request.name)
+        .await
+    {
+        Ok(wishlist) => (
+            StatusCode::CREATED,
+            Json(ApiResponse::success(wishlist, "Wishlist created")),
+        )
+            .into_response(),
+        Err(e) => error_response(e, "Could not create wishlist"),
+    }
+}
+
+/// One of the caller's wishlists with its items
+#[axum::debug_handler]
+async fn get_wishlist(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(wishlist_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .wishlist_service
+        .get_wishlist(wishlist_id, user_id)
+        .await
+    {
+        Ok(wishlist) => Json(ApiResponse::success(wishlist, "Wishlist retrieved")).into_response(),
+        Err(e) => error_response(e, "Could not retrieve wishlist"),
+    }
+}
+
+#[axum::debug_handler]
+async fn rename_wishlist(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(wishlist_id): Path<Uuid>,
+    Json(request): Json<WishlistRequest>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .wishlist_service
+        .rename_wishlist(wishlist_id, user_id, &request.name)
+        .await
+    {
+        Ok(wishlist) => Json(ApiResponse::success(wishlist, "Wishlist renamed")).into_response(),
+        Err(e) => error_response(e, "Could not rename wishlist"),
+    }
+}
+
+#[axum::debug_handler]
+async fn delete_wishlist(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(wishlist_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .wishlist_service
+        .delete_wishlist(wishlist_id, user_id)
+        .await
+    {
+        Ok(()) => Json(ApiResponse::<()>::success((), "Wishlist deleted")).into_response(),
+        Err(e) => error_response(e, "Could not delete wishlist"),
+    }
+}
+
+/// Give a wishlist a share link, its `share_token`
+#[axum::debug_handler]
+async fn share_wishlist(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(wishlist_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state.wishlist_service.share(wishlist_id, user_id).await {
+        Ok(wishlist) => Json(ApiResponse::success(wishlist, "Wishlist shared")).into_response(),
+        Err(e) => error_response(e, "Could not share wishlist"),
+    }
+}
+
+#[axum::debug_handler]
+async fn unshare_wishlist(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(wishlist_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state.wishlist_service.unshare(wishlist_id, user_id).await {
+        Ok(wishlist) => Json(ApiResponse::success(wishlist, "Wishlist is private")).into_response(),
+        Err(e) => error_response(e, "Could not unshare wishlist"),
+    }
+}
+
+/// A wishlist shared by link, with its items
+#[axum::debug_handler]
+async fn get_shared_wishlist(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> impl IntoResponse {
+    match state.wishlist_service.get_shared(&token).await {
+        Ok(wishlist) => Json(ApiResponse::success(wishlist, "Wishlist retrieved")).into_response(),
+        Err(e) => error_response(e, "Could not retrieve wishlist"),
+    }
+}
+
+#[axum::debug_handler]
+async fn add_item(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(wishlist_id): Path<Uuid>,
+    Json(data): Json<NewWishlistItem>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .wishlist_service
+        .add_item(wishlist_id, user_id, data)
+        .await
+    {
+        Ok(item) => (
+            StatusCode::CREATED,
+            Json(ApiResponse::success(item, "Item added to wishlist")),
+        )
+            .into_response(),
+        Err(e) => error_response(e, "Could not add item to wishlist"),
+    }
+}
+
+#[axum::debug_handler]
+async fn remove_item(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path((wishlist_id, item_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .wishlist_service
+        .remove_item(wishlist_id, item_id, user_id)
+        .await
+    {
+        Ok(()) => {
+            Json(ApiResponse::<()>::success((), "Item removed from wishlist")).into_response()
+        }
+        Err(e) => error_response(e, "Could not remove item from wishlist"),
+    }
+}
+
+/// Move a wishlist item to the caller's cart, returning the cart line
+#[axum::debug_handler]
+async fn move_to_cart(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path((wishlist_id, item_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .wishlist_service
+        .move_to_cart(wishlist_id, item_id, user_id)
+        .await
+    {
+        Ok(line) => Json(ApiResponse::success(line, "Item moved to cart")).into_response(),
+        Err(e) => error_response(e, "Could not move item to cart"),
+    }
+}
+
+/// Move a cart line to the caller's "Saved for later" list
+#[axum::debug_handler]
+async fn save_for_later(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthUser>,
+    Path(item_id): Path<Uuid>,
+) -> impl IntoResponse {
+    let user_id = match user_id(&auth) {
+        Ok(id) => id,
+        Err((status, msg)) => {
+            return (status, Json(ApiResponse::<()>::error(msg))).into_response();
+        }
+    };
+    match state
+        .wishlist_service
+        .save_for_later(user_id, item_id)
+        .await
+    {
+        Ok(item) => Json(ApiResponse::success(item, "Item saved for later")).into_response(),
+        Err(e) => error_response(e, "Could not save item for later"),
+    }
+}
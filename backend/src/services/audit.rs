@@ -0,0 +1,260 @@
+use std::{convert::Infallible, sync::Arc};
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    middleware::{auth::AuthUser, request_id::RequestId},
+    models::{
+        audit_event::{self, AuditAction, AuditEntity, Model},
+        user::UserRole,
+    },
+};
+
+use super::errors::ServiceError;
+
+/// Events returned by one query when no limit is given
+const DEFAULT_LIMIT: u64 = 50;
+/// Most events returned by one query
+const MAX_LIMIT: u64 = 200;
+
+/// Who performed an audited action. Handlers take it as an extractor; it
+/// reads the authenticated user and the request id set by the middleware.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuditActor {
+    pub id: Option<Uuid>,
+    pub role: Option<UserRole>,
+    pub request_id: Option<String>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuditActor {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user = parts.extensions.get::<AuthUser>();
+        Ok(AuditActor {
+            id: user.and_then(|user| Uuid::parse_str(&user.id).ok()),
+            role: user.map(|user| user.role.clone()),
+            request_id: parts
+                .extensions
+                .get::<RequestId>()
+                .map(|RequestId(id)| id.clone()),
+        })
+    }
+}
+
+/// An action to record, with snapshots of the record it changed
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    pub action: AuditAction,
+    pub entity_type: AuditEntity,
+    pub entity_id: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl AuditRecord {
+    pub fn new(action: AuditAction, entity_type: AuditEntity, entity_id: impl ToString) -> Self {
+        Self {
+            action,
+            entity_type,
+            entity_id: entity_id.to_string(),
+            before: None,
+            after: None,
+        }
+    }
+
+    /// Snapshot of the record before the action
+    pub fn before(mut self, snapshot: &impl Serialize) -> Self {
+        self.before = serde_json::to_value(snapshot).ok();
+        self
+    }
+
+    /// Snapshot of the record after the action
+    pub fn after(mut self, snapshot: &impl Serialize) -> Self {
+        self.after = serde_json::to_value(snapshot).ok();
+        self
+    }
+}
+
+/// Filters for browsing the audit log. Events come newest first; pass the
+/// `created_at` of the last event seen as `before` to get the next page.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditEventFilter {
+    pub actor_id: Option<Uuid>,
+    pub action: Option<AuditAction>,
+    pub entity_type: Option<AuditEntity>,
+    pub entity_id: Option<String>,
+    pub request_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+    pub limit: Option<u64>,
+}
+
+/// Append-only log of admin and financial actions
+pub struct AuditService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl AuditService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    pub async fn record(
+        &self,
+        actor: &AuditActor,
+        record: AuditRecord,
+    ) -> Result<Model, ServiceError> {
+        let event = audit_event::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            actor_id: Set(actor.id),
+            actor_role: Set(actor.role.clone()),
+            action: Set(record.action),
+            entity_type: Set(record.entity_type),
+            entity_id: Set(record.entity_id),
+            before: Set(record.before),
+            after: Set(record.after),
+            request_id: Set(actor.request_id.clone()),
+            created_at: Set(Utc::now()),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        Ok(event)
+    }
+
+    /// Record an action that already happened. The change is not undone
+    /// when the event cannot be written, so the event goes to the error log
+    /// instead.
+    pub async fn record_or_log(&self, actor: &AuditActor, record: AuditRecord) {
+        let summary = format!(
+            "{} on {} {} by {:?} (request {:?})",
+            record.action, record.entity_type, record.entity_id, actor.id, actor.request_id
+        );
+        if let Err(e) = self.record(actor, record).await {
+            error!("could not write audit event {}: {}", summary, e);
+        }
+    }
+
+    /// Events matching `filter`, newest first
+    pub async fn list_events(&self, filter: AuditEventFilter) -> Result<Vec<Model>, ServiceError> {
+        let mut query = audit_event::Entity::find();
+        if let Some(actor_id) = filter.actor_id {
+            query = query.filter(audit_event::Column::ActorId.eq(actor_id));
+        }
+        if let Some(action) = filter.action {
+            query = query.filter(audit_event::Column::Action.eq(action));
+        }
+        if let Some(entity_type) = filter.entity_type {
+            query = query.filter(audit_event::Column::EntityType.eq(entity_type));
+        }
+        if let Some(entity_id) = filter.entity_id {
+            query = query.filter(audit_event::Column::EntityId.eq(entity_id));
+        }
+        if let Some(request_id) = filter.request_id {
+            query = query.filter(audit_event::Column::RequestId.eq(request_id));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(audit_event::Column::CreatedAt.gte(from));
+        }
+        if let Some(before) = filter.before {
+            query = query.filter(audit_event::Column::CreatedAt.lt(before));
+        }
+        let limit = filter.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let events = query
+            .order_by_desc(audit_event::Column::CreatedAt)
+            .limit(limit)
+            .all(self.db.as_ref())
+            .await?;
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use serde_json::json;
+
+    fn event(record: &AuditRecord, actor: &AuditActor) -> Model {
+        Model {
+            id: Uuid::new_v4(),
+            actor_id: actor.id,
+            actor_role: actor.role.clone(),
+            action: record.action,
+            entity_type: record.entity_type,
+            entity_id: record.entity_id.clone(),
+            before: record.before.clone(),
+            after: record.after.clone(),
+            request_id: actor.request_id.clone(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_event() {
+        let actor = AuditActor {
+            id: Some(Uuid::new_v4()),
+            role: Some(UserRole::Staff),
+            request_id: Some("req-1".to_string()),
+        };
+        let user_id = Uuid::new_v4();
+        let record = AuditRecord::new(AuditAction::UserStatusUpdated, AuditEntity::User, user_id)
+            .before(&json!({ "is_active": true }))
+            .after(&json!({ "is_active": false }));
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![event(&record, &actor)]])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = AuditService::new(db.clone());
+
+        let recorded = service.record(&actor, record).await.unwrap();
+        assert_eq!(recorded.entity_id, user_id.to_string());
+        assert_eq!(recorded.after, Some(json!({ "is_active": false })));
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let sql = log[0].statements()[0].to_string();
+        assert!(sql.starts_with("INSERT INTO \"audit_events\""));
+        assert!(sql.contains("'user_status_updated'"));
+        assert!(sql.contains("'req-1'"));
+    }
+
+    #[tokio::test]
+    async fn test_list_events_applies_filters() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![Vec::<Model>::new()])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = AuditService::new(db.clone());
+
+        service
+            .list_events(AuditEventFilter {
+                action: Some(AuditAction::PaymentRefunded),
+                entity_type: Some(AuditEntity::Payment),
+                limit: Some(1000),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let sql = log[0].statements()[0].to_string();
+        assert!(sql.contains("\"action\" = 'payment_refunded'"));
+        assert!(sql.contains("\"entity_type\" = 'payment'"));
+        assert!(sql.contains("ORDER BY \"audit_events\".\"created_at\" DESC"));
+        assert!(sql.ends_with("LIMIT 200"));
+    }
+}
@@ -1,15 +1,73 @@
 use std::sync::Arc;
 
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    Set,
+};
 use uuid::Uuid;
 
 use crate::models::{
     cart::{self, Model},
     cart_item::{self, CartItem},
+    product_variant,
 };
 
 use super::errors::ServiceError;
 
+/// Add an item to a cart, adding to the quantity of the same line if there
+/// is one. Runs on `db` so callers can make it part of a transaction.
+pub(crate) async fn add_item<C: ConnectionTrait>(
+    db: &C,
+    cart_id: Uuid,
+    item_data: CartItem,
+) -> Result<cart_item::Model, ServiceError> {
+    if let Some(variant_id) = item_data.variant_id {
+        product_variant::Entity::find_by_id(variant_id)
+            .filter(product_variant::Column::ProductId.eq(item_data.product_id))
+            .one(db)
+            .await?
+            .ok_or_else(|| {
+                ServiceError::Validation("Variant not found for this product".to_string())
+            })?;
+    }
+
+    // Check if item already exists in cart, the same variant being the
+    // same line
+    let existing_item = cart_item::Entity::find()
+        .filter(cart_item::Column::CartId.eq(cart_id))
+        .filter(cart_item::Column::ProductId.eq(item_data.product_id))
+        .filter(match item_data.variant_id {
+            Some(variant_id) => cart_item::Column::VariantId.eq(variant_id),
+            None => cart_item::Column::VariantId.is_null(),
+        })
+        .one(db)
+        .await?;
+
+    match existing_item {
+        Some(item) => {
+            // Update quantity if item exists
+            let mut active_model: cart_item::ActiveModel = item.clone().into();
+            active_model.quantity = Set(item.quantity + item_data.quantity);
+            let updated_item = active_model.update(db).await?;
+            Ok(updated_item)
+        }
+        None => {
+            // Create new item if it doesn't exist
+            let new_item = cart_item::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                cart_id: Set(cart_id),
+                product_id: Set(item_data.product_id),
+                variant_id: Set(item_data.variant_id),
+                quantity: Set(item_data.quantity),
+            }
+            .insert(db)
+            .await?;
+
+            Ok(new_item)
+        }
+    }
+}
+
 pub struct CartService {
     db: Arc<DatabaseConnection>,
 }
@@ -26,7 +84,7 @@ impl CartService {
             .await?;
 
         match cart {
-            Some(cart) => Ok(cart.into()),
+            Some(cart) => Ok(cart),
             None => {
                 let new_cart = cart::ActiveModel {
                     id: Set(Uuid::new_v4()),
@@ -37,7 +95,7 @@ impl CartService {
                 .insert(&*self.db)
                 .await?;
 
-                Ok(new_cart.into())
+                Ok(new_cart)
             }
         }
     }
@@ -47,43 +105,17 @@ impl CartService {
         cart_id: Uuid,
         item_data: CartItem,
     ) -> Result<cart_item::Model, ServiceError> {
-        // Check if item already exists in cart
-        let existing_item = cart_item::Entity::find()
-            .filter(cart_item::Column::CartId.eq(cart_id))
-            .filter(cart_item::Column::ProductId.eq(item_data.product_id))
-            .one(&*self.db)
-            .await?;
-
-        match existing_item {
-            Some(item) => {
-                // Update quantity if item exists
-                let mut active_model: cart_item::ActiveModel = item.clone().into();
-                active_model.quantity = Set(item.quantity + item_data.quantity);
-                let updated_item = active_model.update(&*self.db).await?;
-                Ok(updated_item)
-            }
-            None => {
-                // Create new item if it doesn't exist
-                let new_item = cart_item::ActiveModel {
-                    id: Set(Uuid::new_v4()),
-                    cart_id: Set(cart_id),
-                    product_id: Set(item_data.product_id),
-                    quantity: Set(item_data.quantity),
-                }
-                .insert(&*self.db)
-                .await?;
-
-                Ok(new_item.into())
-            }
-        }
+        add_item(&*self.db, cart_id, item_data).await
     }
 
+    /// Set the quantity of a cart line. A quantity of zero or less removes
+    /// the line, which is reported as `None`.
     pub async fn update_cart_item_quantity(
         &self,
         cart_id: Uuid,
         item_id: Uuid,
         quantity: i32,
-    ) -> Result<cart_item::Model, ServiceError> {
+    ) -> Result<Option<cart_item::Model>, ServiceError> {
         let item = cart_item::Entity::find()
             .filter(cart_item::Column::CartId.eq(cart_id))
             .filter(cart_item::Column::Id.eq(item_id))
@@ -96,13 +128,14 @@ impl CartService {
                 .exec(&*self.db)
                 .await
                 .map_err(|e| ServiceError::Validation(e.to_string()))?;
+            return Ok(None);
         }
         if let Some(item) = item {
             let mut active_model: cart_item::ActiveModel = item.into();
             active_model.quantity = Set(quantity);
             let updated_item = active_model.update(&*self.db).await?;
 
-            Ok(updated_item.into())
+            Ok(Some(updated_item))
         } else {
             Err(ServiceError::NotFound("Item not found".to_string()))
         }
@@ -144,28 +177,26 @@ impl CartService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mockall::predicate::*;
     use sea_orm::{MockDatabase, MockExecResult};
 
     #[tokio::test]
     async fn test_get_or_create_cart() {
+        let user_id = Uuid::new_v4();
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
-            // .append_query_results(vec![vec![]]) // Empty result for initial search
             .append_query_results(vec![vec![cart::Model {
                 id: Uuid::new_v4(),
-                user_id: Uuid::parse_str("test_user").unwrap(),
-
+                user_id,
                 created_at: chrono::Utc::now(),
             }]])
             .into_connection();
 
         let service = CartService::new(Arc::new(db));
 
-        let result = service.get_or_create_cart("test_session".to_string()).await;
+        let result = service.get_or_create_cart(user_id.to_string()).await;
         assert!(result.is_ok());
 
         let cart_response = result.unwrap();
-        assert_eq!(cart_response.user_id.to_string(), "test_session");
+        assert_eq!(cart_response.user_id, user_id);
     }
 
     #[tokio::test]
@@ -178,6 +209,7 @@ mod tests {
                 id: Uuid::new_v4(),
                 cart_id,
                 product_id,
+                variant_id: None,
                 quantity: 1,
             }]])
             .into_connection();
@@ -187,6 +219,7 @@ mod tests {
         let item_data = CartItem {
             cart_id,
             product_id,
+            variant_id: None,
             quantity: 1,
         };
 
@@ -199,6 +232,52 @@ mod tests {
         assert_eq!(item_response.quantity, 1);
     }
 
+    #[tokio::test]
+    async fn test_add_variant_to_cart() {
+        let cart_id = Uuid::new_v4();
+        let product_id = Uuid::new_v4();
+        let variant_id = Uuid::new_v4();
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![product_variant::Model {
+                id: variant_id,
+                product_id,
+                sku: "PAGNE-6".to_string(),
+                options: Default::default(),
+                price: crate::models::money::Money::xaf(11000),
+                quantity: 5,
+                image_urls: vec![],
+                position: 0,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            }]])
+            .append_query_results::<cart_item::Model, _, _>(vec![vec![]])
+            .append_query_results(vec![vec![cart_item::Model {
+                id: Uuid::new_v4(),
+                cart_id,
+                product_id,
+                variant_id: Some(variant_id),
+                quantity: 1,
+            }]])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = CartService::new(db.clone());
+
+        let item_data = CartItem {
+            cart_id,
+            product_id,
+            variant_id: Some(variant_id),
+            quantity: 1,
+        };
+
+        let item = service.add_item_to_cart(cart_id, item_data).await.unwrap();
+        assert_eq!(item.variant_id, Some(variant_id));
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let existing_sql = log[1].statements()[0].to_string();
+        assert!(existing_sql.contains(&format!("\"variant_id\" = '{}'", variant_id)));
+    }
+
     #[tokio::test]
     async fn test_update_cart_item_quantity() {
         let cart_id = Uuid::new_v4();
@@ -209,12 +288,14 @@ mod tests {
                 id: item_id,
                 cart_id,
                 product_id,
+                variant_id: None,
                 quantity: 1,
             }]])
             .append_query_results(vec![vec![cart_item::Model {
                 id: item_id,
                 cart_id,
                 product_id,
+                variant_id: None,
                 quantity: 2,
             }]])
             .into_connection();
@@ -224,7 +305,7 @@ mod tests {
         let result = service.update_cart_item_quantity(cart_id, item_id, 2).await;
         assert!(result.is_ok());
 
-        let item_response = result.unwrap();
+        let item_response = result.unwrap().unwrap();
         assert_eq!(item_response.quantity, 2);
     }
 
@@ -238,48 +319,52 @@ mod tests {
                 id: item_id,
                 cart_id,
                 product_id,
+                variant_id: None,
                 quantity: 1,
             }]])
-            .append_exec_results(vec![]) // Simulate successful deletion
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }]) // Simulate successful deletion
             .into_connection();
 
         let service = CartService::new(db.into());
 
         let result = service.update_cart_item_quantity(cart_id, item_id, 0).await;
-        assert!(result.is_err());
-        match result {
-            Err(ServiceError::Validation(msg)) => assert_eq!(msg, "Item removed from cart"),
-            _ => panic!("Expected ValidationError"),
-        }
+        assert!(matches!(result, Ok(None)));
     }
 
     #[tokio::test]
     async fn test_get_cart() {
         let cart_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
             .append_query_results(vec![vec![cart::Model {
                 id: cart_id,
-                user_id: Uuid::parse_str("test_session").unwrap(),
+                user_id,
                 created_at: chrono::Utc::now(),
             }]])
             .into_connection();
 
         let service = CartService::new(db.into());
 
-        let result = service.get_cart(cart_id.to_string()).await;
+        let result = service.get_cart(user_id.to_string()).await;
         assert!(result.is_ok());
 
         let cart_response = result.unwrap();
         let cart_response = cart_response.unwrap();
         assert_eq!(cart_response.id, cart_id);
-        assert_eq!(cart_response.user_id.to_string(), "test_session");
+        assert_eq!(cart_response.user_id, user_id);
     }
 
     #[tokio::test]
     async fn test_clear_cart() {
         let cart_id = Uuid::new_v4();
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
-            .append_exec_results(vec![]) // Simulate successful deletion
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }]) // Simulate successful deletion
             .into_connection();
 
         let service = CartService::new(db.into());
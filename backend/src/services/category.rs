@@ -0,0 +1,610 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
+
+use crate::models::{
+    category::{self, Model},
+    product::{self, ModerationStatus},
+};
+
+use super::{errors::ServiceError, store::slugify};
+
+/// Longest slug accepted in a category URL
+const MAX_SLUG_LEN: usize = 60;
+
+/// Ids of the category whose slug is `$1` and of every category below it
+pub(crate) const SUBTREE_OF_SLUG: &str = "WITH RECURSIVE subtree AS (\
+    SELECT id FROM categories WHERE slug = $1 \
+    UNION ALL SELECT categories.id FROM categories \
+    JOIN subtree ON categories.parent_id = subtree.id) \
+    SELECT id FROM subtree";
+
+#[derive(Deserialize, Debug)]
+pub struct CreateCategory {
+    /// Category to file the new one under, empty for a top-level category
+    pub parent_id: Option<Uuid>,
+    /// Derived from the English name when left out
+    pub slug: Option<String>,
+    pub name_en: String,
+    pub name_fr: String,
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub sort_order: i32,
+}
+
+/// Partial category update. Fields left out are unchanged, an empty icon
+/// clears it and a null `parent_id` moves the category to the top level.
+#[derive(Deserialize, Debug, Default)]
+pub struct UpdateCategory {
+    #[serde(default, deserialize_with = "present")]
+    pub parent_id: Option<Option<Uuid>>,
+    pub slug: Option<String>,
+    pub name_en: Option<String>,
+    pub name_fr: Option<String>,
+    pub icon: Option<String>,
+    pub sort_order: Option<i32>,
+}
+
+/// Tell a field sent as null apart from a field left out
+fn present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+/// A category with the categories below it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: Model,
+    /// Approved products in the category and every category below it
+    pub product_count: u64,
+    pub children: Vec<CategoryNode>,
+}
+
+/// A category as buyers browse it
+#[derive(Debug, Serialize)]
+pub struct CategoryPage {
+    /// Categories above this one, from the top level down to its parent
+    pub ancestors: Vec<Model>,
+    pub category: CategoryNode,
+}
+
+/// Check `category_id` names a category, for ids sent in a request body
+pub(crate) async fn ensure_category_exists<C: ConnectionTrait>(
+    db: &C,
+    category_id: Uuid,
+) -> Result<(), ServiceError> {
+    if category::Entity::find_by_id(category_id)
+        .one(db)
+        .await?
+        .is_none()
+    {
+        return Err(ServiceError::Validation("Category not found".to_string()));
+    }
+    Ok(())
+}
+
+fn validate_slug(slug: &str) -> Result<(), ServiceError> {
+    let valid = !slug.is_empty()
+        && slug.len() <= MAX_SLUG_LEN
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-');
+    if !valid {
+        return Err(ServiceError::Validation(format!(
+            "Category slug must be 1 to {} lowercase letters, digits or dashes",
+            MAX_SLUG_LEN
+        )));
+    }
+    Ok(())
+}
+
+fn required_name(name: &str, language: &str) -> Result<String, ServiceError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ServiceError::Validation(format!(
+            "The {} name cannot be empty",
+            language
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// Arrange `categories`, sorted siblings first, into trees and add up the
+/// product counts of each subtree
+fn build_tree(categories: Vec<Model>, counts: &HashMap<Uuid, u64>) -> Vec<CategoryNode> {
+    fn nodes(
+        parent_id: Option<Uuid>,
+        children: &mut HashMap<Option<Uuid>, Vec<Model>>,
+        counts: &HashMap<Uuid, u64>,
+    ) -> Vec<CategoryNode> {
+        children
+            .remove(&parent_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|category| {
+                let children = nodes(Some(category.id), children, counts);
+                let product_count = counts.get(&category.id).copied().unwrap_or(0)
+                    + children
+                        .iter()
+                        .map(|child| child.product_count)
+                        .sum::<u64>();
+                CategoryNode {
+                    category,
+                    product_count,
+                    children,
+                }
+            })
+            .collect()
+    }
+
+    let mut children: HashMap<Option<Uuid>, Vec<Model>> = HashMap::new();
+    for category in categories {
+        children
+            .entry(category.parent_id)
+            .or_default()
+            .push(category);
+    }
+    nodes(None, &mut children, counts)
+}
+
+/// Take the node at `slug` out of `nodes`, collecting the categories above it
+fn find_subtree(
+    nodes: Vec<CategoryNode>,
+    slug: &str,
+    ancestors: &mut Vec<Model>,
+) -> Option<CategoryNode> {
+    for node in nodes {
+        if node.category.slug == slug {
+            return Some(node);
+        }
+        ancestors.push(node.category);
+        if let Some(found) = find_subtree(node.children, slug, ancestors) {
+            return Some(found);
+        }
+        ancestors.pop();
+    }
+    None
+}
+
+/// The product taxonomy: browsing for buyers, upkeep for staff
+pub struct CategoryService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl CategoryService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    pub async fn get_category(&self, category_id: Uuid) -> Result<Model, ServiceError> {
+        category::Entity::find_by_id(category_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Category not found".to_string()))
+    }
+
+    async fn all_categories(&self) -> Result<Vec<Model>, ServiceError> {
+        Ok(category::Entity::find()
+            .order_by_asc(category::Column::SortOrder)
+            .order_by_asc(category::Column::NameEn)
+            .all(self.db.as_ref())
+            .await?)
+    }
+
+    async fn ensure_slug_available(
+        &self,
+        slug: &str,
+        category_id: Option<Uuid>,
+    ) -> Result<(), ServiceError> {
+        let existing = category::Entity::find()
+            .filter(category::Column::Slug.eq(slug))
+            .one(self.db.as_ref())
+            .await?;
+        if existing.is_some_and(|existing| Some(existing.id) != category_id) {
+            return Err(ServiceError::Validation(
+                "Category slug is already taken".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether `candidate` is `category_id` or one of the categories below it
+    async fn is_within(&self, candidate: Uuid, category_id: Uuid) -> Result<bool, ServiceError> {
+        let parents: HashMap<Uuid, Option<Uuid>> = self
+            .all_categories()
+            .await?
+            .into_iter()
+            .map(|category| (category.id, category.parent_id))
+            .collect();
+        let mut current = Some(candidate);
+        // The tree has no cycles, the bound only guards against bad data
+        for _ in 0..=parents.len() {
+            match current {
+                Some(id) if id == category_id => return Ok(true),
+                Some(id) => current = parents.get(&id).copied().flatten(),
+                None => return Ok(false),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Approved products per category, not counting subcategories
+    async fn product_counts(&self) -> Result<HashMap<Uuid, u64>, ServiceError> {
+        let counts: Vec<(Uuid, i64)> = product::Entity::find()
+            .select_only()
+            .column(product::Column::CategoryId)
+            .column_as(product::Column::Id.count(), "product_count")
+            .filter(product::Column::ModerationStatus.eq(ModerationStatus::Approved))
+            .filter(product::Column::CategoryId.is_not_null())
+            .group_by(product::Column::CategoryId)
+            .into_tuple()
+            .all(self.db.as_ref())
+            .await?;
+        Ok(counts
+            .into_iter()
+            .map(|(category_id, count)| (category_id, count as u64))
+            .collect())
+    }
+
+    /// Every category, top-level ones first, with the approved products of
+    /// each subtree counted
+    pub async fn tree(&self) -> Result<Vec<CategoryNode>, ServiceError> {
+        let categories = self.all_categories().await?;
+        let counts = self.product_counts().await?;
+        Ok(build_tree(categories, &counts))
+    }
+
+    /// The category at `slug` with its subcategories and the path to it
+    pub async fn get_page(&self, slug: &str) -> Result<CategoryPage, ServiceError> {
+        let mut ancestors = Vec::new();
+        let category = find_subtree(self.tree().await?, slug, &mut ancestors)
+            .ok_or_else(|| ServiceError::NotFound("Category not found".to_string()))?;
+        Ok(CategoryPage {
+            ancestors,
+            category,
+        })
+    }
+
+    pub async fn create_category(&self, data: CreateCategory) -> Result<Model, ServiceError> {
+        let name_en = required_name(&data.name_en, "English")?;
+        let name_fr = required_name(&data.name_fr, "French")?;
+        let slug = match data.slug.map(|slug| slug.trim().to_string()) {
+            Some(slug) if !slug.is_empty() => slug,
+            _ => slugify(&name_en),
+        };
+        validate_slug(&slug)?;
+        if let Some(parent_id) = data.parent_id {
+            ensure_category_exists(self.db.as_ref(), parent_id).await?;
+        }
+        self.ensure_slug_available(&slug, None).await?;
+
+        let now = Utc::now();
+        let category = category::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            parent_id: Set(data.parent_id),
+            slug: Set(slug),
+            name_en: Set(name_en),
+            name_fr: Set(name_fr),
+            icon: Set(data
+                .icon
+                .map(|icon| icon.trim().to_string())
+                .filter(|icon| !icon.is_empty())),
+            sort_order: Set(data.sort_order),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        Ok(category)
+    }
+
+    pub async fn update_category(
+        &self,
+        category_id: Uuid,
+        data: UpdateCategory,
+    ) -> Result<Model, ServiceError> {
+        let category = self.get_category(category_id).await?;
+        let mut active_model: category::ActiveModel = category.into();
+
+        if let Some(parent_id) = data.parent_id {
+            if let Some(parent_id) = parent_id {
+                ensure_category_exists(self.db.as_ref(), parent_id).await?;
+                if self.is_within(parent_id, category_id).await? {
+                    return Err(ServiceError::Validation(
+                        "A category cannot be moved below itself".to_string(),
+                    ));
+                }
+            }
+            active_model.parent_id = Set(parent_id);
+        }
+        if let Some(slug) = data.slug {
+            let slug = slug.trim().to_string();
+            validate_slug(&slug)?;
+            self.ensure_slug_available(&slug, Some(category_id)).await?;
+            active_model.slug = Set(slug);
+        }
+        if let Some(name_en) = data.name_en {
+            active_model.name_en = Set(required_name(&name_en, "English")?);
+        }
+        if let Some(name_fr) = data.name_fr {
+            active_model.name_fr = Set(required_name(&name_fr, "French")?);
+        }
+        if let Some(icon) = data.icon {
+            let icon = icon.trim().to_string();
+            active_model.icon = Set((!icon.is_empty()).then_some(icon));
+        }
+        if let Some(sort_order) = data.sort_order {
+            active_model.sort_order = Set(sort_order);
+        }
+        active_model.updated_at = Set(Utc::now());
+
+        Ok(active_model.update(self.db.as_ref()).await?)
+    }
+
+    /// Delete an empty category. Categories with subcategories or products
+    /// have to be emptied or merged into another first.
+    pub async fn delete_category(&self, category_id: Uuid) -> Result<Model, ServiceError> {
+        let category = self.get_category(category_id).await?;
+        let subcategories = category::Entity::find()
+            .filter(category::Column::ParentId.eq(category_id))
+            .count(self.db.as_ref())
+            .await?;
+        if subcategories > 0 {
+            return Err(ServiceError::Validation(
+                "Move or delete the subcategories of this category first".to_string(),
+            ));
+        }
+        let products = product::Entity::find()
+            .filter(product::Column::CategoryId.eq(category_id))
+            .count(self.db.as_ref())
+            .await?;
+        if products > 0 {
+            return Err(ServiceError::Validation(
+                "This category still holds products, merge it into another category".to_string(),
+            ));
+        }
+
+        category.clone().delete(self.db.as_ref()).await?;
+        Ok(category)
+    }
+
+    /// Move the products and subcategories of a category into `target_id`
+    /// and delete it, such as to fold a translation into the original
+    pub async fn merge_category(
+        &self,
+        category_id: Uuid,
+        target_id: Uuid,
+    ) -> Result<(Model, Model), ServiceError> {
+        let category = self.get_category(category_id).await?;
+        let target = self.get_category(target_id).await?;
+        if self.is_within(target_id, category_id).await? {
+            return Err(ServiceError::Validation(
+                "A category cannot be merged into itself or a category below it".to_string(),
+            ));
+        }
+
+        let txn = self.db.begin().await?;
+        product::Entity::update_many()
+            .col_expr(product::Column::CategoryId, Expr::value(target_id))
+            .filter(product::Column::CategoryId.eq(category_id))
+            .exec(&txn)
+            .await?;
+        category::Entity::update_many()
+            .col_expr(category::Column::ParentId, Expr::value(target_id))
+            .col_expr(category::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(category::Column::ParentId.eq(category_id))
+            .exec(&txn)
+            .await?;
+        category.clone().delete(&txn).await?;
+        txn.commit().await?;
+
+        Ok((category, target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Value};
+    use std::collections::BTreeMap;
+
+    fn category(slug: &str, parent_id: Option<Uuid>, sort_order: i32) -> Model {
+        Model {
+            id: Uuid::new_v4(),
+            parent_id,
+            slug: slug.to_string(),
+            name_en: slug.to_string(),
+            name_fr: slug.to_string(),
+            icon: None,
+            sort_order,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn product_count(category_id: Uuid, count: i64) -> BTreeMap<&'static str, Value> {
+        BTreeMap::from([
+            ("category_id", category_id.into()),
+            ("product_count", count.into()),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_category_page_counts_products_of_the_subtree() {
+        let crafts = category("crafts", None, 0);
+        let pottery = category("pottery", Some(crafts.id), 1);
+        let baskets = category("baskets", Some(crafts.id), 0);
+        let vases = category("vases", Some(pottery.id), 0);
+        let food = category("food", None, 1);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![
+                crafts.clone(),
+                baskets.clone(),
+                vases.clone(),
+                food.clone(),
+                pottery.clone(),
+            ]])
+            .append_query_results([vec![
+                product_count(crafts.id, 1),
+                product_count(pottery.id, 2),
+                product_count(vases.id, 4),
+                product_count(baskets.id, 3),
+            ]])
+            .into_connection();
+        let service = CategoryService::new(Arc::new(db));
+
+        let page = service.get_page("pottery").await.unwrap();
+
+        assert_eq!(page.ancestors, vec![crafts.clone()]);
+        assert_eq!(page.category.category, pottery);
+        assert_eq!(page.category.product_count, 6);
+        assert_eq!(page.category.children.len(), 1);
+        assert_eq!(page.category.children[0].category, vases);
+        assert_eq!(page.category.children[0].product_count, 4);
+
+        let counts = HashMap::from([(pottery.id, 2), (vases.id, 4), (baskets.id, 3)]);
+        let tree = build_tree(
+            vec![crafts.clone(), baskets, pottery, vases, food.clone()],
+            &counts,
+        );
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree[0].product_count, 9);
+        assert_eq!(tree[1].category, food);
+        assert_eq!(tree[1].product_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_category_derives_slug_from_english_name() {
+        let parent = category("crafts", None, 0);
+        let mut created = category("woven-baskets", Some(parent.id), 2);
+        created.name_en = "Woven Baskets".to_string();
+        created.name_fr = "Paniers tressés".to_string();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![parent.clone()]])
+            .append_query_results([Vec::<Model>::new()])
+            .append_query_results([vec![created.clone()]])
+            .into_connection();
+        let service = CategoryService::new(Arc::new(db));
+
+        let category = service
+            .create_category(CreateCategory {
+                parent_id: Some(parent.id),
+                slug: None,
+                name_en: " Woven Baskets ".to_string(),
+                name_fr: "Paniers tressés".to_string(),
+                icon: Some(String::new()),
+                sort_order: 2,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(category, created);
+        let db = Arc::into_inner(service.db).unwrap();
+        let log = db.into_transaction_log();
+        let insert = log.last().unwrap().statements()[0].to_string();
+        assert!(insert.contains("'woven-baskets'"), "{}", insert);
+        assert!(insert.contains("'Woven Baskets'"), "{}", insert);
+    }
+
+    #[tokio::test]
+    async fn test_category_cannot_move_below_itself() {
+        let crafts = category("crafts", None, 0);
+        let pottery = category("pottery", Some(crafts.id), 0);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![crafts.clone()]])
+            .append_query_results([vec![pottery.clone()]])
+            .append_query_results([vec![crafts.clone(), pottery.clone()]])
+            .into_connection();
+        let service = CategoryService::new(Arc::new(db));
+
+        let result = service
+            .update_category(
+                crafts.id,
+                UpdateCategory {
+                    parent_id: Some(Some(pottery.id)),
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        let update: UpdateCategory = serde_json::from_str(r#"{"parent_id": null}"#).unwrap();
+        assert_eq!(update.parent_id, Some(None));
+        let update: UpdateCategory = serde_json::from_str("{}").unwrap();
+        assert_eq!(update.parent_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_category_with_products_cannot_be_deleted() {
+        let crafts = category("crafts", None, 0);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![crafts.clone()]])
+            .append_query_results([[BTreeMap::from([("num_items", Value::from(0i64))])]])
+            .append_query_results([[BTreeMap::from([("num_items", Value::from(3i64))])]])
+            .into_connection();
+        let service = CategoryService::new(Arc::new(db));
+
+        let result = service.delete_category(crafts.id).await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_merge_category_moves_products_and_subcategories() {
+        let crafts = category("crafts", None, 0);
+        let artisanat = category("artisanat", None, 0);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![artisanat.clone()]])
+            .append_query_results([vec![crafts.clone()]])
+            .append_query_results([vec![crafts.clone(), artisanat.clone()]])
+            .append_exec_results([
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 4,
+                },
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 0,
+                },
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+            ])
+            .into_connection();
+        let service = CategoryService::new(Arc::new(db));
+
+        let (merged, target) = service
+            .merge_category(artisanat.id, crafts.id)
+            .await
+            .unwrap();
+
+        assert_eq!(merged, artisanat);
+        assert_eq!(target, crafts);
+        let db = Arc::into_inner(service.db).unwrap();
+        let log = db.into_transaction_log();
+        let statements = log.last().unwrap().statements();
+        assert!(statements[1].to_string().starts_with("UPDATE \"products\""));
+        assert!(statements[2]
+            .to_string()
+            .starts_with("UPDATE \"categories\""));
+        assert!(statements[3]
+            .to_string()
+            .starts_with("DELETE FROM \"categories\""));
+    }
+}
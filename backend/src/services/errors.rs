@@ -20,6 +20,12 @@ pub enum ServiceError {
     UserNotFound(String),
     #[error("Invalid password")]
     InvalidPassword,
+    #[error("Payment gateway error: {0}")]
+    PaymentGateway(String),
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+    #[error("Conflict: {0}")]
+    Conflict(String),
 }
 
 impl From<sea_orm::DbErr> for ServiceError {
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use axum::{
     extract::{Multipart, State},
-    http::StatusCode,
+    http::{header::CONTENT_TYPE, StatusCode},
     response::{IntoResponse, Response},
 };
 
@@ -10,19 +10,26 @@ use uuid::Uuid;
 
 use crate::state::AppState;
 
-const ALLOWED_MIME_TYPES: [&str; 4] = ["image/jpeg", "image/png", "image/gif", "image/webp"];
-const MAX_FILE_SIZE: usize = 2 * 1024 * 1024; // 2MB
+pub(crate) const ALLOWED_MIME_TYPES: [&str; 4] =
+    ["image/jpeg", "image/png", "image/gif", "image/webp"];
+pub(crate) const MAX_FILE_SIZE: usize = 2 * 1024 * 1024; // 2MB
 
 #[derive(Debug, Clone, Default)]
 pub struct ImageService {
     pub client: Client,
     pub bucket: String,
+    /// Whether anyone may read the bucket. Private buckets are read through
+    /// presigned URLs.
+    pub public: bool,
 }
 
 impl ImageService {
     pub async fn ensure_bucket_exists(&self) -> Result<()> {
         if !self.client.bucket_exists(&self.bucket).send().await?.exists {
             self.client.create_bucket(&self.bucket).send().await?;
+            if !self.public {
+                return Ok(());
+            }
             // Set bucket policy for public read access
             let policy = r#"
             {
@@ -92,6 +99,25 @@ impl ImageService {
 
         Ok(file_path)
     }
+
+    /// Read `object_name` back with its content type, for buckets that
+    /// are not public
+    pub async fn get_image(&self, object_name: &str) -> Result<(Vec<u8>, String)> {
+        let response = self
+            .client
+            .get_object(&self.bucket, object_name)
+            .send()
+            .await?;
+        let content_type = response
+            .headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+        let data = response.content.to_segmented_bytes().await?.to_bytes();
+
+        Ok((data.to_vec(), content_type))
+    }
 }
 
 pub async fn handle_image_upload(
@@ -145,7 +171,7 @@ pub async fn handle_image_upload(
             }
 
             let object_name = image_service
-                .upload_image((&data).to_vec(), &content_type)
+                .upload_image(data.to_vec(), &content_type)
                 .await
                 .map_err(|e| {
                     (
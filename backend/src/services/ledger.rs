@@ -0,0 +1,1229 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::{Alias, Expr, Query},
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, JoinType,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set, TransactionTrait,
+};
+use serde::Serialize;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::models::{
+    ledger_entry::{self, LedgerAccount},
+    ledger_transaction::{self, LedgerTransactionKind},
+    money::Money,
+    order::Status,
+    payment::{self, PaymentStatus},
+    payout::{self, PayoutStatus},
+    phone::PhoneNumber,
+    user, vendor_order,
+};
+
+use super::errors::ServiceError;
+
+/// Double-entry ledger of the money flowing through the marketplace.
+///
+/// Every completed payment debits the gateway clearing account and credits
+/// each vendor with their sub-total minus the platform commission, which is
+/// credited to the platform. Cancelling a paid vendor order moves its share
+/// to refunds payable, refunds reverse all of it and approved payouts move
+/// funds from a vendor's balance back out of clearing.
+pub struct LedgerService {
+    pub db: Arc<DatabaseConnection>,
+    /// Share of each vendor sub-total kept by the platform, between 0 and 1
+    pub commission_rate: Decimal,
+}
+
+/// A line of a balanced posting
+struct Line {
+    account: LedgerAccount,
+    vendor_id: Option<Uuid>,
+    amount: Money,
+}
+
+/// What a vendor is owed and how much of it can be withdrawn
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VendorBalance {
+    pub vendor_id: Uuid,
+    /// Amount owed to the vendor according to the ledger
+    pub balance: Money,
+    /// Amount held by payouts waiting for approval
+    pub pending_payouts: Money,
+    /// Amount the vendor may still request
+    pub available: Money,
+}
+
+/// A posting to a vendor's account, positive when the vendor is credited
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatementLine {
+    pub transaction_id: Uuid,
+    pub kind: Option<LedgerTransactionKind>,
+    pub description: Option<String>,
+    pub payment_id: Option<Uuid>,
+    pub payout_id: Option<Uuid>,
+    pub amount: Money,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+/// Totals of the ledger checked against the payments and payouts tables
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LedgerReconciliation {
+    /// Sum of the completed payments applied to their order
+    pub collected: Money,
+    /// Sum of the approved payouts
+    pub paid_out: Money,
+    /// Debit balance of the gateway clearing account
+    pub gateway_clearing: Money,
+    /// Credit balance of all vendor accounts
+    pub vendor_payable: Money,
+    /// Credit balance of the platform commission account
+    pub platform_commission: Money,
+    /// Credit balance owed back to buyers of cancelled vendor orders
+    pub refunds_payable: Money,
+    /// Completed payments that have not been posted yet
+    pub unposted_payments: usize,
+    /// Whether the ledger matches the payments and payouts tables
+    pub balanced: bool,
+}
+
+/// Record a balanced ledger transaction. Lines with a zero amount are
+/// dropped and the remaining lines must add up to zero.
+async fn post<C: ConnectionTrait>(
+    db: &C,
+    kind: LedgerTransactionKind,
+    payment_id: Option<Uuid>,
+    payout_id: Option<Uuid>,
+    vendor_order_id: Option<Uuid>,
+    description: String,
+    lines: Vec<Line>,
+) -> Result<ledger_transaction::Model, ServiceError> {
+    let lines: Vec<Line> = lines
+        .into_iter()
+        .filter(|line| line.amount != Money::ZERO)
+        .collect();
+    let total: Money = lines.iter().map(|line| line.amount).sum();
+    if total != Money::ZERO {
+        return Err(ServiceError::GenericError(format!(
+            "Ledger posting \"{}\" is unbalanced by {}",
+            description, total
+        )));
+    }
+
+    let now = Utc::now();
+    let transaction = ledger_transaction::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        kind: Set(kind),
+        payment_id: Set(payment_id),
+        payout_id: Set(payout_id),
+        vendor_order_id: Set(vendor_order_id),
+        description: Set(description),
+        created_at: Set(now),
+    }
+    .insert(db)
+    .await?;
+
+    for line in lines {
+        ledger_entry::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            transaction_id: Set(transaction.id),
+            account: Set(line.account),
+            vendor_id: Set(line.vendor_id),
+            amount: Set(line.amount),
+            created_at: Set(now),
+        }
+        .insert(db)
+        .await?;
+    }
+
+    Ok(transaction)
+}
+
+/// `SUM` of a money column. Postgres sums `BIGINT` as `NUMERIC`, so the
+/// result is cast back to read it as [`Money`].
+fn money_sum(column: impl sea_orm::sea_query::IntoColumnRef) -> sea_orm::sea_query::SimpleExpr {
+    Expr::col(column).sum().cast_as(Alias::new("bigint"))
+}
+
+/// Sum the entries of an account, optionally for a single vendor
+async fn account_total<C: ConnectionTrait>(
+    db: &C,
+    account: LedgerAccount,
+    vendor_id: Option<Uuid>,
+) -> Result<Money, ServiceError> {
+    let mut query = ledger_entry::Entity::find()
+        .select_only()
+        .column_as(money_sum(ledger_entry::Column::Amount), "total")
+        .filter(ledger_entry::Column::Account.eq(account));
+    if let Some(vendor_id) = vendor_id {
+        query = query.filter(ledger_entry::Column::VendorId.eq(vendor_id));
+    }
+    let total: Option<Option<Money>> = query.into_tuple().one(db).await?;
+
+    Ok(total.flatten().unwrap_or_default())
+}
+
+/// Sum the payouts of a vendor in the given status
+async fn payout_total<C: ConnectionTrait>(
+    db: &C,
+    vendor_id: Option<Uuid>,
+    status: PayoutStatus,
+) -> Result<Money, ServiceError> {
+    let mut query = payout::Entity::find()
+        .select_only()
+        .column_as(money_sum(payout::Column::Amount), "total")
+        .filter(payout::Column::Status.eq(status));
+    if let Some(vendor_id) = vendor_id {
+        query = query.filter(payout::Column::VendorId.eq(vendor_id));
+    }
+    let total: Option<Option<Money>> = query.into_tuple().one(db).await?;
+
+    Ok(total.flatten().unwrap_or_default())
+}
+
+/// Compute the balance of a vendor within `db`
+async fn vendor_balance<C: ConnectionTrait>(
+    db: &C,
+    vendor_id: Uuid,
+) -> Result<VendorBalance, ServiceError> {
+    // Vendor accounts are liabilities, so their balance is the credit side
+    let balance = -account_total(db, LedgerAccount::VendorPayable, Some(vendor_id)).await?;
+    let pending_payouts = payout_total(db, Some(vendor_id), PayoutStatus::Requested).await?;
+
+    Ok(VendorBalance {
+        vendor_id,
+        balance,
+        pending_payouts,
+        available: balance - pending_payouts,
+    })
+}
+
+/// Payments applied to their order. The others are never posted and are
+/// refunded, see [`payment::Model::unapplied_reason`].
+const APPLIED: &str = "(payments.payment_details ->> 'unapplied_reason') IS NULL";
+
+/// Move the share of a cancelled vendor order out of its vendor's balance
+/// and the platform commission, to refunds payable until the buyer is paid
+/// back. Vendor orders of an order that is unpaid, or already refunded,
+/// have nothing to move.
+pub(crate) async fn record_vendor_order_cancellation<C: ConnectionTrait>(
+    db: &C,
+    vendor_order: &vendor_order::Model,
+) -> Result<Option<ledger_transaction::Model>, ServiceError> {
+    let Some(posting) = ledger_transaction::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            ledger_transaction::Relation::Payment.def(),
+        )
+        .filter(payment::Column::OrderId.eq(vendor_order.order_id))
+        .filter(ledger_transaction::Column::Kind.eq(LedgerTransactionKind::Payment))
+        .filter(ledger_transaction::Column::PaymentId.not_in_subquery(refunded_payments()))
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let owed: Money = ledger_entry::Entity::find()
+        .filter(ledger_entry::Column::TransactionId.eq(posting.id))
+        .filter(ledger_entry::Column::Account.eq(LedgerAccount::VendorPayable))
+        .filter(ledger_entry::Column::VendorId.eq(vendor_order.vendor_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|entry| -entry.amount)
+        .sum();
+    if owed == Money::ZERO {
+        return Ok(None);
+    }
+
+    let cancellation = post(
+        db,
+        LedgerTransactionKind::Cancellation,
+        posting.payment_id,
+        None,
+        Some(vendor_order.id),
+        format!("Vendor order {} cancelled", vendor_order.id),
+        vec![
+            Line {
+                account: LedgerAccount::VendorPayable,
+                vendor_id: Some(vendor_order.vendor_id),
+                amount: owed,
+            },
+            Line {
+                account: LedgerAccount::PlatformCommission,
+                vendor_id: None,
+                amount: vendor_order.subtotal - owed,
+            },
+            Line {
+                account: LedgerAccount::RefundsPayable,
+                vendor_id: None,
+                amount: -vendor_order.subtotal,
+            },
+        ],
+    )
+    .await?;
+
+    Ok(Some(cancellation))
+}
+
+impl LedgerService {
+    pub fn new(db: Arc<DatabaseConnection>, commission_rate: Decimal) -> Self {
+        Self {
+            db,
+            commission_rate,
+        }
+    }
+
+    /// Post a completed payment, splitting it between the vendors of its
+    /// order and the platform. Anything not owed to a vendor, including
+    /// rounding, is kept as platform commission so the posting balances.
+    /// Cancelled vendor orders are left out, and an order is only posted
+    /// once unless its earlier payment was refunded.
+    pub(crate) async fn record_payment<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        payment: &payment::Model,
+    ) -> Result<ledger_transaction::Model, ServiceError> {
+        let earlier = ledger_transaction::Entity::find()
+            .join(
+                JoinType::InnerJoin,
+                ledger_transaction::Relation::Payment.def(),
+            )
+            .filter(payment::Column::OrderId.eq(payment.order_id))
+            .filter(ledger_transaction::Column::Kind.eq(LedgerTransactionKind::Payment))
+            .filter(ledger_transaction::Column::PaymentId.not_in_subquery(refunded_payments()))
+            .one(db)
+            .await?;
+        if let Some(earlier) = earlier {
+            return Err(ServiceError::Validation(format!(
+                "Order {} is already posted for payment {}",
+                payment.order_id,
+                earlier.payment_id.unwrap_or_default()
+            )));
+        }
+
+        let vendor_orders = vendor_order::Entity::find()
+            .filter(vendor_order::Column::OrderId.eq(payment.order_id))
+            .filter(vendor_order::Column::Status.ne(Status::Cancelled))
+            .order_by_asc(vendor_order::Column::VendorId)
+            .all(db)
+            .await?;
+        if vendor_orders.is_empty() {
+            warn!(
+                "Payment {} is for order {} which has no vendor orders",
+                payment.id, payment.order_id
+            );
+        }
+
+        let mut lines = vec![Line {
+            account: LedgerAccount::GatewayClearing,
+            vendor_id: None,
+            amount: payment.amount,
+        }];
+        let mut owed_to_vendors = Money::ZERO;
+        for vendor_order in vendor_orders {
+            let owed = vendor_order.subtotal - vendor_order.subtotal.share(self.commission_rate);
+            owed_to_vendors += owed;
+            lines.push(Line {
+                account: LedgerAccount::VendorPayable,
+                vendor_id: Some(vendor_order.vendor_id),
+                amount: -owed,
+            });
+        }
+        lines.push(Line {
+            account: LedgerAccount::PlatformCommission,
+            vendor_id: None,
+            amount: -(payment.amount - owed_to_vendors),
+        });
+
+        post(
+            db,
+            LedgerTransactionKind::Payment,
+            Some(payment.id),
+            None,
+            None,
+            format!("Payment for order {}", payment.order_id),
+            lines,
+        )
+        .await
+    }
+
+    /// Reverse the posting of a refunded payment, along with the
+    /// cancellations of its vendor orders. Payments that were never posted
+    /// have nothing to reverse.
+    pub(crate) async fn record_refund<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        payment: &payment::Model,
+    ) -> Result<Option<ledger_transaction::Model>, ServiceError> {
+        let postings = ledger_transaction::Entity::find()
+            .filter(ledger_transaction::Column::PaymentId.eq(payment.id))
+            .filter(ledger_transaction::Column::Kind.is_in([
+                LedgerTransactionKind::Payment,
+                LedgerTransactionKind::Cancellation,
+            ]))
+            .all(db)
+            .await?;
+        if !postings
+            .iter()
+            .any(|posting| posting.kind == LedgerTransactionKind::Payment)
+        {
+            warn!("Refunded payment {} was never posted", payment.id);
+            return Ok(None);
+        }
+
+        // One line per account, netting the cancellations against the payment
+        let mut lines: Vec<Line> = Vec::new();
+        for entry in ledger_entry::Entity::find()
+            .filter(
+                ledger_entry::Column::TransactionId
+                    .is_in(postings.iter().map(|posting| posting.id)),
+            )
+            .all(db)
+            .await?
+        {
+            match lines
+                .iter_mut()
+                .find(|line| line.account == entry.account && line.vendor_id == entry.vendor_id)
+            {
+                Some(line) => line.amount -= entry.amount,
+                None => lines.push(Line {
+                    account: entry.account,
+                    vendor_id: entry.vendor_id,
+                    amount: -entry.amount,
+                }),
+            }
+        }
+
+        let reversal = post(
+            db,
+            LedgerTransactionKind::Refund,
+            Some(payment.id),
+            None,
+            None,
+            format!("Refund for order {}", payment.order_id),
+            lines,
+        )
+        .await?;
+
+        Ok(Some(reversal))
+    }
+
+    /// Post completed payments missing from the ledger, for instance those
+    /// settled before the ledger existed. Returns how many were posted.
+    pub async fn post_unposted_payments(&self) -> Result<usize, ServiceError> {
+        let unposted = payment::Entity::find()
+            .filter(payment::Column::Status.eq(PaymentStatus::Completed))
+            .filter(payment::Column::Id.not_in_subquery(posted_payments()))
+            .order_by_asc(payment::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+
+        let mut posted = 0;
+        for payment in unposted {
+            let txn = self.db.begin().await?;
+            // Lock the payment so a concurrent refund cannot slip in between
+            let payment = payment::Entity::find_by_id(payment.id)
+                .lock_exclusive()
+                .one(&txn)
+                .await?;
+            let already_posted = ledger_transaction::Entity::find()
+                .filter(ledger_transaction::Column::PaymentId.eq(payment.as_ref().map(|p| p.id)))
+                .filter(ledger_transaction::Column::Kind.eq(LedgerTransactionKind::Payment))
+                .one(&txn)
+                .await?
+                .is_some();
+            match payment {
+                Some(payment)
+                    if payment.status == PaymentStatus::Completed
+                        && payment.unapplied_reason().is_none()
+                        && !already_posted =>
+                {
+                    match self.record_payment(&txn, &payment).await {
+                        Ok(_) => posted += 1,
+                        Err(ServiceError::Validation(msg)) => {
+                            warn!("Payment {} was not posted: {}", payment.id, msg)
+                        }
+                        Err(e) => return Err(e),
+                    }
+                }
+                _ => {}
+            }
+            txn.commit().await?;
+        }
+
+        Ok(posted)
+    }
+
+    pub async fn get_vendor_balance(&self, vendor_id: Uuid) -> Result<VendorBalance, ServiceError> {
+        vendor_balance(self.db.as_ref(), vendor_id).await
+    }
+
+    /// Every posting to a vendor's account, newest first
+    pub async fn get_vendor_statement(
+        &self,
+        vendor_id: Uuid,
+    ) -> Result<Vec<StatementLine>, ServiceError> {
+        let entries = ledger_entry::Entity::find()
+            .filter(ledger_entry::Column::Account.eq(LedgerAccount::VendorPayable))
+            .filter(ledger_entry::Column::VendorId.eq(vendor_id))
+            .find_also_related(ledger_transaction::Entity)
+            .order_by_desc(ledger_entry::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+
+        Ok(entries
+            .into_iter()
+            .map(|(entry, transaction)| StatementLine {
+                transaction_id: entry.transaction_id,
+                kind: transaction.as_ref().map(|t| t.kind),
+                description: transaction.as_ref().map(|t| t.description.clone()),
+                payment_id: transaction.as_ref().and_then(|t| t.payment_id),
+                payout_id: transaction.as_ref().and_then(|t| t.payout_id),
+                amount: -entry.amount,
+                created_at: entry.created_at,
+            })
+            .collect())
+    }
+
+    /// Ask for `amount` of a vendor's available balance to be paid out
+    pub async fn request_payout(
+        &self,
+        vendor_id: Uuid,
+        amount: Money,
+        phone: PhoneNumber,
+    ) -> Result<payout::Model, ServiceError> {
+        if !amount.is_positive() {
+            return Err(ServiceError::Validation(
+                "Payout amount must be positive".to_string(),
+            ));
+        }
+        if phone.operator().is_none() {
+            return Err(ServiceError::Validation(
+                "Payouts can only be sent to MTN or Orange mobile money numbers".to_string(),
+            ));
+        }
+
+        let txn = self.db.begin().await?;
+
+        // Serialize the payout requests of a vendor
+        user::Entity::find_by_id(vendor_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Vendor not found".to_string()))?;
+
+        let balance = vendor_balance(&txn, vendor_id).await?;
+        if amount > balance.available {
+            return Err(ServiceError::Validation(format!(
+                "Requested {} but only {} is available",
+                amount, balance.available
+            )));
+        }
+
+        let now = Utc::now();
+        let payout = payout::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            vendor_id: Set(vendor_id),
+            amount: Set(amount),
+            status: Set(PayoutStatus::Requested),
+            phone: Set(phone),
+            note: Set(None),
+            reviewed_by: Set(None),
+            reviewed_at: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+
+        txn.commit().await?;
+
+        Ok(payout)
+    }
+
+    /// Approve or reject a requested payout. Approving posts the payout to
+    /// the ledger, debiting the vendor's balance.
+    pub async fn review_payout(
+        &self,
+        payout_id: Uuid,
+        reviewer_id: Uuid,
+        approve: bool,
+        note: Option<String>,
+    ) -> Result<payout::Model, ServiceError> {
+        let txn = self.db.begin().await?;
+
+        let payout = payout::Entity::find_by_id(payout_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Payout not found".to_string()))?;
+        if payout.status != PayoutStatus::Requested {
+            return Err(ServiceError::Validation(format!(
+                "Payout is already {}",
+                payout.status
+            )));
+        }
+
+        let status = if approve {
+            user::Entity::find_by_id(payout.vendor_id)
+                .lock_exclusive()
+                .one(&txn)
+                .await?;
+            // Refunds may have lowered the balance since the request
+            let balance = vendor_balance(&txn, payout.vendor_id).await?;
+            if payout.amount > balance.balance {
+                return Err(ServiceError::Validation(format!(
+                    "Payout of {} exceeds the vendor balance of {}",
+                    payout.amount, balance.balance
+                )));
+            }
+            post(
+                &txn,
+                LedgerTransactionKind::Payout,
+                None,
+                Some(payout.id),
+                None,
+                format!("Payout to vendor {}", payout.vendor_id),
+                vec![
+                    Line {
+                        account: LedgerAccount::VendorPayable,
+                        vendor_id: Some(payout.vendor_id),
+                        amount: payout.amount,
+                    },
+                    Line {
+                        account: LedgerAccount::GatewayClearing,
+                        vendor_id: None,
+                        amount: -payout.amount,
+                    },
+                ],
+            )
+            .await?;
+            PayoutStatus::Approved
+        } else {
+            PayoutStatus::Rejected
+        };
+
+        let now = Utc::now();
+        let mut active_model: payout::ActiveModel = payout.into();
+        active_model.status = Set(status);
+        active_model.note = Set(note);
+        active_model.reviewed_by = Set(Some(reviewer_id));
+        active_model.reviewed_at = Set(Some(now));
+        active_model.updated_at = Set(now);
+        let payout = active_model.update(&txn).await?;
+
+        txn.commit().await?;
+
+        Ok(payout)
+    }
+
+    pub async fn get_payout(&self, payout_id: Uuid) -> Result<payout::Model, ServiceError> {
+        payout::Entity::find_by_id(payout_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Payout not found".to_string()))
+    }
+
+    /// Payout history, newest first, optionally for a single vendor
+    pub async fn list_payouts(
+        &self,
+        vendor_id: Option<Uuid>,
+        status: Option<PayoutStatus>,
+    ) -> Result<Vec<payout::Model>, ServiceError> {
+        let mut query = payout::Entity::find().order_by_desc(payout::Column::CreatedAt);
+        if let Some(vendor_id) = vendor_id {
+            query = query.filter(payout::Column::VendorId.eq(vendor_id));
+        }
+        if let Some(status) = status {
+            query = query.filter(payout::Column::Status.eq(status));
+        }
+
+        Ok(query.all(self.db.as_ref()).await?)
+    }
+
+    /// Check the ledger against the payments and payouts tables. Money
+    /// collected minus money paid out must sit in gateway clearing, and
+    /// clearing must equal what is owed to vendors plus platform commission.
+    pub async fn reconcile(&self) -> Result<LedgerReconciliation, ServiceError> {
+        let db = self.db.as_ref();
+
+        // Payments being refunded are still posted until their payout is
+        // confirmed
+        let collected: Option<Option<Money>> = payment::Entity::find()
+            .select_only()
+            .column_as(money_sum(payment::Column::Amount), "total")
+            .filter(
+                payment::Column::Status.is_in([PaymentStatus::Completed, PaymentStatus::Refunding]),
+            )
+            .filter(Expr::cust(APPLIED))
+            .into_tuple()
+            .one(db)
+            .await?;
+        let collected = collected.flatten().unwrap_or_default();
+        let paid_out = payout_total(db, None, PayoutStatus::Approved).await?;
+        let gateway_clearing = account_total(db, LedgerAccount::GatewayClearing, None).await?;
+        let vendor_payable = -account_total(db, LedgerAccount::VendorPayable, None).await?;
+        let platform_commission =
+            -account_total(db, LedgerAccount::PlatformCommission, None).await?;
+        let refunds_payable = -account_total(db, LedgerAccount::RefundsPayable, None).await?;
+        let unposted_payments = payment::Entity::find()
+            .filter(payment::Column::Status.eq(PaymentStatus::Completed))
+            .filter(Expr::cust(APPLIED))
+            .filter(payment::Column::Id.not_in_subquery(posted_payments()))
+            .all(db)
+            .await?
+            .len();
+
+        let balanced = unposted_payments == 0
+            && collected - paid_out == gateway_clearing
+            && gateway_clearing == vendor_payable + platform_commission + refunds_payable;
+
+        Ok(LedgerReconciliation {
+            collected,
+            paid_out,
+            gateway_clearing,
+            vendor_payable,
+            platform_commission,
+            refunds_payable,
+            unposted_payments,
+            balanced,
+        })
+    }
+}
+
+/// Ids of the payments that have a posting of `kind`
+fn payments_posted_as(kind: LedgerTransactionKind) -> sea_orm::sea_query::SelectStatement {
+    Query::select()
+        .column(ledger_transaction::Column::PaymentId)
+        .from(ledger_transaction::Entity)
+        .and_where(ledger_transaction::Column::Kind.eq(kind))
+        .and_where(ledger_transaction::Column::PaymentId.is_not_null())
+        .to_owned()
+}
+
+/// Ids of the payments that have a payment posting
+fn posted_payments() -> sea_orm::sea_query::SelectStatement {
+    payments_posted_as(LedgerTransactionKind::Payment)
+}
+
+/// Ids of the payments whose posting was reversed by a refund
+fn refunded_payments() -> sea_orm::sea_query::SelectStatement {
+    payments_posted_as(LedgerTransactionKind::Refund)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase, Value};
+    use std::collections::BTreeMap;
+
+    fn total(amount: Option<Money>) -> BTreeMap<&'static str, Value> {
+        BTreeMap::from([("total", Value::BigInt(amount.map(Money::as_xaf)))])
+    }
+
+    fn completed_payment(order_id: Uuid, amount: Money) -> payment::Model {
+        payment::Model {
+            id: Uuid::new_v4(),
+            order_id,
+            amount,
+            status: PaymentStatus::Completed,
+            payment_method: "mobile_money".to_string(),
+            payment_details: None,
+            transaction_id: Some("trans-1".to_string()),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn vendor_order_row(order_id: Uuid, vendor_id: Uuid, subtotal: Money) -> vendor_order::Model {
+        vendor_order::Model {
+            id: Uuid::new_v4(),
+            order_id,
+            vendor_id,
+            status: Status::Pending,
+            subtotal,
+            carrier: None,
+            tracking_number: None,
+            shipped_at: None,
+            delivered_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn ledger_transaction_row(kind: LedgerTransactionKind) -> ledger_transaction::Model {
+        ledger_transaction::Model {
+            id: Uuid::new_v4(),
+            kind,
+            payment_id: None,
+            payout_id: None,
+            vendor_order_id: None,
+            description: String::new(),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn entry(
+        transaction_id: Uuid,
+        account: LedgerAccount,
+        vendor_id: Option<Uuid>,
+        amount: Money,
+    ) -> ledger_entry::Model {
+        ledger_entry::Model {
+            id: Uuid::new_v4(),
+            transaction_id,
+            account,
+            vendor_id,
+            amount,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn requested_payout(vendor_id: Uuid, amount: Money) -> payout::Model {
+        payout::Model {
+            id: Uuid::new_v4(),
+            vendor_id,
+            amount,
+            status: PayoutStatus::Requested,
+            phone: "677777777".parse().unwrap(),
+            note: None,
+            reviewed_by: None,
+            reviewed_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn vendor(id: Uuid) -> user::Model {
+        user::Model {
+            id,
+            email: None,
+            password_hash: String::new(),
+            role: user::UserRole::Vendor,
+            full_name: "Test Vendor".to_string(),
+            is_active: true,
+            phone: "677777777".parse().unwrap(),
+            phone_verified_at: Some(Utc::now()),
+            failed_login_attempts: 0,
+            locked_until: None,
+            pending_phone: None,
+            vendor_verified_at: None,
+            pending_email: None,
+            avatar_url: None,
+            preferred_language: user::Language::Fr,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_payment_splits_between_vendors_and_platform() {
+        let order_id = Uuid::new_v4();
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let payment = completed_payment(order_id, Money::xaf(3500));
+        let posting = ledger_transaction_row(LedgerTransactionKind::Payment);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results::<ledger_transaction::Model, _, _>(vec![vec![]])
+            .append_query_results(vec![vec![
+                vendor_order_row(order_id, first, Money::xaf(2500)),
+                vendor_order_row(order_id, second, Money::xaf(1000)),
+            ]])
+            .append_query_results(vec![vec![posting.clone()]])
+            .append_query_results(vec![
+                vec![entry(
+                    posting.id,
+                    LedgerAccount::GatewayClearing,
+                    None,
+                    Money::xaf(3500),
+                )],
+                vec![entry(
+                    posting.id,
+                    LedgerAccount::VendorPayable,
+                    Some(first),
+                    -Money::xaf(2250),
+                )],
+                vec![entry(
+                    posting.id,
+                    LedgerAccount::VendorPayable,
+                    Some(second),
+                    -Money::xaf(900),
+                )],
+                vec![entry(
+                    posting.id,
+                    LedgerAccount::PlatformCommission,
+                    None,
+                    -Money::xaf(350),
+                )],
+            ])
+            .into_connection();
+        let db = Arc::new(db);
+        let ledger = LedgerService::new(db.clone(), Decimal::new(1, 1));
+
+        let result = ledger.record_payment(db.as_ref(), &payment).await;
+        assert!(result.is_ok());
+
+        drop(ledger);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let vendor_orders_sql = log[1].statements()[0].to_string();
+        assert!(
+            vendor_orders_sql.contains("\"vendor_orders\".\"status\" <> 'cancelled'"),
+            "{}",
+            vendor_orders_sql
+        );
+        let inserts: Vec<String> = log
+            .iter()
+            .map(|t| t.statements()[0].to_string())
+            .filter(|sql| sql.starts_with("INSERT INTO \"ledger_entries\""))
+            .collect();
+        assert_eq!(inserts.len(), 4);
+        assert!(inserts[1].contains(&first.to_string()) && inserts[1].contains("-2250"));
+        assert!(inserts[2].contains(&second.to_string()) && inserts[2].contains("-900"));
+        assert!(inserts[3].contains("platform_commission") && inserts[3].contains("-350"));
+    }
+
+    #[tokio::test]
+    async fn test_order_is_posted_once() {
+        let order_id = Uuid::new_v4();
+        let earlier = ledger_transaction::Model {
+            payment_id: Some(Uuid::new_v4()),
+            ..ledger_transaction_row(LedgerTransactionKind::Payment)
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![earlier]])
+            .into_connection();
+        let db = Arc::new(db);
+        let ledger = LedgerService::new(db.clone(), Decimal::new(1, 1));
+
+        let result = ledger
+            .record_payment(db.as_ref(), &completed_payment(order_id, Money::xaf(3500)))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        drop(ledger);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        assert_eq!(log.len(), 1);
+        let guard_sql = log[0].statements()[0].to_string();
+        assert!(guard_sql.contains(&order_id.to_string()), "{}", guard_sql);
+        assert!(guard_sql.contains("'refund'"), "{}", guard_sql);
+    }
+
+    #[tokio::test]
+    async fn test_unbalanced_posting_is_rejected() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+
+        let result = post(
+            &db,
+            LedgerTransactionKind::Payout,
+            None,
+            None,
+            None,
+            "Broken".to_string(),
+            vec![Line {
+                account: LedgerAccount::GatewayClearing,
+                vendor_id: None,
+                amount: Money::xaf(100),
+            }],
+        )
+        .await;
+
+        assert!(matches!(result, Err(ServiceError::GenericError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_vendor_balance_holds_pending_payouts() {
+        let vendor_id = Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![
+                vec![total(Some(-Money::xaf(2250)))],
+                vec![total(Some(Money::xaf(1000)))],
+            ])
+            .into_connection();
+        let ledger = LedgerService::new(Arc::new(db), Decimal::new(1, 1));
+
+        let balance = ledger.get_vendor_balance(vendor_id).await.unwrap();
+
+        assert_eq!(balance.balance, Money::xaf(2250));
+        assert_eq!(balance.pending_payouts, Money::xaf(1000));
+        assert_eq!(balance.available, Money::xaf(1250));
+    }
+
+    #[tokio::test]
+    async fn test_payout_cannot_exceed_available_balance() {
+        let vendor_id = Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![vendor(vendor_id)]])
+            .append_query_results(vec![
+                vec![total(Some(-Money::xaf(2250)))],
+                vec![total(None)],
+            ])
+            .into_connection();
+        let ledger = LedgerService::new(Arc::new(db), Decimal::new(1, 1));
+
+        let result = ledger
+            .request_payout(vendor_id, Money::xaf(3000), "677777777".parse().unwrap())
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_request_payout() {
+        let vendor_id = Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![vendor(vendor_id)]])
+            .append_query_results(vec![
+                vec![total(Some(-Money::xaf(2250)))],
+                vec![total(None)],
+            ])
+            .append_query_results(vec![vec![requested_payout(vendor_id, Money::xaf(2000))]])
+            .into_connection();
+        let ledger = LedgerService::new(Arc::new(db), Decimal::new(1, 1));
+
+        let payout = ledger
+            .request_payout(vendor_id, Money::xaf(2000), "677777777".parse().unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(payout.status, PayoutStatus::Requested);
+        assert_eq!(payout.amount, Money::xaf(2000));
+    }
+
+    #[tokio::test]
+    async fn test_approve_payout_posts_to_ledger() {
+        let vendor_id = Uuid::new_v4();
+        let payout = requested_payout(vendor_id, Money::xaf(2000));
+        let posting = ledger_transaction_row(LedgerTransactionKind::Payout);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payout.clone()]])
+            .append_query_results(vec![vec![vendor(vendor_id)]])
+            .append_query_results(vec![
+                vec![total(Some(-Money::xaf(2250)))],
+                vec![total(Some(Money::xaf(2000)))],
+            ])
+            .append_query_results(vec![vec![posting.clone()]])
+            .append_query_results(vec![
+                vec![entry(
+                    posting.id,
+                    LedgerAccount::VendorPayable,
+                    Some(vendor_id),
+                    Money::xaf(2000),
+                )],
+                vec![entry(
+                    posting.id,
+                    LedgerAccount::GatewayClearing,
+                    None,
+                    -Money::xaf(2000),
+                )],
+            ])
+            .append_query_results(vec![vec![payout::Model {
+                status: PayoutStatus::Approved,
+                ..payout.clone()
+            }]])
+            .into_connection();
+        let ledger = LedgerService::new(Arc::new(db), Decimal::new(1, 1));
+
+        let result = ledger
+            .review_payout(payout.id, Uuid::new_v4(), true, None)
+            .await;
+
+        assert_eq!(result.unwrap().status, PayoutStatus::Approved);
+    }
+
+    #[tokio::test]
+    async fn test_reviewed_payout_cannot_be_reviewed_again() {
+        let payout = payout::Model {
+            status: PayoutStatus::Rejected,
+            ..requested_payout(Uuid::new_v4(), Money::xaf(2000))
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payout.clone()]])
+            .into_connection();
+        let ledger = LedgerService::new(Arc::new(db), Decimal::new(1, 1));
+
+        let result = ledger
+            .review_payout(payout.id, Uuid::new_v4(), true, None)
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_balances_against_payments() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![
+                // collected, paid out, clearing, vendor payable, commission,
+                // refunds payable
+                vec![total(Some(Money::xaf(3500)))],
+                vec![total(Some(Money::xaf(2000)))],
+                vec![total(Some(Money::xaf(1500)))],
+                vec![total(Some(-Money::xaf(1000)))],
+                vec![total(Some(-Money::xaf(350)))],
+                vec![total(Some(-Money::xaf(150)))],
+            ])
+            .append_query_results(vec![Vec::<payment::Model>::new()])
+            .into_connection();
+        let db = Arc::new(db);
+        let ledger = LedgerService::new(db.clone(), Decimal::new(1, 1));
+
+        let report = ledger.reconcile().await.unwrap();
+
+        assert!(report.balanced);
+        assert_eq!(report.vendor_payable, Money::xaf(1000));
+        assert_eq!(report.refunds_payable, Money::xaf(150));
+
+        // Payments left off their order are never posted, so not counted
+        drop(ledger);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let collected_sql = log[0].statements()[0].to_string();
+        assert!(
+            collected_sql.contains("unapplied_reason"),
+            "{}",
+            collected_sql
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_vendor_order_is_owed_back_to_the_buyer() {
+        let order_id = Uuid::new_v4();
+        let vendor_id = Uuid::new_v4();
+        let vendor_order = vendor_order_row(order_id, vendor_id, Money::xaf(2500));
+        let posting = ledger_transaction::Model {
+            payment_id: Some(Uuid::new_v4()),
+            ..ledger_transaction_row(LedgerTransactionKind::Payment)
+        };
+        let cancellation = ledger_transaction_row(LedgerTransactionKind::Cancellation);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![posting.clone()]])
+            .append_query_results(vec![vec![entry(
+                posting.id,
+                LedgerAccount::VendorPayable,
+                Some(vendor_id),
+                -Money::xaf(2250),
+            )]])
+            .append_query_results(vec![vec![cancellation.clone()]])
+            .append_query_results(vec![
+                vec![entry(
+                    cancellation.id,
+                    LedgerAccount::VendorPayable,
+                    Some(vendor_id),
+                    Money::xaf(2250),
+                )],
+                vec![entry(
+                    cancellation.id,
+                    LedgerAccount::PlatformCommission,
+                    None,
+                    Money::xaf(250),
+                )],
+                vec![entry(
+                    cancellation.id,
+                    LedgerAccount::RefundsPayable,
+                    None,
+                    -Money::xaf(2500),
+                )],
+            ])
+            .into_connection();
+        let db = Arc::new(db);
+
+        let result = record_vendor_order_cancellation(db.as_ref(), &vendor_order).await;
+        assert!(result.unwrap().is_some());
+
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let inserts: Vec<String> = log
+            .iter()
+            .map(|t| t.statements()[0].to_string())
+            .filter(|sql| sql.starts_with("INSERT INTO \"ledger_entries\""))
+            .collect();
+        assert_eq!(inserts.len(), 3);
+        assert!(inserts[0].contains(&vendor_id.to_string()) && inserts[0].contains("2250"));
+        assert!(inserts[1].contains("platform_commission") && inserts[1].contains("250"));
+        assert!(inserts[2].contains("refunds_payable") && inserts[2].contains("-2500"));
+    }
+
+    #[tokio::test]
+    async fn test_refund_reverses_cancellations_too() {
+        let order_id = Uuid::new_v4();
+        let vendor_id = Uuid::new_v4();
+        let payment = completed_payment(order_id, Money::xaf(2500));
+        let posting = ledger_transaction_row(LedgerTransactionKind::Payment);
+        let cancellation = ledger_transaction_row(LedgerTransactionKind::Cancellation);
+        let refund = ledger_transaction_row(LedgerTransactionKind::Refund);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![posting.clone(), cancellation.clone()]])
+            .append_query_results(vec![vec![
+                entry(
+                    posting.id,
+                    LedgerAccount::GatewayClearing,
+                    None,
+                    Money::xaf(2500),
+                ),
+                entry(
+                    posting.id,
+                    LedgerAccount::VendorPayable,
+                    Some(vendor_id),
+                    -Money::xaf(2250),
+                ),
+                entry(
+                    posting.id,
+                    LedgerAccount::PlatformCommission,
+                    None,
+                    -Money::xaf(250),
+                ),
+                entry(
+                    cancellation.id,
+                    LedgerAccount::VendorPayable,
+                    Some(vendor_id),
+                    Money::xaf(2250),
+                ),
+                entry(
+                    cancellation.id,
+                    LedgerAccount::PlatformCommission,
+                    None,
+                    Money::xaf(250),
+                ),
+                entry(
+                    cancellation.id,
+                    LedgerAccount::RefundsPayable,
+                    None,
+                    -Money::xaf(2500),
+                ),
+            ]])
+            .append_query_results(vec![vec![refund.clone()]])
+            .append_query_results(vec![
+                vec![entry(
+                    refund.id,
+                    LedgerAccount::GatewayClearing,
+                    None,
+                    -Money::xaf(2500),
+                )],
+                vec![entry(
+                    refund.id,
+                    LedgerAccount::RefundsPayable,
+                    None,
+                    Money::xaf(2500),
+                )],
+            ])
+            .into_connection();
+        let db = Arc::new(db);
+        let ledger = LedgerService::new(db.clone(), Decimal::new(1, 1));
+
+        let result = ledger.record_refund(db.as_ref(), &payment).await;
+        assert!(result.unwrap().is_some());
+
+        // The vendor and the platform were already debited by the
+        // cancellation, so only clearing and refunds payable move
+        drop(ledger);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let inserts: Vec<String> = log
+            .iter()
+            .map(|t| t.statements()[0].to_string())
+            .filter(|sql| sql.starts_with("INSERT INTO \"ledger_entries\""))
+            .collect();
+        assert_eq!(inserts.len(), 2);
+        assert!(inserts[0].contains("gateway_clearing") && inserts[0].contains("-2500"));
+        assert!(inserts[1].contains("refunds_payable") && inserts[1].contains("2500"));
+    }
+}
@@ -1,7 +1,23 @@
+pub mod audit;
 pub mod cart;
+pub mod category;
 pub(super) mod errors;
+pub mod ledger;
+pub mod moderation;
 pub mod order;
+pub mod otp;
 pub mod payment;
+pub mod payment_gateway;
+pub mod permission;
 pub mod product;
+pub mod rate_limit;
+pub mod review;
+pub mod search;
+pub mod session;
+pub mod sms;
+pub mod store;
 pub mod user;
+pub mod variant;
+pub mod vendor_application;
+pub mod wishlist;
 pub mod image;
\ No newline at end of file
@@ -0,0 +1,442 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::Deserialize;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::models::{
+    product::{self, Model, ModerationStatus},
+    product_moderation_history,
+    user::{self, UserRole},
+};
+
+use super::{
+    errors::ServiceError,
+    sms::{Sms, SmsSender},
+};
+
+/// Who moved a product through moderation
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModerationActor {
+    pub id: Uuid,
+    pub role: UserRole,
+}
+
+/// A moderator's decision on a product
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModerationDecision {
+    /// `approved`, `rejected` or `suspended`
+    pub status: ModerationStatus,
+    /// Why the product was rejected or suspended, shown to the vendor
+    pub reason: Option<String>,
+    /// Note to the vendor, such as what to fix
+    pub note: Option<String>,
+}
+
+/// Append a row to the moderation history of a product
+pub(crate) async fn record_moderation_change<C: ConnectionTrait>(
+    db: &C,
+    product_id: Uuid,
+    from_status: Option<ModerationStatus>,
+    to_status: ModerationStatus,
+    actor: &ModerationActor,
+    reason: Option<String>,
+    note: Option<String>,
+) -> Result<(), ServiceError> {
+    product_moderation_history::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        product_id: Set(product_id),
+        from_status: Set(from_status),
+        to_status: Set(to_status),
+        changed_by: Set(Some(actor.id)),
+        changed_by_role: Set(Some(actor.role.clone())),
+        reason: Set(reason),
+        note: Set(note),
+        created_at: Set(Utc::now()),
+    }
+    .insert(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Trimmed text, or nothing when it is blank
+fn non_blank(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Review of vendor products before buyers can see them
+pub struct ModerationService {
+    db: Arc<DatabaseConnection>,
+    sms: Arc<dyn SmsSender>,
+}
+
+impl ModerationService {
+    pub fn new(db: Arc<DatabaseConnection>, sms: Arc<dyn SmsSender>) -> Self {
+        Self { db, sms }
+    }
+
+    /// Approve, reject or suspend a product and text the vendor about it.
+    /// Rejections and suspensions must give a reason.
+    pub async fn moderate(
+        &self,
+        product_id: Uuid,
+        moderator: &ModerationActor,
+        decision: ModerationDecision,
+    ) -> Result<Model, ServiceError> {
+        let to = decision.status;
+        if !to.is_decision() {
+            return Err(ServiceError::Validation(
+                "Moderators approve, reject or suspend products".to_string(),
+            ));
+        }
+        let reason = non_blank(decision.reason);
+        if to != ModerationStatus::Approved && reason.is_none() {
+            return Err(ServiceError::Validation(format!(
+                "A reason is required to mark a product {}",
+                to
+            )));
+        }
+        let note = non_blank(decision.note);
+
+        let txn = self.db.begin().await?;
+        let product = self.lock_product(&txn, product_id).await?;
+        let from = product.moderation_status;
+        check_transition(from, to)?;
+
+        let mut active_model: product::ActiveModel = product.into();
+        active_model.moderation_status = Set(to);
+        active_model.rejection_reason = Set(reason.clone());
+        active_model.moderator_note = Set(note.clone());
+        active_model.updated_at = Set(Utc::now());
+        let product = active_model.update(&txn).await?;
+        record_moderation_change(&txn, product_id, Some(from), to, moderator, reason, note).await?;
+        txn.commit().await?;
+
+        self.notify_vendor(&product).await;
+        Ok(product)
+    }
+
+    /// Send a draft, rejected or suspended product to the review queue.
+    /// Only the vendor who makes the product may submit it.
+    pub async fn submit_for_review(
+        &self,
+        product_id: Uuid,
+        vendor_id: Uuid,
+    ) -> Result<Model, ServiceError> {
+        let txn = self.db.begin().await?;
+        let product = self.lock_product(&txn, product_id).await?;
+        if product.seller_id != vendor_id {
+            return Err(ServiceError::Forbidden(
+                "You don't have permission to submit this product".to_string(),
+            ));
+        }
+        let from = product.moderation_status;
+        if from == ModerationStatus::Approved {
+            return Err(ServiceError::Validation(
+                "Product is already approved".to_string(),
+            ));
+        }
+        check_transition(from, ModerationStatus::PendingReview)?;
+
+        let mut active_model: product::ActiveModel = product.into();
+        active_model.moderation_status = Set(ModerationStatus::PendingReview);
+        active_model.updated_at = Set(Utc::now());
+        let product = active_model.update(&txn).await?;
+        let vendor = ModerationActor {
+            id: vendor_id,
+            role: UserRole::Vendor,
+        };
+        record_moderation_change(
+            &txn,
+            product_id,
+            Some(from),
+            ModerationStatus::PendingReview,
+            &vendor,
+            None,
+            None,
+        )
+        .await?;
+        txn.commit().await?;
+
+        Ok(product)
+    }
+
+    /// Products waiting for a moderator, oldest first
+    pub async fn list_pending(&self) -> Result<Vec<Model>, ServiceError> {
+        let products = product::Entity::find()
+            .filter(product::Column::ModerationStatus.eq(ModerationStatus::PendingReview))
+            .order_by_asc(product::Column::UpdatedAt)
+            .all(self.db.as_ref())
+            .await?;
+
+        Ok(products)
+    }
+
+    /// Moderation timeline of a product, oldest change first
+    pub async fn get_history(
+        &self,
+        product_id: Uuid,
+    ) -> Result<Vec<product_moderation_history::Model>, ServiceError> {
+        let history = product_moderation_history::Entity::find()
+            .filter(product_moderation_history::Column::ProductId.eq(product_id))
+            .order_by_asc(product_moderation_history::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+
+        Ok(history)
+    }
+
+    async fn lock_product<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        product_id: Uuid,
+    ) -> Result<Model, ServiceError> {
+        product::Entity::find_by_id(product_id)
+            .lock_exclusive()
+            .one(db)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Product not found".to_string()))
+    }
+
+    /// Text the vendor the outcome of a review. The decision stands when the
+    /// text cannot be sent, so failures only go to the error log.
+    async fn notify_vendor(&self, product: &Model) {
+        let body = match product.moderation_status {
+            ModerationStatus::Approved => format!(
+                "Your product \"{}\" was approved and is now listed on Cameroon Made Market.",
+                product.title
+            ),
+            ModerationStatus::Rejected => format!(
+                "Your product \"{}\" was not approved: {}. Update it and submit it again.",
+                product.title,
+                product.rejection_reason.as_deref().unwrap_or_default()
+            ),
+            ModerationStatus::Suspended => format!(
+                "Your product \"{}\" was taken off sale: {}.",
+                product.title,
+                product.rejection_reason.as_deref().unwrap_or_default()
+            ),
+            ModerationStatus::Draft | ModerationStatus::PendingReview => return,
+        };
+        let vendor = match user::Entity::find_by_id(product.seller_id)
+            .one(self.db.as_ref())
+            .await
+        {
+            Ok(Some(vendor)) => vendor,
+            Ok(None) => return,
+            Err(e) => {
+                error!("could not look up vendor of product {}: {}", product.id, e);
+                return;
+            }
+        };
+        let sms = Sms {
+            to: vendor.phone,
+            body,
+        };
+        if let Err(e) = self.sms.send(&sms).await {
+            error!(
+                "could not tell vendor {} about product {}: {}",
+                vendor.id, product.id, e
+            );
+        }
+    }
+}
+
+fn check_transition(from: ModerationStatus, to: ModerationStatus) -> Result<(), ServiceError> {
+    if from == to {
+        return Err(ServiceError::Validation(format!(
+            "Product is already {}",
+            to
+        )));
+    }
+    if !from.can_transition_to(to) {
+        return Err(ServiceError::Validation(format!(
+            "Cannot move a product from {} to {}",
+            from, to
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::money::Money;
+    use crate::services::sms::RecordingSmsSender;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    fn listed_product(id: Uuid, seller_id: Uuid, status: ModerationStatus) -> Model {
+        Model {
+            id,
+            seller_id,
+            title: "Bamoun vase".to_string(),
+            description: None,
+            price: Money::xaf(15000),
+            category_id: None,
+            quantity: 3,
+            image_urls: vec![],
+            moderation_status: status,
+            rejection_reason: None,
+            moderator_note: None,
+            return_policy: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn history_entry(
+        product_id: Uuid,
+        from_status: ModerationStatus,
+        to_status: ModerationStatus,
+    ) -> product_moderation_history::Model {
+        product_moderation_history::Model {
+            id: Uuid::new_v4(),
+            product_id,
+            from_status: Some(from_status),
+            to_status,
+            changed_by: None,
+            changed_by_role: None,
+            reason: None,
+            note: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn vendor(id: Uuid) -> user::Model {
+        user::Model {
+            id,
+            email: None,
+            password_hash: String::new(),
+            role: UserRole::Vendor,
+            full_name: "Test Vendor".to_string(),
+            is_active: true,
+            phone: "677777777".parse().unwrap(),
+            phone_verified_at: Some(Utc::now()),
+            failed_login_attempts: 0,
+            locked_until: None,
+            pending_phone: None,
+            vendor_verified_at: Some(Utc::now()),
+            pending_email: None,
+            avatar_url: None,
+            preferred_language: user::Language::Fr,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn moderator() -> ModerationActor {
+        ModerationActor {
+            id: Uuid::new_v4(),
+            role: UserRole::Staff,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reject_records_history_and_texts_vendor() {
+        let (product_id, seller_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let pending = listed_product(product_id, seller_id, ModerationStatus::PendingReview);
+        let rejected = Model {
+            moderation_status: ModerationStatus::Rejected,
+            rejection_reason: Some("Photos are blurry".to_string()),
+            ..pending.clone()
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![pending], vec![rejected]])
+            .append_query_results(vec![vec![history_entry(
+                product_id,
+                ModerationStatus::PendingReview,
+                ModerationStatus::Rejected,
+            )]])
+            .append_query_results(vec![vec![vendor(seller_id)]])
+            .into_connection();
+        let db = Arc::new(db);
+        let sms = Arc::new(RecordingSmsSender::default());
+        let service = ModerationService::new(db.clone(), sms.clone());
+
+        let decision = ModerationDecision {
+            status: ModerationStatus::Rejected,
+            reason: Some(" Photos are blurry ".to_string()),
+            note: None,
+        };
+        let product = service
+            .moderate(product_id, &moderator(), decision)
+            .await
+            .unwrap();
+        assert_eq!(product.moderation_status, ModerationStatus::Rejected);
+
+        let sent = sms.sent();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].body.contains("Photos are blurry"));
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let statements = log[0].statements();
+        assert!(statements[2].to_string().contains("'rejected'"));
+        let history = statements[3].to_string();
+        assert!(history.starts_with("INSERT INTO \"product_moderation_history\""));
+        assert!(history.contains("'pending_review'"));
+        assert!(history.contains("'Photos are blurry'"));
+    }
+
+    #[tokio::test]
+    async fn test_rejection_needs_a_reason() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let service = ModerationService::new(Arc::new(db), Arc::new(RecordingSmsSender::default()));
+
+        let decision = ModerationDecision {
+            status: ModerationStatus::Rejected,
+            reason: Some("  ".to_string()),
+            note: None,
+        };
+        let result = service
+            .moderate(Uuid::new_v4(), &moderator(), decision)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_drafts_cannot_be_approved() {
+        let product_id = Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![listed_product(
+                product_id,
+                Uuid::new_v4(),
+                ModerationStatus::Draft,
+            )]])
+            .into_connection();
+        let sms = Arc::new(RecordingSmsSender::default());
+        let service = ModerationService::new(Arc::new(db), sms.clone());
+
+        let decision = ModerationDecision {
+            status: ModerationStatus::Approved,
+            reason: None,
+            note: None,
+        };
+        let result = service.moderate(product_id, &moderator(), decision).await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        assert!(sms.sent().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_only_the_maker_can_resubmit() {
+        let product_id = Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![listed_product(
+                product_id,
+                Uuid::new_v4(),
+                ModerationStatus::Rejected,
+            )]])
+            .into_connection();
+        let service = ModerationService::new(Arc::new(db), Arc::new(RecordingSmsSender::default()));
+
+        let result = service.submit_for_review(product_id, Uuid::new_v4()).await;
+        assert!(matches!(result, Err(ServiceError::Forbidden(_))));
+    }
+}
@@ -1,16 +1,274 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+    sea_query::{Expr, Query},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, EntityTrait,
+    JoinType, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set,
+    TransactionTrait,
 };
 use uuid::Uuid;
 
 use crate::models::{
+    money::Money,
     order::{self, Model, NewOrder, Status},
-    order_item,
+    order_item, order_status_history,
+    product::{self, ModerationStatus},
+    product_variant,
+    user::UserRole,
+    vendor_order::{self, ShippingAddress, VendorOrderDetails},
 };
 
-use super::errors::ServiceError;
+use super::{errors::ServiceError, ledger::record_vendor_order_cancellation};
+use crate::utils::policy::OrderScope;
+
+/// Who is changing the status of an order
+#[derive(Debug, Clone)]
+pub enum Actor {
+    /// Automated changes such as payment confirmation
+    System,
+    /// A signed-in user acting under their role
+    User { id: Uuid, role: UserRole },
+}
+
+/// Check that `actor` may move an order from `from` to `to`.
+///
+/// The system may make any lifecycle step. Buyers may only cancel orders
+/// that are still pending and vendors may ship and deliver their part of
+/// paid orders.
+/// Admins may make any lifecycle step and may also override the lifecycle,
+/// but overrides must give a reason. Cancelled orders stay cancelled, as
+/// their stock has been released.
+fn check_transition(
+    from: Status,
+    to: Status,
+    actor: &Actor,
+    reason: Option<&str>,
+) -> Result<(), ServiceError> {
+    if from == to {
+        return Err(ServiceError::Validation(format!("Order is already {}", to)));
+    }
+    let legal = from.can_transition_to(to);
+
+    match actor {
+        Actor::User {
+            role: UserRole::Admin,
+            ..
+        } => {
+            if from == Status::Cancelled {
+                return Err(ServiceError::Validation(
+                    "A cancelled order cannot be reopened".to_string(),
+                ));
+            }
+            if !legal && reason.is_none_or(|r| r.trim().is_empty()) {
+                return Err(ServiceError::Validation(format!(
+                    "A reason is required to override an order from {} to {}",
+                    from, to
+                )));
+            }
+            Ok(())
+        }
+        _ if !legal => Err(ServiceError::Validation(format!(
+            "Cannot move an order from {} to {}",
+            from, to
+        ))),
+        Actor::System => Ok(()),
+        Actor::User {
+            role: UserRole::Buyer,
+            ..
+        } if from == Status::Pending && to == Status::Cancelled => Ok(()),
+        Actor::User {
+            role: UserRole::Vendor,
+            ..
+        } if matches!(to, Status::Shipped | Status::Delivered) => Ok(()),
+        Actor::User { role, .. } => Err(ServiceError::Forbidden(format!(
+            "{:?} users cannot move an order from {} to {}",
+            role, from, to
+        ))),
+    }
+}
+
+/// Append a row to the status history of an order
+async fn record_status_change<C: ConnectionTrait>(
+    db: &C,
+    order_id: Uuid,
+    from_status: Option<Status>,
+    to_status: Status,
+    actor: &Actor,
+    reason: Option<String>,
+) -> Result<(), ServiceError> {
+    let (changed_by, changed_by_role) = match actor {
+        Actor::System => (None, None),
+        Actor::User { id, role } => (Some(*id), Some(role.clone())),
+    };
+    order_status_history::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        order_id: Set(order_id),
+        from_status: Set(from_status),
+        to_status: Set(to_status),
+        changed_by: Set(changed_by),
+        changed_by_role: Set(changed_by_role),
+        reason: Set(reason),
+        created_at: Set(chrono::Utc::now()),
+    }
+    .insert(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Move an order to `status` on an open transaction; see
+/// [`OrderService::update_order_status`]. Other services use this to change
+/// an order as part of their own transaction.
+///
+/// Vendor orders that are still open follow the order, so paying or
+/// cancelling an order pays or cancels every vendor's part of it.
+pub(crate) async fn transition_order<C: ConnectionTrait>(
+    db: &C,
+    order_id: Uuid,
+    status: Status,
+    actor: &Actor,
+    reason: Option<String>,
+) -> Result<Model, ServiceError> {
+    if let Actor::User {
+        role: UserRole::Vendor,
+        ..
+    } = actor
+    {
+        return Err(ServiceError::Forbidden(
+            "Vendors fulfil orders through their vendor orders".to_string(),
+        ));
+    }
+
+    let order = order::Entity::find_by_id(order_id)
+        .lock_exclusive()
+        .one(db)
+        .await?
+        .ok_or_else(|| ServiceError::NotFound("Order not found".to_string()))?;
+
+    check_transition(order.status, status, actor, reason.as_deref())?;
+
+    let from_status = order.status;
+    let mut active_model: order::ActiveModel = order.into();
+    active_model.status = Set(status);
+    let updated_order = active_model.update(db).await?;
+
+    record_status_change(db, order_id, Some(from_status), status, actor, reason).await?;
+
+    // Lines of vendor orders already shipped, delivered or cancelled keep
+    // their stock as it is; so do lines of an order that left as a whole
+    let released = if status == Status::Cancelled {
+        let mut still_held = Condition::any().add(vendor_order::Column::Status.is_not_in([
+            Status::Shipped,
+            Status::Delivered,
+            Status::Cancelled,
+        ]));
+        if !matches!(from_status, Status::Shipped | Status::Delivered) {
+            still_held = still_held.add(order_item::Column::VendorOrderId.is_null());
+        }
+        order_item::Entity::find()
+            .join(JoinType::LeftJoin, order_item::Relation::VendorOrder.def())
+            .filter(order_item::Column::OrderId.eq(order_id))
+            .filter(still_held)
+            .all(db)
+            .await?
+    } else {
+        Vec::new()
+    };
+    let cancelled = if status == Status::Cancelled {
+        vendor_order::Entity::find()
+            .filter(vendor_order::Column::OrderId.eq(order_id))
+            .filter(vendor_order::Column::Status.is_not_in([Status::Delivered, Status::Cancelled]))
+            .all(db)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    vendor_order::Entity::update_many()
+        .col_expr(vendor_order::Column::Status, Expr::value(status))
+        .col_expr(
+            vendor_order::Column::UpdatedAt,
+            Expr::value(chrono::Utc::now()),
+        )
+        .filter(vendor_order::Column::OrderId.eq(order_id))
+        .filter(vendor_order::Column::Status.is_not_in([Status::Delivered, Status::Cancelled]))
+        .exec(db)
+        .await?;
+
+    release_stock(db, released).await?;
+    for vendor_order in &cancelled {
+        record_vendor_order_cancellation(db, vendor_order).await?;
+    }
+
+    Ok(updated_order)
+}
+
+/// Put the quantities of cancelled order lines back in stock, on the
+/// variant ordered as well as the product
+async fn release_stock<C: ConnectionTrait>(
+    db: &C,
+    items: Vec<order_item::Model>,
+) -> Result<(), ServiceError> {
+    for item in items {
+        product::Entity::update_many()
+            .col_expr(
+                product::Column::Quantity,
+                Expr::col(product::Column::Quantity).add(item.quantity),
+            )
+            .col_expr(product::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+            .filter(product::Column::Id.eq(item.product_id))
+            .exec(db)
+            .await?;
+        if let Some(variant_id) = item.variant_id {
+            product_variant::Entity::update_many()
+                .col_expr(
+                    product_variant::Column::Quantity,
+                    Expr::col(product_variant::Column::Quantity).add(item.quantity),
+                )
+                .col_expr(
+                    product_variant::Column::UpdatedAt,
+                    Expr::value(chrono::Utc::now()),
+                )
+                .filter(product_variant::Column::Id.eq(variant_id))
+                .exec(db)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A line of an order being placed, priced from the catalog
+struct OrderLine {
+    product: product::Model,
+    /// The variant bought, for products that have variants
+    variant: Option<product_variant::Model>,
+    quantity: i32,
+}
+
+impl OrderLine {
+    fn unit_price(&self) -> Money {
+        self.variant
+            .as_ref()
+            .map_or(self.product.price, |variant| variant.price)
+    }
+
+    fn subtotal(&self) -> Result<Money, ServiceError> {
+        self.unit_price().checked_mul(i64::from(self.quantity))
+    }
+}
+
+/// A change to a vendor's part of an order
+#[derive(Debug, Clone)]
+pub struct VendorOrderUpdate {
+    pub status: Status,
+    pub carrier: Option<String>,
+    pub tracking_number: Option<String>,
+    pub reason: Option<String>,
+}
 
 pub struct OrderService {
     db: Arc<DatabaseConnection>,
@@ -21,7 +279,111 @@ impl OrderService {
         Self { db }
     }
 
+    /// Create an order, pricing every line from the product catalog.
+    /// The client only chooses products, variants and quantities; unit
+    /// prices and the order total are always taken from the current price
+    /// of the variant, or of the product when it has no variants.
+    ///
+    /// Placement runs in a single transaction: the affected product and
+    /// variant rows are locked, stock is checked and decremented, and any
+    /// failure rolls the whole order back.
     pub async fn create_order(&self, order_data: NewOrder) -> Result<order::Model, ServiceError> {
+        if order_data.items.is_empty() {
+            return Err(ServiceError::Validation(
+                "Order must contain at least one item".to_string(),
+            ));
+        }
+
+        // Merge repeated lines and keep them sorted so concurrent orders
+        // always lock product rows in the same order.
+        let mut requested: BTreeMap<Uuid, BTreeMap<Option<Uuid>, i32>> = BTreeMap::new();
+        for item in &order_data.items {
+            let product_id = Uuid::parse_str(&item.product_id)
+                .map_err(|_| ServiceError::Validation("malformed body".to_string()))?;
+            if item.quantity == 0 {
+                return Err(ServiceError::Validation(format!(
+                    "Quantity for product {} must be at least 1",
+                    product_id
+                )));
+            }
+            let quantity = i32::try_from(item.quantity).map_err(|_| {
+                ServiceError::Validation(format!(
+                    "Quantity for product {} is too large",
+                    product_id
+                ))
+            })?;
+            let entry = requested
+                .entry(product_id)
+                .or_default()
+                .entry(item.variant_id)
+                .or_insert(0);
+            *entry = entry.saturating_add(quantity);
+        }
+
+        let txn = self.db.begin().await?;
+
+        let mut lines = Vec::with_capacity(requested.len());
+        for (product_id, quantities) in requested {
+            let product = product::Entity::find_by_id(product_id)
+                .lock_exclusive()
+                .one(&txn)
+                .await?
+                .ok_or_else(|| {
+                    ServiceError::NotFound(format!("Product {} not found", product_id))
+                })?;
+            if product.moderation_status != ModerationStatus::Approved {
+                return Err(ServiceError::Validation(format!(
+                    "Product {} is not available for sale",
+                    product_id
+                )));
+            }
+            let variants = product_variant::Entity::find()
+                .filter(product_variant::Column::ProductId.eq(product_id))
+                .order_by_asc(product_variant::Column::Id)
+                .lock_exclusive()
+                .all(&txn)
+                .await?;
+            for (variant_id, quantity) in quantities {
+                let variant = match variant_id {
+                    None if variants.is_empty() => None,
+                    None => {
+                        return Err(ServiceError::Validation(format!(
+                            "Choose a variant of product {}",
+                            product_id
+                        )))
+                    }
+                    Some(variant_id) => Some(
+                        variants
+                            .iter()
+                            .find(|variant| variant.id == variant_id)
+                            .cloned()
+                            .ok_or_else(|| {
+                                ServiceError::Validation(format!(
+                                    "Variant {} not found for product {}",
+                                    variant_id, product_id
+                                ))
+                            })?,
+                    ),
+                };
+                let available = variant.as_ref().map_or(product.quantity, |v| v.quantity);
+                if available < quantity {
+                    return Err(ServiceError::Validation(format!(
+                        "Insufficient stock for product {}: {} requested, {} available",
+                        product_id, quantity, available
+                    )));
+                }
+                lines.push(OrderLine {
+                    product: product.clone(),
+                    variant,
+                    quantity,
+                });
+            }
+        }
+
+        let total = lines.iter().try_fold(Money::ZERO, |total, line| {
+            total.checked_add(line.subtotal()?)
+        })?;
+
         let order = order::ActiveModel {
             id: Set(Uuid::new_v4()),
             user_id: Set(order_data.user_id),
@@ -32,27 +394,99 @@ impl OrderService {
             city: Set(order_data.city),
             region: Set(order_data.region),
             status: Set(order_data.status),
-            total: Set(order_data.total),
+            total: Set(total),
             created_at: Set(chrono::Utc::now()),
+            deleted_at: Set(None),
         }
-        .insert(&*self.db)
+        .insert(&txn)
+        .await?;
+
+        record_status_change(
+            &txn,
+            order.id,
+            None,
+            order.status,
+            &Actor::User {
+                id: order_data.user_id,
+                role: UserRole::Buyer,
+            },
+            None,
+        )
         .await?;
 
-        // Create order items
-        for item in order_data.items {
-            order_item::ActiveModel {
+        // Split the order into one vendor order per seller
+        let mut by_vendor: BTreeMap<Uuid, Vec<OrderLine>> = BTreeMap::new();
+        for line in lines {
+            by_vendor
+                .entry(line.product.seller_id)
+                .or_default()
+                .push(line);
+        }
+
+        for (vendor_id, lines) in by_vendor {
+            let subtotal = lines.iter().try_fold(Money::ZERO, |subtotal, line| {
+                subtotal.checked_add(line.subtotal()?)
+            })?;
+            let vendor_order = vendor_order::ActiveModel {
                 id: Set(Uuid::new_v4()),
                 order_id: Set(order.id),
-                product_id: Set(Uuid::parse_str(&item.product_id)
-                    .map_err(|_| ServiceError::Validation("malformed body".to_string()))?),
-                quantity: Set(item.quantity as i32),
-                price: Set(item.price),
+                vendor_id: Set(vendor_id),
+                status: Set(order.status),
+                subtotal: Set(subtotal),
+                carrier: Set(None),
+                tracking_number: Set(None),
+                shipped_at: Set(None),
+                delivered_at: Set(None),
+                created_at: Set(order.created_at),
+                updated_at: Set(order.created_at),
             }
-            .insert(&*self.db)
+            .insert(&txn)
             .await?;
+
+            // Create order items and reserve the stock they consume
+            for line in lines {
+                order_item::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    order_id: Set(order.id),
+                    product_id: Set(line.product.id),
+                    variant_id: Set(line.variant.as_ref().map(|variant| variant.id)),
+                    sku: Set(line.variant.as_ref().map(|variant| variant.sku.clone())),
+                    quantity: Set(line.quantity),
+                    price: Set(line.unit_price()),
+                    vendor_order_id: Set(Some(vendor_order.id)),
+                }
+                .insert(&txn)
+                .await?;
+
+                product::Entity::update_many()
+                    .col_expr(
+                        product::Column::Quantity,
+                        Expr::col(product::Column::Quantity).sub(line.quantity),
+                    )
+                    .col_expr(product::Column::UpdatedAt, Expr::value(chrono::Utc::now()))
+                    .filter(product::Column::Id.eq(line.product.id))
+                    .exec(&txn)
+                    .await?;
+                if let Some(variant) = &line.variant {
+                    product_variant::Entity::update_many()
+                        .col_expr(
+                            product_variant::Column::Quantity,
+                            Expr::col(product_variant::Column::Quantity).sub(line.quantity),
+                        )
+                        .col_expr(
+                            product_variant::Column::UpdatedAt,
+                            Expr::value(chrono::Utc::now()),
+                        )
+                        .filter(product_variant::Column::Id.eq(variant.id))
+                        .exec(&txn)
+                        .await?;
+                }
+            }
         }
 
-        Ok(order.into())
+        txn.commit().await?;
+
+        Ok(order)
     }
 
     pub async fn get_order_by_id(
@@ -60,6 +494,7 @@ impl OrderService {
         order_id: Uuid,
     ) -> Result<Option<order::Model>, ServiceError> {
         let order = order::Entity::find_by_id(order_id)
+            .filter(order::Column::DeletedAt.is_null())
             .one(&*self.db)
             .await
             .map_err(|e| ServiceError::NotFound(e.to_string()))?;
@@ -67,38 +502,106 @@ impl OrderService {
         Ok(order)
     }
 
+    /// Fetch an order only if it is visible within `scope`. Orders outside
+    /// the scope are reported as missing so their existence is not leaked.
+    pub async fn get_order_in_scope(
+        &self,
+        order_id: Uuid,
+        scope: OrderScope,
+    ) -> Result<Option<order::Model>, ServiceError> {
+        let order = match self.get_order_by_id(order_id).await? {
+            Some(order) => order,
+            None => return Ok(None),
+        };
+
+        let visible = match scope {
+            OrderScope::All => true,
+            OrderScope::Buyer(buyer_id) => order.user_id == buyer_id,
+            OrderScope::Vendor(seller_id) => {
+                order_item::Entity::find()
+                    .join(JoinType::InnerJoin, order_item::Relation::Product.def())
+                    .filter(order_item::Column::OrderId.eq(order_id))
+                    .filter(product::Column::SellerId.eq(seller_id))
+                    .count(&*self.db)
+                    .await?
+                    > 0
+            }
+        };
+
+        Ok(visible.then_some(order))
+    }
+
+    /// Move an order to `status`, enforcing the order lifecycle and the
+    /// transitions each role may perform, and record the change in the
+    /// order's status history. Cancelling an order releases its stock.
     pub async fn update_order_status(
         &self,
         order_id: Uuid,
         status: Status,
+        actor: Actor,
+        reason: Option<String>,
     ) -> Result<Model, ServiceError> {
-        let order = order::Entity::find_by_id(order_id)
-            .one(&*self.db)
-            .await
-            .map_err(|e| ServiceError::NotFound(e.to_string()))?;
-        if let Some(order) = order {
-            let mut active_model: order::ActiveModel = order.into();
-            active_model.status = Set(status.into());
-            let updated_order = active_model.update(&*self.db).await?;
+        let txn = self.db.begin().await?;
+        let updated_order = transition_order(&txn, order_id, status, &actor, reason).await?;
+        txn.commit().await?;
 
-            Ok(updated_order.into())
-        } else {
-            return Err(ServiceError::NotFound("Order not found".to_string()));
-        }
+        Ok(updated_order)
     }
-    /// List orders with optional filters by session ID or status
+
+    /// Status timeline of an order, oldest change first
+    pub async fn get_order_history(
+        &self,
+        order_id: Uuid,
+    ) -> Result<Vec<order_status_history::Model>, ServiceError> {
+        let history = order_status_history::Entity::find()
+            .filter(order_status_history::Column::OrderId.eq(order_id))
+            .order_by_asc(order_status_history::Column::CreatedAt)
+            .all(&*self.db)
+            .await?;
+
+        Ok(history)
+    }
+
+    /// List the orders visible within `scope`, optionally filtered by the
+    /// buyer who placed them or by status
     pub async fn list_orders(
         &self,
-        session_id: Option<Uuid>,
+        scope: OrderScope,
+        user_id: Option<Uuid>,
         status: Option<Status>,
     ) -> Result<Vec<Model>, ServiceError> {
-        let mut query = order::Entity::find();
+        let mut query = order::Entity::find().filter(order::Column::DeletedAt.is_null());
 
-        if let Some(session_id) = session_id {
-            query = query.filter(order::Column::UserId.eq(session_id));
+        match scope {
+            OrderScope::All => {}
+            OrderScope::Buyer(buyer_id) => {
+                query = query.filter(order::Column::UserId.eq(buyer_id));
+            }
+            OrderScope::Vendor(seller_id) => {
+                query = query.filter(
+                    order::Column::Id.in_subquery(
+                        Query::select()
+                            .column((order_item::Entity, order_item::Column::OrderId))
+                            .from(order_item::Entity)
+                            .inner_join(
+                                product::Entity,
+                                Expr::col((product::Entity, product::Column::Id))
+                                    .equals((order_item::Entity, order_item::Column::ProductId)),
+                            )
+                            .and_where(
+                                Expr::col((product::Entity, product::Column::SellerId))
+                                    .eq(seller_id),
+                            )
+                            .to_owned(),
+                    ),
+                );
+            }
+        }
+        if let Some(user_id) = user_id {
+            query = query.filter(order::Column::UserId.eq(user_id));
         }
         if let Some(status) = status {
-            query = query.filter(order::Column::Status.eq::<String>(status.into()));
+            query = query.filter(order::Column::Status.eq(status));
         }
 
         let orders = query
@@ -109,86 +612,507 @@ impl OrderService {
         Ok(orders)
     }
 
+    /// Items of an order visible within `scope`; vendors only see the lines
+    /// for their own products
     pub async fn get_order_items(
         &self,
         order_id: Uuid,
+        scope: OrderScope,
     ) -> Result<Vec<order_item::Model>, ServiceError> {
-        let items = order_item::Entity::find()
-            .filter(order_item::Column::OrderId.eq(order_id))
+        let mut query = order_item::Entity::find().filter(order_item::Column::OrderId.eq(order_id));
+
+        if let OrderScope::Vendor(seller_id) = scope {
+            query = query
+                .join(JoinType::InnerJoin, order_item::Relation::Product.def())
+                .filter(product::Column::SellerId.eq(seller_id));
+        }
+
+        let items = query.all(&*self.db).await?;
+
+        Ok(items)
+    }
+
+    /// Vendor orders of an order visible within `scope`, with their lines.
+    /// Vendors only see their own part of the order.
+    pub async fn get_vendor_orders(
+        &self,
+        order_id: Uuid,
+        scope: OrderScope,
+    ) -> Result<Vec<VendorOrderDetails>, ServiceError> {
+        let mut query =
+            vendor_order::Entity::find().filter(vendor_order::Column::OrderId.eq(order_id));
+        if let OrderScope::Vendor(seller_id) = scope {
+            query = query.filter(vendor_order::Column::VendorId.eq(seller_id));
+        }
+
+        let vendor_orders = query
+            .order_by_asc(vendor_order::Column::CreatedAt)
             .all(&*self.db)
             .await?;
 
-        Ok(items)
+        self.with_items(vendor_orders.into_iter().map(|v| (v, None)).collect())
+            .await
     }
 
-    pub async fn delete_order(&self, order_id: Uuid) -> Result<(), ServiceError> {
-        // Delete order items first
-        order_item::Entity::delete_many()
-            .filter(order_item::Column::OrderId.eq(order_id))
-            .exec(&*self.db)
+    /// Fulfilment view: the vendor orders visible within `scope`, newest
+    /// first, with their lines and where to deliver them
+    pub async fn list_vendor_orders(
+        &self,
+        scope: OrderScope,
+        status: Option<Status>,
+    ) -> Result<Vec<VendorOrderDetails>, ServiceError> {
+        let mut query = vendor_order::Entity::find()
+            .find_also_related(order::Entity)
+            .filter(order::Column::DeletedAt.is_null());
+
+        match scope {
+            OrderScope::All => {}
+            OrderScope::Buyer(buyer_id) => {
+                query = query.filter(order::Column::UserId.eq(buyer_id));
+            }
+            OrderScope::Vendor(seller_id) => {
+                query = query.filter(vendor_order::Column::VendorId.eq(seller_id));
+            }
+        }
+        if let Some(status) = status {
+            query = query.filter(vendor_order::Column::Status.eq(status));
+        }
+
+        let rows = query
+            .order_by_desc(vendor_order::Column::CreatedAt)
+            .all(&*self.db)
             .await?;
 
-        // Then delete the order
-        order::Entity::delete_by_id(order_id)
+        self.with_items(
+            rows.into_iter()
+                .map(|(v, order)| (v, order.as_ref().map(ShippingAddress::from)))
+                .collect(),
+        )
+        .await
+    }
+
+    async fn with_items(
+        &self,
+        vendor_orders: Vec<(vendor_order::Model, Option<ShippingAddress>)>,
+    ) -> Result<Vec<VendorOrderDetails>, ServiceError> {
+        if vendor_orders.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let ids: Vec<Uuid> = vendor_orders.iter().map(|(v, _)| v.id).collect();
+        let mut items: HashMap<Uuid, Vec<order_item::Model>> = HashMap::new();
+        for item in order_item::Entity::find()
+            .filter(order_item::Column::VendorOrderId.is_in(ids))
+            .all(&*self.db)
+            .await?
+        {
+            if let Some(vendor_order_id) = item.vendor_order_id {
+                items.entry(vendor_order_id).or_default().push(item);
+            }
+        }
+
+        Ok(vendor_orders
+            .into_iter()
+            .map(|(vendor_order, shipping)| VendorOrderDetails {
+                items: items.remove(&vendor_order.id).unwrap_or_default(),
+                vendor_order,
+                shipping,
+            })
+            .collect())
+    }
+
+    /// Move a vendor's part of an order through fulfilment.
+    ///
+    /// Vendors ship and deliver their own vendor orders once the order has
+    /// been paid, recording carrier and tracking details when given. Admins
+    /// may also cancel a vendor order, which releases its stock unless it
+    /// was already shipped. Once every vendor order still open has shipped
+    /// or been delivered, the parent order follows; once every vendor order
+    /// is cancelled, so is the parent order.
+    pub async fn update_vendor_order_status(
+        &self,
+        vendor_order_id: Uuid,
+        update: VendorOrderUpdate,
+        actor: Actor,
+    ) -> Result<vendor_order::Model, ServiceError> {
+        let txn = self.db.begin().await?;
+
+        let vendor_order = vendor_order::Entity::find_by_id(vendor_order_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Vendor order not found".to_string()))?;
+
+        match &actor {
+            Actor::User {
+                id,
+                role: UserRole::Vendor,
+            } if *id != vendor_order.vendor_id => {
+                return Err(ServiceError::NotFound("Vendor order not found".to_string()));
+            }
+            Actor::User {
+                role: UserRole::Buyer,
+                ..
+            } => {
+                return Err(ServiceError::Forbidden(
+                    "Buyers cannot update vendor orders".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        check_transition(
+            vendor_order.status,
+            update.status,
+            &actor,
+            update.reason.as_deref(),
+        )?;
+
+        let order_id = vendor_order.order_id;
+        let from_status = vendor_order.status;
+        let now = chrono::Utc::now();
+        let mut active_model: vendor_order::ActiveModel = vendor_order.into();
+        active_model.status = Set(update.status);
+        if update.carrier.is_some() {
+            active_model.carrier = Set(update.carrier);
+        }
+        if update.tracking_number.is_some() {
+            active_model.tracking_number = Set(update.tracking_number);
+        }
+        match update.status {
+            Status::Shipped => active_model.shipped_at = Set(Some(now)),
+            Status::Delivered => active_model.delivered_at = Set(Some(now)),
+            _ => {}
+        }
+        active_model.updated_at = Set(now);
+        let updated = active_model.update(&txn).await?;
+
+        if update.status == Status::Cancelled {
+            // Goods that already left the vendor are not back in stock
+            if !matches!(from_status, Status::Shipped | Status::Delivered) {
+                let items = order_item::Entity::find()
+                    .filter(order_item::Column::VendorOrderId.eq(vendor_order_id))
+                    .all(&txn)
+                    .await?;
+                release_stock(&txn, items).await?;
+            }
+            record_vendor_order_cancellation(&txn, &updated).await?;
+        }
+
+        // Roll the parent order up once every open vendor order has moved
+        // on, or cancel it when no vendor is left to fulfil it
+        let open = vendor_order::Entity::find()
+            .filter(vendor_order::Column::OrderId.eq(order_id))
+            .filter(vendor_order::Column::Status.ne(Status::Cancelled))
+            .all(&txn)
+            .await?;
+        let target = if open.is_empty() {
+            Some(Status::Cancelled)
+        } else if open.iter().all(|v| v.status == Status::Delivered) {
+            Some(Status::Delivered)
+        } else if open
+            .iter()
+            .all(|v| matches!(v.status, Status::Shipped | Status::Delivered))
+        {
+            Some(Status::Shipped)
+        } else {
+            None
+        };
+
+        if let Some(target) = target {
+            let order = order::Entity::find_by_id(order_id)
+                .one(&txn)
+                .await?
+                .ok_or_else(|| ServiceError::NotFound("Order not found".to_string()))?;
+            let mut steps = Vec::new();
+            if target == Status::Cancelled {
+                if matches!(order.status, Status::Pending | Status::Processing) {
+                    steps.push(Status::Cancelled);
+                }
+            } else {
+                if order.status == Status::Processing {
+                    steps.push(Status::Shipped);
+                }
+                if target == Status::Delivered
+                    && matches!(order.status, Status::Processing | Status::Shipped)
+                {
+                    steps.push(Status::Delivered);
+                }
+            }
+            for step in steps {
+                transition_order(
+                    &txn,
+                    order_id,
+                    step,
+                    &Actor::System,
+                    Some(format!("All vendor orders {}", step)),
+                )
+                .await?;
+            }
+        }
+
+        txn.commit().await?;
+
+        Ok(updated)
+    }
+
+    /// Soft delete an order; its items, payments and history are kept
+    pub async fn delete_order(&self, order_id: Uuid) -> Result<(), ServiceError> {
+        let result = order::Entity::update_many()
+            .col_expr(order::Column::DeletedAt, Expr::value(chrono::Utc::now()))
+            .filter(order::Column::Id.eq(order_id))
+            .filter(order::Column::DeletedAt.is_null())
             .exec(&*self.db)
             .await?;
 
+        if result.rows_affected == 0 {
+            return Err(ServiceError::NotFound("Order not found".to_string()));
+        }
+
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::models::order::CreateOrder;
+    use crate::routes::order::OrderItemRequest;
 
     use super::*;
-    use sea_orm::MockDatabase;
+    use crate::models::ledger_transaction;
+    use sea_orm::{MockDatabase, MockExecResult};
+
+    fn catalog_product(id: Uuid, price: Money, is_approved: bool) -> product::Model {
+        let moderation_status = if is_approved {
+            ModerationStatus::Approved
+        } else {
+            ModerationStatus::PendingReview
+        };
+        product::Model {
+            id,
+            seller_id: Uuid::new_v4(),
+            title: "Ndop Cloth".to_string(),
+            description: None,
+            price,
+            category_id: None,
+            quantity: 10,
+            image_urls: vec![],
+            moderation_status,
+            rejection_reason: None,
+            moderator_note: None,
+            return_policy: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn new_order(product_id: Uuid, quantity: u32) -> NewOrder {
+        NewOrder {
+            user_id: Uuid::new_v4(),
+            customer_name: "Test Customer".to_string(),
+            customer_email: Some("test@example.com".to_string()),
+            customer_phone: "677777777".parse().unwrap(),
+            delivery_address: "Test Address".to_string(),
+            status: Status::Pending,
+            items: vec![OrderItemRequest {
+                product_id: product_id.to_string(),
+                variant_id: None,
+                quantity,
+            }],
+            city: "Test City".to_string(),
+            region: "Test Region".to_string(),
+        }
+    }
 
     #[tokio::test]
     async fn test_create_order() {
+        let product_id = Uuid::new_v4();
+        let order_id = Uuid::new_v4();
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![catalog_product(
+                product_id,
+                Money::xaf(50),
+                true,
+            )]])
+            .append_query_results::<product_variant::Model, _, _>(vec![vec![]])
             .append_query_results(vec![vec![order::Model {
-                id: Uuid::new_v4(),
+                id: order_id,
                 user_id: Uuid::new_v4(),
                 customer_name: "Test Customer".to_string(),
                 customer_email: Some("test@example.com".to_string()),
-                customer_phone: "1234567890".to_string(),
+                customer_phone: "677777777".parse().unwrap(),
                 delivery_address: "Test Address".to_string(),
                 region: "Test Region".to_string(),
                 city: "Test City".to_string(),
-                status: "pending".to_string(),
-                total: 100.0, // 100.00
+                status: Status::Pending,
+                total: Money::xaf(100), // 100.00
                 created_at: chrono::Utc::now(),
+                deleted_at: None,
             }]])
+            .append_query_results(vec![vec![history_row(order_id, None, Status::Pending)]])
+            .append_query_results(vec![vec![vendor_order_row(
+                order_id,
+                Uuid::new_v4(),
+                Status::Pending,
+            )]])
             .append_query_results(vec![vec![order_item::Model {
                 id: Uuid::new_v4(),
-                order_id: Uuid::new_v4(),
-                product_id: Uuid::new_v4(),
-                price: 50.0, // 50.00
+                order_id,
+                product_id,
+                variant_id: None,
+                sku: None,
+                price: Money::xaf(50), // 50.00
                 quantity: 2,
+                vendor_order_id: None,
             }]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }]) // Simulate stock decrement
             .into_connection();
 
         let service = OrderService::new(db.into());
 
-        let _order_data = CreateOrder {
+        let result = service.create_order(new_order(product_id, 2)).await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().total, Money::xaf(100));
+    }
+
+    #[tokio::test]
+    async fn test_create_order_rejects_unapproved_product() {
+        let product_id = Uuid::new_v4();
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![catalog_product(
+                product_id,
+                Money::xaf(50),
+                false,
+            )]])
+            .into_connection();
+
+        let service = OrderService::new(db.into());
+
+        let result = service.create_order(new_order(product_id, 1)).await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_create_order_rejects_insufficient_stock() {
+        let product_id = Uuid::new_v4();
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![catalog_product(
+                product_id,
+                Money::xaf(50),
+                true,
+            )]])
+            .append_query_results::<product_variant::Model, _, _>(vec![vec![]])
+            .into_connection();
+
+        let service = OrderService::new(db.into());
+
+        // The catalog product only has 10 units in stock
+        let result = service.create_order(new_order(product_id, 11)).await;
+        match result {
+            Err(ServiceError::Validation(msg)) => assert!(msg.contains("Insufficient stock")),
+            _ => panic!("Expected ValidationError"),
+        }
+    }
+
+    fn catalog_variant(product_id: Uuid, sku: &str, price: Money) -> product_variant::Model {
+        product_variant::Model {
             id: Uuid::new_v4(),
+            product_id,
+            sku: sku.to_string(),
+            options: Default::default(),
+            price,
+            quantity: 3,
+            image_urls: vec![],
+            position: 0,
             created_at: chrono::Utc::now(),
-            user_id: "test_session".to_string(),
-            customer_name: "Test Customer".to_string(),
-            customer_email: Some("test@example.com".to_string()),
-            customer_phone: "1234567890".to_string(),
-            delivery_address: "Test Address".to_string(),
-            region: "Test Region".to_string(),
-            city: "Test City".to_string(),
-            status: "pending".to_string(),
-            total: 1000.0,
-            items: vec![order::Items {
-                product_id: Uuid::new_v4(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_order_prices_and_reserves_the_variant() {
+        let product_id = Uuid::new_v4();
+        let order_id = Uuid::new_v4();
+        let short = catalog_variant(product_id, "PAGNE-3", Money::xaf(6000));
+        let long = catalog_variant(product_id, "PAGNE-6", Money::xaf(11000));
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![catalog_product(
+                product_id,
+                Money::xaf(6000),
+                true,
+            )]])
+            .append_query_results(vec![vec![short, long.clone()]])
+            .append_query_results(vec![vec![order_with_status(order_id, Status::Pending)]])
+            .append_query_results(vec![vec![history_row(order_id, None, Status::Pending)]])
+            .append_query_results(vec![vec![vendor_order_row(
+                order_id,
+                Uuid::new_v4(),
+                Status::Pending,
+            )]])
+            .append_query_results(vec![vec![order_item::Model {
+                id: Uuid::new_v4(),
+                order_id,
+                product_id,
+                variant_id: Some(long.id),
+                sku: Some(long.sku.clone()),
+                price: long.price,
                 quantity: 2,
-                price: 50.0,
-            }],
-        };
+                vendor_order_id: None,
+            }]])
+            .append_exec_results(vec![
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+            ])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = OrderService::new(db.clone());
+
+        let mut order = new_order(product_id, 2);
+        order.items[0].variant_id = Some(long.id);
+        service.create_order(order).await.unwrap();
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let statements = log[0].statements();
+        let order_sql = statements[3].to_string();
+        assert!(order_sql.contains("22000"), "{}", order_sql);
+        let item_sql = statements[6].to_string();
+        assert!(item_sql.contains("'PAGNE-6'"), "{}", item_sql);
+        assert!(statements[8]
+            .to_string()
+            .starts_with("UPDATE \"product_variants\""));
+    }
+
+    #[tokio::test]
+    async fn test_create_order_requires_a_variant() {
+        let product_id = Uuid::new_v4();
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![catalog_product(
+                product_id,
+                Money::xaf(6000),
+                true,
+            )]])
+            .append_query_results(vec![vec![catalog_variant(
+                product_id,
+                "PAGNE-3",
+                Money::xaf(6000),
+            )]])
+            .into_connection();
+
+        let service = OrderService::new(db.into());
+
+        let result = service.create_order(new_order(product_id, 1)).await;
+        match result {
+            Err(ServiceError::Validation(msg)) => assert!(msg.contains("Choose a variant")),
+            _ => panic!("Expected ValidationError"),
+        }
     }
 
     #[tokio::test]
@@ -200,13 +1124,14 @@ mod tests {
                 user_id: Uuid::new_v4(),
                 customer_name: "Test Customer".to_string(),
                 customer_email: Some("test@example.com".to_string()),
-                customer_phone: "1234567890".to_string(),
+                customer_phone: "677777777".parse().unwrap(),
                 delivery_address: "Test Address".to_string(),
                 region: "Test Region".to_string(),
                 city: "Test City".to_string(),
-                status: "pending".to_string(),
-                total: 10.0,
+                status: Status::Pending,
+                total: Money::xaf(10),
                 created_at: chrono::Utc::now(),
+                deleted_at: None,
             }]])
             .into_connection();
 
@@ -218,50 +1143,247 @@ mod tests {
         let order_response = result.unwrap();
         let order_response = order_response.unwrap();
         assert_eq!(order_response.id, order_id);
-        assert_eq!(order_response.status, "pending");
+        assert_eq!(order_response.status, Status::Pending);
+    }
+
+    fn order_with_status(order_id: Uuid, status: Status) -> order::Model {
+        order::Model {
+            id: order_id,
+            user_id: Uuid::new_v4(),
+            customer_name: "Test Customer".to_string(),
+            customer_email: Some("test@example.com".to_string()),
+            customer_phone: "677777777".parse().unwrap(),
+            delivery_address: "Test Address".to_string(),
+            region: "Test Region".to_string(),
+            city: "Test City".to_string(),
+            status,
+            total: Money::xaf(100),
+            created_at: chrono::Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    fn history_row(
+        order_id: Uuid,
+        from: Option<Status>,
+        to: Status,
+    ) -> order_status_history::Model {
+        order_status_history::Model {
+            id: Uuid::new_v4(),
+            order_id,
+            from_status: from,
+            to_status: to,
+            changed_by: None,
+            changed_by_role: None,
+            reason: None,
+            created_at: chrono::Utc::now(),
+        }
+    }
+
+    fn vendor_order_row(order_id: Uuid, vendor_id: Uuid, status: Status) -> vendor_order::Model {
+        vendor_order::Model {
+            id: Uuid::new_v4(),
+            order_id,
+            vendor_id,
+            status,
+            subtotal: Money::xaf(100),
+            carrier: None,
+            tracking_number: None,
+            shipped_at: None,
+            delivered_at: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn user(role: UserRole) -> Actor {
+        Actor::User {
+            id: Uuid::new_v4(),
+            role,
+        }
     }
 
     #[tokio::test]
     async fn test_update_order_status() {
         let order_id = Uuid::new_v4();
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
-            .append_query_results(vec![vec![order::Model {
-                id: order_id,
-                user_id: Uuid::new_v4(),
-                customer_name: "Test Customer".to_string(),
-                customer_email: Some("test@example.com".to_string()),
-                customer_phone: "1234567890".to_string(),
-                delivery_address: "Test Address".to_string(),
-                region: "Test Region".to_string(),
-                city: "Test City".to_string(),
-                status: "pending".to_string(),
-                total: 100.0,
-                created_at: chrono::Utc::now(),
-            }]])
-            .append_query_results(vec![vec![order::Model {
-                id: order_id,
-               user_id: Uuid::new_v4(),
-                customer_name: "Test Customer".to_string(),
-                customer_email: Some("test@example.com".to_string()),
-                customer_phone: "1234567890".to_string(),
-                delivery_address: "Test Address".to_string(),
-                region: "Test Region".to_string(),
-                city: "Test City".to_string(),
-                status: "completed".to_string(),
-                total: 100.0,
-                created_at: chrono::Utc::now(),
-            }]])
+            .append_query_results(vec![
+                vec![order_with_status(order_id, Status::Processing)],
+                vec![order_with_status(order_id, Status::Shipped)],
+            ])
+            .append_query_results(vec![vec![history_row(
+                order_id,
+                Some(Status::Processing),
+                Status::Shipped,
+            )]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }]) // Vendor orders follow the order
             .into_connection();
 
         let service = OrderService::new(db.into());
 
         let result = service
-            .update_order_status(order_id, Status::Delivered)
+            .update_order_status(order_id, Status::Shipped, user(UserRole::Admin), None)
             .await;
         assert!(result.is_ok());
 
         let order_response = result.unwrap();
-        assert_eq!(order_response.status, "completed");
+        assert_eq!(order_response.status, Status::Shipped);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_order_releases_stock() {
+        let order_id = Uuid::new_v4();
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![
+                vec![order_with_status(order_id, Status::Pending)],
+                vec![order_with_status(order_id, Status::Cancelled)],
+            ])
+            .append_query_results(vec![vec![history_row(
+                order_id,
+                Some(Status::Pending),
+                Status::Cancelled,
+            )]])
+            .append_query_results(vec![vec![order_item::Model {
+                id: Uuid::new_v4(),
+                order_id,
+                product_id: Uuid::new_v4(),
+                variant_id: None,
+                sku: None,
+                price: Money::xaf(50),
+                quantity: 2,
+                vendor_order_id: None,
+            }]])
+            .append_query_results(vec![Vec::<vendor_order::Model>::new()])
+            .append_exec_results(vec![
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                }, // Vendor orders follow the order
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                }, // Simulate stock release
+            ])
+            .into_connection();
+
+        let db = Arc::new(db);
+        let service = OrderService::new(db.clone());
+
+        let result = service
+            .update_order_status(order_id, Status::Cancelled, user(UserRole::Buyer), None)
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().status, Status::Cancelled);
+
+        // Lines of vendor orders already shipped, delivered or cancelled are
+        // skipped
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let items_sql = log[0].statements()[4].to_string();
+        assert!(
+            items_sql.contains(
+                "\"vendor_orders\".\"status\" NOT IN ('shipped', 'delivered', 'cancelled')"
+            ),
+            "{}",
+            items_sql
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_order_status_rejects_illegal_transition() {
+        let order_id = Uuid::new_v4();
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![order_with_status(order_id, Status::Delivered)]])
+            .into_connection();
+
+        let service = OrderService::new(db.into());
+
+        let result = service
+            .update_order_status(order_id, Status::Pending, user(UserRole::Buyer), None)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[test]
+    fn test_check_transition_roles() {
+        // Buyers may only cancel
+        assert!(check_transition(
+            Status::Pending,
+            Status::Cancelled,
+            &user(UserRole::Buyer),
+            None
+        )
+        .is_ok());
+        assert!(matches!(
+            check_transition(
+                Status::Processing,
+                Status::Shipped,
+                &user(UserRole::Buyer),
+                None
+            ),
+            Err(ServiceError::Forbidden(_))
+        ));
+        // Paid orders are no longer the buyer's to cancel
+        assert!(matches!(
+            check_transition(
+                Status::Processing,
+                Status::Cancelled,
+                &user(UserRole::Buyer),
+                None
+            ),
+            Err(ServiceError::Forbidden(_))
+        ));
+        // Vendors ship but cannot mark an order as paid
+        assert!(check_transition(
+            Status::Processing,
+            Status::Shipped,
+            &user(UserRole::Vendor),
+            None
+        )
+        .is_ok());
+        assert!(matches!(
+            check_transition(
+                Status::Pending,
+                Status::Processing,
+                &user(UserRole::Vendor),
+                None
+            ),
+            Err(ServiceError::Forbidden(_))
+        ));
+        // Payment confirmation is done by the system
+        assert!(
+            check_transition(Status::Pending, Status::Processing, &Actor::System, None).is_ok()
+        );
+        // Admin overrides need a reason
+        assert!(matches!(
+            check_transition(
+                Status::Delivered,
+                Status::Pending,
+                &user(UserRole::Admin),
+                None
+            ),
+            Err(ServiceError::Validation(_))
+        ));
+        assert!(check_transition(
+            Status::Delivered,
+            Status::Pending,
+            &user(UserRole::Admin),
+            Some("Parcel returned to sender")
+        )
+        .is_ok());
+        // Not even admins reopen a cancelled order
+        assert!(matches!(
+            check_transition(
+                Status::Cancelled,
+                Status::Processing,
+                &user(UserRole::Admin),
+                Some("Buyer changed their mind")
+            ),
+            Err(ServiceError::Validation(_))
+        ));
     }
 
     #[tokio::test]
@@ -271,36 +1393,40 @@ mod tests {
             .append_query_results(vec![vec![
                 order::Model {
                     id: Uuid::new_v4(),
-                    user_id: user_id,
+                    user_id,
                     customer_name: "Customer 1".to_string(),
                     customer_email: Some("customer1@example.com".to_string()),
-                    customer_phone: "1234567890".to_string(),
+                    customer_phone: "677777777".parse().unwrap(),
                     delivery_address: "Address 1".to_string(),
                     region: "Region 1".to_string(),
                     city: "City 1".to_string(),
-                    status: "pending".to_string(),
-                    total: 100.0,
+                    status: Status::Pending,
+                    total: Money::xaf(100),
                     created_at: chrono::Utc::now(),
+                    deleted_at: None,
                 },
                 order::Model {
                     id: Uuid::new_v4(),
-                    user_id: user_id,
+                    user_id,
                     customer_name: "Customer 2".to_string(),
                     customer_email: Some("customer2@example.com".to_string()),
-                    customer_phone: "0987654321".to_string(),
+                    customer_phone: "699999999".parse().unwrap(),
                     delivery_address: "Address 2".to_string(),
                     region: "Region 2".to_string(),
                     city: "City 2".to_string(),
-                    status: "completed".to_string(),
-                    total: 100.0,
+                    status: Status::Delivered,
+                    total: Money::xaf(100),
                     created_at: chrono::Utc::now(),
+                    deleted_at: None,
                 },
             ]])
             .into_connection();
 
         let service = OrderService::new(db.into());
 
-        let result = service.list_orders(Some(user_id), None).await;
+        let result = service
+            .list_orders(OrderScope::Buyer(user_id), None, None)
+            .await;
         assert!(result.is_ok());
 
         let orders = result.unwrap();
@@ -318,22 +1444,28 @@ mod tests {
                     id: Uuid::new_v4(),
                     order_id,
                     product_id: Uuid::new_v4(),
-                    price: 50.0,
+                    variant_id: None,
+                    sku: None,
+                    price: Money::xaf(50),
                     quantity: 2,
+                    vendor_order_id: None,
                 },
                 order_item::Model {
                     id: Uuid::new_v4(),
                     order_id,
                     product_id: Uuid::new_v4(),
-                    price: 30.0,
+                    variant_id: None,
+                    sku: None,
+                    price: Money::xaf(30),
                     quantity: 1,
+                    vendor_order_id: None,
                 },
             ]])
             .into_connection();
 
         let service = OrderService::new(db.into());
 
-        let result = service.get_order_items(order_id).await;
+        let result = service.get_order_items(order_id, OrderScope::All).await;
         assert!(result.is_ok());
 
         let items = result.unwrap();
@@ -346,8 +1478,10 @@ mod tests {
     async fn test_delete_order() {
         let order_id = Uuid::new_v4();
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
-            .append_exec_results(vec![]) // Simulate successful deletion of order items
-            .append_exec_results(vec![]) // Simulate successful deletion of order
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }]) // Simulate the order being marked as deleted
             .into_connection();
 
         let service = OrderService::new(db.into());
@@ -355,4 +1489,291 @@ mod tests {
         let result = service.delete_order(order_id).await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_delete_order_not_found() {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            }])
+            .into_connection();
+
+        let service = OrderService::new(db.into());
+
+        let result = service.delete_order(Uuid::new_v4()).await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_get_order_in_scope_hides_other_buyers_orders() {
+        let order_id = Uuid::new_v4();
+        let order = order_with_status(order_id, Status::Pending);
+        let owner = order.user_id;
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![order.clone()], vec![order]])
+            .into_connection();
+
+        let service = OrderService::new(db.into());
+
+        let own = service
+            .get_order_in_scope(order_id, OrderScope::Buyer(owner))
+            .await;
+        assert!(matches!(own, Ok(Some(_))));
+
+        let other = service
+            .get_order_in_scope(order_id, OrderScope::Buyer(Uuid::new_v4()))
+            .await;
+        assert!(matches!(other, Ok(None)));
+    }
+
+    #[tokio::test]
+    async fn test_vendors_cannot_change_the_whole_order() {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres).into_connection();
+
+        let service = OrderService::new(db.into());
+
+        let result = service
+            .update_order_status(
+                Uuid::new_v4(),
+                Status::Shipped,
+                user(UserRole::Vendor),
+                None,
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Forbidden(_))));
+    }
+
+    fn shipping(status: Status) -> VendorOrderUpdate {
+        VendorOrderUpdate {
+            status,
+            carrier: Some("Campost".to_string()),
+            tracking_number: Some("CP123456CM".to_string()),
+            reason: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_last_vendor_to_ship_ships_the_order() {
+        let order_id = Uuid::new_v4();
+        let vendor_id = Uuid::new_v4();
+        let own = vendor_order_row(order_id, vendor_id, Status::Processing);
+        let shipped = vendor_order::Model {
+            status: Status::Shipped,
+            carrier: Some("Campost".to_string()),
+            tracking_number: Some("CP123456CM".to_string()),
+            shipped_at: Some(chrono::Utc::now()),
+            ..own.clone()
+        };
+        let other = vendor_order_row(order_id, Uuid::new_v4(), Status::Shipped);
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![
+                vec![own.clone()],
+                vec![shipped.clone()],
+                vec![shipped.clone(), other],
+            ])
+            .append_query_results(vec![
+                vec![order_with_status(order_id, Status::Processing)],
+                vec![order_with_status(order_id, Status::Processing)],
+                vec![order_with_status(order_id, Status::Shipped)],
+            ])
+            .append_query_results(vec![vec![history_row(
+                order_id,
+                Some(Status::Processing),
+                Status::Shipped,
+            )]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            }])
+            .into_connection();
+
+        let service = OrderService::new(db.into());
+
+        let actor = Actor::User {
+            id: vendor_id,
+            role: UserRole::Vendor,
+        };
+        let result = service
+            .update_vendor_order_status(own.id, shipping(Status::Shipped), actor)
+            .await
+            .unwrap();
+        assert_eq!(result.status, Status::Shipped);
+        assert_eq!(result.tracking_number.as_deref(), Some("CP123456CM"));
+    }
+
+    #[tokio::test]
+    async fn test_cancelling_the_last_vendor_order_cancels_the_order() {
+        let order_id = Uuid::new_v4();
+        let own = vendor_order_row(order_id, Uuid::new_v4(), Status::Processing);
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![
+                vec![own.clone()],
+                vec![vendor_order::Model {
+                    status: Status::Cancelled,
+                    ..own.clone()
+                }],
+            ])
+            .append_query_results(vec![vec![order_item::Model {
+                id: Uuid::new_v4(),
+                order_id,
+                product_id: Uuid::new_v4(),
+                variant_id: None,
+                sku: None,
+                price: Money::xaf(50),
+                quantity: 2,
+                vendor_order_id: Some(own.id),
+            }]])
+            .append_query_results(vec![Vec::<ledger_transaction::Model>::new()])
+            .append_query_results(vec![Vec::<vendor_order::Model>::new()])
+            .append_query_results(vec![
+                vec![order_with_status(order_id, Status::Processing)],
+                vec![order_with_status(order_id, Status::Processing)],
+                vec![order_with_status(order_id, Status::Cancelled)],
+            ])
+            .append_query_results(vec![vec![history_row(
+                order_id,
+                Some(Status::Processing),
+                Status::Cancelled,
+            )]])
+            .append_query_results(vec![Vec::<order_item::Model>::new()])
+            .append_query_results(vec![Vec::<vendor_order::Model>::new()])
+            .append_exec_results(vec![
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                }, // Stock of the cancelled vendor order
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 0,
+                }, // No other vendor order is open
+            ])
+            .into_connection();
+
+        let db = Arc::new(db);
+        let service = OrderService::new(db.clone());
+
+        let result = service
+            .update_vendor_order_status(
+                own.id,
+                VendorOrderUpdate {
+                    status: Status::Cancelled,
+                    carrier: None,
+                    tracking_number: None,
+                    reason: None,
+                },
+                user(UserRole::Admin),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.status, Status::Cancelled);
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        assert!(log[0]
+            .statements()
+            .iter()
+            .any(|s| s.sql.starts_with("UPDATE \"orders\"")));
+    }
+
+    #[tokio::test]
+    async fn test_cancelling_a_shipped_vendor_order_keeps_its_stock() {
+        let order_id = Uuid::new_v4();
+        let own = vendor_order_row(order_id, Uuid::new_v4(), Status::Shipped);
+        let other = vendor_order_row(order_id, Uuid::new_v4(), Status::Processing);
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![
+                vec![own.clone()],
+                vec![vendor_order::Model {
+                    status: Status::Cancelled,
+                    ..own.clone()
+                }],
+            ])
+            .append_query_results(vec![Vec::<ledger_transaction::Model>::new()])
+            .append_query_results(vec![vec![other]])
+            .into_connection();
+
+        let db = Arc::new(db);
+        let service = OrderService::new(db.clone());
+
+        service
+            .update_vendor_order_status(
+                own.id,
+                VendorOrderUpdate {
+                    status: Status::Cancelled,
+                    carrier: None,
+                    tracking_number: None,
+                    reason: Some("Parcel lost by the carrier".to_string()),
+                },
+                user(UserRole::Admin),
+            )
+            .await
+            .unwrap();
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        assert!(!log[0]
+            .statements()
+            .iter()
+            .any(|s| s.sql.contains("\"order_items\"") || s.sql.contains("\"products\"")));
+    }
+
+    #[tokio::test]
+    async fn test_vendor_cannot_ship_unpaid_or_foreign_vendor_orders() {
+        let order_id = Uuid::new_v4();
+        let vendor_id = Uuid::new_v4();
+        let unpaid = vendor_order_row(order_id, vendor_id, Status::Pending);
+        let foreign = vendor_order_row(order_id, Uuid::new_v4(), Status::Processing);
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![unpaid.clone()], vec![foreign.clone()]])
+            .into_connection();
+
+        let service = OrderService::new(db.into());
+
+        let actor = Actor::User {
+            id: vendor_id,
+            role: UserRole::Vendor,
+        };
+        let result = service
+            .update_vendor_order_status(unpaid.id, shipping(Status::Shipped), actor.clone())
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        let result = service
+            .update_vendor_order_status(foreign.id, shipping(Status::Shipped), actor)
+            .await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_get_vendor_orders_groups_items() {
+        let order_id = Uuid::new_v4();
+        let first = vendor_order_row(order_id, Uuid::new_v4(), Status::Processing);
+        let second = vendor_order_row(order_id, Uuid::new_v4(), Status::Processing);
+        let line = |vendor_order_id: Uuid| order_item::Model {
+            id: Uuid::new_v4(),
+            order_id,
+            product_id: Uuid::new_v4(),
+            variant_id: None,
+            sku: None,
+            price: Money::xaf(50),
+            quantity: 1,
+            vendor_order_id: Some(vendor_order_id),
+        };
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![first.clone(), second.clone()]])
+            .append_query_results(vec![vec![line(first.id), line(first.id), line(second.id)]])
+            .into_connection();
+
+        let service = OrderService::new(db.into());
+
+        let vendor_orders = service
+            .get_vendor_orders(order_id, OrderScope::All)
+            .await
+            .unwrap();
+        assert_eq!(vendor_orders.len(), 2);
+        assert_eq!(vendor_orders[0].items.len(), 2);
+        assert_eq!(vendor_orders[1].items.len(), 1);
+        assert!(vendor_orders[0].shipping.is_none());
+    }
 }
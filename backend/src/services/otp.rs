@@ -0,0 +1,341 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sea_orm::{
+    sea_query::Expr, ActiveEnum, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait,
+    QueryFilter, QueryOrder, Set,
+};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::models::{
+    otp_code::{self, OtpPurpose},
+    phone::PhoneNumber,
+};
+
+use super::{
+    errors::ServiceError,
+    sms::{Sms, SmsSender},
+};
+
+/// Seconds a code can be used for after it is sent
+pub const CODE_TTL_SECS: i64 = 600;
+/// Wrong codes tolerated before a code stops working
+pub const MAX_ATTEMPTS: i32 = 5;
+/// Seconds to wait before another code is sent to the same phone
+pub const RESEND_AFTER_SECS: i64 = 60;
+/// Codes sent to the same phone for the same purpose within an hour
+pub const MAX_CODES_PER_HOUR: usize = 5;
+
+/// One-time codes texted to phones.
+///
+/// Codes are six digits, stored as a keyed hash and usable once. Only the newest code
+/// sent for a phone and purpose works, it expires after [`CODE_TTL_SECS`]
+/// and stops working after [`MAX_ATTEMPTS`] wrong guesses.
+pub struct OtpService {
+    db: Arc<DatabaseConnection>,
+    sms: Arc<dyn SmsSender>,
+    secret: String,
+}
+
+/// Generate a random six digit code
+fn new_code() -> String {
+    format!("{:06}", rand::thread_rng().gen_range(0..1_000_000))
+}
+
+/// HMAC-SHA256 of a code under `secret`. The phone and purpose are part of
+/// the message, so without the secret a leaked hash cannot be reversed by
+/// trying the million possible codes.
+fn hash_code(secret: &str, phone: &PhoneNumber, purpose: OtpPurpose, code: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(phone.as_str().as_bytes());
+    mac.update(b":");
+    mac.update(purpose.to_value().as_bytes());
+    mac.update(b":");
+    mac.update(code.as_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+impl OtpService {
+    pub fn new(db: Arc<DatabaseConnection>, sms: Arc<dyn SmsSender>, secret: String) -> Self {
+        Self { db, sms, secret }
+    }
+
+    /// Text a new code for `purpose` to `phone`, replacing any earlier one
+    pub async fn send_code(
+        &self,
+        phone: &PhoneNumber,
+        purpose: OtpPurpose,
+    ) -> Result<(), ServiceError> {
+        let now = Utc::now();
+        let recent = otp_code::Entity::find()
+            .filter(otp_code::Column::Phone.eq(phone.clone()))
+            .filter(otp_code::Column::Purpose.eq(purpose))
+            .filter(otp_code::Column::CreatedAt.gt(now - Duration::hours(1)))
+            .order_by_desc(otp_code::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+        if let Some(last) = recent.first() {
+            let wait = last.created_at + Duration::seconds(RESEND_AFTER_SECS) - now;
+            if wait > Duration::zero() {
+                return Err(ServiceError::TooManyRequests(format!(
+                    "Wait {} seconds before requesting another code",
+                    wait.num_seconds() + 1
+                )));
+            }
+        }
+        if recent.len() >= MAX_CODES_PER_HOUR {
+            return Err(ServiceError::TooManyRequests(
+                "Too many codes requested, try again later".to_string(),
+            ));
+        }
+
+        otp_code::Entity::update_many()
+            .col_expr(otp_code::Column::ConsumedAt, Expr::value(now))
+            .filter(otp_code::Column::Phone.eq(phone.clone()))
+            .filter(otp_code::Column::Purpose.eq(purpose))
+            .filter(otp_code::Column::ConsumedAt.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+
+        let code = new_code();
+        otp_code::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            phone: Set(phone.clone()),
+            purpose: Set(purpose),
+            code_hash: Set(hash_code(&self.secret, phone, purpose, &code)),
+            attempts: Set(0),
+            expires_at: Set(now + Duration::seconds(CODE_TTL_SECS)),
+            consumed_at: Set(None),
+            created_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        self.sms
+            .send(&Sms {
+                to: phone.clone(),
+                body: format!(
+                    "Your Cameroon Made Market code is {}. It expires in {} minutes.",
+                    code,
+                    CODE_TTL_SECS / 60
+                ),
+            })
+            .await
+    }
+
+    /// Check `code` against the newest code sent to `phone` for `purpose`,
+    /// using it up when it matches
+    pub async fn verify_code(
+        &self,
+        phone: &PhoneNumber,
+        purpose: OtpPurpose,
+        code: &str,
+    ) -> Result<(), ServiceError> {
+        let now = Utc::now();
+        let invalid = || ServiceError::Validation("Code is invalid or has expired".to_string());
+        let otp = otp_code::Entity::find()
+            .filter(otp_code::Column::Phone.eq(phone.clone()))
+            .filter(otp_code::Column::Purpose.eq(purpose))
+            .filter(otp_code::Column::ConsumedAt.is_null())
+            .filter(otp_code::Column::ExpiresAt.gt(now))
+            .order_by_desc(otp_code::Column::CreatedAt)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(invalid)?;
+
+        if otp.attempts >= MAX_ATTEMPTS {
+            return Err(ServiceError::Validation(
+                "Too many wrong codes, request a new one".to_string(),
+            ));
+        }
+        if hash_code(&self.secret, phone, purpose, code.trim()) != otp.code_hash {
+            otp_code::Entity::update_many()
+                .col_expr(
+                    otp_code::Column::Attempts,
+                    Expr::col(otp_code::Column::Attempts).add(1),
+                )
+                .filter(otp_code::Column::Id.eq(otp.id))
+                .exec(self.db.as_ref())
+                .await?;
+            return Err(ServiceError::Validation("Incorrect code".to_string()));
+        }
+
+        // Consume with a guarded update so a code works once even when it
+        // is submitted twice at the same time
+        let result = otp_code::Entity::update_many()
+            .col_expr(otp_code::Column::ConsumedAt, Expr::value(now))
+            .filter(otp_code::Column::Id.eq(otp.id))
+            .filter(otp_code::Column::ConsumedAt.is_null())
+            .filter(otp_code::Column::Attempts.lt(MAX_ATTEMPTS))
+            .exec(self.db.as_ref())
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(invalid());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::sms::RecordingSmsSender;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    const SECRET: &str = "test_secret";
+
+    fn service(db: Arc<DatabaseConnection>, sms: Arc<RecordingSmsSender>) -> OtpService {
+        OtpService::new(db, sms, SECRET.to_string())
+    }
+
+    fn phone() -> PhoneNumber {
+        "677777777".parse().unwrap()
+    }
+
+    fn code_row(code: &str, attempts: i32, sent_secs_ago: i64) -> otp_code::Model {
+        let created_at = Utc::now() - Duration::seconds(sent_secs_ago);
+        otp_code::Model {
+            id: Uuid::new_v4(),
+            phone: phone(),
+            purpose: OtpPurpose::PhoneVerification,
+            code_hash: hash_code(SECRET, &phone(), OtpPurpose::PhoneVerification, code),
+            attempts,
+            expires_at: created_at + Duration::seconds(CODE_TTL_SECS),
+            consumed_at: None,
+            created_at,
+        }
+    }
+
+    fn exec(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_code_texts_code_and_stores_hash() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![Vec::<otp_code::Model>::new()])
+            .append_exec_results(vec![exec(0)])
+            .append_query_results(vec![vec![code_row("123456", 0, 0)]])
+            .into_connection();
+        let sms = Arc::new(RecordingSmsSender::default());
+        let service = service(Arc::new(db), sms.clone());
+
+        service
+            .send_code(&phone(), OtpPurpose::PhoneVerification)
+            .await
+            .unwrap();
+
+        let sent = sms.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, phone());
+        assert!(sent[0]
+            .body
+            .split(|c: char| !c.is_ascii_digit())
+            .any(|word| word.len() == 6));
+    }
+
+    #[tokio::test]
+    async fn test_send_code_is_throttled() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![code_row("123456", 0, 10)]])
+            .into_connection();
+        let sms = Arc::new(RecordingSmsSender::default());
+        let service = service(Arc::new(db), sms.clone());
+
+        let result = service
+            .send_code(&phone(), OtpPurpose::PhoneVerification)
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::TooManyRequests(_))));
+        assert!(sms.sent().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_send_code_caps_codes_per_hour() {
+        let recent = (0..MAX_CODES_PER_HOUR as i64)
+            .map(|i| code_row("123456", 0, 600 + i * 300))
+            .collect::<Vec<_>>();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![recent])
+            .into_connection();
+        let service = service(Arc::new(db), Arc::new(RecordingSmsSender::default()));
+
+        let result = service
+            .send_code(&phone(), OtpPurpose::PhoneVerification)
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::TooManyRequests(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_code_consumes_matching_code() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![code_row("123456", 0, 30)]])
+            .append_exec_results(vec![exec(1)])
+            .into_connection();
+        let service = service(Arc::new(db), Arc::new(RecordingSmsSender::default()));
+
+        let result = service
+            .verify_code(&phone(), OtpPurpose::PhoneVerification, " 123456 ")
+            .await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_verify_code_counts_wrong_attempts() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![code_row("123456", 0, 30)]])
+            .append_exec_results(vec![exec(1)])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = service(db.clone(), Arc::new(RecordingSmsSender::default()));
+
+        let result = service
+            .verify_code(&phone(), OtpPurpose::PhoneVerification, "654321")
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        assert!(log[1].statements()[0]
+            .to_string()
+            .contains("\"attempts\" = \"attempts\" + 1"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_code_locks_after_too_many_attempts() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![code_row("123456", MAX_ATTEMPTS, 30)]])
+            .into_connection();
+        let service = service(Arc::new(db), Arc::new(RecordingSmsSender::default()));
+
+        // Even the right code no longer works
+        let result = service
+            .verify_code(&phone(), OtpPurpose::PhoneVerification, "123456")
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[test]
+    fn test_code_hash_is_keyed() {
+        let hash = hash_code(SECRET, &phone(), OtpPurpose::PhoneVerification, "123456");
+        assert_ne!(
+            hash,
+            hash_code("other", &phone(), OtpPurpose::PhoneVerification, "123456")
+        );
+        assert_ne!(
+            hash,
+            hash_code(SECRET, &phone(), OtpPurpose::PasswordReset, "123456")
+        );
+    }
+}
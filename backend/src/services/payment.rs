@@ -1,31 +1,103 @@
-use fapshi_rs::client::FapshiClient;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use std::{fmt, sync::Arc};
+
+use chrono::{Duration, Utc};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, SqlErr, TransactionTrait,
+};
+use serde::Serialize;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::models::{
-    order::Status,
-    payment::{self, Model},
+    money::Money,
+    order::{self, Status},
+    payment::{self, Model, PaymentStatus},
+    phone::PhoneNumber,
 };
 
-use super::errors::ServiceError;
-
+use super::{
+    errors::ServiceError,
+    ledger::LedgerService,
+    order::{transition_order, Actor},
+    payment_gateway::{GatewayTransaction, PaymentGateway, RefundRequest},
+};
 
 pub struct PaymentService {
-    pub db: DatabaseConnection,
-    pub client: FapshiClient,
+    pub db: Arc<DatabaseConnection>,
+    pub gateway: Arc<dyn PaymentGateway>,
+    pub ledger: Arc<LedgerService>,
 }
 pub struct CreatePayment {
     pub order_id: Uuid,
-    pub amount: f64,
+    pub amount: Money,
     pub payment_method: String,
     pub payment_details: Option<serde_json::Value>,
+    pub transaction_id: Option<String>,
+}
+
+/// Outcome of one pass over the payments stuck in pending
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ReconciliationReport {
+    pub checked: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub expired: usize,
+    pub still_pending: usize,
+    pub errors: usize,
+}
+
+impl fmt::Display for ReconciliationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "checked {}, completed {}, failed {}, expired {}, still pending {}, errors {}",
+            self.checked,
+            self.completed,
+            self.failed,
+            self.expired,
+            self.still_pending,
+            self.errors
+        )
+    }
+}
+
+/// Outcome of one pass over the payments stuck in refunding
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct RefundReconciliationReport {
+    pub checked: usize,
+    pub refunded: usize,
+    pub reopened: usize,
+    pub still_refunding: usize,
+    pub errors: usize,
+}
+
+impl fmt::Display for RefundReconciliationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "checked {}, refunded {}, reopened {}, still refunding {}, errors {}",
+            self.checked, self.refunded, self.reopened, self.still_refunding, self.errors
+        )
+    }
 }
 
 impl PaymentService {
-    pub fn new(db: DatabaseConnection, client: FapshiClient) -> Self {
-        Self { db, client }
+    pub fn new(
+        db: Arc<DatabaseConnection>,
+        gateway: Arc<dyn PaymentGateway>,
+        ledger: Arc<LedgerService>,
+    ) -> Self {
+        Self {
+            db,
+            gateway,
+            ledger,
+        }
     }
 
+    /// Record a payment waiting for the gateway. Fails with a conflict when
+    /// the order already has one, which the database enforces, so two
+    /// concurrent checkouts cannot both be collected.
     pub async fn create_payment(
         &self,
         payment_data: CreatePayment,
@@ -34,21 +106,28 @@ impl PaymentService {
             id: Set(Uuid::new_v4()),
             order_id: Set(payment_data.order_id),
             amount: Set(payment_data.amount),
-            status: Set(Status::Pending.into()),
+            status: Set(PaymentStatus::Pending),
             payment_method: Set(payment_data.payment_method),
             payment_details: Set(payment_data.payment_details),
+            transaction_id: Set(payment_data.transaction_id),
             created_at: Set(chrono::Utc::now()),
             updated_at: Set(chrono::Utc::now()),
         }
-        .insert(&self.db)
-        .await?;
+        .insert(self.db.as_ref())
+        .await
+        .map_err(|e| match e.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => ServiceError::Conflict(
+                "A payment for this order is already in progress".to_string(),
+            ),
+            _ => e.into(),
+        })?;
 
-        Ok(payment.into())
+        Ok(payment)
     }
 
     pub async fn get_payment_by_id(&self, payment_id: Uuid) -> Result<Option<Model>, ServiceError> {
         let payment = payment::Entity::find_by_id(payment_id)
-            .one(&self.db)
+            .one(self.db.as_ref())
             .await
             .map_err(|e| ServiceError::NotFound(e.to_string()))?;
 
@@ -61,29 +140,41 @@ impl PaymentService {
     ) -> Result<Option<Model>, ServiceError> {
         let payment = payment::Entity::find()
             .filter(payment::Column::OrderId.eq(order_id))
-            .one(&self.db)
+            .one(self.db.as_ref())
             .await
             .map_err(|e| ServiceError::NotFound(e.to_string()))?;
 
         Ok(payment)
     }
 
+    pub async fn get_payment_by_transaction_id(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<Model>, ServiceError> {
+        let payment = payment::Entity::find()
+            .filter(payment::Column::TransactionId.eq(transaction_id))
+            .one(self.db.as_ref())
+            .await?;
+
+        Ok(payment)
+    }
+
     pub async fn update_payment_status(
         &self,
         payment_id: Uuid,
-        status: String,
+        status: PaymentStatus,
     ) -> Result<Option<Model>, ServiceError> {
         let payment = payment::Entity::find_by_id(payment_id)
-            .one(&self.db)
+            .one(self.db.as_ref())
             .await
             .map_err(|e| ServiceError::NotFound(e.to_string()))?;
         if let Some(payment) = payment {
             let mut active_model: payment::ActiveModel = payment.into();
             active_model.status = Set(status);
             active_model.updated_at = Set(chrono::Utc::now());
-            let updated_payment = active_model.update(&self.db).await?;
+            let updated_payment = active_model.update(self.db.as_ref()).await?;
 
-            Ok(updated_payment.into())
+            Ok(Some(updated_payment))
         } else {
             Err(ServiceError::NotFound("Payment not found".into()))
         }
@@ -95,7 +186,7 @@ impl PaymentService {
         payment_details: serde_json::Value,
     ) -> Result<Model, ServiceError> {
         let payment = payment::Entity::find_by_id(payment_id)
-            .one(&self.db)
+            .one(self.db.as_ref())
             .await
             .map_err(|e| ServiceError::NotFound(e.to_string()))?;
 
@@ -103,23 +194,472 @@ impl PaymentService {
             let mut active_model: payment::ActiveModel = payment.into();
             active_model.payment_details = Set(Some(payment_details));
             active_model.updated_at = Set(chrono::Utc::now());
-            let updated_payment = active_model.update(&self.db).await?;
+            let updated_payment = active_model.update(self.db.as_ref()).await?;
 
-            Ok(updated_payment.into())
+            Ok(updated_payment)
         } else {
             Err(ServiceError::NotFound("Payment not found".into()))
         }
     }
+
+    /// Fetch the status of a transaction from the payment gateway. Webhook
+    /// bodies are not signed, so callbacks are only trusted once confirmed
+    /// this way.
+    pub async fn verify_transaction(
+        &self,
+        transaction_id: &str,
+    ) -> Result<GatewayTransaction, ServiceError> {
+        self.gateway.transaction_status(transaction_id).await
+    }
+
+    /// Apply a verified provider transaction to the matching payment.
+    ///
+    /// A successful payment moves its order from pending to processing and
+    /// is posted to the vendor ledger. If the order no longer awaits
+    /// payment, because it was cancelled or paid already, the payment is
+    /// flagged with the reason, left out of the ledger and refunded. A
+    /// failed or expired payment leaves the order pending so the buyer can
+    /// pay again. Payments that already reached a final state are returned
+    /// unchanged, so repeated callbacks are harmless.
+    pub async fn apply_transaction_status(
+        &self,
+        transaction: &GatewayTransaction,
+    ) -> Result<Model, ServiceError> {
+        let txn = self.db.begin().await?;
+
+        let payment = payment::Entity::find()
+            .filter(payment::Column::TransactionId.eq(&transaction.transaction_id))
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Payment not found".to_string()))?;
+
+        let status = transaction.status;
+        if payment.status.is_final() || status == PaymentStatus::Pending {
+            return Ok(payment);
+        }
+
+        if let Some(external_id) = &transaction.external_id {
+            if *external_id != payment.order_id.to_string() {
+                return Err(ServiceError::Validation(format!(
+                    "Transaction {} does not belong to order {}",
+                    transaction.transaction_id, payment.order_id
+                )));
+            }
+        }
+        if status == PaymentStatus::Completed && transaction.amount < payment.amount {
+            return Err(ServiceError::Validation(format!(
+                "Transaction {} paid {} of {}",
+                transaction.transaction_id, transaction.amount, payment.amount
+            )));
+        }
+
+        let order_id = payment.order_id;
+        let unapplied = if status == PaymentStatus::Completed {
+            match order::Entity::find_by_id(order_id)
+                .lock_exclusive()
+                .one(&txn)
+                .await?
+            {
+                Some(order) if order.status == Status::Pending => None,
+                Some(order) => Some(format!(
+                    "Order was {} when the payment completed",
+                    order.status
+                )),
+                None => Some("Order no longer exists".to_string()),
+            }
+        } else {
+            None
+        };
+
+        let mut details = payment.payment_details.clone();
+        if let Some(reason) = &unapplied {
+            let details = details.get_or_insert_with(|| serde_json::json!({}));
+            details["unapplied_reason"] = serde_json::Value::String(reason.clone());
+        }
+        let mut active_model: payment::ActiveModel = payment.into();
+        active_model.status = Set(status);
+        active_model.payment_details = Set(details);
+        active_model.updated_at = Set(chrono::Utc::now());
+        let updated_payment = active_model.update(&txn).await?;
+
+        match &unapplied {
+            None if status == PaymentStatus::Completed => {
+                transition_order(
+                    &txn,
+                    order_id,
+                    Status::Processing,
+                    &Actor::System,
+                    Some(format!("Payment {} confirmed", transaction.transaction_id)),
+                )
+                .await?;
+                self.ledger.record_payment(&txn, &updated_payment).await?;
+            }
+            None => info!(
+                "Payment {} for order {} is {}",
+                transaction.transaction_id, order_id, status
+            ),
+            Some(reason) => warn!(
+                "Payment {} for order {} is not applied: {}",
+                transaction.transaction_id, order_id, reason
+            ),
+        }
+
+        txn.commit().await?;
+
+        if unapplied.is_some() {
+            // If the gateway fails now the refund is settled by reconciliation
+            match self.refund_payment(updated_payment.id).await {
+                Ok(refunded) => return Ok(refunded),
+                Err(e) => error!(
+                    "Could not refund unapplied payment {}: {}",
+                    updated_payment.id, e
+                ),
+            }
+        }
+
+        Ok(updated_payment)
+    }
+
+    /// A payment of the order still waiting for the gateway, if any. A new
+    /// payment must not start while one is in flight, or both could be
+    /// collected.
+    pub async fn pending_payment_for_order(
+        &self,
+        order_id: Uuid,
+    ) -> Result<Option<Model>, ServiceError> {
+        Ok(payment::Entity::find()
+            .filter(payment::Column::OrderId.eq(order_id))
+            .filter(payment::Column::Status.eq(PaymentStatus::Pending))
+            .one(self.db.as_ref())
+            .await?)
+    }
+
+    /// Return the funds of a completed payment to the phone that paid it,
+    /// reverse its ledger posting and cancel its order. An order already
+    /// shipped cannot be refunded until it is delivered.
+    ///
+    /// If the gateway fails the payment stays refunding, because the payout
+    /// may have gone through anyway. [`Self::reconcile_refunds`] then finds
+    /// out from the provider, so the buyer is never paid back twice.
+    pub async fn refund_payment(&self, payment_id: Uuid) -> Result<Model, ServiceError> {
+        let payment = payment::Entity::find_by_id(payment_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Payment not found".to_string()))?;
+
+        if payment.status != PaymentStatus::Completed {
+            return Err(ServiceError::Validation(format!(
+                "Only completed payments can be refunded, payment is {}",
+                payment.status
+            )));
+        }
+        if payment.unapplied_reason().is_none() {
+            let order = order::Entity::find_by_id(payment.order_id)
+                .one(self.db.as_ref())
+                .await?;
+            if order.is_some_and(|order| order.status == Status::Shipped) {
+                return Err(ServiceError::Validation(
+                    "The order is on its way, it can be refunded once delivered".to_string(),
+                ));
+            }
+        }
+        let details = payment
+            .payment_details
+            .clone()
+            .unwrap_or_else(|| serde_json::json!({}));
+        let phone = details
+            .get("phone")
+            .and_then(|phone| phone.as_str())
+            .and_then(|phone| phone.parse::<PhoneNumber>().ok())
+            .ok_or_else(|| {
+                ServiceError::Validation("Payment has no phone number to refund".to_string())
+            })?;
+
+        // Claim the payment first, so a concurrent refund of it finds it
+        // refunding and the buyer is not paid back twice
+        let claimed = payment::Entity::update_many()
+            .col_expr(
+                payment::Column::Status,
+                Expr::value(PaymentStatus::Refunding),
+            )
+            .col_expr(payment::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(payment::Column::Id.eq(payment.id))
+            .filter(payment::Column::Status.eq(PaymentStatus::Completed))
+            .exec(self.db.as_ref())
+            .await?;
+        if claimed.rows_affected == 0 {
+            return Err(ServiceError::Validation(
+                "Payment is already being refunded".to_string(),
+            ));
+        }
+
+        let refund = self
+            .gateway
+            .refund(&RefundRequest {
+                amount: payment.amount,
+                phone,
+                name: details
+                    .get("name")
+                    .and_then(|name| name.as_str())
+                    .map(str::to_string),
+                external_id: Some(payment.id.to_string()),
+                message: Some(format!("Refund for order {}", payment.order_id)),
+            })
+            .await;
+        let receipt = match refund {
+            Ok(receipt) => receipt,
+            Err(e) => {
+                warn!(
+                    "Refund of payment {} is left refunding until its payout is reconciled: {}",
+                    payment.id, e
+                );
+                return Err(e);
+            }
+        };
+
+        self.finish_refund(payment, receipt.transaction_id).await
+    }
+
+    /// Record a refund the provider paid out: mark the payment refunded,
+    /// reverse its ledger posting and cancel its order if it was applied
+    /// and is not shipped yet
+    async fn finish_refund(
+        &self,
+        payment: Model,
+        refund_transaction_id: String,
+    ) -> Result<Model, ServiceError> {
+        let payment_id = payment.id;
+        let order_id = payment.order_id;
+        let applied = payment.unapplied_reason().is_none();
+        let mut details = payment
+            .payment_details
+            .clone()
+            .unwrap_or_else(|| serde_json::json!({}));
+        details["refund_transaction_id"] = serde_json::Value::String(refund_transaction_id);
+
+        let txn = self.db.begin().await?;
+        let mut active_model: payment::ActiveModel = payment.into();
+        active_model.status = Set(PaymentStatus::Refunded);
+        active_model.payment_details = Set(Some(details));
+        active_model.updated_at = Set(chrono::Utc::now());
+        let updated_payment = active_model.update(&txn).await?;
+        self.ledger.record_refund(&txn, &updated_payment).await?;
+
+        if applied {
+            if let Some(order) = order::Entity::find_by_id(order_id).one(&txn).await? {
+                if matches!(order.status, Status::Pending | Status::Processing) {
+                    transition_order(
+                        &txn,
+                        order_id,
+                        Status::Cancelled,
+                        &Actor::System,
+                        Some(format!("Payment {} refunded", payment_id)),
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        txn.commit().await?;
+
+        Ok(updated_payment)
+    }
+
+    /// Settle refunds left refunding for longer than `refunding_for`
+    /// because the gateway failed while paying them out.
+    ///
+    /// A payout the provider completed finishes the refund. One that failed,
+    /// or that the provider has no record of, puts the payment back to
+    /// completed so it can be refunded again.
+    pub async fn reconcile_refunds(
+        &self,
+        refunding_for: Duration,
+    ) -> Result<RefundReconciliationReport, ServiceError> {
+        let stuck_payments = payment::Entity::find()
+            .filter(payment::Column::Status.eq(PaymentStatus::Refunding))
+            .filter(payment::Column::UpdatedAt.lt(Utc::now() - refunding_for))
+            .order_by_asc(payment::Column::UpdatedAt)
+            .all(self.db.as_ref())
+            .await?;
+
+        let mut report = RefundReconciliationReport::default();
+        for payment in stuck_payments {
+            report.checked += 1;
+            let payment_id = payment.id;
+            let outcome = match self.gateway.refund_status(&payment_id.to_string()).await {
+                Ok(Some(payout)) if payout.status == PaymentStatus::Completed => self
+                    .finish_refund(payment, payout.transaction_id)
+                    .await
+                    .map(|payment| payment.status),
+                Ok(Some(payout)) if payout.status == PaymentStatus::Pending => {
+                    Ok(PaymentStatus::Refunding)
+                }
+                Ok(_) => self.reopen_refund(payment_id).await,
+                Err(e) => Err(e),
+            };
+            match outcome {
+                Ok(PaymentStatus::Refunded) => report.refunded += 1,
+                Ok(PaymentStatus::Completed) => report.reopened += 1,
+                Ok(_) => report.still_refunding += 1,
+                Err(e) => {
+                    warn!(
+                        "Could not reconcile refund of payment {}: {}",
+                        payment_id, e
+                    );
+                    report.errors += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Put a refund that was never paid out back to completed, see
+    /// [`Self::reconcile_refunds`]
+    async fn reopen_refund(&self, payment_id: Uuid) -> Result<PaymentStatus, ServiceError> {
+        let reopened = payment::Entity::update_many()
+            .col_expr(
+                payment::Column::Status,
+                Expr::value(PaymentStatus::Completed),
+            )
+            .col_expr(payment::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(payment::Column::Id.eq(payment_id))
+            .filter(payment::Column::Status.eq(PaymentStatus::Refunding))
+            .exec(self.db.as_ref())
+            .await?;
+        if reopened.rows_affected == 0 {
+            return Err(ServiceError::Validation(format!(
+                "Payment {} is no longer refunding",
+                payment_id
+            )));
+        }
+        info!(
+            "Refund of payment {} was not paid out, it can be tried again",
+            payment_id
+        );
+
+        Ok(PaymentStatus::Completed)
+    }
+
+    /// Resolve payments that have been pending for longer than `pending_for`,
+    /// for instance because their webhook was lost.
+    ///
+    /// Each payment is looked up with the gateway and settled like a
+    /// callback would. Payments still pending after `abandon_after` are
+    /// expired with the gateway and, unless another payment for the order
+    /// is in flight, their order is cancelled to release its stock.
+    pub async fn reconcile_pending_payments(
+        &self,
+        pending_for: Duration,
+        abandon_after: Duration,
+    ) -> Result<ReconciliationReport, ServiceError> {
+        let now = Utc::now();
+        let stale_payments = payment::Entity::find()
+            .filter(payment::Column::Status.eq(PaymentStatus::Pending))
+            .filter(payment::Column::TransactionId.is_not_null())
+            .filter(payment::Column::CreatedAt.lt(now - pending_for))
+            .order_by_asc(payment::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+
+        let mut report = ReconciliationReport::default();
+        for payment in stale_payments {
+            report.checked += 1;
+            let transaction_id = payment.transaction_id.clone().unwrap_or_default();
+            let outcome = match self.gateway.transaction_status(&transaction_id).await {
+                Ok(transaction) if transaction.status != PaymentStatus::Pending => self
+                    .apply_transaction_status(&transaction)
+                    .await
+                    .map(|payment| payment.status),
+                Ok(_) if payment.created_at < now - abandon_after => self
+                    .expire_payment(&payment)
+                    .await
+                    .map(|payment| payment.status),
+                Ok(_) => Ok(PaymentStatus::Pending),
+                Err(e) => Err(e),
+            };
+            match outcome {
+                Ok(PaymentStatus::Completed) => report.completed += 1,
+                Ok(PaymentStatus::Failed) => report.failed += 1,
+                Ok(PaymentStatus::Expired) => report.expired += 1,
+                Ok(_) => report.still_pending += 1,
+                Err(e) => {
+                    warn!("Could not reconcile payment {}: {}", payment.id, e);
+                    report.errors += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Expire an abandoned payment, see [`Self::reconcile_pending_payments`]
+    async fn expire_payment(&self, payment: &Model) -> Result<Model, ServiceError> {
+        let transaction_id = payment.transaction_id.clone().unwrap_or_default();
+        self.gateway.expire_transaction(&transaction_id).await?;
+
+        let txn = self.db.begin().await?;
+
+        let payment = payment::Entity::find_by_id(payment.id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Payment not found".to_string()))?;
+        if payment.status.is_final() {
+            return Ok(payment);
+        }
+
+        let payment_id = payment.id;
+        let order_id = payment.order_id;
+        let mut active_model: payment::ActiveModel = payment.into();
+        active_model.status = Set(PaymentStatus::Expired);
+        active_model.updated_at = Set(Utc::now());
+        let updated_payment = active_model.update(&txn).await?;
+
+        let other_payment = payment::Entity::find()
+            .filter(payment::Column::OrderId.eq(order_id))
+            .filter(payment::Column::Id.ne(payment_id))
+            .filter(
+                payment::Column::Status.is_in([PaymentStatus::Pending, PaymentStatus::Completed]),
+            )
+            .one(&txn)
+            .await?;
+        if other_payment.is_none() {
+            if let Some(order) = order::Entity::find_by_id(order_id).one(&txn).await? {
+                if order.status == Status::Pending {
+                    transition_order(
+                        &txn,
+                        order_id,
+                        Status::Cancelled,
+                        &Actor::System,
+                        Some(format!("Payment {} expired unpaid", transaction_id)),
+                    )
+                    .await?;
+                }
+            }
+        }
+
+        txn.commit().await?;
+
+        Ok(updated_payment)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use mockall::predicate::*;
+    use crate::{
+        models::{
+            ledger_entry::{self, LedgerAccount},
+            ledger_transaction::{self, LedgerTransactionKind},
+            order_status_history, vendor_order,
+        },
+        services::payment_gateway::{DirectPaymentRequest, MockGateway},
+    };
     use rust_decimal::Decimal;
-    use sea_orm::MockDatabase;
+    use sea_orm::{IntoMockRow, MockDatabase, MockExecResult};
     use serde_json::json;
-    use test_log;
 
     #[tokio::test]
     async fn test_create_payment() {
@@ -128,29 +668,31 @@ mod tests {
             .append_query_results(vec![vec![payment::Model {
                 id: Uuid::new_v4(),
                 order_id,
-                amount: 200.0, // 100.00
-                status: "pending".to_string(),
+                amount: Money::xaf(200),
+                status: PaymentStatus::Pending,
                 payment_method: "card".to_string(),
                 payment_details: Some(json!({
                     "card_last4": "4242",
                     "card_brand": "visa"
                 })),
                 created_at: chrono::Utc::now(),
+                transaction_id: None,
                 updated_at: chrono::Utc::now(),
             }]])
             .into_connection();
-        let client = FapshiClient::new("https://api.fapshi.com", "test_api_key", true).unwrap();
-
-        let service = PaymentService::new(db, client);
+        let db = Arc::new(db);
+        let ledger = Arc::new(LedgerService::new(db.clone(), Decimal::new(1, 1)));
+        let service = PaymentService::new(db.clone(), Arc::new(MockGateway::default()), ledger);
 
         let payment_data = CreatePayment {
             order_id,
-            amount: 100.0,
+            amount: Money::xaf(100),
             payment_method: "card".to_string(),
             payment_details: Some(json!({
                 "card_last4": "4242",
                 "card_brand": "visa"
             })),
+            transaction_id: None,
         };
 
         let result = service.create_payment(payment_data).await;
@@ -158,8 +700,18 @@ mod tests {
 
         let payment_response = result.unwrap();
         assert_eq!(payment_response.order_id, order_id);
-        assert_eq!(payment_response.status, "pending");
-        assert_eq!(payment_response.amount, 100.0);
+        assert_eq!(payment_response.status, PaymentStatus::Pending);
+        // The mock answers with its own row, so check what was inserted
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let insert = &log[0].statements()[0];
+        assert!(insert.sql.starts_with("INSERT INTO \"payments\""));
+        assert!(insert
+            .values
+            .as_ref()
+            .unwrap()
+            .0
+            .contains(&sea_orm::Value::BigInt(Some(100))));
     }
 
     #[tokio::test]
@@ -170,21 +722,20 @@ mod tests {
             .append_query_results(vec![vec![payment::Model {
                 id: payment_id,
                 order_id,
-                amount: 100.0,
-                status: "pending".to_string(),
+                amount: Money::xaf(100),
+                status: PaymentStatus::Pending,
                 payment_method: "card".to_string(),
                 payment_details: Some(json!({
                     "card_last4": "4242",
                     "card_brand": "visa"
                 })),
                 created_at: chrono::Utc::now(),
+                transaction_id: None,
                 updated_at: chrono::Utc::now(),
             }]])
             .into_connection();
 
-            let client = FapshiClient::new("https://api.fapshi.com", "test_api_key", true).unwrap();
-
-            let service = PaymentService::new(db, client);
+        let service = service(db);
 
         let result = service.get_payment_by_id(payment_id).await;
         assert!(result.is_ok());
@@ -204,42 +755,656 @@ mod tests {
                 vec![payment::Model {
                     id: payment_id,
                     order_id,
-                    amount: 100.0,
-                    status: "pending".to_string(),
+                    amount: Money::xaf(100),
+                    status: PaymentStatus::Pending,
                     payment_method: "card".to_string(),
                     payment_details: Some(json!({
                         "card_last4": "4242",
                         "card_brand": "visa"
                     })),
                     created_at: chrono::Utc::now(),
+                    transaction_id: None,
                     updated_at: chrono::Utc::now(),
                 }],
                 vec![payment::Model {
                     id: payment_id,
                     order_id,
-                    amount: 100.0,
-                    status: "completed".to_string(),
+                    amount: Money::xaf(100),
+                    status: PaymentStatus::Completed,
                     payment_method: "card".to_string(),
                     payment_details: Some(json!({
                         "card_last4": "4242",
                         "card_brand": "visa"
                     })),
                     created_at: chrono::Utc::now(),
+                    transaction_id: None,
                     updated_at: chrono::Utc::now(),
                 }],
             ])
             .into_connection();
 
-            let client = FapshiClient::new("https://api.fapshi.com", "test_api_key", true).unwrap();
-
-            let service = PaymentService::new(db, client);
+        let service = service(db);
 
         let result = service
-            .update_payment_status(payment_id, "completed".to_string())
+            .update_payment_status(payment_id, PaymentStatus::Completed)
             .await;
         assert!(result.is_ok());
 
         let payment_response = result.unwrap();
-        assert_eq!(payment_response.unwrap().status, "completed");
+        assert_eq!(payment_response.unwrap().status, PaymentStatus::Completed);
+    }
+
+    fn pending_payment(order_id: Uuid, transaction_id: &str) -> payment::Model {
+        payment::Model {
+            id: Uuid::new_v4(),
+            order_id,
+            amount: Money::xaf(5000),
+            status: PaymentStatus::Pending,
+            payment_method: "mobile_money".to_string(),
+            payment_details: None,
+            transaction_id: Some(transaction_id.to_string()),
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn order_with_status(order_id: Uuid, status: Status) -> order::Model {
+        order::Model {
+            id: order_id,
+            user_id: Uuid::new_v4(),
+            customer_name: "Test Customer".to_string(),
+            customer_email: None,
+            customer_phone: "670000000".parse().unwrap(),
+            delivery_address: "Test Address".to_string(),
+            region: "Centre".to_string(),
+            city: "Yaounde".to_string(),
+            status,
+            total: Money::xaf(5000),
+            created_at: chrono::Utc::now(),
+            deleted_at: None,
+        }
+    }
+
+    fn provider_transaction(
+        transaction_id: &str,
+        order_id: Uuid,
+        status: PaymentStatus,
+        amount: Money,
+    ) -> GatewayTransaction {
+        GatewayTransaction {
+            transaction_id: transaction_id.to_string(),
+            status,
+            amount,
+            medium: Some("mobile money".to_string()),
+            external_id: Some(order_id.to_string()),
+            user_id: None,
+            date_initiated: "2026-10-17".to_string(),
+        }
+    }
+
+    /// Query results for posting a payment of order `order_id`, not posted
+    /// before, whose only vendor order is worth the whole payment
+    fn ledger_posting(order_id: Uuid) -> Vec<Vec<sea_orm::MockRow>> {
+        let vendor_order = vendor_order::Model {
+            id: Uuid::new_v4(),
+            order_id,
+            vendor_id: Uuid::new_v4(),
+            status: Status::Pending,
+            subtotal: Money::xaf(5000),
+            carrier: None,
+            tracking_number: None,
+            shipped_at: None,
+            delivered_at: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        let posting = ledger_transaction::Model {
+            id: Uuid::new_v4(),
+            kind: LedgerTransactionKind::Payment,
+            payment_id: None,
+            payout_id: None,
+            vendor_order_id: None,
+            description: String::new(),
+            created_at: chrono::Utc::now(),
+        };
+        let entry = |account, vendor_id, amount| ledger_entry::Model {
+            id: Uuid::new_v4(),
+            transaction_id: posting.id,
+            account,
+            vendor_id,
+            amount,
+            created_at: chrono::Utc::now(),
+        };
+        vec![
+            vec![],
+            vec![vendor_order.clone().into_mock_row()],
+            vec![posting.clone().into_mock_row()],
+            vec![entry(LedgerAccount::GatewayClearing, None, Money::xaf(5000)).into_mock_row()],
+            vec![entry(
+                LedgerAccount::VendorPayable,
+                Some(vendor_order.vendor_id),
+                -Money::xaf(4500),
+            )
+            .into_mock_row()],
+            vec![entry(LedgerAccount::PlatformCommission, None, -Money::xaf(500)).into_mock_row()],
+        ]
+    }
+
+    fn service(db: DatabaseConnection) -> PaymentService {
+        with_gateway(db, Arc::new(MockGateway::default()))
+    }
+
+    fn with_gateway(db: DatabaseConnection, gateway: Arc<MockGateway>) -> PaymentService {
+        let db = Arc::new(db);
+        let ledger = Arc::new(LedgerService::new(db.clone(), Decimal::new(1, 1)));
+        PaymentService::new(db, gateway, ledger)
+    }
+
+    #[tokio::test]
+    async fn test_successful_transaction_moves_order_to_processing() {
+        let order_id = Uuid::new_v4();
+        let payment = pending_payment(order_id, "trans-1");
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payment.clone()]])
+            .append_query_results(vec![vec![order_with_status(order_id, Status::Pending)]])
+            .append_query_results(vec![vec![payment::Model {
+                status: PaymentStatus::Completed,
+                ..payment
+            }]])
+            .append_query_results(vec![
+                vec![order_with_status(order_id, Status::Pending)],
+                vec![order_with_status(order_id, Status::Processing)],
+            ])
+            .append_query_results(vec![vec![order_status_history::Model {
+                id: Uuid::new_v4(),
+                order_id,
+                from_status: Some(Status::Pending),
+                to_status: Status::Processing,
+                changed_by: None,
+                changed_by_role: None,
+                reason: Some("Payment trans-1 confirmed".to_string()),
+                created_at: chrono::Utc::now(),
+            }]])
+            .append_query_results(ledger_posting(order_id))
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+
+        let transaction = provider_transaction(
+            "trans-1",
+            order_id,
+            PaymentStatus::Completed,
+            Money::xaf(5000),
+        );
+        let result = service(db).apply_transaction_status(&transaction).await;
+
+        assert_eq!(result.unwrap().status, PaymentStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_failed_transaction_leaves_order_pending() {
+        let order_id = Uuid::new_v4();
+        let payment = pending_payment(order_id, "trans-2");
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![
+                vec![payment.clone()],
+                vec![payment::Model {
+                    status: PaymentStatus::Failed,
+                    ..payment
+                }],
+            ])
+            .into_connection();
+
+        let transaction =
+            provider_transaction("trans-2", order_id, PaymentStatus::Failed, Money::xaf(5000));
+        let result = service(db).apply_transaction_status(&transaction).await;
+
+        assert_eq!(result.unwrap().status, PaymentStatus::Failed);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_callback_is_ignored() {
+        let order_id = Uuid::new_v4();
+        let payment = payment::Model {
+            status: PaymentStatus::Completed,
+            ..pending_payment(order_id, "trans-3")
+        };
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payment]])
+            .into_connection();
+
+        let transaction =
+            provider_transaction("trans-3", order_id, PaymentStatus::Failed, Money::xaf(5000));
+        let result = service(db).apply_transaction_status(&transaction).await;
+
+        assert_eq!(result.unwrap().status, PaymentStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_underpaid_transaction_is_rejected() {
+        let order_id = Uuid::new_v4();
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![pending_payment(order_id, "trans-4")]])
+            .into_connection();
+
+        let transaction = provider_transaction(
+            "trans-4",
+            order_id,
+            PaymentStatus::Completed,
+            Money::xaf(100),
+        );
+        let result = service(db).apply_transaction_status(&transaction).await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_payment_for_cancelled_order_is_refunded() {
+        let order_id = Uuid::new_v4();
+        let payment = payment::Model {
+            payment_details: Some(json!({ "name": "Test Customer", "phone": "677777777" })),
+            ..pending_payment(order_id, "trans-7")
+        };
+        let flagged = payment::Model {
+            status: PaymentStatus::Completed,
+            payment_details: Some(json!({
+                "name": "Test Customer",
+                "phone": "677777777",
+                "unapplied_reason": "Order was cancelled when the payment completed"
+            })),
+            ..payment.clone()
+        };
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payment.clone()]])
+            .append_query_results(vec![vec![order_with_status(order_id, Status::Cancelled)]])
+            .append_query_results(vec![
+                vec![flagged.clone()],
+                vec![flagged.clone()],
+                vec![payment::Model {
+                    status: PaymentStatus::Refunded,
+                    ..flagged
+                }],
+            ])
+            .append_query_results(vec![Vec::<ledger_transaction::Model>::new()])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+
+        let transaction = provider_transaction(
+            "trans-7",
+            order_id,
+            PaymentStatus::Completed,
+            Money::xaf(5000),
+        );
+        let db = Arc::new(db);
+        let ledger = Arc::new(LedgerService::new(db.clone(), Decimal::new(1, 1)));
+        let service = PaymentService::new(db.clone(), Arc::new(MockGateway::default()), ledger);
+        let result = service.apply_transaction_status(&transaction).await;
+
+        assert_eq!(result.unwrap().status, PaymentStatus::Refunded);
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        assert!(!log
+            .iter()
+            .flat_map(|t| t.statements())
+            .any(|s| s.sql.contains("INSERT INTO \"ledger_transactions\"")));
+    }
+
+    #[tokio::test]
+    async fn test_refund_payment() {
+        let order_id = Uuid::new_v4();
+        let payment = payment::Model {
+            status: PaymentStatus::Completed,
+            payment_details: Some(json!({ "name": "Test Customer", "phone": "677777777" })),
+            ..pending_payment(order_id, "trans-5")
+        };
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payment.clone()]])
+            .append_query_results(vec![vec![order_with_status(order_id, Status::Processing)]])
+            .append_query_results(vec![vec![payment::Model {
+                status: PaymentStatus::Refunded,
+                ..payment.clone()
+            }]])
+            .append_query_results(vec![Vec::<ledger_transaction::Model>::new()])
+            .append_query_results(vec![
+                vec![order_with_status(order_id, Status::Processing)],
+                vec![order_with_status(order_id, Status::Processing)],
+                vec![order_with_status(order_id, Status::Cancelled)],
+            ])
+            .append_query_results(vec![vec![order_status_history::Model {
+                id: Uuid::new_v4(),
+                order_id,
+                from_status: Some(Status::Processing),
+                to_status: Status::Cancelled,
+                changed_by: None,
+                changed_by_role: None,
+                reason: Some(format!("Payment {} refunded", payment.id)),
+                created_at: chrono::Utc::now(),
+            }]])
+            .append_query_results(vec![Vec::<crate::models::order_item::Model>::new()])
+            .append_query_results(vec![Vec::<vendor_order::Model>::new()])
+            .append_exec_results(vec![
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+                MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                },
+            ])
+            .into_connection();
+        let db = Arc::new(db);
+        let ledger = Arc::new(LedgerService::new(db.clone(), Decimal::new(1, 1)));
+        let service = PaymentService::new(db.clone(), Arc::new(MockGateway::default()), ledger);
+
+        let result = service.refund_payment(payment.id).await;
+
+        assert_eq!(result.unwrap().status, PaymentStatus::Refunded);
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let refund_txn = log.last().unwrap().statements();
+        assert_eq!(refund_txn[0].sql, "BEGIN");
+        assert!(refund_txn
+            .iter()
+            .any(|s| s.sql.starts_with("UPDATE \"orders\"")));
+    }
+
+    #[tokio::test]
+    async fn test_refund_of_shipped_order_is_rejected() {
+        let order_id = Uuid::new_v4();
+        let payment = payment::Model {
+            status: PaymentStatus::Completed,
+            payment_details: Some(json!({ "phone": "677777777" })),
+            ..pending_payment(order_id, "trans-9")
+        };
+        let gateway = Arc::new(MockGateway::default());
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payment.clone()]])
+            .append_query_results(vec![vec![order_with_status(order_id, Status::Shipped)]])
+            .into_connection();
+
+        let result = with_gateway(db, gateway.clone())
+            .refund_payment(payment.id)
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        assert!(gateway.transaction_status("mock-000001").await.is_err());
+    }
+
+    /// A completed payment that never reached its order, so refunding it
+    /// leaves the order alone
+    fn unapplied_payment(status: PaymentStatus, transaction_id: &str) -> payment::Model {
+        payment::Model {
+            status,
+            payment_details: Some(json!({
+                "phone": "677777777",
+                "unapplied_reason": "Order was cancelled when the payment completed"
+            })),
+            ..pending_payment(Uuid::new_v4(), transaction_id)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refund_stays_refunding_when_gateway_fails() {
+        let payment = unapplied_payment(PaymentStatus::Completed, "trans-10");
+        let gateway = Arc::new(MockGateway::default());
+        gateway.lose_refund_receipts();
+        let db = Arc::new(
+            MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+                .append_query_results(vec![vec![payment.clone()]])
+                .append_exec_results(vec![MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected: 1,
+                }])
+                .into_connection(),
+        );
+        let ledger = Arc::new(LedgerService::new(db.clone(), Decimal::new(1, 1)));
+        let service = PaymentService::new(db.clone(), gateway.clone(), ledger);
+
+        let result = service.refund_payment(payment.id).await;
+
+        assert!(matches!(result, Err(ServiceError::PaymentGateway(_))));
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let updates: Vec<_> = log
+            .iter()
+            .flat_map(|t| t.statements())
+            .filter(|s| s.sql.starts_with("UPDATE \"payments\""))
+            .collect();
+        assert_eq!(updates.len(), 1);
+        assert!(gateway
+            .refund_status(&payment.id.to_string())
+            .await
+            .unwrap()
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_finishes_paid_out_refund() {
+        let payment = unapplied_payment(PaymentStatus::Refunding, "trans-11");
+        let gateway = Arc::new(MockGateway::default());
+        gateway.lose_refund_receipts();
+        let _ = gateway
+            .refund(&RefundRequest {
+                amount: payment.amount,
+                phone: "677777777".parse().unwrap(),
+                name: None,
+                external_id: Some(payment.id.to_string()),
+                message: None,
+            })
+            .await;
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![
+                vec![payment.clone()],
+                vec![payment::Model {
+                    status: PaymentStatus::Refunded,
+                    ..payment.clone()
+                }],
+            ])
+            .append_query_results(vec![Vec::<ledger_transaction::Model>::new()])
+            .into_connection();
+
+        let report = with_gateway(db, gateway)
+            .reconcile_refunds(Duration::minutes(15))
+            .await
+            .unwrap();
+
+        assert_eq!(report.checked, 1);
+        assert_eq!(report.refunded, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_reopens_refund_never_paid_out() {
+        let payment = unapplied_payment(PaymentStatus::Refunding, "trans-12");
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payment.clone()]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+
+        let report = service(db)
+            .reconcile_refunds(Duration::minutes(15))
+            .await
+            .unwrap();
+
+        assert_eq!(report.checked, 1);
+        assert_eq!(report.reopened, 1);
+    }
+
+    #[tokio::test]
+    async fn test_refund_requires_completed_payment() {
+        let payment = pending_payment(Uuid::new_v4(), "trans-6");
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payment.clone()]])
+            .into_connection();
+
+        let result = service(db).refund_payment(payment.id).await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_payment_is_refunded_once() {
+        let payment = payment::Model {
+            status: PaymentStatus::Completed,
+            payment_details: Some(json!({ "phone": "677777777" })),
+            ..pending_payment(Uuid::new_v4(), "trans-8")
+        };
+        let gateway = Arc::new(MockGateway::default());
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payment.clone()]])
+            .append_query_results(vec![vec![order_with_status(
+                payment.order_id,
+                Status::Processing,
+            )]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            }])
+            .into_connection();
+
+        let result = with_gateway(db, gateway.clone())
+            .refund_payment(payment.id)
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        assert!(gateway.transaction_status("mock-000001").await.is_err());
+    }
+
+    async fn mock_transaction(gateway: &MockGateway, order_id: Uuid) -> String {
+        gateway
+            .initiate_direct_payment(&DirectPaymentRequest {
+                amount: Money::xaf(5000),
+                phone: "677777777".parse().unwrap(),
+                name: None,
+                email: None,
+                user_id: None,
+                external_id: Some(order_id.to_string()),
+                message: None,
+            })
+            .await
+            .unwrap()
+            .transaction_id
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_settles_paid_payment() {
+        let order_id = Uuid::new_v4();
+        let gateway = Arc::new(MockGateway::default());
+        let transaction_id = mock_transaction(&gateway, order_id).await;
+        let payment = pending_payment(order_id, &transaction_id);
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payment.clone()], vec![payment.clone()]])
+            .append_query_results(vec![vec![order_with_status(order_id, Status::Pending)]])
+            .append_query_results(vec![vec![payment::Model {
+                status: PaymentStatus::Completed,
+                ..payment
+            }]])
+            .append_query_results(vec![
+                vec![order_with_status(order_id, Status::Pending)],
+                vec![order_with_status(order_id, Status::Processing)],
+            ])
+            .append_query_results(vec![vec![order_status_history::Model {
+                id: Uuid::new_v4(),
+                order_id,
+                from_status: Some(Status::Pending),
+                to_status: Status::Processing,
+                changed_by: None,
+                changed_by_role: None,
+                reason: None,
+                created_at: chrono::Utc::now(),
+            }]])
+            .append_query_results(ledger_posting(order_id))
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+
+        let service = with_gateway(db, gateway);
+        let report = service
+            .reconcile_pending_payments(Duration::minutes(15), Duration::days(1))
+            .await
+            .unwrap();
+
+        assert_eq!(report.checked, 1);
+        assert_eq!(report.completed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_expires_abandoned_payment() {
+        let order_id = Uuid::new_v4();
+        let gateway = Arc::new(MockGateway::default());
+        let transaction_id = mock_transaction(&gateway, order_id).await;
+        gateway.set_status(&transaction_id, PaymentStatus::Pending);
+        let payment = payment::Model {
+            created_at: chrono::Utc::now() - Duration::days(2),
+            ..pending_payment(order_id, &transaction_id)
+        };
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![
+                vec![payment.clone()],
+                vec![payment.clone()],
+                vec![payment::Model {
+                    status: PaymentStatus::Expired,
+                    ..payment
+                }],
+                vec![],
+            ])
+            .append_query_results(vec![
+                vec![order_with_status(order_id, Status::Pending)],
+                vec![order_with_status(order_id, Status::Pending)],
+                vec![order_with_status(order_id, Status::Cancelled)],
+            ])
+            .append_query_results(vec![vec![order_status_history::Model {
+                id: Uuid::new_v4(),
+                order_id,
+                from_status: Some(Status::Pending),
+                to_status: Status::Cancelled,
+                changed_by: None,
+                changed_by_role: None,
+                reason: None,
+                created_at: chrono::Utc::now(),
+            }]])
+            .append_query_results(vec![Vec::<crate::models::order_item::Model>::new()])
+            .append_query_results(vec![Vec::<vendor_order::Model>::new()])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+
+        let service = with_gateway(db, gateway.clone());
+        let report = service
+            .reconcile_pending_payments(Duration::minutes(15), Duration::days(1))
+            .await
+            .unwrap();
+
+        assert_eq!(report.expired, 1);
+        let transaction = gateway.transaction_status(&transaction_id).await.unwrap();
+        assert_eq!(transaction.status, PaymentStatus::Expired);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_counts_gateway_errors() {
+        let payment = pending_payment(Uuid::new_v4(), "unknown");
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![payment]])
+            .into_connection();
+
+        let report = service(db)
+            .reconcile_pending_payments(Duration::minutes(15), Duration::days(1))
+            .await
+            .unwrap();
+
+        assert_eq!(report.checked, 1);
+        assert_eq!(report.errors, 1);
     }
 }
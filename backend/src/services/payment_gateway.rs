@@ -0,0 +1,573 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use async_trait::async_trait;
+use fapshi_rs::{
+    api::{payment::PaymentApi, transaction::TransactionApi},
+    client::FapshiClient,
+    models::{self as fapshi, PaymentTransactionResponse},
+};
+use serde::Serialize;
+
+use crate::models::{
+    money::Money,
+    payment::PaymentStatus,
+    phone::{Operator, PhoneNumber},
+};
+
+use super::errors::ServiceError;
+
+/// Collect a payment directly from a mobile money account
+#[derive(Debug, Clone)]
+pub struct DirectPaymentRequest {
+    pub amount: Money,
+    pub phone: PhoneNumber,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub user_id: Option<String>,
+    /// Our reference for the payment, the order id
+    pub external_id: Option<String>,
+    pub message: Option<String>,
+}
+
+/// Collect a payment through a page hosted by the provider
+#[derive(Debug, Clone)]
+pub struct PaymentLinkRequest {
+    pub amount: Money,
+    pub email: Option<String>,
+    pub redirect_url: Option<String>,
+    pub user_id: Option<String>,
+    /// Our reference for the payment, the order id
+    pub external_id: Option<String>,
+    pub message: String,
+}
+
+/// A payment accepted by the provider and awaiting confirmation
+#[derive(Debug, Clone, Serialize)]
+pub struct InitiatedPayment {
+    pub transaction_id: String,
+    /// Page the buyer must visit to pay, for hosted payments only
+    pub payment_link: Option<String>,
+}
+
+/// The provider's view of a transaction
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GatewayTransaction {
+    pub transaction_id: String,
+    pub status: PaymentStatus,
+    pub amount: Money,
+    pub medium: Option<String>,
+    pub external_id: Option<String>,
+    pub user_id: Option<String>,
+    pub date_initiated: String,
+}
+
+/// Send collected funds back to the buyer
+#[derive(Debug, Clone)]
+pub struct RefundRequest {
+    pub amount: Money,
+    pub phone: PhoneNumber,
+    pub name: Option<String>,
+    /// Our reference for the refund, the payment id
+    pub external_id: Option<String>,
+    pub message: Option<String>,
+}
+
+/// A refund accepted by the provider
+#[derive(Debug, Clone, Serialize)]
+pub struct RefundReceipt {
+    pub transaction_id: String,
+}
+
+/// Operations the marketplace needs from a payment provider
+#[async_trait]
+pub trait PaymentGateway: Debug + Send + Sync {
+    async fn initiate_direct_payment(
+        &self,
+        request: &DirectPaymentRequest,
+    ) -> Result<InitiatedPayment, ServiceError>;
+
+    async fn create_payment_link(
+        &self,
+        request: &PaymentLinkRequest,
+    ) -> Result<InitiatedPayment, ServiceError>;
+
+    async fn transaction_status(
+        &self,
+        transaction_id: &str,
+    ) -> Result<GatewayTransaction, ServiceError>;
+
+    async fn user_transactions(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<GatewayTransaction>, ServiceError>;
+
+    /// Close a pending transaction so it can no longer be paid
+    async fn expire_transaction(&self, transaction_id: &str) -> Result<(), ServiceError>;
+
+    /// Send a refund. An error does not mean nothing was paid out: the
+    /// payout may have gone through before the answer was lost.
+    async fn refund(&self, request: &RefundRequest) -> Result<RefundReceipt, ServiceError>;
+
+    /// The payout of a refund, found by the `external_id` it was sent with,
+    /// or `None` if the provider never received it
+    async fn refund_status(
+        &self,
+        reference: &str,
+    ) -> Result<Option<GatewayTransaction>, ServiceError>;
+}
+
+fn gateway_error(e: impl ToString) -> ServiceError {
+    ServiceError::PaymentGateway(e.to_string())
+}
+
+/// Fapshi's name for the mobile money service of an operator
+fn fapshi_medium(phone: &PhoneNumber) -> Option<String> {
+    phone.operator().map(|operator| {
+        match operator {
+            Operator::Mtn => "mobile money",
+            Operator::Orange => "orange money",
+        }
+        .to_string()
+    })
+}
+
+/// Largest direct payment Fapshi can be asked for. Its API takes the
+/// amount as an `f32`, which holds whole numbers exactly only up to 2^24.
+const MAX_DIRECT_PAYMENT: Money = Money::xaf(1 << 24);
+
+/// Payment gateway backed by the Fapshi API
+#[derive(Debug, Clone)]
+pub struct FapshiGateway {
+    client: FapshiClient,
+}
+
+/// Body of a Fapshi payout, which is how Fapshi returns money to a payer
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FapshiPayout<'a> {
+    amount: i64,
+    phone: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    /// Fapshi only lists transactions by user id, so the reference is sent
+    /// as one too to find the payout again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    external_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<&'a str>,
+}
+
+impl FapshiGateway {
+    pub fn new(client: FapshiClient) -> Self {
+        Self { client }
+    }
+}
+
+impl From<fapshi::TransactionStatus> for GatewayTransaction {
+    fn from(transaction: fapshi::TransactionStatus) -> Self {
+        let status = match transaction.status {
+            fapshi::Status::CREATED | fapshi::Status::PENDING => PaymentStatus::Pending,
+            fapshi::Status::SUCCESSFUL => PaymentStatus::Completed,
+            fapshi::Status::FAILED => PaymentStatus::Failed,
+            fapshi::Status::EXPIRED => PaymentStatus::Expired,
+        };
+        Self {
+            transaction_id: transaction.transaction_id,
+            status,
+            amount: Money::from_f64(transaction.amount),
+            medium: transaction.medium,
+            external_id: transaction.external_id,
+            user_id: transaction.user_id,
+            date_initiated: transaction.date_initiated,
+        }
+    }
+}
+
+#[async_trait]
+impl PaymentGateway for FapshiGateway {
+    async fn initiate_direct_payment(
+        &self,
+        request: &DirectPaymentRequest,
+    ) -> Result<InitiatedPayment, ServiceError> {
+        if request.amount > MAX_DIRECT_PAYMENT {
+            return Err(ServiceError::Validation(format!(
+                "Direct payments are limited to {}, {} was requested",
+                MAX_DIRECT_PAYMENT, request.amount
+            )));
+        }
+        let payment_request = fapshi::DirectPaymentRequest {
+            amount: request.amount.as_xaf() as f32,
+            phone: request.phone.national().to_string(),
+            medium: fapshi_medium(&request.phone),
+            name: request.name.clone(),
+            email: request.email.clone(),
+            user_id: request.user_id.clone(),
+            external_id: request.external_id.clone(),
+            message: request.message.clone(),
+        };
+        let response = PaymentApi::initiate_direct_payment(&self.client, &payment_request)
+            .await
+            .map_err(gateway_error)?;
+
+        Ok(InitiatedPayment {
+            transaction_id: response.transaction_id,
+            payment_link: None,
+        })
+    }
+
+    async fn create_payment_link(
+        &self,
+        request: &PaymentLinkRequest,
+    ) -> Result<InitiatedPayment, ServiceError> {
+        let payment_request = fapshi::PaymentRequest {
+            amount: request.amount.to_f64(),
+            email: request.email.clone(),
+            redirect_url: request.redirect_url.clone(),
+            user_id: request.user_id.clone(),
+            external_id: request.external_id.clone(),
+            message: request.message.clone(),
+            card_only: None,
+        };
+        let response = PaymentApi::create_payment(&self.client, &payment_request)
+            .await
+            .map_err(gateway_error)?;
+
+        Ok(InitiatedPayment {
+            transaction_id: response.transaction_id,
+            payment_link: Some(response.payment_link),
+        })
+    }
+
+    async fn transaction_status(
+        &self,
+        transaction_id: &str,
+    ) -> Result<GatewayTransaction, ServiceError> {
+        TransactionApi::get_status(&self.client, transaction_id)
+            .await
+            .map(GatewayTransaction::from)
+            .map_err(gateway_error)
+    }
+
+    async fn user_transactions(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<GatewayTransaction>, ServiceError> {
+        let transactions = TransactionApi::get_transactions_by_user_id(&self.client, user_id)
+            .await
+            .map_err(gateway_error)?;
+
+        Ok(transactions
+            .into_iter()
+            .map(GatewayTransaction::from)
+            .collect())
+    }
+
+    async fn expire_transaction(&self, transaction_id: &str) -> Result<(), ServiceError> {
+        TransactionApi::expire_transaction(&self.client, transaction_id)
+            .await
+            .map_err(gateway_error)
+    }
+
+    async fn refund(&self, request: &RefundRequest) -> Result<RefundReceipt, ServiceError> {
+        let body = serde_json::to_string(&FapshiPayout {
+            amount: request.amount.as_xaf(),
+            phone: request.phone.national(),
+            name: request.name.as_deref(),
+            user_id: request.external_id.as_deref(),
+            external_id: request.external_id.as_deref(),
+            message: request.message.as_deref(),
+        })
+        .map_err(gateway_error)?;
+        let response = self
+            .client
+            .post("payout", &body)
+            .await
+            .map_err(gateway_error)?;
+        let response: PaymentTransactionResponse =
+            serde_json::from_str(&response).map_err(gateway_error)?;
+
+        Ok(RefundReceipt {
+            transaction_id: response.transaction_id,
+        })
+    }
+
+    async fn refund_status(
+        &self,
+        reference: &str,
+    ) -> Result<Option<GatewayTransaction>, ServiceError> {
+        Ok(self
+            .user_transactions(reference)
+            .await?
+            .into_iter()
+            .find(|t| t.external_id.as_deref() == Some(reference)))
+    }
+}
+
+/// Phone number the mock gateway always declines, to exercise failed payments
+pub const MOCK_DECLINED_PHONE: &str = "670000000";
+
+/// In-memory payment gateway for running checkout offline.
+///
+/// It behaves deterministically: transaction ids are numbered in order,
+/// every payment succeeds unless it comes from [`MOCK_DECLINED_PHONE`], and
+/// tests can force any other outcome with [`MockGateway::set_status`].
+#[derive(Debug, Default)]
+pub struct MockGateway {
+    next_id: AtomicU64,
+    transactions: Mutex<HashMap<String, GatewayTransaction>>,
+    lose_refund_receipts: AtomicBool,
+}
+
+impl MockGateway {
+    fn record(
+        &self,
+        amount: Money,
+        status: PaymentStatus,
+        user_id: Option<String>,
+        external_id: Option<String>,
+    ) -> String {
+        let transaction_id = format!(
+            "mock-{:06}",
+            self.next_id.fetch_add(1, Ordering::SeqCst) + 1
+        );
+        let transaction = GatewayTransaction {
+            transaction_id: transaction_id.clone(),
+            status,
+            amount,
+            medium: Some("mobile money".to_string()),
+            external_id,
+            user_id,
+            date_initiated: chrono::Utc::now().to_rfc3339(),
+        };
+        self.transactions
+            .lock()
+            .unwrap()
+            .insert(transaction_id.clone(), transaction);
+        transaction_id
+    }
+
+    /// Make refunds pay out but answer with an error, like a request that
+    /// timed out after the provider acted on it
+    pub fn lose_refund_receipts(&self) {
+        self.lose_refund_receipts.store(true, Ordering::SeqCst);
+    }
+
+    /// Force the outcome of a transaction
+    pub fn set_status(&self, transaction_id: &str, status: PaymentStatus) {
+        if let Some(transaction) = self.transactions.lock().unwrap().get_mut(transaction_id) {
+            transaction.status = status;
+        }
+    }
+}
+
+#[async_trait]
+impl PaymentGateway for MockGateway {
+    async fn initiate_direct_payment(
+        &self,
+        request: &DirectPaymentRequest,
+    ) -> Result<InitiatedPayment, ServiceError> {
+        let status = if request.phone.national() == MOCK_DECLINED_PHONE {
+            PaymentStatus::Failed
+        } else {
+            PaymentStatus::Completed
+        };
+        let transaction_id = self.record(
+            request.amount,
+            status,
+            request.user_id.clone(),
+            request.external_id.clone(),
+        );
+
+        Ok(InitiatedPayment {
+            transaction_id,
+            payment_link: None,
+        })
+    }
+
+    async fn create_payment_link(
+        &self,
+        request: &PaymentLinkRequest,
+    ) -> Result<InitiatedPayment, ServiceError> {
+        let transaction_id = self.record(
+            request.amount,
+            PaymentStatus::Completed,
+            request.user_id.clone(),
+            request.external_id.clone(),
+        );
+        let payment_link = request
+            .redirect_url
+            .clone()
+            .unwrap_or_else(|| format!("http://localhost/mock-payments/{}", transaction_id));
+
+        Ok(InitiatedPayment {
+            transaction_id,
+            payment_link: Some(payment_link),
+        })
+    }
+
+    async fn transaction_status(
+        &self,
+        transaction_id: &str,
+    ) -> Result<GatewayTransaction, ServiceError> {
+        self.transactions
+            .lock()
+            .unwrap()
+            .get(transaction_id)
+            .cloned()
+            .ok_or_else(|| gateway_error(format!("Unknown transaction {}", transaction_id)))
+    }
+
+    async fn user_transactions(
+        &self,
+        user_id: &str,
+    ) -> Result<Vec<GatewayTransaction>, ServiceError> {
+        let mut transactions: Vec<GatewayTransaction> = self
+            .transactions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|t| t.user_id.as_deref() == Some(user_id))
+            .cloned()
+            .collect();
+        transactions.sort_by(|a, b| a.transaction_id.cmp(&b.transaction_id));
+
+        Ok(transactions)
+    }
+
+    async fn expire_transaction(&self, transaction_id: &str) -> Result<(), ServiceError> {
+        let mut transactions = self.transactions.lock().unwrap();
+        let transaction = transactions
+            .get_mut(transaction_id)
+            .ok_or_else(|| gateway_error(format!("Unknown transaction {}", transaction_id)))?;
+        if transaction.status == PaymentStatus::Completed {
+            return Err(gateway_error(format!(
+                "Transaction {} is already paid",
+                transaction_id
+            )));
+        }
+        transaction.status = PaymentStatus::Expired;
+
+        Ok(())
+    }
+
+    async fn refund(&self, request: &RefundRequest) -> Result<RefundReceipt, ServiceError> {
+        let transaction_id = self.record(
+            request.amount,
+            PaymentStatus::Completed,
+            None,
+            request.external_id.clone(),
+        );
+        if self.lose_refund_receipts.load(Ordering::SeqCst) {
+            return Err(gateway_error("Timed out waiting for the payout"));
+        }
+
+        Ok(RefundReceipt { transaction_id })
+    }
+
+    async fn refund_status(
+        &self,
+        reference: &str,
+    ) -> Result<Option<GatewayTransaction>, ServiceError> {
+        Ok(self
+            .transactions
+            .lock()
+            .unwrap()
+            .values()
+            .find(|t| t.external_id.as_deref() == Some(reference))
+            .cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn direct_payment(phone: &str) -> DirectPaymentRequest {
+        DirectPaymentRequest {
+            amount: Money::xaf(5000),
+            phone: phone.parse().unwrap(),
+            name: None,
+            email: None,
+            user_id: Some("user-1".to_string()),
+            external_id: Some("order-1".to_string()),
+            message: None,
+        }
+    }
+
+    #[test]
+    fn test_fapshi_medium_follows_operator() {
+        let medium = |phone: &str| fapshi_medium(&phone.parse().unwrap());
+        assert_eq!(medium("677777777").as_deref(), Some("mobile money"));
+        assert_eq!(medium("699999999").as_deref(), Some("orange money"));
+        assert_eq!(medium("662345678"), None);
+    }
+
+    #[tokio::test]
+    async fn test_fapshi_refuses_amounts_an_f32_cannot_hold() {
+        let gateway = FapshiGateway::new(FapshiClient::new("user", "key", true).unwrap());
+        let request = DirectPaymentRequest {
+            amount: MAX_DIRECT_PAYMENT + Money::xaf(1),
+            ..direct_payment("677777777")
+        };
+
+        let result = gateway.initiate_direct_payment(&request).await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_mock_gateway_is_deterministic() {
+        let gateway = MockGateway::default();
+
+        let paid = gateway
+            .initiate_direct_payment(&direct_payment("677777777"))
+            .await
+            .unwrap();
+        let declined = gateway
+            .initiate_direct_payment(&direct_payment(MOCK_DECLINED_PHONE))
+            .await
+            .unwrap();
+
+        assert_eq!(paid.transaction_id, "mock-000001");
+        assert_eq!(declined.transaction_id, "mock-000002");
+        let paid = gateway
+            .transaction_status(&paid.transaction_id)
+            .await
+            .unwrap();
+        assert_eq!(paid.status, PaymentStatus::Completed);
+        assert_eq!(paid.external_id.as_deref(), Some("order-1"));
+        let declined = gateway
+            .transaction_status(&declined.transaction_id)
+            .await
+            .unwrap();
+        assert_eq!(declined.status, PaymentStatus::Failed);
+        assert_eq!(gateway.user_transactions("user-1").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_mock_gateway_set_status() {
+        let gateway = MockGateway::default();
+        let payment = gateway
+            .initiate_direct_payment(&direct_payment("677777777"))
+            .await
+            .unwrap();
+
+        gateway.set_status(&payment.transaction_id, PaymentStatus::Expired);
+
+        let transaction = gateway
+            .transaction_status(&payment.transaction_id)
+            .await
+            .unwrap();
+        assert_eq!(transaction.status, PaymentStatus::Expired);
+        assert!(gateway.transaction_status("unknown").await.is_err());
+    }
+}
@@ -0,0 +1,454 @@
+use std::{collections::HashSet, sync::Arc};
+
+use chrono::Utc;
+use sea_orm::{
+    sea_query::OnConflict, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait,
+    Iterable, JoinType, ModelTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{
+    role::{self, Model},
+    role_assignment,
+    role_permission::{self, Permission},
+    user::{self, UserRole},
+};
+
+use super::errors::ServiceError;
+
+#[derive(Deserialize, Debug)]
+pub struct NewRole {
+    pub name: String,
+    pub description: Option<String>,
+    pub permissions: Vec<Permission>,
+}
+
+/// A role and the permissions it grants
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoleWithPermissions {
+    #[serde(flatten)]
+    pub role: Model,
+    pub permissions: Vec<Permission>,
+}
+
+/// Staff roles and the permissions they grant.
+///
+/// Admins hold every permission. Staff accounts hold the permissions of the
+/// roles assigned to them, buyers and vendors hold none.
+pub struct PermissionService {
+    db: Arc<DatabaseConnection>,
+}
+
+/// Drop repeated permissions, keeping the first of each
+fn dedup(permissions: Vec<Permission>) -> Vec<Permission> {
+    let mut unique = Vec::with_capacity(permissions.len());
+    for permission in permissions {
+        if !unique.contains(&permission) {
+            unique.push(permission);
+        }
+    }
+    unique
+}
+
+impl PermissionService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// The role a user holds and everything it allows them to do. The role
+    /// is read from the database, not taken from the access token, so a
+    /// demotion applies to tokens already issued.
+    pub async fn permissions_for(
+        &self,
+        user_id: Uuid,
+    ) -> Result<(UserRole, HashSet<Permission>), ServiceError> {
+        let role = user::Entity::find_by_id(user_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::Unauthorized("Unknown user".to_string()))?
+            .role;
+        let permissions = match role {
+            UserRole::Admin => Permission::iter().collect(),
+            UserRole::Staff => {
+                let granted = role_permission::Entity::find()
+                    .join(JoinType::InnerJoin, role_permission::Relation::Role.def())
+                    .join(JoinType::InnerJoin, role::Relation::RoleAssignment.def())
+                    .filter(role_assignment::Column::UserId.eq(user_id))
+                    .all(self.db.as_ref())
+                    .await?;
+                granted.into_iter().map(|row| row.permission).collect()
+            }
+            UserRole::Vendor | UserRole::Buyer => HashSet::new(),
+        };
+
+        Ok((role, permissions))
+    }
+
+    /// Every role with its permissions, by name
+    pub async fn list_roles(&self) -> Result<Vec<RoleWithPermissions>, ServiceError> {
+        let roles = role::Entity::find()
+            .find_with_related(role_permission::Entity)
+            .order_by_asc(role::Column::Name)
+            .all(self.db.as_ref())
+            .await?;
+
+        Ok(roles
+            .into_iter()
+            .map(|(role, permissions)| RoleWithPermissions {
+                role,
+                permissions: permissions.into_iter().map(|row| row.permission).collect(),
+            })
+            .collect())
+    }
+
+    pub async fn get_role(&self, role_id: Uuid) -> Result<RoleWithPermissions, ServiceError> {
+        let (role, permissions) = role::Entity::find_by_id(role_id)
+            .find_with_related(role_permission::Entity)
+            .all(self.db.as_ref())
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ServiceError::NotFound("Role not found".to_string()))?;
+
+        Ok(RoleWithPermissions {
+            role,
+            permissions: permissions.into_iter().map(|row| row.permission).collect(),
+        })
+    }
+
+    /// The roles with the given ids. Fails when one of them does not exist.
+    pub async fn find_roles(&self, role_ids: &[Uuid]) -> Result<Vec<Model>, ServiceError> {
+        let roles = role::Entity::find()
+            .filter(role::Column::Id.is_in(role_ids.iter().copied()))
+            .all(self.db.as_ref())
+            .await?;
+        if let Some(missing) = role_ids
+            .iter()
+            .find(|id| !roles.iter().any(|role| role.id == **id))
+        {
+            return Err(ServiceError::NotFound(format!(
+                "Role {} not found",
+                missing
+            )));
+        }
+
+        Ok(roles)
+    }
+
+    /// Create a role. Names are lowercase and unique.
+    pub async fn create_role(
+        &self,
+        new_role: NewRole,
+    ) -> Result<RoleWithPermissions, ServiceError> {
+        let name = new_role.name.trim().to_lowercase();
+        if name.is_empty() {
+            return Err(ServiceError::Validation(
+                "Role name is required".to_string(),
+            ));
+        }
+        let permissions = dedup(new_role.permissions);
+        if permissions.is_empty() {
+            return Err(ServiceError::Validation(
+                "A role needs at least one permission".to_string(),
+            ));
+        }
+        let taken = role::Entity::find()
+            .filter(role::Column::Name.eq(name.as_str()))
+            .one(self.db.as_ref())
+            .await?;
+        if taken.is_some() {
+            return Err(ServiceError::Validation(format!(
+                "A role named {} already exists",
+                name
+            )));
+        }
+
+        let txn = self.db.begin().await?;
+        let role = role::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(name),
+            description: Set(new_role
+                .description
+                .map(|description| description.trim().to_string())
+                .filter(|description| !description.is_empty())),
+            created_at: Set(Utc::now()),
+        }
+        .insert(&txn)
+        .await?;
+        role_permission::Entity::insert_many(permissions.iter().map(|permission| {
+            role_permission::ActiveModel {
+                role_id: Set(role.id),
+                permission: Set(*permission),
+            }
+        }))
+        .exec_without_returning(&txn)
+        .await?;
+        txn.commit().await?;
+
+        Ok(RoleWithPermissions { role, permissions })
+    }
+
+    /// Replace the permissions of a role. Staff holding it gain or lose
+    /// access on their next request.
+    pub async fn set_role_permissions(
+        &self,
+        role_id: Uuid,
+        permissions: Vec<Permission>,
+    ) -> Result<RoleWithPermissions, ServiceError> {
+        let permissions = dedup(permissions);
+        if permissions.is_empty() {
+            return Err(ServiceError::Validation(
+                "A role needs at least one permission".to_string(),
+            ));
+        }
+
+        let txn = self.db.begin().await?;
+        let role = role::Entity::find_by_id(role_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Role not found".to_string()))?;
+        role_permission::Entity::delete_many()
+            .filter(role_permission::Column::RoleId.eq(role.id))
+            .exec(&txn)
+            .await?;
+        role_permission::Entity::insert_many(permissions.iter().map(|permission| {
+            role_permission::ActiveModel {
+                role_id: Set(role.id),
+                permission: Set(*permission),
+            }
+        }))
+        .exec_without_returning(&txn)
+        .await?;
+        txn.commit().await?;
+
+        Ok(RoleWithPermissions { role, permissions })
+    }
+
+    /// The roles assigned to a user
+    pub async fn roles_of(&self, user_id: Uuid) -> Result<Vec<Model>, ServiceError> {
+        let roles = role::Entity::find()
+            .inner_join(role_assignment::Entity)
+            .filter(role_assignment::Column::UserId.eq(user_id))
+            .order_by_asc(role::Column::Name)
+            .all(self.db.as_ref())
+            .await?;
+
+        Ok(roles)
+    }
+
+    /// Give a role to a staff account. Assigning a role it already holds
+    /// does nothing.
+    pub async fn assign_role(
+        &self,
+        user_id: Uuid,
+        role_id: Uuid,
+        assigned_by: Uuid,
+    ) -> Result<(), ServiceError> {
+        let user = user::Entity::find_by_id(user_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::UserNotFound("User not found".to_string()))?;
+        if user.role != UserRole::Staff {
+            return Err(ServiceError::Validation(
+                "Roles can only be assigned to staff accounts".to_string(),
+            ));
+        }
+        let role = role::Entity::find_by_id(role_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Role not found".to_string()))?;
+
+        role_assignment::Entity::insert(role_assignment::ActiveModel {
+            user_id: Set(user.id),
+            role_id: Set(role.id),
+            assigned_by: Set(Some(assigned_by)),
+            created_at: Set(Utc::now()),
+        })
+        .on_conflict(
+            OnConflict::columns([
+                role_assignment::Column::UserId,
+                role_assignment::Column::RoleId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .exec_without_returning(self.db.as_ref())
+        .await?;
+
+        Ok(())
+    }
+
+    /// Take a role away from a staff account
+    pub async fn revoke_role(&self, user_id: Uuid, role_id: Uuid) -> Result<(), ServiceError> {
+        let assignment = role_assignment::Entity::find_by_id((user_id, role_id))
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Role is not assigned to user".to_string()))?;
+        assignment.delete(self.db.as_ref()).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::Language;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn staff(role: UserRole) -> user::Model {
+        user::Model {
+            id: Uuid::new_v4(),
+            full_name: "Test Staff".to_string(),
+            email: None,
+            phone: "677777777".parse().unwrap(),
+            phone_verified_at: Some(Utc::now()),
+            failed_login_attempts: 0,
+            locked_until: None,
+            avatar_url: None,
+            preferred_language: Language::Fr,
+            pending_email: None,
+            pending_phone: None,
+            vendor_verified_at: None,
+            password_hash: "hashed_password".to_string(),
+            is_active: true,
+            role,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn role(name: &str) -> Model {
+        Model {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_permissions_follow_the_stored_role() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![
+                vec![staff(UserRole::Admin)],
+                vec![staff(UserRole::Buyer)],
+            ])
+            .into_connection();
+        let service = PermissionService::new(Arc::new(db));
+
+        let (role, admin) = service.permissions_for(Uuid::new_v4()).await.unwrap();
+        assert_eq!(role, UserRole::Admin);
+        assert!(Permission::iter().all(|permission| admin.contains(&permission)));
+        let (role, buyer) = service.permissions_for(Uuid::new_v4()).await.unwrap();
+        assert_eq!(role, UserRole::Buyer);
+        assert!(buyer.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_user_has_no_permissions() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![Vec::<user::Model>::new()])
+            .into_connection();
+        let service = PermissionService::new(Arc::new(db));
+
+        let result = service.permissions_for(Uuid::new_v4()).await;
+
+        assert!(matches!(result, Err(ServiceError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_staff_hold_permissions_of_their_roles() {
+        let support = role("support");
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![staff(UserRole::Staff)]])
+            .append_query_results(vec![vec![
+                role_permission::Model {
+                    role_id: support.id,
+                    permission: Permission::ViewOrders,
+                },
+                role_permission::Model {
+                    role_id: support.id,
+                    permission: Permission::IssueRefunds,
+                },
+            ]])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = PermissionService::new(db.clone());
+
+        let (_, permissions) = service.permissions_for(Uuid::new_v4()).await.unwrap();
+        assert!(permissions.contains(&Permission::ViewOrders));
+        assert!(permissions.contains(&Permission::IssueRefunds));
+        assert!(!permissions.contains(&Permission::ManageUsers));
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let sql = log[1].statements()[0].to_string();
+        assert!(sql.contains("INNER JOIN \"role_assignments\""));
+    }
+
+    #[tokio::test]
+    async fn test_create_role_rejects_taken_name() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![role("moderator")]])
+            .into_connection();
+        let service = PermissionService::new(Arc::new(db));
+
+        let result = service
+            .create_role(NewRole {
+                name: " Moderator ".to_string(),
+                description: None,
+                permissions: vec![Permission::ModerateProducts],
+            })
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_create_role() {
+        let created = role("finance");
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![Vec::<Model>::new()])
+            .append_query_results(vec![vec![created.clone()]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 2,
+            }])
+            .into_connection();
+        let service = PermissionService::new(Arc::new(db));
+
+        let role = service
+            .create_role(NewRole {
+                name: "Finance".to_string(),
+                description: Some("Pays vendors".to_string()),
+                permissions: vec![
+                    Permission::ViewFinance,
+                    Permission::ManagePayouts,
+                    Permission::ViewFinance,
+                ],
+            })
+            .await
+            .unwrap();
+        assert_eq!(role.role.name, "finance");
+        assert_eq!(
+            role.permissions,
+            vec![Permission::ViewFinance, Permission::ManagePayouts]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_roles_are_only_assigned_to_staff() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![staff(UserRole::Vendor)]])
+            .into_connection();
+        let service = PermissionService::new(Arc::new(db));
+
+        let result = service
+            .assign_role(Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4())
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+}
@@ -1,17 +1,26 @@
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, JoinType, QueryFilter,
-    QueryOrder, QuerySelect, RelationTrait, Set,
+    QueryOrder, QuerySelect, RelationTrait, Set, TransactionTrait,
 };
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::models::{
+    money::Money,
     order::{self, Status},
     order_item,
-    product::{self, Model},
+    product::{self, ModerationStatus, Model},
+    user::UserRole,
 };
 
-use super::errors::ServiceError;
+use super::{
+    errors::ServiceError,
+    category::ensure_category_exists,
+    moderation::{record_moderation_change, ModerationActor},
+    review::{rating_summaries, RatingSummary},
+    store::{store_summaries, StoreSummary},
+    variant::sync_product_stock,
+};
 
 pub struct ProductService {
     db: Arc<DatabaseConnection>,
@@ -23,18 +32,20 @@ pub struct CreateProduct {
     pub title: String,
     pub description: Option<String>,
     pub quantity: i32,
-    pub price: f64,
-    pub category: Option<String>,
+    pub price: Money,
+    pub category_id: Option<Uuid>,
     pub image_urls: Vec<String>,
     pub return_policy: Option<String>,
+    /// Keep the product as a draft instead of submitting it for review
+    pub draft: bool,
 }
 
 pub struct UpdateProduct {
     pub title: Option<String>,
     pub description: Option<String>,
     pub quantity: Option<i32>,
-    pub price: Option<f64>,
-    pub category: Option<String>,
+    pub price: Option<Money>,
+    pub category_id: Option<Uuid>,
     pub image_urls: Option<Vec<String>>,
     pub return_policy: Option<String>,
 }
@@ -44,7 +55,21 @@ pub struct ProductWithStats {
     #[serde(flatten)]
     pub product: Model,
     pub sales: i32,
-    pub revenue: f64,
+    pub revenue: Money,
+    /// Average rating and number of published reviews
+    #[serde(flatten)]
+    pub rating: RatingSummary,
+    /// Store of the vendor who makes the product, if they opened one
+    pub store: Option<StoreSummary>,
+}
+
+/// An approved product as buyers browse it
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ProductListing {
+    #[serde(flatten)]
+    pub product: Model,
+    /// Store of the vendor who makes the product, if they opened one
+    pub store: Option<StoreSummary>,
 }
 
 impl ProductService {
@@ -52,26 +77,44 @@ impl ProductService {
         Self { db }
     }
 
+    /// Store a new product. It waits for a moderator unless the vendor
+    /// keeps it as a draft.
     pub async fn create_product(&self, product_data: CreateProduct) -> Result<Model, ServiceError> {
+        if let Some(category_id) = product_data.category_id {
+            ensure_category_exists(self.db.as_ref(), category_id).await?;
+        }
+        let status = if product_data.draft {
+            ModerationStatus::Draft
+        } else {
+            ModerationStatus::PendingReview
+        };
+        let txn = self.db.begin().await?;
         let product = product::ActiveModel {
             id: Set(Uuid::new_v4()),
             seller_id: Set(product_data.seller_id),
             title: Set(product_data.title),
-            is_rejected: Set(false),
             description: Set(product_data.description),
             price: Set(product_data.price),
-            category: Set(product_data.category),
+            category_id: Set(product_data.category_id),
             image_urls: Set(product_data.image_urls),
             quantity: Set(product_data.quantity),
             return_policy: Set(product_data.return_policy),
-            is_approved: Set(false),
+            moderation_status: Set(status),
+            rejection_reason: Set(None),
+            moderator_note: Set(None),
             created_at: Set(chrono::Utc::now()),
             updated_at: Set(chrono::Utc::now()),
         }
-        .insert(&*self.db)
+        .insert(&txn)
         .await?;
+        let vendor = ModerationActor {
+            id: product.seller_id,
+            role: UserRole::Vendor,
+        };
+        record_moderation_change(&txn, product.id, None, status, &vendor, None, None).await?;
+        txn.commit().await?;
 
-        Ok(product.into())
+        Ok(product)
     }
 
     pub async fn get_product_by_id(
@@ -85,16 +128,24 @@ impl ProductService {
 
         if let Some(product) = product {
             let stats = self.calculate_product_stats(product_id).await?;
+            let store = store_summaries(&self.db, [product.seller_id])
+                .await?
+                .remove(&product.seller_id);
             Ok(Some(ProductWithStats {
                 product,
                 sales: stats.sales,
                 revenue: stats.revenue,
+                rating: stats.rating,
+                store,
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// Apply the vendor's changes to a product. Editing what buyers read
+    /// (title, description, category, images or return policy) sends an
+    /// approved product back to review; price and stock changes do not.
     pub async fn update_product(
         &self,
         product_id: Uuid,
@@ -105,6 +156,28 @@ impl ProductService {
             .await
             .map_err(|e| ServiceError::NotFound(e.to_string()))?;
         if let Some(product) = product {
+            let content_changed = product_data
+                .title
+                .as_ref()
+                .is_some_and(|title| *title != product.title)
+                || product_data
+                    .description
+                    .as_ref()
+                    .is_some_and(|description| Some(description) != product.description.as_ref())
+                || product_data
+                    .category_id
+                    .is_some_and(|category_id| Some(category_id) != product.category_id)
+                || product_data
+                    .image_urls
+                    .as_ref()
+                    .is_some_and(|image_urls| *image_urls != product.image_urls)
+                || product_data.return_policy.as_ref().is_some_and(|return_policy| {
+                    Some(return_policy) != product.return_policy.as_ref()
+                });
+            let price_or_stock_changed =
+                product_data.price.is_some() || product_data.quantity.is_some();
+            let back_to_review =
+                content_changed && product.moderation_status == ModerationStatus::Approved;
             let mut active_model: product::ActiveModel = product.clone().into();
 
             if let Some(title) = product_data.title {
@@ -116,8 +189,11 @@ impl ProductService {
             if let Some(price) = product_data.price {
                 active_model.price = Set(price);
             }
-            if let Some(category) = product_data.category {
-                active_model.category = Set(Some(category));
+            if let Some(category_id) = product_data.category_id {
+                if Some(category_id) != product.category_id {
+                    ensure_category_exists(self.db.as_ref(), category_id).await?;
+                }
+                active_model.category_id = Set(Some(category_id));
             }
             if let Some(image_urls) = product_data.image_urls {
                 active_model.image_urls = Set(image_urls);
@@ -128,10 +204,38 @@ impl ProductService {
             if let Some(return_policy) = product_data.return_policy {
                 active_model.return_policy = Set(Some(return_policy));
             }
+            if back_to_review {
+                active_model.moderation_status = Set(ModerationStatus::PendingReview);
+            }
             active_model.updated_at = Set(chrono::Utc::now());
 
-            let updated_product = active_model.update(&*self.db).await?;
-            Ok(updated_product.into())
+            let txn = self.db.begin().await?;
+            let mut updated_product = active_model.update(&txn).await?;
+            if back_to_review {
+                let vendor = ModerationActor {
+                    id: product.seller_id,
+                    role: UserRole::Vendor,
+                };
+                record_moderation_change(
+                    &txn,
+                    product_id,
+                    Some(ModerationStatus::Approved),
+                    ModerationStatus::PendingReview,
+                    &vendor,
+                    Some("Content edited".to_string()),
+                    None,
+                )
+                .await?;
+            }
+            // A product with variants takes its price and stock from them
+            if price_or_stock_changed {
+                if let Some((price, quantity)) = sync_product_stock(&txn, product_id).await? {
+                    updated_product.price = price;
+                    updated_product.quantity = quantity;
+                }
+            }
+            txn.commit().await?;
+            Ok(updated_product)
         } else {
             Err(ServiceError::NotFound("Product not found".into()))
         }
@@ -144,25 +248,26 @@ impl ProductService {
         Ok(())
     }
 
-    pub async fn list_products(&self) -> Result<Vec<Model>, ServiceError> {
+    pub async fn list_products(&self) -> Result<Vec<ProductListing>, ServiceError> {
         let query = product::Entity::find()
-            .filter(product::Column::IsApproved.eq(true));
+            .filter(product::Column::ModerationStatus.eq(ModerationStatus::Approved));
 
         let products = query
             .order_by_desc(product::Column::CreatedAt)
             .all(&*self.db)
             .await?;
 
-        tracing::info!("Found {} approved products", products.len());
-        for product in &products {
-            tracing::info!("Product: id={}, title={}, is_approved={}", 
-                product.id, 
-                product.title, 
-                product.is_approved
-            );
-        }
+        tracing::debug!("Found {} approved products", products.len());
 
-        Ok(products)
+        let stores =
+            store_summaries(&self.db, products.iter().map(|product| product.seller_id)).await?;
+        Ok(products
+            .into_iter()
+            .map(|product| ProductListing {
+                store: stores.get(&product.seller_id).cloned(),
+                product,
+            })
+            .collect())
     }
 
     pub async fn list_products_by(
@@ -181,14 +286,19 @@ impl ProductService {
             .await
             .map_err(|e| ServiceError::DatabaseError(format!("Failed to fetch products: {}", e)))?;
 
+        let stores =
+            store_summaries(&self.db, products.iter().map(|product| product.seller_id)).await?;
         let mut products_with_stats = Vec::new();
         for product in products {
+            let store = stores.get(&product.seller_id).cloned();
             match self.calculate_product_stats(product.id).await {
                 Ok(stats) => {
                     products_with_stats.push(ProductWithStats {
                         product,
                         sales: stats.sales,
                         revenue: stats.revenue,
+                        rating: stats.rating,
+                        store,
                     });
                 }
                 Err(e) => {
@@ -201,7 +311,9 @@ impl ProductService {
                     products_with_stats.push(ProductWithStats {
                         product,
                         sales: 0,
-                        revenue: 0.0,
+                        revenue: Money::ZERO,
+                        rating: RatingSummary::default(),
+                        store,
                     });
                 }
             }
@@ -214,8 +326,8 @@ impl ProductService {
         &self,
         product_id: Uuid,
     ) -> Result<ProductStats, ServiceError> {
-        // Get the product first to get its price
-        let product = product::Entity::find_by_id(product_id)
+        // Make sure the product exists
+        product::Entity::find_by_id(product_id)
             .one(&*self.db)
             .await
             .map_err(|e| ServiceError::DatabaseError(format!("Failed to fetch product: {}", e)))?
@@ -227,7 +339,7 @@ impl ProductService {
         let order_items = order_item::Entity::find()
             .join(JoinType::InnerJoin, order_item::Relation::Order.def())
             .filter(order_item::Column::ProductId.eq(product_id))
-            .filter(order::Column::Status.eq(Status::Delivered.to_string()))
+            .filter(order::Column::Status.eq(Status::Delivered))
             .all(&*self.db)
             .await
             .map_err(|e| {
@@ -235,92 +347,124 @@ impl ProductService {
             })?;
 
         let sales = order_items.iter().map(|item| item.quantity).sum();
-        let revenue = order_items.iter().fold(0.0, |acc, item| {
-            acc + (item.quantity as f64 * product.price)
-        });
-
-        Ok(ProductStats { sales, revenue })
-    }
-
-    pub async fn approve_product(&self, product_id: Uuid) -> Result<Model, ServiceError> {
-        let product = product::Entity::find_by_id(product_id)
-            .one(&*self.db)
-            .await
-            .map_err(|e| ServiceError::NotFound(e.to_string()))?;
-        if let Some(product) = product {
-            tracing::info!("Approving product: id={}, title={}, current is_approved={}", 
-                product.id, 
-                product.title, 
-                product.is_approved
-            );
-            let mut active_model: product::ActiveModel = product.clone().into();
-            active_model.is_approved = Set(true);
-            active_model.updated_at = Set(chrono::Utc::now());
-            let updated_product = active_model.update(&*self.db).await?;
-            tracing::info!("Product approved successfully: id={}, title={}, new is_approved={}", 
-                updated_product.id, 
-                updated_product.title, 
-                updated_product.is_approved
-            );
-            Ok(updated_product.into())
-        } else {
-            Err(ServiceError::NotFound("Product not found".into()))
-        }
-    }
-
-    pub async fn list_pending_products(&self) -> Result<Vec<Model>, ServiceError> {
-        let products = product::Entity::find()
-            .filter(product::Column::IsApproved.eq(false))
-            .order_by_desc(product::Column::CreatedAt)
-            .all(&*self.db)
-            .await?;
-        Ok(products)
+        // Revenue is what buyers paid, not the product's current price
+        let revenue = order_items.iter().try_fold(Money::ZERO, |revenue, item| {
+            revenue.checked_add(item.price.checked_mul(i64::from(item.quantity))?)
+        })?;
+
+        let rating = rating_summaries(self.db.as_ref(), [product_id])
+            .await?
+            .remove(&product_id)
+            .unwrap_or_default();
+
+        Ok(ProductStats {
+            sales,
+            revenue,
+            rating,
+        })
     }
 }
 
 #[derive(Debug)]
 struct ProductStats {
     sales: i32,
-    revenue: f64,
+    revenue: Money,
+    rating: RatingSummary,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sea_orm::MockDatabase;
+    use crate::models::{category, product_moderation_history, store};
+    use sea_orm::{MockDatabase, Value};
+    use std::collections::BTreeMap;
+
+    /// Price and stock totals of a product's variants, as the sync reads them
+    fn variant_totals(
+        price: Option<i64>,
+        quantity: Option<i64>,
+    ) -> BTreeMap<&'static str, Value> {
+        BTreeMap::from([
+            ("price", Value::BigInt(price)),
+            ("quantity", Value::BigInt(quantity)),
+        ])
+    }
+
+    fn category(id: Uuid) -> category::Model {
+        category::Model {
+            id,
+            parent_id: None,
+            slug: "textiles".to_string(),
+            name_en: "Textiles".to_string(),
+            name_fr: "Textiles".to_string(),
+            icon: None,
+            sort_order: 0,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    fn history_entry(
+        product_id: Uuid,
+        from_status: Option<ModerationStatus>,
+        to_status: ModerationStatus,
+    ) -> product_moderation_history::Model {
+        product_moderation_history::Model {
+            id: Uuid::new_v4(),
+            product_id,
+            from_status,
+            to_status,
+            changed_by: None,
+            changed_by_role: Some(UserRole::Vendor),
+            reason: None,
+            note: None,
+            created_at: chrono::Utc::now(),
+        }
+    }
 
     #[tokio::test]
     async fn test_create_product() {
         let seller_id = Uuid::new_v4();
+        let product_id = Uuid::new_v4();
+        let category_id = Uuid::new_v4();
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![category(category_id)]])
             .append_query_results(vec![vec![product::Model {
-                id: Uuid::new_v4(),
-                seller_id: seller_id,
+                id: product_id,
+                seller_id,
                 title: "Test Product".to_string(),
                 description: Some("Test Description".to_string()),
-                price: 10.0,
-                category: Some("Test Category".to_string()),
-                is_rejected: false,
+                price: Money::xaf(100),
+                category_id: Some(category_id),
                 image_urls: vec!["test.jpg".to_string()],
                 quantity: 1,
                 return_policy: Some("Test Refund Policy".to_string()),
-                is_approved: false,
+                moderation_status: ModerationStatus::PendingReview,
+                rejection_reason: None,
+                moderator_note: None,
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
             }]])
+            .append_query_results(vec![vec![history_entry(
+                product_id,
+                None,
+                ModerationStatus::PendingReview,
+            )]])
             .into_connection();
+        let db = Arc::new(db);
 
-        let service = ProductService::new(Arc::new(db));
+        let service = ProductService::new(db.clone());
 
         let product_data = CreateProduct {
             seller_id,
             title: "Test Product".to_string(),
             description: Some("Test Description".to_string()),
-            price: 100.0,
-            category: Some("Test Category".to_string()),
+            price: Money::xaf(100),
+            category_id: Some(category_id),
             image_urls: vec!["test.jpg".to_string()],
             return_policy: Some("Test Refund Policy".to_string()),
             quantity: 1,
+            draft: false,
         };
 
         let result = service.create_product(product_data).await;
@@ -328,27 +472,58 @@ mod tests {
 
         let product_response = result.unwrap();
         assert_eq!(product_response.title, "Test Product");
-        assert_eq!(product_response.price, 100.0);
+        assert_eq!(product_response.price, Money::xaf(100));
         assert_eq!(product_response.seller_id, seller_id);
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let statements = log[1].statements();
+        assert!(statements[1].to_string().contains("'pending_review'"));
+        assert!(statements[2]
+            .to_string()
+            .starts_with("INSERT INTO \"product_moderation_history\""));
     }
 
     #[tokio::test]
     async fn test_get_product_by_id() {
         let product_id = Uuid::new_v4();
         let seller_id = Uuid::new_v4();
+        let product = product::Model {
+            id: product_id,
+            seller_id,
+            title: "Test Product".to_string(),
+            description: Some("Test Description".to_string()),
+            price: Money::xaf(100),
+            quantity: 1,
+            category_id: None,
+            image_urls: vec!["test.jpg".to_string()],
+            return_policy: Some("Test Refund Policy".to_string()),
+            moderation_status: ModerationStatus::PendingReview,
+            rejection_reason: None,
+            moderator_note: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
-            .append_query_results(vec![vec![product::Model {
-                id: product_id,
-                seller_id: seller_id,
-                title: "Test Product".to_string(),
-                description: Some("Test Description".to_string()),
-                price: 100.0,
-                quantity: 1,
-                is_rejected: false,
-                category: Some("Test Category".to_string()),
-                image_urls: vec!["test.jpg".to_string()],
-                return_policy: Some("Test Refund Policy".to_string()),
-                is_approved: false,
+            .append_query_results(vec![vec![product.clone()], vec![product]])
+            .append_query_results::<order_item::Model, _, _>(vec![vec![]]) // No delivered sales yet
+            .append_query_results([[BTreeMap::from([
+                ("product_id", Value::from(product_id)),
+                ("rating_average", Value::from(4.5f64)),
+                ("rating_count", Value::from(2i64)),
+            ])]])
+            .append_query_results(vec![vec![store::Model {
+                id: Uuid::new_v4(),
+                owner_id: seller_id,
+                name: "Test Store".to_string(),
+                slug: "test-store".to_string(),
+                logo_url: None,
+                banner_url: None,
+                description: None,
+                region: None,
+                city: None,
+                artisan_story: None,
+                contact_preferences: Default::default(),
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
             }]])
@@ -363,44 +538,56 @@ mod tests {
         let product_response = product_response.unwrap();
         assert_eq!(product_response.product.id, product_id);
         assert_eq!(product_response.product.title, "Test Product");
+        assert_eq!(product_response.rating.rating_average, Some(4.5));
+        assert_eq!(product_response.rating.rating_count, 2);
+        assert_eq!(product_response.store.unwrap().slug, "test-store");
     }
 
     #[tokio::test]
     async fn test_update_product() {
         let product_id = Uuid::new_v4();
         let seller_id = Uuid::new_v4();
+        let category_id = Uuid::new_v4();
+        let updated_category_id = Uuid::new_v4();
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
             .append_query_results(vec![
                 vec![product::Model {
                     id: product_id,
-                    seller_id: seller_id,
+                    seller_id,
                     title: "Test Product".to_string(),
                     description: Some("Test Description".to_string()),
-                    price: 1000.0,
+                    price: Money::xaf(1000),
                     quantity: 1,
-                    category: Some("Test Category".to_string()),
-                    is_rejected: false,                    image_urls: vec!["test.jpg".to_string()],
+                    category_id: Some(category_id),
+                    image_urls: vec!["test.jpg".to_string()],
                     return_policy: Some("Test Refund Policy".to_string()),
-                    is_approved: false,
+                    moderation_status: ModerationStatus::PendingReview,
+                    rejection_reason: None,
+                    moderator_note: None,
                     created_at: chrono::Utc::now(),
                     updated_at: chrono::Utc::now(),
                 }],
+            ])
+            .append_query_results(vec![vec![category(updated_category_id)]])
+            .append_query_results(vec![
                 vec![product::Model {
                     id: product_id,
-                    seller_id: seller_id,
+                    seller_id,
                     title: "Updated Product".to_string(),
                     description: Some("Updated Description".to_string()),
-                    price: 100.0,
+                    price: Money::xaf(100),
                     quantity: 1,
-                    category: Some("Updated Category".to_string()),
+                    category_id: Some(updated_category_id),
                     image_urls: vec!["updated.jpg".to_string()],
                     return_policy: Some("Updated Refund Policy".to_string()),
-                    is_rejected: false,
-                    is_approved: false,
+                    moderation_status: ModerationStatus::PendingReview,
+                    rejection_reason: None,
+                    moderator_note: None,
                     created_at: chrono::Utc::now(),
                     updated_at: chrono::Utc::now(),
                 }],
             ])
+            .append_query_results([[variant_totals(None, None)]])
             .into_connection();
 
         let service = ProductService::new(Arc::new(db));
@@ -408,8 +595,8 @@ mod tests {
         let update_data = UpdateProduct {
             title: Some("Updated Product".to_string()),
             description: Some("Updated Description".to_string()),
-            price: Some(100.0),
-            category: Some("Updated Category".to_string()),
+            price: Some(Money::xaf(100)),
+            category_id: Some(updated_category_id),
             image_urls: Some(vec!["updated.jpg".to_string()]),
             return_policy: Some("Updated Refund Policy".to_string()),
             quantity: Some(1),
@@ -420,7 +607,7 @@ mod tests {
 
         let product_response = result.unwrap();
         assert_eq!(product_response.title, "Updated Product");
-        assert_eq!(product_response.price, 100.0);
+        assert_eq!(product_response.price, Money::xaf(100));
     }
 
     #[tokio::test]
@@ -430,35 +617,38 @@ mod tests {
             .append_query_results(vec![vec![
                 product::Model {
                     id: Uuid::new_v4(),
-                    seller_id: seller_id,
+                    seller_id,
                     title: "Product 1".to_string(),
                     description: Some("Description 1".to_string()),
-                    price: 100.0,
+                    price: Money::xaf(100),
                     quantity: 1,
-                    category: Some("Category A".to_string()),
+                    category_id: None,
                     image_urls: vec!["1.jpg".to_string()],
-                    is_rejected: false,
-                    return_policy: Some("Refund Policy 1".to_string()),
-                    is_approved: false,
+                        return_policy: Some("Refund Policy 1".to_string()),
+                        moderation_status: ModerationStatus::PendingReview,
+                        rejection_reason: None,
+                        moderator_note: None,
                     created_at: chrono::Utc::now(),
                     updated_at: chrono::Utc::now(),
                 },
                 product::Model {
                     id: Uuid::new_v4(),
-                    seller_id: seller_id,
+                    seller_id,
                     title: "Product 2".to_string(),
                     description: Some("Description 2".to_string()),
-                    price: 100.0,
+                    price: Money::xaf(100),
                     quantity: 1,
-                    category: Some("Category B".to_string()),
+                    category_id: None,
                     image_urls: vec!["2.jpg".to_string()],
                     return_policy: Some("Refund Policy 2".to_string()),
-                    is_rejected: false,
-                    is_approved: false,
+                    moderation_status: ModerationStatus::PendingReview,
+                    rejection_reason: None,
+                    moderator_note: None,
                     created_at: chrono::Utc::now(),
                     updated_at: chrono::Utc::now(),
                 },
             ]])
+            .append_query_results::<store::Model, _, _>(vec![vec![]]) // Seller has no store yet
             .into_connection();
 
         let service = ProductService::new(Arc::new(db));
@@ -468,7 +658,112 @@ mod tests {
 
         let products = result.unwrap();
         assert_eq!(products.len(), 2);
-        assert_eq!(products[0].title, "Product 1");
-        assert_eq!(products[1].title, "Product 2");
+        assert_eq!(products[0].product.title, "Product 1");
+        assert_eq!(products[1].product.title, "Product 2");
+        assert!(products[0].store.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_editing_approved_product_sends_it_back_to_review() {
+        let product_id = Uuid::new_v4();
+        let product = product::Model {
+            id: product_id,
+            seller_id: Uuid::new_v4(),
+            title: "Ndop Cloth".to_string(),
+            description: Some("Hand-dyed cotton".to_string()),
+            price: Money::xaf(15000),
+            quantity: 3,
+            category_id: None,
+            image_urls: vec!["ndop.jpg".to_string()],
+            return_policy: None,
+            moderation_status: ModerationStatus::Approved,
+            rejection_reason: None,
+            moderator_note: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        let edited = product::Model {
+            title: "Ndop Cloth, 2m".to_string(),
+            moderation_status: ModerationStatus::PendingReview,
+            ..product.clone()
+        };
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![product], vec![edited]])
+            .append_query_results(vec![vec![history_entry(
+                product_id,
+                Some(ModerationStatus::Approved),
+                ModerationStatus::PendingReview,
+            )]])
+            .append_query_results([[variant_totals(None, None)]])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = ProductService::new(db.clone());
+
+        let update_data = UpdateProduct {
+            title: Some("Ndop Cloth, 2m".to_string()),
+            description: None,
+            price: None,
+            category_id: None,
+            image_urls: None,
+            return_policy: None,
+            quantity: Some(2),
+        };
+        let updated = service.update_product(product_id, update_data).await.unwrap();
+        assert_eq!(updated.moderation_status, ModerationStatus::PendingReview);
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let statements = log[1].statements();
+        assert!(statements[1].to_string().contains("'pending_review'"));
+        let history = statements[2].to_string();
+        assert!(history.starts_with("INSERT INTO \"product_moderation_history\""));
+        assert!(history.contains("'Content edited'"));
+    }
+
+    #[tokio::test]
+    async fn test_product_with_variants_keeps_their_price_and_stock() {
+        let product_id = Uuid::new_v4();
+        let product = product::Model {
+            id: product_id,
+            seller_id: Uuid::new_v4(),
+            title: "Penja pepper".to_string(),
+            description: None,
+            price: Money::xaf(2500),
+            quantity: 12,
+            category_id: None,
+            image_urls: vec![],
+            return_policy: None,
+            moderation_status: ModerationStatus::Approved,
+            rejection_reason: None,
+            moderator_note: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        let edited = product::Model {
+            quantity: 40,
+            ..product.clone()
+        };
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![product], vec![edited]])
+            .append_query_results([[variant_totals(Some(2500), Some(12))]])
+            .append_exec_results([sea_orm::MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let service = ProductService::new(Arc::new(db));
+
+        let update_data = UpdateProduct {
+            title: None,
+            description: None,
+            price: None,
+            category_id: None,
+            image_urls: None,
+            return_policy: None,
+            quantity: Some(40),
+        };
+        let updated = service.update_product(product_id, update_data).await.unwrap();
+        assert_eq!(updated.quantity, 12);
+        assert_eq!(updated.price, Money::xaf(2500));
     }
 }
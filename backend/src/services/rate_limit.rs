@@ -0,0 +1,257 @@
+use std::{collections::HashMap, fmt::Debug, sync::Arc, sync::Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    sea_query::OnConflict, ActiveModelTrait, DatabaseConnection, EntityTrait, QuerySelect, Set,
+    TransactionTrait,
+};
+
+use crate::models::rate_limit_bucket;
+
+use super::errors::ServiceError;
+
+/// A token bucket holding `per_minute` requests, refilled continuously at
+/// `per_minute` requests a minute
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub per_minute: u32,
+}
+
+impl RateLimit {
+    /// A limit of `per_minute` requests a minute, none when it is 0
+    pub fn per_minute(per_minute: u32) -> Option<RateLimit> {
+        (per_minute > 0).then_some(RateLimit { per_minute })
+    }
+
+    fn capacity(self) -> f64 {
+        self.per_minute as f64
+    }
+
+    fn refill_per_sec(self) -> f64 {
+        self.per_minute as f64 / 60.0
+    }
+}
+
+/// Where rate limit buckets are kept
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateLimitBackend {
+    /// In process memory, each instance enforcing its own limits
+    Memory,
+    /// In Postgres, shared by every instance
+    Postgres,
+}
+
+/// Outcome of asking for a request to go through
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    Allowed,
+    /// The bucket is empty, the next request is allowed after this many seconds
+    Limited {
+        retry_after_secs: u64,
+    },
+}
+
+/// State of a bucket at `updated_at`
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Bucket {
+    tokens: f64,
+    updated_at: DateTime<Utc>,
+}
+
+/// Tokens in `bucket` once refilled up to `now`. A bucket seen for the
+/// first time starts full.
+fn refill(bucket: Option<Bucket>, limit: RateLimit, now: DateTime<Utc>) -> f64 {
+    match bucket {
+        Some(bucket) => {
+            let elapsed = (now - bucket.updated_at).num_milliseconds().max(0) as f64 / 1000.0;
+            (bucket.tokens + elapsed * limit.refill_per_sec()).min(limit.capacity())
+        }
+        None => limit.capacity(),
+    }
+}
+
+/// Refill `bucket` up to `now` and take a token from it if one is left
+fn take(bucket: Option<Bucket>, limit: RateLimit, now: DateTime<Utc>) -> (Bucket, Decision) {
+    let tokens = refill(bucket, limit, now);
+    if tokens >= 1.0 {
+        let bucket = Bucket {
+            tokens: tokens - 1.0,
+            updated_at: now,
+        };
+        (bucket, Decision::Allowed)
+    } else {
+        let retry_after_secs = ((1.0 - tokens) / limit.refill_per_sec()).ceil() as u64;
+        let bucket = Bucket {
+            tokens,
+            updated_at: now,
+        };
+        (bucket, Decision::Limited { retry_after_secs })
+    }
+}
+
+/// Where rate limit buckets are kept
+#[async_trait]
+pub trait RateLimitStore: Debug + Send + Sync {
+    /// Count a request against the bucket `key`
+    async fn acquire(&self, key: &str, limit: RateLimit) -> Result<Decision, ServiceError>;
+}
+
+/// Buckets above which full buckets are dropped from memory
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+/// Keeps buckets in process memory. Each instance enforces its own limits.
+#[derive(Debug, Default)]
+pub struct MemoryRateLimitStore {
+    buckets: Mutex<HashMap<String, (Bucket, RateLimit)>>,
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryRateLimitStore {
+    async fn acquire(&self, key: &str, limit: RateLimit) -> Result<Decision, ServiceError> {
+        let now = Utc::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_IDLE_BUCKETS {
+            // A bucket that has refilled is the same as no bucket
+            buckets
+                .retain(|_, (bucket, limit)| refill(Some(*bucket), *limit, now) < limit.capacity());
+        }
+
+        let (bucket, decision) = take(buckets.get(key).map(|(bucket, _)| *bucket), limit, now);
+        buckets.insert(key.to_string(), (bucket, limit));
+        Ok(decision)
+    }
+}
+
+/// Keeps buckets in the `rate_limit_buckets` table so that every instance
+/// shares the same limits
+#[derive(Debug)]
+pub struct PostgresRateLimitStore {
+    db: Arc<DatabaseConnection>,
+}
+
+impl PostgresRateLimitStore {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for PostgresRateLimitStore {
+    async fn acquire(&self, key: &str, limit: RateLimit) -> Result<Decision, ServiceError> {
+        let now = Utc::now();
+        let txn = self.db.begin().await?;
+
+        // Make sure the row exists so concurrent requests queue on its lock
+        rate_limit_bucket::Entity::insert(rate_limit_bucket::ActiveModel {
+            key: Set(key.to_string()),
+            tokens: Set(limit.capacity()),
+            updated_at: Set(now),
+        })
+        .on_conflict(
+            OnConflict::column(rate_limit_bucket::Column::Key)
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+
+        let row = rate_limit_bucket::Entity::find_by_id(key.to_string())
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| {
+                ServiceError::GenericError(format!("Rate limit bucket {} vanished", key))
+            })?;
+        let (bucket, decision) = take(
+            Some(Bucket {
+                tokens: row.tokens,
+                updated_at: row.updated_at,
+            }),
+            limit,
+            now,
+        );
+
+        let mut active_model: rate_limit_bucket::ActiveModel = row.into();
+        active_model.tokens = Set(bucket.tokens);
+        active_model.updated_at = Set(bucket.updated_at);
+        active_model.update(&txn).await?;
+        txn.commit().await?;
+
+        Ok(decision)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    const LIMIT: RateLimit = RateLimit { per_minute: 6 };
+
+    #[test]
+    fn test_bucket_allows_burst_then_limits() {
+        let now = Utc::now();
+        let mut bucket = None;
+        for _ in 0..6 {
+            let (next, decision) = take(bucket, LIMIT, now);
+            assert_eq!(decision, Decision::Allowed);
+            bucket = Some(next);
+        }
+
+        let (_, decision) = take(bucket, LIMIT, now);
+        assert_eq!(
+            decision,
+            Decision::Limited {
+                retry_after_secs: 10
+            }
+        );
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let now = Utc::now();
+        let empty = Bucket {
+            tokens: 0.0,
+            updated_at: now,
+        };
+
+        let (_, decision) = take(Some(empty), LIMIT, now + Duration::seconds(5));
+        assert_eq!(
+            decision,
+            Decision::Limited {
+                retry_after_secs: 5
+            }
+        );
+
+        let (bucket, decision) = take(Some(empty), LIMIT, now + Duration::seconds(10));
+        assert_eq!(decision, Decision::Allowed);
+        assert!(bucket.tokens.abs() < 1e-9);
+
+        // Refilling stops at the capacity
+        let (bucket, _) = take(Some(empty), LIMIT, now + Duration::hours(1));
+        assert!((bucket.tokens - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_zero_disables_limit() {
+        assert_eq!(RateLimit::per_minute(0), None);
+        assert_eq!(
+            RateLimit::per_minute(10),
+            Some(RateLimit { per_minute: 10 })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_keeps_keys_apart() {
+        let store = MemoryRateLimitStore::default();
+        let limit = RateLimit { per_minute: 1 };
+
+        assert_eq!(store.acquire("a", limit).await.unwrap(), Decision::Allowed);
+        assert!(matches!(
+            store.acquire("a", limit).await.unwrap(),
+            Decision::Limited { .. }
+        ));
+        assert_eq!(store.acquire("b", limit).await.unwrap(), Decision::Allowed);
+    }
+}
@@ -0,0 +1,604 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{
+    order::{self, Status},
+    order_item, product,
+    review::{self, Model, ReviewStatus},
+    review_report, vendor_order,
+};
+
+use super::errors::ServiceError;
+
+/// Longest review or reply, in characters
+const MAX_TEXT_LENGTH: usize = 2000;
+/// Most photos a buyer may attach to a review
+const MAX_PHOTOS: usize = 5;
+
+/// A buyer's review of a product
+#[derive(Deserialize, Debug)]
+pub struct NewReview {
+    /// Number of stars, from 1 to 5
+    pub rating: i32,
+    pub body: String,
+    #[serde(default)]
+    pub image_urls: Vec<String>,
+}
+
+/// A moderator's decision on a review
+#[derive(Deserialize, Debug, Clone)]
+pub struct ReviewDecision {
+    /// `hidden` or `published`
+    pub status: ReviewStatus,
+    /// Why the review is hidden, required to hide it
+    pub reason: Option<String>,
+}
+
+/// Average rating and number of published reviews of a product
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct RatingSummary {
+    /// Average number of stars, to two decimals; empty without reviews
+    pub rating_average: Option<f64>,
+    pub rating_count: i64,
+}
+
+/// The published reviews of a product, newest first
+#[derive(Debug, Serialize)]
+pub struct ProductReviews {
+    #[serde(flatten)]
+    pub rating: RatingSummary,
+    pub reviews: Vec<Model>,
+}
+
+/// A review with the reports moderators have not dealt with yet
+#[derive(Debug, Serialize)]
+pub struct ReportedReview {
+    #[serde(flatten)]
+    pub review: Model,
+    pub reports: Vec<review_report::Model>,
+}
+
+/// Rating summaries of the given products, counting published reviews only.
+/// Products without reviews are left out.
+pub(crate) async fn rating_summaries<C: ConnectionTrait>(
+    db: &C,
+    product_ids: impl IntoIterator<Item = Uuid>,
+) -> Result<HashMap<Uuid, RatingSummary>, ServiceError> {
+    let mut product_ids: Vec<Uuid> = product_ids.into_iter().collect();
+    product_ids.sort_unstable();
+    product_ids.dedup();
+    if product_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let rows: Vec<(Uuid, f64, i64)> = review::Entity::find()
+        .select_only()
+        .column(review::Column::ProductId)
+        .column_as(
+            Expr::cust("ROUND(AVG(reviews.rating), 2)::float8"),
+            "rating_average",
+        )
+        .column_as(review::Column::Id.count(), "rating_count")
+        .filter(review::Column::ProductId.is_in(product_ids))
+        .filter(review::Column::Status.eq(ReviewStatus::Published))
+        .group_by(review::Column::ProductId)
+        .into_tuple()
+        .all(db)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(product_id, average, count)| {
+            (
+                product_id,
+                RatingSummary {
+                    rating_average: Some(average),
+                    rating_count: count,
+                },
+            )
+        })
+        .collect())
+}
+
+/// Trimmed text, or a validation error when it is blank or too long
+fn required_text(text: &str, what: &str) -> Result<String, ServiceError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(ServiceError::Validation(format!("{} is required", what)));
+    }
+    if text.chars().count() > MAX_TEXT_LENGTH {
+        return Err(ServiceError::Validation(format!(
+            "{} must be at most {} characters",
+            what, MAX_TEXT_LENGTH
+        )));
+    }
+    Ok(text.to_string())
+}
+
+/// Ratings and reviews of products by the buyers who received them
+pub struct ReviewService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl ReviewService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    async fn lock_review<C: ConnectionTrait>(
+        db: &C,
+        review_id: Uuid,
+    ) -> Result<Model, ServiceError> {
+        review::Entity::find_by_id(review_id)
+            .lock_exclusive()
+            .one(db)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Review not found".to_string()))
+    }
+
+    pub async fn get_review(&self, review_id: Uuid) -> Result<Model, ServiceError> {
+        review::Entity::find_by_id(review_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Review not found".to_string()))
+    }
+
+    /// Review a product. Only a buyer whose order of the product, or their
+    /// vendor's part of it, was delivered may review it, once.
+    pub async fn create_review(
+        &self,
+        product_id: Uuid,
+        buyer_id: Uuid,
+        data: NewReview,
+    ) -> Result<Model, ServiceError> {
+        if !(1..=5).contains(&data.rating) {
+            return Err(ServiceError::Validation(
+                "Rating must be between 1 and 5".to_string(),
+            ));
+        }
+        let body = required_text(&data.body, "Review")?;
+        if data.image_urls.len() > MAX_PHOTOS {
+            return Err(ServiceError::Validation(format!(
+                "A review holds at most {} photos",
+                MAX_PHOTOS
+            )));
+        }
+
+        product::Entity::find_by_id(product_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Product not found".to_string()))?;
+        let purchase = order_item::Entity::find()
+            .join(JoinType::InnerJoin, order_item::Relation::Order.def())
+            .join(JoinType::LeftJoin, order_item::Relation::VendorOrder.def())
+            .filter(order_item::Column::ProductId.eq(product_id))
+            .filter(order::Column::UserId.eq(buyer_id))
+            .filter(order::Column::DeletedAt.is_null())
+            .filter(
+                Condition::any()
+                    .add(order::Column::Status.eq(Status::Delivered))
+                    .add(vendor_order::Column::Status.eq(Status::Delivered)),
+            )
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| {
+                ServiceError::Forbidden(
+                    "Only buyers who received this product can review it".to_string(),
+                )
+            })?;
+        let existing = review::Entity::find()
+            .filter(review::Column::ProductId.eq(product_id))
+            .filter(review::Column::BuyerId.eq(buyer_id))
+            .one(self.db.as_ref())
+            .await?;
+        if existing.is_some() {
+            return Err(ServiceError::Validation(
+                "You already reviewed this product".to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+        let review = review::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            product_id: Set(product_id),
+            buyer_id: Set(buyer_id),
+            order_id: Set(Some(purchase.order_id)),
+            rating: Set(data.rating),
+            body: Set(body),
+            image_urls: Set(data.image_urls),
+            status: Set(ReviewStatus::Published),
+            hidden_reason: Set(None),
+            vendor_reply: Set(None),
+            vendor_replied_at: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        Ok(review)
+    }
+
+    /// Published reviews of a product, newest first, with its rating
+    pub async fn list_product_reviews(
+        &self,
+        product_id: Uuid,
+    ) -> Result<ProductReviews, ServiceError> {
+        product::Entity::find_by_id(product_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Product not found".to_string()))?;
+        let reviews = review::Entity::find()
+            .filter(review::Column::ProductId.eq(product_id))
+            .filter(review::Column::Status.eq(ReviewStatus::Published))
+            .order_by_desc(review::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+        let rating = rating_summaries(self.db.as_ref(), [product_id])
+            .await?
+            .remove(&product_id)
+            .unwrap_or_default();
+
+        Ok(ProductReviews { rating, reviews })
+    }
+
+    /// Answer a review of one of the vendor's products. A review gets one
+    /// reply, which cannot be changed.
+    pub async fn reply(
+        &self,
+        review_id: Uuid,
+        vendor_id: Uuid,
+        reply: &str,
+    ) -> Result<Model, ServiceError> {
+        let reply = required_text(reply, "Reply")?;
+
+        let txn = self.db.begin().await?;
+        let review = Self::lock_review(&txn, review_id).await?;
+        let product = product::Entity::find_by_id(review.product_id)
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Product not found".to_string()))?;
+        if product.seller_id != vendor_id {
+            return Err(ServiceError::Forbidden(
+                "You can only reply to reviews of your products".to_string(),
+            ));
+        }
+        if review.vendor_reply.is_some() {
+            return Err(ServiceError::Validation(
+                "You already replied to this review".to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+        let mut active_model: review::ActiveModel = review.into();
+        active_model.vendor_reply = Set(Some(reply));
+        active_model.vendor_replied_at = Set(Some(now));
+        active_model.updated_at = Set(now);
+        let review = active_model.update(&txn).await?;
+        txn.commit().await?;
+
+        Ok(review)
+    }
+
+    /// Flag a published review for moderators, once per user
+    pub async fn report(
+        &self,
+        review_id: Uuid,
+        reporter_id: Uuid,
+        reason: &str,
+    ) -> Result<review_report::Model, ServiceError> {
+        let reason = required_text(reason, "Reason")?;
+        let review = self.get_review(review_id).await?;
+        if review.status != ReviewStatus::Published {
+            return Err(ServiceError::NotFound("Review not found".to_string()));
+        }
+        let existing = review_report::Entity::find()
+            .filter(review_report::Column::ReviewId.eq(review_id))
+            .filter(review_report::Column::ReporterId.eq(reporter_id))
+            .one(self.db.as_ref())
+            .await?;
+        if existing.is_some() {
+            return Err(ServiceError::Validation(
+                "You already reported this review".to_string(),
+            ));
+        }
+
+        let report = review_report::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            review_id: Set(review_id),
+            reporter_id: Set(reporter_id),
+            reason: Set(reason),
+            resolved_at: Set(None),
+            created_at: Set(Utc::now()),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        Ok(report)
+    }
+
+    /// Reviews with open reports, the longest waiting first
+    pub async fn list_reported(&self) -> Result<Vec<ReportedReview>, ServiceError> {
+        let reports = review_report::Entity::find()
+            .filter(review_report::Column::ResolvedAt.is_null())
+            .order_by_asc(review_report::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+        if reports.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut order = Vec::new();
+        let mut by_review: BTreeMap<Uuid, Vec<review_report::Model>> = BTreeMap::new();
+        for report in reports {
+            let reports = by_review.entry(report.review_id).or_default();
+            if reports.is_empty() {
+                order.push(report.review_id);
+            }
+            reports.push(report);
+        }
+        let mut reviews: HashMap<Uuid, Model> = review::Entity::find()
+            .filter(review::Column::Id.is_in(order.clone()))
+            .all(self.db.as_ref())
+            .await?
+            .into_iter()
+            .map(|review| (review.id, review))
+            .collect();
+
+        Ok(order
+            .into_iter()
+            .filter_map(|review_id| {
+                Some(ReportedReview {
+                    review: reviews.remove(&review_id)?,
+                    reports: by_review.remove(&review_id).unwrap_or_default(),
+                })
+            })
+            .collect())
+    }
+
+    /// Hide or restore a review, closing its open reports
+    pub async fn moderate(
+        &self,
+        review_id: Uuid,
+        decision: ReviewDecision,
+    ) -> Result<Model, ServiceError> {
+        let reason = decision
+            .reason
+            .map(|reason| reason.trim().to_string())
+            .filter(|reason| !reason.is_empty());
+        let hidden_reason = match decision.status {
+            ReviewStatus::Hidden => Some(reason.ok_or_else(|| {
+                ServiceError::Validation("A reason is required to hide a review".to_string())
+            })?),
+            ReviewStatus::Published => None,
+        };
+
+        let txn = self.db.begin().await?;
+        let review = Self::lock_review(&txn, review_id).await?;
+        let now = Utc::now();
+        let mut active_model: review::ActiveModel = review.into();
+        active_model.status = Set(decision.status);
+        active_model.hidden_reason = Set(hidden_reason);
+        active_model.updated_at = Set(now);
+        let review = active_model.update(&txn).await?;
+        review_report::Entity::update_many()
+            .col_expr(review_report::Column::ResolvedAt, Expr::value(now))
+            .filter(review_report::Column::ReviewId.eq(review_id))
+            .filter(review_report::Column::ResolvedAt.is_null())
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+
+        Ok(review)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{money::Money, product::ModerationStatus};
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn product(seller_id: Uuid) -> product::Model {
+        product::Model {
+            id: Uuid::new_v4(),
+            seller_id,
+            title: "Penja pepper".to_string(),
+            description: None,
+            price: Money::xaf(2500),
+            category_id: None,
+            quantity: 10,
+            image_urls: vec![],
+            moderation_status: ModerationStatus::Approved,
+            rejection_reason: None,
+            moderator_note: None,
+            return_policy: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn order_line(product_id: Uuid) -> order_item::Model {
+        order_item::Model {
+            id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            product_id,
+            variant_id: None,
+            sku: None,
+            price: Money::xaf(2500),
+            quantity: 1,
+            vendor_order_id: None,
+        }
+    }
+
+    fn review(product_id: Uuid, buyer_id: Uuid) -> Model {
+        Model {
+            id: Uuid::new_v4(),
+            product_id,
+            buyer_id,
+            order_id: None,
+            rating: 4,
+            body: "Very fragrant".to_string(),
+            image_urls: vec![],
+            status: ReviewStatus::Published,
+            hidden_reason: None,
+            vendor_reply: None,
+            vendor_replied_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn new_review(rating: i32) -> NewReview {
+        NewReview {
+            rating,
+            body: " Very fragrant ".to_string(),
+            image_urls: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_review_from_delivered_order() {
+        let product = product(Uuid::new_v4());
+        let buyer_id = Uuid::new_v4();
+        let line = order_line(product.id);
+        let created = review(product.id, buyer_id);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![product.clone()]])
+            .append_query_results([vec![line.clone()]])
+            .append_query_results::<Model, _, _>([vec![]])
+            .append_query_results([vec![created.clone()]])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = ReviewService::new(db.clone());
+
+        let result = service
+            .create_review(product.id, buyer_id, new_review(4))
+            .await
+            .unwrap();
+        assert_eq!(result, created);
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let purchase_sql = log[1].statements()[0].to_string();
+        assert!(purchase_sql.contains("\"orders\".\"status\" = 'delivered'"));
+        assert!(purchase_sql.contains("\"vendor_orders\".\"status\" = 'delivered'"));
+        let insert_sql = log[3].statements()[0].to_string();
+        assert!(insert_sql.contains("'Very fragrant'"), "{}", insert_sql);
+        assert!(insert_sql.contains(&line.order_id.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_only_buyers_who_received_the_product_review_it() {
+        let product = product(Uuid::new_v4());
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![product.clone()]])
+            .append_query_results::<order_item::Model, _, _>([vec![]])
+            .into_connection();
+        let service = ReviewService::new(Arc::new(db));
+
+        let result = service
+            .create_review(product.id, Uuid::new_v4(), new_review(5))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Forbidden(_))));
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let service = ReviewService::new(Arc::new(db));
+        for rating in [0, 6] {
+            let result = service
+                .create_review(product.id, Uuid::new_v4(), new_review(rating))
+                .await;
+            assert!(matches!(result, Err(ServiceError::Validation(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_vendor_replies_once() {
+        let vendor_id = Uuid::new_v4();
+        let product = product(vendor_id);
+        let replied = Model {
+            vendor_reply: Some("Thank you!".to_string()),
+            vendor_replied_at: Some(Utc::now()),
+            ..review(product.id, Uuid::new_v4())
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![replied.clone()]])
+            .append_query_results([vec![product.clone()]])
+            .into_connection();
+        let service = ReviewService::new(Arc::new(db));
+
+        let result = service.reply(replied.id, vendor_id, "Thanks again").await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![replied.clone()]])
+            .append_query_results([vec![product.clone()]])
+            .into_connection();
+        let service = ReviewService::new(Arc::new(db));
+
+        let result = service.reply(replied.id, Uuid::new_v4(), "Thanks").await;
+        assert!(matches!(result, Err(ServiceError::Forbidden(_))));
+    }
+
+    #[tokio::test]
+    async fn test_hiding_a_review_closes_its_reports() {
+        let reviewed = review(Uuid::new_v4(), Uuid::new_v4());
+        let hidden = Model {
+            status: ReviewStatus::Hidden,
+            hidden_reason: Some("Insults the vendor".to_string()),
+            ..reviewed.clone()
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![reviewed.clone()], vec![hidden.clone()]])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 2,
+            }])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = ReviewService::new(db.clone());
+
+        let missing_reason = service
+            .moderate(
+                reviewed.id,
+                ReviewDecision {
+                    status: ReviewStatus::Hidden,
+                    reason: Some("  ".to_string()),
+                },
+            )
+            .await;
+        assert!(matches!(missing_reason, Err(ServiceError::Validation(_))));
+
+        let result = service
+            .moderate(
+                reviewed.id,
+                ReviewDecision {
+                    status: ReviewStatus::Hidden,
+                    reason: Some("Insults the vendor".to_string()),
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.status, ReviewStatus::Hidden);
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let resolve_sql = log[0].statements()[3].to_string();
+        assert!(
+            resolve_sql.starts_with("UPDATE \"review_reports\""),
+            "{}",
+            resolve_sql
+        );
+        assert!(resolve_sql.contains("\"resolved_at\" IS NULL"));
+    }
+}
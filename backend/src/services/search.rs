@@ -0,0 +1,510 @@
+use std::{fmt, str::FromStr, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    sea_query::{Expr, Query, SimpleExpr},
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult, Order, PaginatorTrait,
+    QueryFilter, QueryOrder, QueryResult, QuerySelect, Value,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{
+    money::Money,
+    product::{self, Model, ModerationStatus},
+    store::{self, Region},
+};
+
+use super::{
+    category::SUBTREE_OF_SLUG, errors::ServiceError, product::ProductListing,
+    store::store_summaries,
+};
+
+/// Results returned by one search when no limit is given
+const DEFAULT_LIMIT: u64 = 20;
+/// Most results returned by one search
+const MAX_LIMIT: u64 = 100;
+
+/// Units of the product bought in paid orders. Pending and cancelled orders
+/// do not count towards popularity.
+const UNITS_SOLD: &str = "(SELECT COALESCE(SUM(order_items.quantity), 0)::bigint \
+    FROM order_items JOIN orders ON orders.id = order_items.order_id \
+    WHERE order_items.product_id = products.id \
+    AND orders.status IN ('processing', 'shipped', 'delivered'))";
+
+/// Average number of stars of the product's published reviews, in
+/// hundredths of a star. Products without reviews rank last.
+const AVERAGE_RATING: &str = "(SELECT COALESCE(ROUND(AVG(reviews.rating) * 100), 0)::bigint \
+    FROM reviews WHERE reviews.product_id = products.id \
+    AND reviews.status = 'published')";
+
+/// Matches products whose title or description, or the English or French
+/// name of their category, contain the words of `$1`, read as a French or
+/// an English query
+const MATCHES_TEXT: &str = "(products.search_vector @@ \
+    (websearch_to_tsquery('english', $1) || websearch_to_tsquery('french', $1)) \
+    OR products.category_id IN (SELECT id FROM categories WHERE \
+    (to_tsvector('english', name_en) || to_tsvector('french', name_fr)) @@ \
+    (websearch_to_tsquery('english', $1) || websearch_to_tsquery('french', $1))))";
+
+/// How search results are ordered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    /// Most recently listed first
+    #[default]
+    Newest,
+    /// Cheapest first
+    PriceAsc,
+    /// Most expensive first
+    PriceDesc,
+    /// Most units sold first
+    Popularity,
+    /// Best average rating first
+    Rating,
+}
+
+impl SearchSort {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SearchSort::Newest => "newest",
+            SearchSort::PriceAsc => "price_asc",
+            SearchSort::PriceDesc => "price_desc",
+            SearchSort::Popularity => "popularity",
+            SearchSort::Rating => "rating",
+        }
+    }
+
+    fn order(&self) -> Order {
+        match self {
+            SearchSort::PriceAsc => Order::Asc,
+            SearchSort::Newest
+            | SearchSort::PriceDesc
+            | SearchSort::Popularity
+            | SearchSort::Rating => Order::Desc,
+        }
+    }
+
+    /// Expression the results are sorted by, before the product id
+    fn key(&self) -> SimpleExpr {
+        match self {
+            SearchSort::Newest => Expr::col((product::Entity, product::Column::CreatedAt)).into(),
+            SearchSort::PriceAsc | SearchSort::PriceDesc => {
+                Expr::col((product::Entity, product::Column::Price)).into()
+            }
+            SearchSort::Popularity => Expr::cust(UNITS_SOLD),
+            SearchSort::Rating => Expr::cust(AVERAGE_RATING),
+        }
+    }
+}
+
+impl fmt::Display for SearchSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Filters, sort order and page of a product search. Every filter is
+/// optional; only approved products are ever returned.
+#[derive(Debug, Default, Deserialize)]
+pub struct ProductSearch {
+    /// Words to look for in the title, description and category name
+    pub q: Option<String>,
+    /// Lowest price, in francs
+    pub min_price: Option<i64>,
+    /// Highest price, in francs
+    pub max_price: Option<i64>,
+    /// Slug of a category; products of its subcategories match too
+    pub category: Option<String>,
+    /// Region of the store that makes the product
+    pub region: Option<Region>,
+    pub vendor_id: Option<Uuid>,
+    /// Only products with stock left
+    #[serde(default)]
+    pub in_stock: bool,
+    #[serde(default)]
+    pub sort: SearchSort,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    pub limit: Option<u64>,
+}
+
+/// One page of search results
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResults {
+    pub items: Vec<ProductListing>,
+    /// Number of products matching the filters, over every page
+    pub total: u64,
+    /// Pass as `cursor` to get the next page; empty on the last page
+    pub next_cursor: Option<String>,
+}
+
+/// Position after the last result of a page: the sort it was made for, the
+/// sort key and the id of that result
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SearchCursor {
+    sort: SearchSort,
+    key: i64,
+    id: Uuid,
+}
+
+impl SearchCursor {
+    fn after(sort: SearchSort, hit: &RankedProduct) -> Self {
+        let key = match sort {
+            SearchSort::Newest => hit.product.created_at.timestamp_micros(),
+            SearchSort::PriceAsc | SearchSort::PriceDesc => hit.product.price.as_xaf(),
+            SearchSort::Popularity => hit.units_sold,
+            SearchSort::Rating => hit.average_rating,
+        };
+        SearchCursor {
+            sort,
+            key,
+            id: hit.product.id,
+        }
+    }
+
+    /// The sort key as a value to compare the key expression with
+    fn key_value(&self) -> Result<Value, ServiceError> {
+        Ok(match self.sort {
+            SearchSort::Newest => DateTime::<Utc>::from_timestamp_micros(self.key)
+                .ok_or_else(invalid_cursor)?
+                .into(),
+            SearchSort::PriceAsc | SearchSort::PriceDesc => Money::xaf(self.key).into(),
+            SearchSort::Popularity | SearchSort::Rating => self.key.into(),
+        })
+    }
+}
+
+impl fmt::Display for SearchCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.sort, self.key, self.id)
+    }
+}
+
+impl FromStr for SearchCursor {
+    type Err = ServiceError;
+
+    fn from_str(cursor: &str) -> Result<Self, Self::Err> {
+        let mut parts = cursor.splitn(3, '.');
+        let (Some(sort), Some(key), Some(id)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(invalid_cursor());
+        };
+        let sort = [
+            SearchSort::Newest,
+            SearchSort::PriceAsc,
+            SearchSort::PriceDesc,
+            SearchSort::Popularity,
+            SearchSort::Rating,
+        ]
+        .into_iter()
+        .find(|candidate| candidate.as_str() == sort)
+        .ok_or_else(invalid_cursor)?;
+        Ok(SearchCursor {
+            sort,
+            key: key.parse().map_err(|_| invalid_cursor())?,
+            id: id.parse().map_err(|_| invalid_cursor())?,
+        })
+    }
+}
+
+fn invalid_cursor() -> ServiceError {
+    ServiceError::Validation("Invalid cursor".to_string())
+}
+
+/// A product with the number of units sold and its average rating, which
+/// popularity and rating sort by
+#[derive(Debug, Clone)]
+struct RankedProduct {
+    product: Model,
+    units_sold: i64,
+    average_rating: i64,
+}
+
+impl FromQueryResult for RankedProduct {
+    fn from_query_result(res: &QueryResult, pre: &str) -> Result<Self, DbErr> {
+        Ok(RankedProduct {
+            product: Model::from_query_result(res, pre)?,
+            units_sold: res.try_get(pre, "units_sold")?,
+            average_rating: res.try_get(pre, "average_rating")?,
+        })
+    }
+}
+
+/// Full-text search over the approved catalogue
+pub struct SearchService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl SearchService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// One page of approved products matching `search`, with the number of
+    /// matches over every page
+    pub async fn search_products(
+        &self,
+        search: ProductSearch,
+    ) -> Result<SearchResults, ServiceError> {
+        let sort = search.sort;
+        let cursor = search
+            .cursor
+            .as_deref()
+            .map(SearchCursor::from_str)
+            .transpose()?;
+        if cursor.is_some_and(|cursor| cursor.sort != sort) {
+            return Err(ServiceError::Validation(
+                "The cursor belongs to another sort order".to_string(),
+            ));
+        }
+        if let (Some(min), Some(max)) = (search.min_price, search.max_price) {
+            if min > max {
+                return Err(ServiceError::Validation(
+                    "min_price cannot be above max_price".to_string(),
+                ));
+            }
+        }
+
+        let mut query = product::Entity::find()
+            .filter(product::Column::ModerationStatus.eq(ModerationStatus::Approved));
+        if let Some(q) = search.q.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+            query = query.filter(Expr::cust_with_values(MATCHES_TEXT, [q]));
+        }
+        if let Some(min_price) = search.min_price {
+            query = query.filter(product::Column::Price.gte(Money::xaf(min_price)));
+        }
+        if let Some(max_price) = search.max_price {
+            query = query.filter(product::Column::Price.lte(Money::xaf(max_price)));
+        }
+        if let Some(category) = search
+            .category
+            .as_deref()
+            .map(str::trim)
+            .filter(|category| !category.is_empty())
+        {
+            query = query.filter(Expr::cust_with_values(
+                format!("products.category_id IN ({})", SUBTREE_OF_SLUG),
+                [category.to_lowercase()],
+            ));
+        }
+        if let Some(region) = search.region {
+            query = query.filter(
+                product::Column::SellerId.in_subquery(
+                    Query::select()
+                        .column(store::Column::OwnerId)
+                        .from(store::Entity)
+                        .and_where(store::Column::Region.eq(region))
+                        .to_owned(),
+                ),
+            );
+        }
+        if let Some(vendor_id) = search.vendor_id {
+            query = query.filter(product::Column::SellerId.eq(vendor_id));
+        }
+        if search.in_stock {
+            query = query.filter(product::Column::Quantity.gt(0));
+        }
+
+        let total = query.clone().count(self.db.as_ref()).await?;
+
+        let id = || Expr::col((product::Entity, product::Column::Id));
+        if let Some(cursor) = cursor {
+            let position = Expr::tuple([sort.key(), id().into()]);
+            let last = Expr::tuple([
+                Expr::val(cursor.key_value()?).into(),
+                Expr::val(cursor.id).into(),
+            ]);
+            query = query.filter(match sort.order() {
+                Order::Asc => position.gt(last),
+                _ => position.lt(last),
+            });
+        }
+        let limit = search.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let mut hits = query
+            .column_as(Expr::cust(UNITS_SOLD), "units_sold")
+            .column_as(Expr::cust(AVERAGE_RATING), "average_rating")
+            .order_by(sort.key(), sort.order())
+            .order_by(id(), sort.order())
+            .limit(limit + 1)
+            .into_model::<RankedProduct>()
+            .all(self.db.as_ref())
+            .await?;
+
+        let next_cursor = if hits.len() as u64 > limit {
+            hits.truncate(limit as usize);
+            hits.last()
+                .map(|hit| SearchCursor::after(sort, hit).to_string())
+        } else {
+            None
+        };
+        let stores =
+            store_summaries(&self.db, hits.iter().map(|hit| hit.product.seller_id)).await?;
+        let items = hits
+            .into_iter()
+            .map(|hit| ProductListing {
+                store: stores.get(&hit.product.seller_id).cloned(),
+                product: hit.product,
+            })
+            .collect();
+
+        Ok(SearchResults {
+            items,
+            total,
+            next_cursor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{DatabaseBackend, MockDatabase};
+    use std::collections::BTreeMap;
+
+    fn approved_product(price: i64) -> Model {
+        Model {
+            id: Uuid::new_v4(),
+            seller_id: Uuid::new_v4(),
+            title: "Ndop Cloth".to_string(),
+            description: Some("Hand-dyed cotton from the Grassfields".to_string()),
+            price: Money::xaf(price),
+            category_id: None,
+            quantity: 4,
+            image_urls: vec![],
+            moderation_status: ModerationStatus::Approved,
+            rejection_reason: None,
+            moderator_note: None,
+            return_policy: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// A result row: the product's columns, its units sold and its rating
+    fn row(product: &Model, units_sold: i64) -> BTreeMap<&'static str, Value> {
+        BTreeMap::from([
+            ("id", product.id.into()),
+            ("seller_id", product.seller_id.into()),
+            ("title", product.title.clone().into()),
+            ("description", product.description.clone().into()),
+            ("price", product.price.into()),
+            ("category_id", product.category_id.into()),
+            ("quantity", product.quantity.into()),
+            ("image_urls", product.image_urls.clone().into()),
+            ("moderation_status", product.moderation_status.into()),
+            ("rejection_reason", product.rejection_reason.clone().into()),
+            ("moderator_note", product.moderator_note.clone().into()),
+            ("return_policy", product.return_policy.clone().into()),
+            ("created_at", product.created_at.into()),
+            ("updated_at", product.updated_at.into()),
+            ("units_sold", units_sold.into()),
+            ("average_rating", 0i64.into()),
+        ])
+    }
+
+    fn count(total: i64) -> BTreeMap<&'static str, Value> {
+        BTreeMap::from([("num_items", total.into())])
+    }
+
+    #[test]
+    fn test_cursor_round_trips() {
+        let cursor = SearchCursor {
+            sort: SearchSort::PriceDesc,
+            key: 15000,
+            id: Uuid::new_v4(),
+        };
+        assert_eq!(cursor.to_string().parse::<SearchCursor>().unwrap(), cursor);
+        assert!("price_desc.abc".parse::<SearchCursor>().is_err());
+        assert!("cheapest.1.00000000-0000-0000-0000-000000000000"
+            .parse::<SearchCursor>()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_filters_and_pages() {
+        let (first, second) = (approved_product(12000), approved_product(9000));
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![count(3)]])
+            .append_query_results(vec![vec![row(&first, 2), row(&second, 0)]])
+            .append_query_results::<store::Model, _, _>(vec![vec![]])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = SearchService::new(db.clone());
+
+        let results = service
+            .search_products(ProductSearch {
+                q: Some("tissu ndop".to_string()),
+                min_price: Some(5000),
+                category: Some("textiles".to_string()),
+                region: Some(Region::Northwest),
+                in_stock: true,
+                sort: SearchSort::PriceDesc,
+                limit: Some(1),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(results.total, 3);
+        assert_eq!(results.items.len(), 1);
+        assert_eq!(results.items[0].product.id, first.id);
+        let next = results.next_cursor.unwrap();
+        assert_eq!(next, format!("price_desc.12000.{}", first.id));
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let count_sql = log[0].statements()[0].to_string();
+        assert!(count_sql.contains("websearch_to_tsquery('french', 'tissu ndop')"));
+        assert!(count_sql.contains("\"region\" = 'northwest'"));
+        assert!(count_sql.contains("WHERE slug = 'textiles'"));
+        assert!(count_sql.contains("\"quantity\" > 0"));
+        let page_sql = log[1].statements()[0].to_string();
+        assert!(page_sql.contains("'approved'"));
+        assert!(page_sql.contains("ORDER BY \"products\".\"price\" DESC, \"products\".\"id\" DESC"));
+        assert!(page_sql.ends_with("LIMIT 2"));
+    }
+
+    #[tokio::test]
+    async fn test_search_sorts_by_rating() {
+        let product = approved_product(2500);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![count(1)]])
+            .append_query_results(vec![vec![row(&product, 0)]])
+            .append_query_results::<store::Model, _, _>(vec![vec![]])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = SearchService::new(db.clone());
+
+        let last = Uuid::new_v4();
+        let results = service
+            .search_products(ProductSearch {
+                sort: SearchSort::Rating,
+                cursor: Some(format!("rating.450.{}", last)),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(results.items.len(), 1);
+        assert!(results.next_cursor.is_none());
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let page_sql = log[1].statements()[0].to_string();
+        assert!(page_sql.contains("reviews.status = 'published'"), "{}", page_sql);
+        assert!(page_sql.contains(&format!("< (450, '{}')", last)), "{}", page_sql);
+    }
+
+    #[tokio::test]
+    async fn test_cursor_must_match_sort() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let service = SearchService::new(Arc::new(db));
+
+        let result = service
+            .search_products(ProductSearch {
+                sort: SearchSort::Newest,
+                cursor: Some(format!("popularity.4.{}", Uuid::new_v4())),
+                ..Default::default()
+            })
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+}
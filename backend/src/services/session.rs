@@ -0,0 +1,449 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use rand::RngCore;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    config::Config,
+    middleware::auth::generate_token,
+    models::{session, user},
+};
+
+use super::errors::ServiceError;
+
+/// Signed-in sessions of users.
+///
+/// A session pairs short-lived access tokens with a refresh token that is
+/// stored hashed and replaced every time it is used. Presenting a refresh
+/// token that was already replaced means it leaked, so the whole session is
+/// revoked.
+pub struct SessionService {
+    db: Arc<DatabaseConnection>,
+    config: Arc<Config>,
+}
+
+/// Tokens handed to a client when it signs in or refreshes
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IssuedTokens {
+    /// Access token to send as `Authorization: Bearer`
+    pub token: String,
+    /// Single-use token to obtain the next access token
+    pub refresh_token: String,
+    /// Seconds until the access token expires
+    pub expires_in: i64,
+}
+
+/// Generate a random refresh token
+fn new_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Refresh tokens are only stored as their SHA-256 hash
+pub(crate) fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+impl SessionService {
+    pub fn new(db: Arc<DatabaseConnection>, config: Arc<Config>) -> Self {
+        Self { db, config }
+    }
+
+    fn issue(
+        &self,
+        user: &user::Model,
+        session_id: Uuid,
+        refresh_token: String,
+    ) -> Result<IssuedTokens, ServiceError> {
+        let token = generate_token(
+            &user.id.to_string(),
+            user.role.clone(),
+            session_id,
+            &self.config,
+        )
+        .map_err(|_| ServiceError::InternalServerError)?;
+
+        Ok(IssuedTokens {
+            token,
+            refresh_token,
+            expires_in: self.config.jwt_expires_in,
+        })
+    }
+
+    /// Open a session for a user whose credentials were checked
+    pub async fn start_session(
+        &self,
+        user: &user::Model,
+        user_agent: Option<String>,
+    ) -> Result<IssuedTokens, ServiceError> {
+        let refresh_token = new_refresh_token();
+        let now = Utc::now();
+        let session = session::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(user.id),
+            refresh_token_hash: Set(hash_token(&refresh_token)),
+            previous_token_hash: Set(None),
+            user_agent: Set(user_agent),
+            expires_at: Set(now + Duration::seconds(self.config.refresh_token_expires_in)),
+            revoked_at: Set(None),
+            last_used_at: Set(now),
+            created_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        self.issue(user, session.id, refresh_token)
+    }
+
+    /// Exchange a refresh token for a new access token and refresh token
+    pub async fn refresh(
+        &self,
+        refresh_token: &str,
+    ) -> Result<(user::Model, IssuedTokens), ServiceError> {
+        let token_hash = hash_token(refresh_token);
+        let now = Utc::now();
+
+        let Some((session, user)) = session::Entity::find()
+            .filter(session::Column::RefreshTokenHash.eq(&token_hash))
+            .find_also_related(user::Entity)
+            .one(self.db.as_ref())
+            .await?
+        else {
+            // A replaced token coming back means someone else holds it
+            if let Some(session) = session::Entity::find()
+                .filter(session::Column::PreviousTokenHash.eq(&token_hash))
+                .one(self.db.as_ref())
+                .await?
+            {
+                tracing::warn!(
+                    "Refresh token of session {} was reused, revoking it",
+                    session.id
+                );
+                self.revoke_session(session.id, session.user_id).await?;
+            }
+            return Err(ServiceError::Unauthorized(
+                "Invalid refresh token".to_string(),
+            ));
+        };
+
+        if !session.is_active(now) {
+            return Err(ServiceError::Unauthorized("Session has ended".to_string()));
+        }
+        let user = match user {
+            Some(user) if user.is_active => user,
+            _ => {
+                return Err(ServiceError::Unauthorized(
+                    "Account is disabled".to_string(),
+                ))
+            }
+        };
+
+        // Rotate only if the token is still current, so two requests racing
+        // with the same token cannot both get a new one
+        let next_token = new_refresh_token();
+        let rotated = session::Entity::update_many()
+            .col_expr(
+                session::Column::PreviousTokenHash,
+                Expr::value(Some(token_hash.clone())),
+            )
+            .col_expr(
+                session::Column::RefreshTokenHash,
+                Expr::value(hash_token(&next_token)),
+            )
+            .col_expr(
+                session::Column::ExpiresAt,
+                Expr::value(now + Duration::seconds(self.config.refresh_token_expires_in)),
+            )
+            .col_expr(session::Column::LastUsedAt, Expr::value(now))
+            .filter(session::Column::Id.eq(session.id))
+            .filter(session::Column::RefreshTokenHash.eq(&token_hash))
+            .exec(self.db.as_ref())
+            .await?;
+        if rotated.rows_affected == 0 {
+            return Err(ServiceError::Unauthorized(
+                "Invalid refresh token".to_string(),
+            ));
+        }
+
+        let tokens = self.issue(&user, session.id, next_token)?;
+        Ok((user, tokens))
+    }
+
+    /// Check that an access token may still be used: its session must be
+    /// active and belong to `user_id`, and the user must be active
+    pub async fn authenticate(&self, session_id: Uuid, user_id: Uuid) -> Result<(), ServiceError> {
+        let (session, user) = session::Entity::find_by_id(session_id)
+            .find_also_related(user::Entity)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::Unauthorized("Unknown session".to_string()))?;
+
+        if session.user_id != user_id {
+            return Err(ServiceError::Unauthorized(
+                "Session belongs to another user".to_string(),
+            ));
+        }
+        // Revoking a session cuts off its access tokens at once
+        if session.revoked_at.is_some() {
+            return Err(ServiceError::Unauthorized(
+                "Session was revoked".to_string(),
+            ));
+        }
+        if !user.is_some_and(|user| user.is_active) {
+            return Err(ServiceError::Unauthorized(
+                "Account is disabled".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Sessions of a user that can still be refreshed, newest first
+    pub async fn list_sessions(&self, user_id: Uuid) -> Result<Vec<session::Model>, ServiceError> {
+        let sessions = session::Entity::find()
+            .filter(session::Column::UserId.eq(user_id))
+            .filter(session::Column::RevokedAt.is_null())
+            .filter(session::Column::ExpiresAt.gt(Utc::now()))
+            .order_by_desc(session::Column::LastUsedAt)
+            .all(self.db.as_ref())
+            .await?;
+
+        Ok(sessions)
+    }
+
+    /// Log a session out
+    pub async fn revoke_session(
+        &self,
+        session_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), ServiceError> {
+        let result = session::Entity::update_many()
+            .col_expr(session::Column::RevokedAt, Expr::value(Utc::now()))
+            .filter(session::Column::Id.eq(session_id))
+            .filter(session::Column::UserId.eq(user_id))
+            .filter(session::Column::RevokedAt.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(ServiceError::NotFound("Session not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Log a user out everywhere. Returns how many sessions were revoked.
+    pub async fn revoke_all_sessions(&self, user_id: Uuid) -> Result<u64, ServiceError> {
+        let result = session::Entity::update_many()
+            .col_expr(session::Column::RevokedAt, Expr::value(Utc::now()))
+            .filter(session::Column::UserId.eq(user_id))
+            .filter(session::Column::RevokedAt.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+
+    /// Log a user out everywhere except `keep_session_id`, the session they
+    /// are using. Returns how many sessions were revoked.
+    pub async fn revoke_other_sessions(
+        &self,
+        user_id: Uuid,
+        keep_session_id: Uuid,
+    ) -> Result<u64, ServiceError> {
+        let result = session::Entity::update_many()
+            .col_expr(session::Column::RevokedAt, Expr::value(Utc::now()))
+            .filter(session::Column::UserId.eq(user_id))
+            .filter(session::Column::Id.ne(keep_session_id))
+            .filter(session::Column::RevokedAt.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+
+        Ok(result.rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::{Language, UserRole};
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn service(db: DatabaseConnection) -> SessionService {
+        let config = Config {
+            jwt_secret: "test_secret".to_string(),
+            ..Default::default()
+        };
+        SessionService::new(Arc::new(db), Arc::new(config))
+    }
+
+    fn active_user(id: Uuid) -> user::Model {
+        user::Model {
+            id,
+            email: None,
+            password_hash: String::new(),
+            role: UserRole::Buyer,
+            full_name: "Test User".to_string(),
+            is_active: true,
+            phone: "677777777".parse().unwrap(),
+            phone_verified_at: Some(Utc::now()),
+            failed_login_attempts: 0,
+            locked_until: None,
+            pending_phone: None,
+            vendor_verified_at: None,
+            pending_email: None,
+            avatar_url: None,
+            preferred_language: Language::Fr,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn session_row(user_id: Uuid, refresh_token: &str) -> session::Model {
+        session::Model {
+            id: Uuid::new_v4(),
+            user_id,
+            refresh_token_hash: hash_token(refresh_token),
+            previous_token_hash: None,
+            user_agent: None,
+            expires_at: Utc::now() + Duration::days(30),
+            revoked_at: None,
+            last_used_at: Utc::now(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_refresh_tokens_are_random_and_hashed() {
+        let token = new_refresh_token();
+        assert_eq!(token.len(), 64);
+        assert_ne!(token, new_refresh_token());
+        assert_eq!(hash_token(&token), hash_token(&token));
+        assert_ne!(hash_token(&token), token);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rotates_token() {
+        let user = active_user(Uuid::new_v4());
+        let session = session_row(user.id, "first");
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![(session, user)]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+
+        let (_, tokens) = service(db).refresh("first").await.unwrap();
+
+        assert_ne!(tokens.refresh_token, "first");
+        assert!(!tokens.token.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_rotates_once() {
+        let user = active_user(Uuid::new_v4());
+        let session = session_row(user.id, "first");
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![(session, user)]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 0,
+            }])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = SessionService::new(db.clone(), Arc::new(Config::default()));
+
+        let result = service.refresh("first").await;
+        assert!(matches!(result, Err(ServiceError::Unauthorized(_))));
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        assert!(log[1].statements()[0]
+            .to_string()
+            .contains("AND \"sessions\".\"refresh_token_hash\" = '"));
+    }
+
+    #[tokio::test]
+    async fn test_reused_refresh_token_revokes_session() {
+        let user_id = Uuid::new_v4();
+        let session = session::Model {
+            previous_token_hash: Some(hash_token("stolen")),
+            ..session_row(user_id, "current")
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![Vec::<(session::Model, user::Model)>::new()])
+            .append_query_results(vec![vec![session]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = SessionService::new(db.clone(), Arc::new(Config::default()));
+
+        let result = service.refresh("stolen").await;
+        assert!(matches!(result, Err(ServiceError::Unauthorized(_))));
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        assert!(log.iter().any(|t| t.statements()[0]
+            .to_string()
+            .starts_with("UPDATE \"sessions\"")));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_rejects_disabled_user() {
+        let user = user::Model {
+            is_active: false,
+            ..active_user(Uuid::new_v4())
+        };
+        let session = session_row(user.id, "first");
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![(session, user)]])
+            .into_connection();
+
+        let result = service(db).refresh("first").await;
+
+        assert!(matches!(result, Err(ServiceError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_rejects_revoked_session() {
+        let user = active_user(Uuid::new_v4());
+        let session = session::Model {
+            revoked_at: Some(Utc::now()),
+            ..session_row(user.id, "first")
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![(session.clone(), user.clone())]])
+            .into_connection();
+
+        let result = service(db).authenticate(session.id, user.id).await;
+
+        assert!(matches!(result, Err(ServiceError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_rejects_disabled_user() {
+        let user = user::Model {
+            is_active: false,
+            ..active_user(Uuid::new_v4())
+        };
+        let session = session_row(user.id, "first");
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![(session.clone(), user.clone())]])
+            .into_connection();
+
+        let result = service(db).authenticate(session.id, user.id).await;
+
+        assert!(matches!(result, Err(ServiceError::Unauthorized(_))));
+    }
+}
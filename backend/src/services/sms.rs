@@ -0,0 +1,125 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use tracing::{info, warn};
+
+use crate::models::phone::PhoneNumber;
+
+use super::errors::ServiceError;
+
+/// A text message handed to an [`SmsSender`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sms {
+    pub to: PhoneNumber,
+    pub body: String,
+}
+
+/// Delivers text messages to phones
+#[async_trait]
+pub trait SmsSender: Debug + Send + Sync {
+    async fn send(&self, sms: &Sms) -> Result<(), ServiceError>;
+}
+
+/// SMS sender for development.
+///
+/// Nothing leaves the machine: every message, one-time codes included, is
+/// written to the log so a developer can sign up without a phone. The
+/// configuration refuses it when `APP_ENV` is `production`.
+#[derive(Debug, Default)]
+pub struct LogSmsSender;
+
+#[async_trait]
+impl SmsSender for LogSmsSender {
+    async fn send(&self, sms: &Sms) -> Result<(), ServiceError> {
+        warn!("SMS to {} not sent, development only: {}", sms.to, sms.body);
+        Ok(())
+    }
+}
+
+/// SMS sender backed by the Twilio Messages API
+#[derive(Clone)]
+pub struct TwilioSmsSender {
+    client: reqwest::Client,
+    account_sid: String,
+    auth_token: String,
+    /// Number or alphanumeric sender id the messages come from
+    from: String,
+}
+
+impl TwilioSmsSender {
+    pub fn new(account_sid: String, auth_token: String, from: String) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            account_sid,
+            auth_token,
+            from,
+        }
+    }
+}
+
+// Written by hand to keep the auth token out of logs
+impl Debug for TwilioSmsSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TwilioSmsSender")
+            .field("account_sid", &self.account_sid)
+            .field("from", &self.from)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl SmsSender for TwilioSmsSender {
+    async fn send(&self, sms: &Sms) -> Result<(), ServiceError> {
+        let url = format!(
+            "https://api.twilio.com/2010-04-01/Accounts/{}/Messages.json",
+            self.account_sid
+        );
+        let response = self
+            .client
+            .post(url)
+            .basic_auth(&self.account_sid, Some(&self.auth_token))
+            .form(&[
+                ("To", sms.to.as_str()),
+                ("From", self.from.as_str()),
+                ("Body", sms.body.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| ServiceError::GenericError(format!("SMS provider error: {}", e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ServiceError::GenericError(format!(
+                "SMS provider answered {}: {}",
+                status, body
+            )));
+        }
+        info!("SMS sent to {}", sms.to);
+        Ok(())
+    }
+}
+
+/// SMS sender for tests, keeping messages where tests can read them back
+/// with [`RecordingSmsSender::sent`]
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct RecordingSmsSender {
+    sent: std::sync::Mutex<Vec<Sms>>,
+}
+
+#[cfg(test)]
+impl RecordingSmsSender {
+    /// Messages sent so far, oldest first
+    pub fn sent(&self) -> Vec<Sms> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl SmsSender for RecordingSmsSender {
+    async fn send(&self, sms: &Sms) -> Result<(), ServiceError> {
+        self.sent.lock().unwrap().push(sms.clone());
+        Ok(())
+    }
+}
@@ -0,0 +1,481 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{
+    phone::PhoneNumber,
+    product::{self, ModerationStatus},
+    store::{self, ContactPreferences, Model, Region},
+    user::{self, UserRole},
+};
+
+use super::errors::ServiceError;
+
+/// Longest slug accepted in a store URL
+const MAX_SLUG_LEN: usize = 60;
+
+#[derive(Deserialize, Debug)]
+pub struct CreateStore {
+    pub name: String,
+    /// Derived from the name when left out
+    pub slug: Option<String>,
+    pub logo_url: Option<String>,
+    pub banner_url: Option<String>,
+    pub description: Option<String>,
+    pub region: Option<Region>,
+    pub city: Option<String>,
+    pub artisan_story: Option<String>,
+    #[serde(default)]
+    pub contact_preferences: ContactPreferences,
+}
+
+/// Partial store update. Fields left out are unchanged, empty strings clear
+/// optional ones.
+#[derive(Deserialize, Debug, Default)]
+pub struct UpdateStore {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    pub logo_url: Option<String>,
+    pub banner_url: Option<String>,
+    pub description: Option<String>,
+    pub region: Option<Region>,
+    pub city: Option<String>,
+    pub artisan_story: Option<String>,
+    pub contact_preferences: Option<ContactPreferences>,
+}
+
+/// What product listings show of the store that makes a product
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StoreSummary {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub logo_url: Option<String>,
+    pub region: Option<Region>,
+    pub city: Option<String>,
+}
+
+impl From<Model> for StoreSummary {
+    fn from(store: Model) -> Self {
+        Self {
+            id: store.id,
+            name: store.name,
+            slug: store.slug,
+            logo_url: store.logo_url,
+            region: store.region,
+            city: store.city,
+        }
+    }
+}
+
+/// A store as buyers see it, with the maker's name and the contact details
+/// the owner chose to show
+#[derive(Debug, Serialize)]
+pub struct StoreProfile {
+    #[serde(flatten)]
+    pub store: Model,
+    pub maker: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phone: Option<PhoneNumber>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// A public shop page
+#[derive(Debug, Serialize)]
+pub struct StorePage {
+    pub store: StoreProfile,
+    pub products: Vec<product::Model>,
+}
+
+/// Lowercase `name`, dropping accents and joining words with dashes
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars().flat_map(char::to_lowercase) {
+        let c = match c {
+            'à' | 'â' | 'ä' => 'a',
+            'ç' => 'c',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'î' | 'ï' => 'i',
+            'ô' | 'ö' => 'o',
+            'ù' | 'û' | 'ü' => 'u',
+            c => c,
+        };
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.truncate(MAX_SLUG_LEN);
+    slug.trim_end_matches('-').to_string()
+}
+
+fn validate_slug(slug: &str) -> Result<(), ServiceError> {
+    let valid = !slug.is_empty()
+        && slug.len() <= MAX_SLUG_LEN
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        && !slug.starts_with('-')
+        && !slug.ends_with('-');
+    if !valid {
+        return Err(ServiceError::Validation(format!(
+            "Store URL must be 1 to {} lowercase letters, digits or dashes",
+            MAX_SLUG_LEN
+        )));
+    }
+    Ok(())
+}
+
+/// Trim `value`, treating an empty string as no value
+fn optional(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn required_name(name: &str) -> Result<String, ServiceError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ServiceError::Validation(
+            "Store name is required".to_string(),
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// Vendor storefronts
+pub struct StoreService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl StoreService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    async fn ensure_slug_available(
+        &self,
+        slug: &str,
+        store_id: Option<Uuid>,
+    ) -> Result<(), ServiceError> {
+        let existing = store::Entity::find()
+            .filter(store::Column::Slug.eq(slug))
+            .one(self.db.as_ref())
+            .await?;
+        if existing.is_some_and(|existing| Some(existing.id) != store_id) {
+            return Err(ServiceError::Validation(
+                "Store URL is already taken".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Open the vendor's store. Each vendor has one store.
+    pub async fn create_store(
+        &self,
+        owner_id: Uuid,
+        data: CreateStore,
+    ) -> Result<Model, ServiceError> {
+        let name = required_name(&data.name)?;
+        let slug = match optional(data.slug) {
+            Some(slug) => slug,
+            None => slugify(&name),
+        };
+        validate_slug(&slug)?;
+
+        let owner = user::Entity::find_by_id(owner_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::UserNotFound("User not found".to_string()))?;
+        if owner.role != UserRole::Vendor {
+            return Err(ServiceError::Forbidden(
+                "Only vendors can open a store".to_string(),
+            ));
+        }
+        if self.get_store_by_owner(owner_id).await?.is_some() {
+            return Err(ServiceError::Validation(
+                "Vendor already has a store".to_string(),
+            ));
+        }
+        self.ensure_slug_available(&slug, None).await?;
+
+        let now = Utc::now();
+        let store = store::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            owner_id: Set(owner_id),
+            name: Set(name),
+            slug: Set(slug),
+            logo_url: Set(optional(data.logo_url)),
+            banner_url: Set(optional(data.banner_url)),
+            description: Set(optional(data.description)),
+            region: Set(data.region),
+            city: Set(optional(data.city)),
+            artisan_story: Set(optional(data.artisan_story)),
+            contact_preferences: Set(data.contact_preferences),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        Ok(store)
+    }
+
+    pub async fn update_store(
+        &self,
+        owner_id: Uuid,
+        data: UpdateStore,
+    ) -> Result<Model, ServiceError> {
+        let store = self
+            .get_store_by_owner(owner_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Store not found".to_string()))?;
+        let store_id = store.id;
+        let mut active_model: store::ActiveModel = store.into();
+
+        if let Some(name) = data.name {
+            active_model.name = Set(required_name(&name)?);
+        }
+        if let Some(slug) = data.slug {
+            let slug = slug.trim().to_string();
+            validate_slug(&slug)?;
+            self.ensure_slug_available(&slug, Some(store_id)).await?;
+            active_model.slug = Set(slug);
+        }
+        if data.logo_url.is_some() {
+            active_model.logo_url = Set(optional(data.logo_url));
+        }
+        if data.banner_url.is_some() {
+            active_model.banner_url = Set(optional(data.banner_url));
+        }
+        if data.description.is_some() {
+            active_model.description = Set(optional(data.description));
+        }
+        if let Some(region) = data.region {
+            active_model.region = Set(Some(region));
+        }
+        if data.city.is_some() {
+            active_model.city = Set(optional(data.city));
+        }
+        if data.artisan_story.is_some() {
+            active_model.artisan_story = Set(optional(data.artisan_story));
+        }
+        if let Some(contact_preferences) = data.contact_preferences {
+            active_model.contact_preferences = Set(contact_preferences);
+        }
+        active_model.updated_at = Set(Utc::now());
+
+        Ok(active_model.update(self.db.as_ref()).await?)
+    }
+
+    pub async fn get_store_by_owner(&self, owner_id: Uuid) -> Result<Option<Model>, ServiceError> {
+        let store = store::Entity::find()
+            .filter(store::Column::OwnerId.eq(owner_id))
+            .one(self.db.as_ref())
+            .await?;
+
+        Ok(store)
+    }
+
+    /// The public page of the store at `slug`, with its approved products.
+    /// Stores of disabled or unverified vendors are not shown.
+    pub async fn get_store_page(&self, slug: &str) -> Result<StorePage, ServiceError> {
+        let not_found = || ServiceError::NotFound("Store not found".to_string());
+        let (store, owner) = store::Entity::find()
+            .filter(store::Column::Slug.eq(slug))
+            .find_also_related(user::Entity)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(not_found)?;
+        let owner = owner.ok_or_else(not_found)?;
+        if !owner.is_active || owner.vendor_verified_at.is_none() {
+            return Err(not_found());
+        }
+
+        let products = product::Entity::find()
+            .filter(product::Column::SellerId.eq(owner.id))
+            .filter(product::Column::ModerationStatus.eq(ModerationStatus::Approved))
+            .order_by_desc(product::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+
+        let contact = &store.contact_preferences;
+        let phone = contact.show_phone.then_some(owner.phone);
+        let email = owner.email.filter(|_| contact.show_email);
+        Ok(StorePage {
+            store: StoreProfile {
+                store,
+                maker: owner.full_name,
+                phone,
+                email,
+            },
+            products,
+        })
+    }
+}
+
+/// The stores of `owner_ids`, keyed by owner
+pub async fn store_summaries(
+    db: &DatabaseConnection,
+    owner_ids: impl IntoIterator<Item = Uuid>,
+) -> Result<HashMap<Uuid, StoreSummary>, ServiceError> {
+    let mut owner_ids: Vec<Uuid> = owner_ids.into_iter().collect();
+    owner_ids.sort_unstable();
+    owner_ids.dedup();
+    if owner_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let stores = store::Entity::find()
+        .filter(store::Column::OwnerId.is_in(owner_ids))
+        .all(db)
+        .await?;
+    Ok(stores
+        .into_iter()
+        .map(|store| (store.owner_id, StoreSummary::from(store)))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{money::Money, user::Language};
+    use sea_orm::{DatabaseBackend, MockDatabase};
+
+    fn owner(vendor_verified: bool) -> user::Model {
+        user::Model {
+            id: Uuid::new_v4(),
+            full_name: "Test Vendor".to_string(),
+            email: Some("vendor@example.com".to_string()),
+            phone: "677777777".parse().unwrap(),
+            phone_verified_at: Some(Utc::now()),
+            failed_login_attempts: 0,
+            locked_until: None,
+            avatar_url: None,
+            preferred_language: Language::Fr,
+            pending_email: None,
+            pending_phone: None,
+            vendor_verified_at: vendor_verified.then(Utc::now),
+            password_hash: "hashed_password".to_string(),
+            is_active: true,
+            role: UserRole::Vendor,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn store(owner_id: Uuid) -> Model {
+        Model {
+            id: Uuid::new_v4(),
+            owner_id,
+            name: "Poterie de Foumban".to_string(),
+            slug: "poterie-de-foumban".to_string(),
+            logo_url: None,
+            banner_url: None,
+            description: None,
+            region: Some(Region::West),
+            city: Some("Foumban".to_string()),
+            artisan_story: None,
+            contact_preferences: ContactPreferences {
+                show_phone: true,
+                ..Default::default()
+            },
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Poterie de Foumban"), "poterie-de-foumban");
+        assert_eq!(
+            slugify("  Café & Cacao -- Bafoussam! "),
+            "cafe-cacao-bafoussam"
+        );
+        assert_eq!(slugify("Tissage Ndop 2"), "tissage-ndop-2");
+        assert_eq!(slugify("!!!"), "");
+        assert!(validate_slug("poterie-de-foumban").is_ok());
+        assert!(validate_slug("Poterie").is_err());
+        assert!(validate_slug("-poterie").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_store_rejects_taken_slug() {
+        let owner = owner(true);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![owner.clone()]])
+            .append_query_results(vec![Vec::<Model>::new()])
+            .append_query_results(vec![vec![store(Uuid::new_v4())]])
+            .into_connection();
+        let service = StoreService::new(Arc::new(db));
+
+        let data = CreateStore {
+            name: "Poterie de Foumban".to_string(),
+            slug: None,
+            logo_url: None,
+            banner_url: None,
+            description: None,
+            region: Some(Region::West),
+            city: None,
+            artisan_story: None,
+            contact_preferences: ContactPreferences::default(),
+        };
+        let result = service.create_store(owner.id, data).await;
+        assert!(
+            matches!(result, Err(ServiceError::Validation(ref msg)) if msg == "Store URL is already taken")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_page_shows_chosen_contacts_and_approved_products() {
+        let owner = owner(true);
+        let store = store(owner.id);
+        let product = product::Model {
+            id: Uuid::new_v4(),
+            seller_id: owner.id,
+            title: "Bamoun vase".to_string(),
+            description: None,
+            price: Money::xaf(15000),
+            category_id: None,
+            quantity: 3,
+            image_urls: vec![],
+            moderation_status: ModerationStatus::Approved,
+            rejection_reason: None,
+            moderator_note: None,
+            return_policy: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![(store.clone(), Some(owner.clone()))]])
+            .append_query_results(vec![vec![product.clone()]])
+            .into_connection();
+        let service = StoreService::new(Arc::new(db));
+
+        let page = service.get_store_page(&store.slug).await.unwrap();
+        assert_eq!(page.store.maker, "Test Vendor");
+        assert_eq!(page.store.phone, Some(owner.phone));
+        assert_eq!(page.store.email, None);
+        assert_eq!(page.products, vec![product]);
+    }
+
+    #[tokio::test]
+    async fn test_store_page_hides_unverified_vendor() {
+        let owner = owner(false);
+        let store = store(owner.id);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![(store.clone(), Some(owner))]])
+            .into_connection();
+        let service = StoreService::new(Arc::new(db));
+
+        let result = service.get_store_page(&store.slug).await;
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+    }
+}
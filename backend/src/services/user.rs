@@ -1,15 +1,19 @@
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
-use chrono::Utc;
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use chrono::{Duration, Utc};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    Set,
+};
 use serde::{Deserialize, Serialize};
-use tracing::error;
+use tracing::{error, warn};
 use uuid::Uuid;
 
 use crate::{
-    config::Config,
-    middleware::auth::generate_token,
-    models::user::{self, Model, UserRole},
+    models::{
+        phone::PhoneNumber,
+        user::{self, Language, Model, UserRole},
+    },
     utils::password::{hash_password, verify_password},
 };
 
@@ -22,14 +26,14 @@ pub struct UserService {
 pub struct CreateUser {
     pub full_name: String,
     pub email: Option<String>,
-    pub phone: i32,
+    pub phone: PhoneNumber,
     pub password: String,
     pub role: UserRole,
 }
 
 #[derive(Deserialize)]
 pub struct LoginRequest {
-    pub phone: i32,
+    pub phone: PhoneNumber,
     pub password: String,
     pub role: Option<UserRole>,
 }
@@ -39,13 +43,79 @@ pub struct LoginResponse {
     pub role: UserRole,
     pub user_id: Uuid,
     pub token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
     pub full_name: String,
 }
+
+#[derive(Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+/// How many failed logins lock an account, and for how long
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoginLockout {
+    pub max_failures: i32,
+    pub lock_secs: i64,
+}
+
+impl Default for LoginLockout {
+    fn default() -> Self {
+        Self {
+            max_failures: 5,
+            lock_secs: 900,
+        }
+    }
+}
+
+/// Hash checked against when no account matches, so that unknown phone
+/// numbers take as long to reject as wrong passwords
+fn dummy_password_hash() -> &'static str {
+    static HASH: OnceLock<String> = OnceLock::new();
+    HASH.get_or_init(|| hash_password("not a real password").expect("hashing a constant"))
+}
+
+/// Shortest password accepted when one is chosen or changed
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+fn validate_password(password: &str) -> Result<(), ServiceError> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(ServiceError::Validation(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LEN
+        )));
+    }
+    Ok(())
+}
+
+fn validate_email(email: &str) -> Result<(), ServiceError> {
+    let valid = email
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+    if !valid || email.chars().any(char::is_whitespace) {
+        return Err(ServiceError::Validation(
+            "Email address is invalid".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Partial profile update. Fields left out are unchanged. A new email or
+/// phone is only stored as pending until it is confirmed with a code.
+#[derive(Deserialize, Debug, Default)]
 pub struct UpdateUser {
     pub full_name: Option<String>,
     pub email: Option<String>,
-    pub phone: i32,
-    pub password: String,
+    pub phone: Option<PhoneNumber>,
+    /// An empty string removes the avatar
+    pub avatar_url: Option<String>,
+    pub preferred_language: Option<Language>,
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
 }
 
 impl UserService {
@@ -53,7 +123,23 @@ impl UserService {
         Self { db }
     }
 
+    /// Two accounts cannot share a phone number
+    async fn ensure_phone_available(&self, phone: &PhoneNumber) -> Result<(), ServiceError> {
+        let existing = user::Entity::find()
+            .filter(user::Column::Phone.eq(phone.clone()))
+            .one(&*self.db)
+            .await?;
+        if existing.is_some() {
+            return Err(ServiceError::Validation(
+                "Phone number is already registered".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     pub async fn create_user(&self, user_data: CreateUser) -> Result<Model, ServiceError> {
+        validate_password(&user_data.password)?;
+        self.ensure_phone_available(&user_data.phone).await?;
         let password_hash = hash_password(&user_data.password)?;
 
         let active_model = user::ActiveModel {
@@ -61,15 +147,23 @@ impl UserService {
             full_name: Set(user_data.full_name.clone()),
             email: Set(user_data.email),
             phone: Set(user_data.phone),
+            phone_verified_at: Set(None),
+            failed_login_attempts: Set(0),
+            locked_until: Set(None),
+            avatar_url: Set(None),
+            preferred_language: Set(Language::default()),
+            pending_email: Set(None),
+            pending_phone: Set(None),
+            vendor_verified_at: Set(None),
             password_hash: Set(password_hash),
             is_active: Set(true),
-            role: Set(user_data.role.into()),
+            role: Set(user_data.role),
             created_at: Set(Utc::now()),
             updated_at: Set(Utc::now()),
         };
         let result = active_model.insert(&*self.db).await;
         match result {
-            Ok(user) => Ok(user.into()),
+            Ok(user) => Ok(user),
             Err(e) => {
                 error!(error = %e, "Failed to insert user");
                 Err(ServiceError::InternalServerError)
@@ -79,31 +173,142 @@ impl UserService {
 
     pub async fn login(
         &self,
-        phone: i32,
+        phone: PhoneNumber,
         password: String,
-        config: &Config,
         expected_role: Option<UserRole>,
-    ) -> Result<(Model, String), ServiceError> {
+        lockout: LoginLockout,
+    ) -> Result<Model, ServiceError> {
+        // Every way of getting the credentials wrong fails the same way, so
+        // responses do not tell which accounts exist
+        let invalid = || ServiceError::Unauthorized("Invalid phone number or password".to_string());
         let user = user::Entity::find()
             .filter(user::Column::Phone.eq(phone))
             .one(&*self.db)
             .await
             .map_err(|_| ServiceError::InternalServerError)?;
-        if let Some(user) = user {
-            if let Some(role) = expected_role {
-                if user.role != role {
-                    return Err(ServiceError::InvalidPassword)?;
-                }
-            }
-            if !verify_password(&password, &user.password_hash)? {
-                return Err(ServiceError::InvalidPassword)?;
-            }
-            let token = generate_token(&user.id.to_string(), user.role.clone(), &config)
-                .map_err(|_| ServiceError::InternalServerError)?;
-            Ok((user, token))
-        } else {
-            Err(ServiceError::UserNotFound("User not found".to_string()))
+        let Some(user) = user else {
+            // Spend as long as a real check would
+            verify_password(&password, dummy_password_hash())?;
+            return Err(invalid());
+        };
+
+        let now = Utc::now();
+        if user.locked_until.is_some_and(|until| until > now) {
+            warn!("Login refused for locked user {}", user.id);
+            return Err(invalid());
+        }
+        let password_matches = verify_password(&password, &user.password_hash)?;
+        if !password_matches || expected_role.is_some_and(|role| role != user.role) {
+            self.record_failed_login(&user, lockout).await?;
+            return Err(invalid());
+        }
+        if user.failed_login_attempts > 0 {
+            user::Entity::update_many()
+                .col_expr(user::Column::FailedLoginAttempts, Expr::value(0))
+                .filter(user::Column::Id.eq(user.id))
+                .exec(&*self.db)
+                .await?;
+        }
+
+        if !user.is_active {
+            return Err(ServiceError::Unauthorized(
+                "Account is disabled".to_string(),
+            ));
+        }
+        if user.phone_verified_at.is_none() {
+            return Err(ServiceError::Unauthorized(
+                "Phone number is not verified".to_string(),
+            ));
+        }
+        Ok(user)
+    }
+
+    /// Count a failed login, locking the account once `lockout.max_failures`
+    /// is reached. The counter starts over after a lockout.
+    async fn record_failed_login(
+        &self,
+        user: &Model,
+        lockout: LoginLockout,
+    ) -> Result<(), ServiceError> {
+        let locked_until = Utc::now() + Duration::seconds(lockout.lock_secs);
+        user::Entity::update_many()
+            .col_expr(
+                user::Column::LockedUntil,
+                Expr::cust_with_values(
+                    "CASE WHEN failed_login_attempts + 1 >= $1 THEN $2 ELSE locked_until END",
+                    [
+                        sea_orm::Value::from(lockout.max_failures),
+                        sea_orm::Value::from(locked_until),
+                    ],
+                ),
+            )
+            .col_expr(
+                user::Column::FailedLoginAttempts,
+                Expr::cust_with_values(
+                    "CASE WHEN failed_login_attempts + 1 >= $1 THEN 0 ELSE failed_login_attempts + 1 END",
+                    [lockout.max_failures],
+                ),
+            )
+            .filter(user::Column::Id.eq(user.id))
+            .exec(&*self.db)
+            .await?;
+        if user.failed_login_attempts + 1 >= lockout.max_failures {
+            warn!("Locked user {} after repeated failed logins", user.id);
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_user_by_phone(
+        &self,
+        phone: &PhoneNumber,
+    ) -> Result<Option<Model>, ServiceError> {
+        let user = user::Entity::find()
+            .filter(user::Column::Phone.eq(phone.clone()))
+            .one(self.db.as_ref())
+            .await?;
+
+        Ok(user)
+    }
+
+    /// Record that the owner of `phone` proved they hold it
+    pub async fn mark_phone_verified(&self, phone: &PhoneNumber) -> Result<Model, ServiceError> {
+        let user = self
+            .get_user_by_phone(phone)
+            .await?
+            .ok_or_else(|| ServiceError::UserNotFound("User not found".to_string()))?;
+        if user.phone_verified_at.is_some() {
+            return Ok(user);
         }
+
+        let mut active_model: user::ActiveModel = user.into();
+        active_model.phone_verified_at = Set(Some(Utc::now()));
+        active_model.updated_at = Set(Utc::now());
+        Ok(active_model.update(self.db.as_ref()).await?)
+    }
+
+    /// Replace the password of the owner of `phone`. Receiving the reset
+    /// code also proves they own the phone.
+    pub async fn reset_password(
+        &self,
+        phone: &PhoneNumber,
+        new_password: &str,
+    ) -> Result<Model, ServiceError> {
+        validate_password(new_password)?;
+        let user = self
+            .get_user_by_phone(phone)
+            .await?
+            .ok_or_else(|| ServiceError::UserNotFound("User not found".to_string()))?;
+
+        let now = Utc::now();
+        let verified_at = user.phone_verified_at.unwrap_or(now);
+        let mut active_model: user::ActiveModel = user.into();
+        active_model.password_hash = Set(hash_password(new_password)?);
+        active_model.phone_verified_at = Set(Some(verified_at));
+        active_model.failed_login_attempts = Set(0);
+        active_model.locked_until = Set(None);
+        active_model.updated_at = Set(now);
+        Ok(active_model.update(self.db.as_ref()).await?)
     }
 
     pub async fn get_user_by_id(&self, user_id: Uuid) -> Result<Option<Model>, ServiceError> {
@@ -112,36 +317,129 @@ impl UserService {
             .await
             .map_err(|e| ServiceError::NotFound(e.to_string()))?;
 
-        Ok(user.into())
+        Ok(user)
+    }
+
+    async fn find_user(&self, user_id: Uuid) -> Result<Model, ServiceError> {
+        user::Entity::find_by_id(user_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::UserNotFound("User not found".to_string()))
     }
 
+    /// Apply the fields of `user_data` that are set. Name, avatar and
+    /// language change straight away; a new email or phone is kept pending
+    /// until [`Self::confirm_email_change`] or [`Self::confirm_phone_change`].
     pub async fn update_user(
         &self,
         user_id: Uuid,
         user_data: UpdateUser,
     ) -> Result<Model, ServiceError> {
-        let user = user::Entity::find_by_id(user_id)
-            .one(&*self.db)
-            .await
-            .map_err(|e| ServiceError::NotFound(e.to_string()))?;
-        if let Some(user) = user {
-            let mut active_model: user::ActiveModel = user.clone().into();
-
-            if let Some(name) = user_data.full_name {
-                active_model.full_name = Set(name);
+        let user = self.find_user(user_id).await?;
+        let mut active_model: user::ActiveModel = user.clone().into();
+
+        if let Some(name) = user_data.full_name {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(ServiceError::Validation(
+                    "Full name cannot be empty".to_string(),
+                ));
             }
-            if let Some(email) = user_data.email {
-                active_model.email = Set(Some(email));
+            active_model.full_name = Set(name.to_string());
+        }
+        if let Some(avatar_url) = user_data.avatar_url {
+            let avatar_url = avatar_url.trim();
+            if avatar_url.is_empty() {
+                active_model.avatar_url = Set(None);
+            } else if avatar_url.starts_with("https://") || avatar_url.starts_with("http://") {
+                active_model.avatar_url = Set(Some(avatar_url.to_string()));
+            } else {
+                return Err(ServiceError::Validation(
+                    "Avatar must be an http(s) URL".to_string(),
+                ));
             }
-            active_model.phone = Set(user_data.phone);
-            active_model.password_hash = Set(hash_password(&user_data.password)?);
+        }
+        if let Some(language) = user_data.preferred_language {
+            active_model.preferred_language = Set(language);
+        }
+        if let Some(email) = user_data.email {
+            let email = email.trim().to_lowercase();
+            validate_email(&email)?;
+            let pending = (user.email.as_deref() != Some(email.as_str())).then_some(email);
+            active_model.pending_email = Set(pending);
+        }
+        if let Some(phone) = user_data.phone {
+            let pending = if phone == user.phone {
+                None
+            } else {
+                self.ensure_phone_available(&phone).await?;
+                Some(phone)
+            };
+            active_model.pending_phone = Set(pending);
+        }
+        active_model.updated_at = Set(Utc::now());
 
-            let updated_user = active_model.update(self.db.as_ref()).await?;
-            Ok(updated_user.into())
-        } else {
-            Err(ServiceError::UserNotFound("User not found".to_string()))
+        Ok(active_model.update(self.db.as_ref()).await?)
+    }
+
+    /// Make the pending email the user's email, once they entered the code
+    pub async fn confirm_email_change(&self, user_id: Uuid) -> Result<Model, ServiceError> {
+        let user = self.find_user(user_id).await?;
+        let Some(email) = user.pending_email.clone() else {
+            return Err(ServiceError::Validation(
+                "No email change is pending".to_string(),
+            ));
+        };
+
+        let mut active_model: user::ActiveModel = user.into();
+        active_model.email = Set(Some(email));
+        active_model.pending_email = Set(None);
+        active_model.updated_at = Set(Utc::now());
+        Ok(active_model.update(self.db.as_ref()).await?)
+    }
+
+    /// Make the pending phone the user's phone, once they entered the code
+    /// texted to it
+    pub async fn confirm_phone_change(&self, user_id: Uuid) -> Result<Model, ServiceError> {
+        let user = self.find_user(user_id).await?;
+        let Some(phone) = user.pending_phone.clone() else {
+            return Err(ServiceError::Validation(
+                "No phone number change is pending".to_string(),
+            ));
+        };
+        // Someone may have registered the number since it was requested
+        self.ensure_phone_available(&phone).await?;
+
+        let now = Utc::now();
+        let mut active_model: user::ActiveModel = user.into();
+        active_model.phone = Set(phone);
+        active_model.phone_verified_at = Set(Some(now));
+        active_model.pending_phone = Set(None);
+        active_model.updated_at = Set(now);
+        Ok(active_model.update(self.db.as_ref()).await?)
+    }
+
+    /// Replace the password of a signed in user, who must know the current one
+    pub async fn change_password(
+        &self,
+        user_id: Uuid,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<Model, ServiceError> {
+        let user = self.find_user(user_id).await?;
+        if !verify_password(current_password, &user.password_hash)? {
+            return Err(ServiceError::Validation(
+                "Current password is incorrect".to_string(),
+            ));
         }
+        validate_password(new_password)?;
+
+        let mut active_model: user::ActiveModel = user.into();
+        active_model.password_hash = Set(hash_password(new_password)?);
+        active_model.updated_at = Set(Utc::now());
+        Ok(active_model.update(self.db.as_ref()).await?)
     }
+
     pub async fn delete_user(&self, user_id: Uuid) -> Result<(), ServiceError> {
         user::Entity::delete_by_id(user_id).exec(&*self.db).await?;
         Ok(())
@@ -156,19 +454,33 @@ impl UserService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sea_orm::MockDatabase;
+    use chrono::DateTime;
+    use sea_orm::{MockDatabase, MockExecResult};
+
+    fn phone(number: &str) -> PhoneNumber {
+        number.parse().unwrap()
+    }
 
     #[tokio::test]
     async fn test_create_user() {
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![Vec::<user::Model>::new()])
             .append_query_results(vec![vec![user::Model {
                 id: Uuid::new_v4(),
                 full_name: "Test User".to_string(),
                 email: Some("test@example.com".to_string()),
-                phone: 654988322,
+                phone: phone("677777777"),
+                phone_verified_at: Some(Utc::now()),
+                failed_login_attempts: 0,
+                locked_until: None,
+                pending_phone: None,
+                vendor_verified_at: None,
+                pending_email: None,
+                avatar_url: None,
+                preferred_language: Language::Fr,
                 password_hash: "hashed_password".to_string(),
                 is_active: true,
-                role: UserRole::Vendor.into(),
+                role: UserRole::Vendor,
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
             }]])
@@ -179,7 +491,7 @@ mod tests {
         let user_data = CreateUser {
             full_name: "Test User".to_string(),
             email: Some("test@example.com".to_string()),
-            phone: 1234567890,
+            phone: phone("677777777"),
             password: "password123".to_string(),
             role: UserRole::Vendor,
         };
@@ -190,10 +502,48 @@ mod tests {
         let user_response = result.unwrap();
         assert_eq!(user_response.full_name, "Test User");
         assert_eq!(user_response.email, Some("test@example.com".to_string()));
-        assert_eq!(user_response.phone, 1234567890);
+        assert_eq!(user_response.phone, phone("677777777"));
         assert_eq!(user_response.role, UserRole::Vendor);
     }
 
+    #[tokio::test]
+    async fn test_create_user_rejects_taken_phone() {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user::Model {
+                id: Uuid::new_v4(),
+                full_name: "Existing User".to_string(),
+                email: None,
+                phone: phone("677777777"),
+                phone_verified_at: Some(Utc::now()),
+                failed_login_attempts: 0,
+                locked_until: None,
+                pending_phone: None,
+                vendor_verified_at: None,
+                pending_email: None,
+                avatar_url: None,
+                preferred_language: Language::Fr,
+                password_hash: "hashed_password".to_string(),
+                is_active: true,
+                role: UserRole::Buyer,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            }]])
+            .into_connection();
+
+        let service = UserService::new(db.into());
+
+        let user_data = CreateUser {
+            full_name: "Test User".to_string(),
+            email: None,
+            phone: phone("+237 677 77 77 77"),
+            password: "password123".to_string(),
+            role: UserRole::Buyer,
+        };
+
+        let result = service.create_user(user_data).await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
     #[tokio::test]
     async fn test_login_success() {
         let user_id = Uuid::new_v4();
@@ -202,10 +552,18 @@ mod tests {
                 id: user_id,
                 full_name: "Test User".to_string(),
                 email: Some("test@example.com".to_string()),
-                phone: 1234567890,
+                phone: phone("677777777"),
+                phone_verified_at: Some(Utc::now()),
+                failed_login_attempts: 0,
+                locked_until: None,
+                pending_phone: None,
+                vendor_verified_at: None,
+                pending_email: None,
+                avatar_url: None,
+                preferred_language: Language::Fr,
                 password_hash: hash_password("password123").unwrap(),
                 is_active: true,
-                role: UserRole::Admin.into(),
+                role: UserRole::Admin,
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
             }]])
@@ -213,28 +571,184 @@ mod tests {
 
         let service = UserService::new(db.into());
 
-        let config = Config {
-            jwt_secret: "test_secret".to_string(),
-            jwt_expires_in: 24,
-            ..Default::default()
-        };
         let result = service
-            .login(123, "password123".to_string(), &config, None)
+            .login(
+                phone("677777777"),
+                "password123".to_string(),
+                None,
+                LoginLockout::default(),
+            )
             .await;
         assert!(result.is_ok());
     }
 
+    fn login_user(failed_login_attempts: i32, locked_until: Option<DateTime<Utc>>) -> Model {
+        user::Model {
+            id: Uuid::new_v4(),
+            full_name: "Test User".to_string(),
+            email: Some("test@example.com".to_string()),
+            phone: phone("677777777"),
+            phone_verified_at: Some(Utc::now()),
+            failed_login_attempts,
+            locked_until,
+            pending_phone: None,
+            vendor_verified_at: None,
+            pending_email: None,
+            avatar_url: None,
+            preferred_language: Language::Fr,
+            password_hash: hash_password("password123").unwrap(),
+            is_active: true,
+            role: UserRole::Buyer,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
     #[tokio::test]
     async fn test_login_invalid_credentials() {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![login_user(0, None)]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let db = Arc::new(db);
+
+        let service = UserService::new(db.clone());
+
+        let result = service
+            .login(
+                phone("677777777"),
+                "wrong_password".to_string(),
+                None,
+                LoginLockout::default(),
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(ServiceError::Unauthorized(msg)) if msg == "Invalid phone number or password"
+        ));
+
+        // The failure is counted towards a lockout
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let update = log[1].statements()[0].to_string();
+        assert!(update.starts_with("UPDATE \"users\""));
+        assert!(update.contains("failed_login_attempts + 1"));
+    }
+
+    #[tokio::test]
+    async fn test_login_failures_look_the_same() {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![Vec::<user::Model>::new()])
+            .append_query_results(vec![vec![login_user(
+                0,
+                Some(Utc::now() + Duration::minutes(10)),
+            )]])
+            .into_connection();
+
+        let service = UserService::new(db.into());
+
+        // An unknown number, and a locked account given the right password
+        for _ in 0..2 {
+            let result = service
+                .login(
+                    phone("677777777"),
+                    "password123".to_string(),
+                    None,
+                    LoginLockout::default(),
+                )
+                .await;
+            assert!(matches!(
+                result,
+                Err(ServiceError::Unauthorized(msg)) if msg == "Invalid phone number or password"
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_login_success_clears_failures() {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![login_user(
+                3,
+                Some(Utc::now() - Duration::minutes(1)),
+            )]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+
+        let service = UserService::new(db.into());
+
+        let result = service
+            .login(
+                phone("677777777"),
+                "password123".to_string(),
+                None,
+                LoginLockout::default(),
+            )
+            .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_login_rejects_disabled_account() {
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
             .append_query_results(vec![vec![user::Model {
                 id: Uuid::new_v4(),
                 full_name: "Test User".to_string(),
-                email: Some("test@example.com".to_string()),
-                phone: 1234567890,
+                email: None,
+                phone: phone("677777777"),
+                phone_verified_at: Some(Utc::now()),
+                failed_login_attempts: 0,
+                locked_until: None,
+                pending_phone: None,
+                vendor_verified_at: None,
+                pending_email: None,
+                avatar_url: None,
+                preferred_language: Language::Fr,
+                password_hash: hash_password("password123").unwrap(),
+                is_active: false,
+                role: UserRole::Buyer,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            }]])
+            .into_connection();
+
+        let service = UserService::new(db.into());
+
+        let result = service
+            .login(
+                phone("677777777"),
+                "password123".to_string(),
+                None,
+                LoginLockout::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_login_rejects_unverified_phone() {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user::Model {
+                id: Uuid::new_v4(),
+                full_name: "Test User".to_string(),
+                email: None,
+                phone: phone("677777777"),
+                phone_verified_at: None,
+                failed_login_attempts: 0,
+                locked_until: None,
+                pending_phone: None,
+                vendor_verified_at: None,
+                pending_email: None,
+                avatar_url: None,
+                preferred_language: Language::Fr,
                 password_hash: hash_password("password123").unwrap(),
                 is_active: true,
-                role: UserRole::Buyer.into(),
+                role: UserRole::Buyer,
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
             }]])
@@ -242,15 +756,57 @@ mod tests {
 
         let service = UserService::new(db.into());
 
-        let config = Config {
-            jwt_secret: "test_secret".to_string(),
-            jwt_expires_in: 24,
-            ..Default::default()
-        };
         let result = service
-            .login(123, "wrong_password".to_string(), &config, None)
+            .login(
+                phone("677777777"),
+                "password123".to_string(),
+                None,
+                LoginLockout::default(),
+            )
             .await;
-        assert!(result.is_err());
+        assert!(matches!(result, Err(ServiceError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn test_reset_password_verifies_phone() {
+        let user = user::Model {
+            id: Uuid::new_v4(),
+            full_name: "Test User".to_string(),
+            email: None,
+            phone: phone("677777777"),
+            phone_verified_at: None,
+            failed_login_attempts: 0,
+            locked_until: None,
+            pending_phone: None,
+            vendor_verified_at: None,
+            pending_email: None,
+            avatar_url: None,
+            preferred_language: Language::Fr,
+            password_hash: hash_password("old_password").unwrap(),
+            is_active: true,
+            role: UserRole::Buyer,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user.clone()]])
+            .append_query_results(vec![vec![user::Model {
+                phone_verified_at: Some(Utc::now()),
+                failed_login_attempts: 0,
+                locked_until: None,
+                password_hash: hash_password("new_password").unwrap(),
+                ..user
+            }]])
+            .into_connection();
+
+        let service = UserService::new(db.into());
+
+        let updated = service
+            .reset_password(&phone("677777777"), "new_password")
+            .await
+            .unwrap();
+        assert!(updated.phone_verified_at.is_some());
+        assert!(verify_password("new_password", &updated.password_hash).unwrap());
     }
 
     #[tokio::test]
@@ -261,10 +817,18 @@ mod tests {
                 id: user_id,
                 full_name: "Test User".to_string(),
                 email: Some("test@example.com".to_string()),
-                phone: 123,
+                phone: phone("651234567"),
+                phone_verified_at: Some(Utc::now()),
+                failed_login_attempts: 0,
+                locked_until: None,
+                pending_phone: None,
+                vendor_verified_at: None,
+                pending_email: None,
+                avatar_url: None,
+                preferred_language: Language::Fr,
                 password_hash: "hashed_password".to_string(),
                 is_active: true,
-                role: UserRole::Vendor.into(),
+                role: UserRole::Vendor,
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
             }]])
@@ -284,50 +848,100 @@ mod tests {
 
     #[tokio::test]
     async fn test_update_user() {
-        let user_id = Uuid::new_v4();
+        let user = login_user(0, None);
         let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
-            .append_query_results(vec![
-                vec![user::Model {
-                    id: user_id,
-                    full_name: "Test User".to_string(),
-                    email: Some("test@example.com".to_string()),
-                    phone: 123,
-                    password_hash: "hashed_password".to_string(),
-                    is_active: true,
-                    role: UserRole::Admin.into(),
-                    created_at: Utc::now(),
-                    updated_at: Utc::now(),
-                }],
-                vec![user::Model {
-                    id: user_id,
-                    full_name: "Updated User".to_string(),
-                    email: Some("test@example.com".to_string()),
-                    phone: 98,
-                    password_hash: "hashed_password".to_string(),
-                    is_active: true,
-                    role: UserRole::Admin.into(),
-                    created_at: Utc::now(),
-                    updated_at: Utc::now(),
-                }],
-            ])
+            .append_query_results(vec![vec![user.clone()]])
+            .append_query_results(vec![Vec::<Model>::new()])
+            .append_query_results(vec![vec![user::Model {
+                full_name: "Updated User".to_string(),
+                preferred_language: Language::En,
+                pending_phone: Some(phone("699999999")),
+                ..user.clone()
+            }]])
             .into_connection();
+        let db = Arc::new(db);
+        let service = UserService::new(db.clone());
+
+        let update_data = UpdateUser {
+            full_name: Some("Updated User".to_string()),
+            phone: Some(phone("699999999")),
+            preferred_language: Some(Language::En),
+            ..Default::default()
+        };
 
+        let user_response = service.update_user(user.id, update_data).await.unwrap();
+        assert_eq!(user_response.full_name, "Updated User");
+        // The new phone waits for its code, the current one keeps working
+        assert_eq!(user_response.phone, phone("677777777"));
+        assert_eq!(user_response.pending_phone, Some(phone("699999999")));
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let update = log[2].statements()[0].to_string();
+        assert!(update.starts_with("UPDATE \"users\""));
+        assert!(!update.contains(" \"phone\" ="));
+        assert!(!update.contains("\"password_hash\" ="));
+    }
+
+    #[tokio::test]
+    async fn test_update_user_rejects_invalid_avatar() {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![login_user(0, None)]])
+            .into_connection();
         let service = UserService::new(db.into());
 
         let update_data = UpdateUser {
-            full_name: Some("Updated User".to_string()),
-            email: None,
-            phone: 98,
-            password: "02".to_string(),
+            avatar_url: Some("javascript:alert(1)".to_string()),
+            ..Default::default()
         };
+        let result = service.update_user(Uuid::new_v4(), update_data).await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
 
-        let result = service.update_user(user_id, update_data).await;
-        assert!(result.is_ok());
+    #[tokio::test]
+    async fn test_confirm_phone_change() {
+        let user = user::Model {
+            phone_verified_at: None,
+            pending_phone: Some(phone("699999999")),
+            ..login_user(0, None)
+        };
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user.clone()]])
+            .append_query_results(vec![Vec::<Model>::new()])
+            .append_query_results(vec![vec![user::Model {
+                phone: phone("699999999"),
+                phone_verified_at: Some(Utc::now()),
+                pending_phone: None,
+                ..user.clone()
+            }]])
+            .into_connection();
+        let service = UserService::new(db.into());
 
-        let user_response = result.unwrap();
-        assert_eq!(user_response.full_name, "Updated User");
-        assert_eq!(user_response.phone, 98);
-        assert_eq!(user_response.role, UserRole::Admin);
+        let updated = service.confirm_phone_change(user.id).await.unwrap();
+        assert_eq!(updated.phone, phone("699999999"));
+        assert!(updated.phone_verified_at.is_some());
+        assert!(updated.pending_phone.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_change_password_requires_current_password() {
+        let db = MockDatabase::new(sea_orm::DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![login_user(0, None)]])
+            .append_query_results(vec![vec![login_user(0, None)]])
+            .into_connection();
+        let service = UserService::new(db.into());
+
+        let result = service
+            .change_password(Uuid::new_v4(), "wrong_password", "new_password")
+            .await;
+        assert!(
+            matches!(result, Err(ServiceError::Validation(ref msg)) if msg == "Current password is incorrect")
+        );
+
+        let result = service
+            .change_password(Uuid::new_v4(), "password123", "short")
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
     }
 
     #[tokio::test]
@@ -339,9 +953,17 @@ mod tests {
                     full_name: "User 1".to_string(),
                     email: Some("user1@example.com".to_string()),
                     is_active: true,
-                    phone: 123,
+                    phone: phone("651234567"),
+                    phone_verified_at: Some(Utc::now()),
+                    failed_login_attempts: 0,
+                    locked_until: None,
+                    pending_phone: None,
+                    vendor_verified_at: None,
+                    pending_email: None,
+                    avatar_url: None,
+                    preferred_language: Language::Fr,
                     password_hash: "hashed_password".to_string(),
-                    role: UserRole::Buyer.into(),
+                    role: UserRole::Buyer,
                     created_at: Utc::now(),
                     updated_at: Utc::now(),
                 },
@@ -350,9 +972,17 @@ mod tests {
                     full_name: "User 2".to_string(),
                     email: Some("user2@example.com".to_string()),
                     is_active: true,
-                    phone: 98,
+                    phone: phone("699999999"),
+                    phone_verified_at: Some(Utc::now()),
+                    failed_login_attempts: 0,
+                    locked_until: None,
+                    pending_phone: None,
+                    vendor_verified_at: None,
+                    pending_email: None,
+                    avatar_url: None,
+                    preferred_language: Language::Fr,
                     password_hash: "hashed_password".to_string(),
-                    role: UserRole::Vendor.into(),
+                    role: UserRole::Vendor,
                     created_at: Utc::now(),
                     updated_at: Utc::now(),
                 },
@@ -0,0 +1,602 @@
+use std::{collections::HashSet, sync::Arc};
+
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection,
+    EntityTrait, ModelTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{
+    money::{self, Money},
+    product, product_option,
+    product_variant::{self, Model, VariantOptions},
+};
+
+use super::errors::ServiceError;
+
+/// One option a product comes in, as the vendor sets it
+#[derive(Deserialize, Debug, Clone)]
+pub struct OptionInput {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CreateVariant {
+    pub sku: String,
+    /// One value for each of the product's options
+    pub options: VariantOptions,
+    #[serde(deserialize_with = "money::non_negative")]
+    pub price: Money,
+    pub quantity: i32,
+    #[serde(default)]
+    pub image_urls: Vec<String>,
+    #[serde(default)]
+    pub position: i32,
+}
+
+/// Partial variant update. Fields left out are unchanged.
+#[derive(Deserialize, Debug, Default)]
+pub struct UpdateVariant {
+    pub sku: Option<String>,
+    pub options: Option<VariantOptions>,
+    #[serde(default, deserialize_with = "money::non_negative_opt")]
+    pub price: Option<Money>,
+    pub quantity: Option<i32>,
+    pub image_urls: Option<Vec<String>>,
+    pub position: Option<i32>,
+}
+
+/// The options of a product and the variants made of them
+#[derive(Debug, Serialize)]
+pub struct ProductVariants {
+    pub options: Vec<product_option::Model>,
+    pub variants: Vec<Model>,
+}
+
+/// Keep the price and stock of a product with variants in step with them:
+/// its price is the cheapest variant's and its stock that of every variant.
+/// Products without variants are left alone. Returns the price and stock
+/// set, if any.
+pub(crate) async fn sync_product_stock<C: ConnectionTrait>(
+    db: &C,
+    product_id: Uuid,
+) -> Result<Option<(Money, i32)>, ServiceError> {
+    let totals: Option<(Option<Money>, Option<i64>)> = product_variant::Entity::find()
+        .select_only()
+        .column_as(product_variant::Column::Price.min(), "price")
+        .column_as(product_variant::Column::Quantity.sum(), "quantity")
+        .filter(product_variant::Column::ProductId.eq(product_id))
+        .into_tuple()
+        .one(db)
+        .await?;
+    if let Some((Some(price), Some(quantity))) = totals {
+        let quantity = i32::try_from(quantity)
+            .map_err(|_| ServiceError::Validation("Stock is too large".to_string()))?;
+        product::Entity::update_many()
+            .col_expr(product::Column::Price, Expr::value(price))
+            .col_expr(product::Column::Quantity, Expr::value(quantity))
+            .col_expr(product::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(product::Column::Id.eq(product_id))
+            .exec(db)
+            .await?;
+        return Ok(Some((price, quantity)));
+    }
+    Ok(None)
+}
+
+/// Check `values` gives each of `options` one of its values, and nothing else
+fn check_variant_options(
+    options: &[product_option::Model],
+    values: &VariantOptions,
+) -> Result<(), ServiceError> {
+    if options.is_empty() {
+        return Err(ServiceError::Validation(
+            "Set the options of the product before adding variants".to_string(),
+        ));
+    }
+    for option in options {
+        match values.0.get(&option.name) {
+            None => {
+                return Err(ServiceError::Validation(format!(
+                    "A value is required for {}",
+                    option.name
+                )))
+            }
+            Some(value) if !option.values.contains(value) => {
+                return Err(ServiceError::Validation(format!(
+                    "{} is not a value of {}",
+                    value, option.name
+                )))
+            }
+            Some(_) => {}
+        }
+    }
+    if let Some(name) = values
+        .0
+        .keys()
+        .find(|name| !options.iter().any(|option| option.name == **name))
+    {
+        return Err(ServiceError::Validation(format!(
+            "The product has no {} option",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Trimmed option names and values, rejecting blanks and repeats
+fn clean_options(options: Vec<OptionInput>) -> Result<Vec<OptionInput>, ServiceError> {
+    let mut names = HashSet::new();
+    options
+        .into_iter()
+        .map(|option| {
+            let name = option.name.trim().to_string();
+            if name.is_empty() || !names.insert(name.to_lowercase()) {
+                return Err(ServiceError::Validation(
+                    "Option names must be given and distinct".to_string(),
+                ));
+            }
+            let mut seen = HashSet::new();
+            let values = option
+                .values
+                .into_iter()
+                .map(|value| value.trim().to_string())
+                .collect::<Vec<_>>();
+            if values.is_empty()
+                || values
+                    .iter()
+                    .any(|value| value.is_empty() || !seen.insert(value.clone()))
+            {
+                return Err(ServiceError::Validation(format!(
+                    "The values of {} must be given and distinct",
+                    name
+                )));
+            }
+            Ok(OptionInput { name, values })
+        })
+        .collect()
+}
+
+fn check_stock(price: Money, quantity: i32) -> Result<(), ServiceError> {
+    if !price.is_positive() {
+        return Err(ServiceError::Validation(
+            "Price must be positive".to_string(),
+        ));
+    }
+    if quantity < 0 {
+        return Err(ServiceError::Validation(
+            "Quantity cannot be negative".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Options and variants of products, managed by the vendor who makes them
+pub struct VariantService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl VariantService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// Lock the product so changes to its variants apply one at a time, and
+    /// check it belongs to `seller_id`
+    async fn lock_product<C: ConnectionTrait>(
+        db: &C,
+        product_id: Uuid,
+        seller_id: Uuid,
+    ) -> Result<product::Model, ServiceError> {
+        let product = product::Entity::find_by_id(product_id)
+            .lock_exclusive()
+            .one(db)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Product not found".to_string()))?;
+        if product.seller_id != seller_id {
+            return Err(ServiceError::Forbidden(
+                "You don't have permission to change this product".to_string(),
+            ));
+        }
+        Ok(product)
+    }
+
+    async fn options_of<C: ConnectionTrait>(
+        db: &C,
+        product_id: Uuid,
+    ) -> Result<Vec<product_option::Model>, ServiceError> {
+        Ok(product_option::Entity::find()
+            .filter(product_option::Column::ProductId.eq(product_id))
+            .order_by_asc(product_option::Column::Position)
+            .all(db)
+            .await?)
+    }
+
+    async fn variants_of<C: ConnectionTrait>(
+        db: &C,
+        product_id: Uuid,
+    ) -> Result<Vec<Model>, ServiceError> {
+        Ok(product_variant::Entity::find()
+            .filter(product_variant::Column::ProductId.eq(product_id))
+            .order_by_asc(product_variant::Column::Position)
+            .order_by_asc(product_variant::Column::CreatedAt)
+            .all(db)
+            .await?)
+    }
+
+    /// Check no other variant of the product has the same SKU or options
+    fn check_distinct(
+        variants: &[Model],
+        variant_id: Option<Uuid>,
+        sku: &str,
+        options: &VariantOptions,
+    ) -> Result<(), ServiceError> {
+        for other in variants.iter().filter(|other| Some(other.id) != variant_id) {
+            if other.sku == sku {
+                return Err(ServiceError::Validation(format!(
+                    "Another variant already uses the SKU {}",
+                    sku
+                )));
+            }
+            if other.options == *options {
+                return Err(ServiceError::Validation(format!(
+                    "Variant {} already has these options",
+                    other.sku
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn get_variants(&self, product_id: Uuid) -> Result<ProductVariants, ServiceError> {
+        product::Entity::find_by_id(product_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Product not found".to_string()))?;
+        Ok(ProductVariants {
+            options: Self::options_of(self.db.as_ref(), product_id).await?,
+            variants: Self::variants_of(self.db.as_ref(), product_id).await?,
+        })
+    }
+
+    /// Replace the options of a product. Every existing variant must still
+    /// have one valid value per option.
+    pub async fn set_options(
+        &self,
+        product_id: Uuid,
+        seller_id: Uuid,
+        options: Vec<OptionInput>,
+    ) -> Result<Vec<product_option::Model>, ServiceError> {
+        let options = clean_options(options)?;
+        let txn = self.db.begin().await?;
+        Self::lock_product(&txn, product_id, seller_id).await?;
+
+        let new_options: Vec<product_option::Model> = options
+            .into_iter()
+            .enumerate()
+            .map(|(position, option)| product_option::Model {
+                id: Uuid::new_v4(),
+                product_id,
+                name: option.name,
+                values: option.values,
+                position: position as i32,
+            })
+            .collect();
+        for variant in Self::variants_of(&txn, product_id).await? {
+            check_variant_options(&new_options, &variant.options).map_err(|_| {
+                ServiceError::Validation(format!(
+                    "Variant {} does not fit the new options, update or delete it first",
+                    variant.sku
+                ))
+            })?;
+        }
+
+        product_option::Entity::delete_many()
+            .filter(product_option::Column::ProductId.eq(product_id))
+            .exec(&txn)
+            .await?;
+        let mut saved = Vec::with_capacity(new_options.len());
+        for option in new_options {
+            let active_model: product_option::ActiveModel = option.into();
+            saved.push(active_model.insert(&txn).await?);
+        }
+        txn.commit().await?;
+
+        Ok(saved)
+    }
+
+    pub async fn create_variant(
+        &self,
+        product_id: Uuid,
+        seller_id: Uuid,
+        data: CreateVariant,
+    ) -> Result<Model, ServiceError> {
+        let sku = data.sku.trim().to_string();
+        if sku.is_empty() {
+            return Err(ServiceError::Validation("SKU is required".to_string()));
+        }
+        check_stock(data.price, data.quantity)?;
+
+        let txn = self.db.begin().await?;
+        Self::lock_product(&txn, product_id, seller_id).await?;
+        let options = Self::options_of(&txn, product_id).await?;
+        check_variant_options(&options, &data.options)?;
+        let variants = Self::variants_of(&txn, product_id).await?;
+        Self::check_distinct(&variants, None, &sku, &data.options)?;
+
+        let now = Utc::now();
+        let variant = product_variant::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            product_id: Set(product_id),
+            sku: Set(sku),
+            options: Set(data.options),
+            price: Set(data.price),
+            quantity: Set(data.quantity),
+            image_urls: Set(data.image_urls),
+            position: Set(data.position),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+        sync_product_stock(&txn, product_id).await?;
+        txn.commit().await?;
+
+        Ok(variant)
+    }
+
+    pub async fn update_variant(
+        &self,
+        product_id: Uuid,
+        variant_id: Uuid,
+        seller_id: Uuid,
+        data: UpdateVariant,
+    ) -> Result<Model, ServiceError> {
+        let txn = self.db.begin().await?;
+        Self::lock_product(&txn, product_id, seller_id).await?;
+        let variants = Self::variants_of(&txn, product_id).await?;
+        let variant = variants
+            .iter()
+            .find(|variant| variant.id == variant_id)
+            .cloned()
+            .ok_or_else(|| ServiceError::NotFound("Variant not found".to_string()))?;
+
+        let sku = match data.sku {
+            Some(sku) => sku.trim().to_string(),
+            None => variant.sku.clone(),
+        };
+        if sku.is_empty() {
+            return Err(ServiceError::Validation("SKU is required".to_string()));
+        }
+        let options = data.options.unwrap_or_else(|| variant.options.clone());
+        check_variant_options(&Self::options_of(&txn, product_id).await?, &options)?;
+        Self::check_distinct(&variants, Some(variant_id), &sku, &options)?;
+        let price = data.price.unwrap_or(variant.price);
+        let quantity = data.quantity.unwrap_or(variant.quantity);
+        check_stock(price, quantity)?;
+
+        let mut active_model: product_variant::ActiveModel = variant.into();
+        active_model.sku = Set(sku);
+        active_model.options = Set(options);
+        active_model.price = Set(price);
+        active_model.quantity = Set(quantity);
+        if let Some(image_urls) = data.image_urls {
+            active_model.image_urls = Set(image_urls);
+        }
+        if let Some(position) = data.position {
+            active_model.position = Set(position);
+        }
+        active_model.updated_at = Set(Utc::now());
+        let variant = active_model.update(&txn).await?;
+        sync_product_stock(&txn, product_id).await?;
+        txn.commit().await?;
+
+        Ok(variant)
+    }
+
+    pub async fn delete_variant(
+        &self,
+        product_id: Uuid,
+        variant_id: Uuid,
+        seller_id: Uuid,
+    ) -> Result<(), ServiceError> {
+        let txn = self.db.begin().await?;
+        Self::lock_product(&txn, product_id, seller_id).await?;
+        let variant = product_variant::Entity::find_by_id(variant_id)
+            .filter(product_variant::Column::ProductId.eq(product_id))
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Variant not found".to_string()))?;
+        variant.delete(&txn).await?;
+        sync_product_stock(&txn, product_id).await?;
+        txn.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::product::ModerationStatus;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Value};
+    use std::collections::BTreeMap;
+
+    fn pagne(seller_id: Uuid) -> product::Model {
+        product::Model {
+            id: Uuid::new_v4(),
+            seller_id,
+            title: "Wax pagne".to_string(),
+            description: None,
+            price: Money::xaf(6000),
+            category_id: None,
+            quantity: 0,
+            image_urls: vec![],
+            moderation_status: ModerationStatus::Approved,
+            rejection_reason: None,
+            moderator_note: None,
+            return_policy: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn length_option(product_id: Uuid) -> product_option::Model {
+        product_option::Model {
+            id: Uuid::new_v4(),
+            product_id,
+            name: "Length".to_string(),
+            values: vec!["3 yards".to_string(), "6 yards".to_string()],
+            position: 0,
+        }
+    }
+
+    fn variant(product_id: Uuid, sku: &str, length: &str, price: i64) -> Model {
+        Model {
+            id: Uuid::new_v4(),
+            product_id,
+            sku: sku.to_string(),
+            options: options(&[("Length", length)]),
+            price: Money::xaf(price),
+            quantity: 5,
+            image_urls: vec![],
+            position: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn options(pairs: &[(&str, &str)]) -> VariantOptions {
+        VariantOptions(
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_variant_options_must_match_product_options() {
+        let option = length_option(Uuid::new_v4());
+        let options_ok = options(&[("Length", "6 yards")]);
+        assert!(check_variant_options(std::slice::from_ref(&option), &options_ok).is_ok());
+        for bad in [
+            options(&[]),
+            options(&[("Length", "12 yards")]),
+            options(&[("Length", "6 yards"), ("Color", "Blue")]),
+        ] {
+            assert!(matches!(
+                check_variant_options(std::slice::from_ref(&option), &bad),
+                Err(ServiceError::Validation(_))
+            ));
+        }
+        assert!(check_variant_options(&[], &options_ok).is_err());
+        assert!(clean_options(vec![
+            OptionInput {
+                name: "Size".to_string(),
+                values: vec!["M".to_string()],
+            },
+            OptionInput {
+                name: " size".to_string(),
+                values: vec!["L".to_string()],
+            },
+        ])
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_variant_updates_product_price_and_stock() {
+        let seller_id = Uuid::new_v4();
+        let product = pagne(seller_id);
+        let existing = variant(product.id, "PAGNE-3", "3 yards", 6000);
+        let created = variant(product.id, "PAGNE-6", "6 yards", 11000);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![product.clone()]])
+            .append_query_results([vec![length_option(product.id)]])
+            .append_query_results([vec![existing]])
+            .append_query_results([vec![created.clone()]])
+            .append_query_results([[BTreeMap::from([
+                ("price", Value::from(6000i64)),
+                ("quantity", Value::from(10i64)),
+            ])]])
+            .append_exec_results([MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let service = VariantService::new(Arc::new(db));
+
+        let result = service
+            .create_variant(
+                product.id,
+                seller_id,
+                CreateVariant {
+                    sku: " PAGNE-6 ".to_string(),
+                    options: options(&[("Length", "6 yards")]),
+                    price: Money::xaf(11000),
+                    quantity: 5,
+                    image_urls: vec![],
+                    position: 0,
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result, created);
+        let db = Arc::into_inner(service.db).unwrap();
+        let log = db.into_transaction_log();
+        let statements = log[0].statements();
+        assert!(statements[4].to_string().contains("'PAGNE-6'"));
+        let sync = statements[6].to_string();
+        assert!(sync.starts_with("UPDATE \"products\""), "{}", sync);
+        assert!(sync.contains("\"price\" = 6000"), "{}", sync);
+        assert!(sync.contains("\"quantity\" = 10"), "{}", sync);
+    }
+
+    #[tokio::test]
+    async fn test_create_variant_rejects_duplicate_options() {
+        let seller_id = Uuid::new_v4();
+        let product = pagne(seller_id);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![product.clone()]])
+            .append_query_results([vec![length_option(product.id)]])
+            .append_query_results([vec![variant(product.id, "PAGNE-6", "6 yards", 11000)]])
+            .into_connection();
+        let service = VariantService::new(Arc::new(db));
+
+        let result = service
+            .create_variant(
+                product.id,
+                seller_id,
+                CreateVariant {
+                    sku: "PAGNE-6-BIS".to_string(),
+                    options: options(&[("Length", "6 yards")]),
+                    price: Money::xaf(10000),
+                    quantity: 1,
+                    image_urls: vec![],
+                    position: 0,
+                },
+            )
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_only_the_vendor_changes_variants() {
+        let product = pagne(Uuid::new_v4());
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![product.clone()]])
+            .into_connection();
+        let service = VariantService::new(Arc::new(db));
+
+        let result = service
+            .delete_variant(product.id, Uuid::new_v4(), Uuid::new_v4())
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Forbidden(_))));
+    }
+}
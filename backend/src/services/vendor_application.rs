@@ -0,0 +1,357 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::models::{
+    user::{self, UserRole},
+    vendor_application::{self, Model, VendorApplicationStatus},
+};
+
+use super::errors::ServiceError;
+
+/// Details a vendor submits to be allowed to sell
+#[derive(Debug)]
+pub struct NewVendorApplication {
+    pub business_name: String,
+    pub trade_registry_number: String,
+    pub taxpayer_number: String,
+    /// Object name of the uploaded ID document
+    pub id_document: String,
+}
+
+/// Vendor onboarding.
+///
+/// Vendors apply with their business details and an ID document. Once an
+/// admin approves the application, `users.vendor_verified_at` is set and the
+/// vendor can list products.
+pub struct VendorApplicationService {
+    db: Arc<DatabaseConnection>,
+}
+
+/// Trim `value`, failing when nothing is left
+fn required(value: String, field: &str) -> Result<String, ServiceError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(ServiceError::Validation(format!("{} is required", field)));
+    }
+    Ok(value.to_string())
+}
+
+impl VendorApplicationService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// Submit an application for review. Vendors with a pending or approved
+    /// application cannot submit another.
+    pub async fn submit(
+        &self,
+        user_id: Uuid,
+        application: NewVendorApplication,
+    ) -> Result<Model, ServiceError> {
+        let business_name = required(application.business_name, "Business name")?;
+        let trade_registry_number =
+            required(application.trade_registry_number, "Trade registry number")?;
+        let taxpayer_number = required(application.taxpayer_number, "Taxpayer number")?;
+
+        let user = user::Entity::find_by_id(user_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::UserNotFound("User not found".to_string()))?;
+        if user.role != UserRole::Vendor {
+            return Err(ServiceError::Forbidden(
+                "Only vendor accounts can apply to sell".to_string(),
+            ));
+        }
+        if user.vendor_verified_at.is_some() {
+            return Err(ServiceError::Validation(
+                "Vendor is already verified".to_string(),
+            ));
+        }
+        if let Some(latest) = self.latest_application(user_id).await? {
+            if latest.status == VendorApplicationStatus::Pending {
+                return Err(ServiceError::Validation(
+                    "An application is already waiting for review".to_string(),
+                ));
+            }
+        }
+
+        let now = Utc::now();
+        let application = vendor_application::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(user_id),
+            business_name: Set(business_name),
+            trade_registry_number: Set(trade_registry_number),
+            taxpayer_number: Set(taxpayer_number),
+            id_document: Set(application.id_document),
+            status: Set(VendorApplicationStatus::Pending),
+            rejection_reason: Set(None),
+            reviewed_by: Set(None),
+            reviewed_at: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        Ok(application)
+    }
+
+    /// The vendor's most recent application, if they applied
+    pub async fn latest_application(&self, user_id: Uuid) -> Result<Option<Model>, ServiceError> {
+        let application = vendor_application::Entity::find()
+            .filter(vendor_application::Column::UserId.eq(user_id))
+            .order_by_desc(vendor_application::Column::CreatedAt)
+            .one(self.db.as_ref())
+            .await?;
+
+        Ok(application)
+    }
+
+    pub async fn get_application(&self, application_id: Uuid) -> Result<Model, ServiceError> {
+        vendor_application::Entity::find_by_id(application_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Vendor application not found".to_string()))
+    }
+
+    /// Applications, newest first, optionally only those in `status`
+    pub async fn list_applications(
+        &self,
+        status: Option<VendorApplicationStatus>,
+    ) -> Result<Vec<Model>, ServiceError> {
+        let mut query = vendor_application::Entity::find();
+        if let Some(status) = status {
+            query = query.filter(vendor_application::Column::Status.eq(status));
+        }
+        let applications = query
+            .order_by_desc(vendor_application::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+
+        Ok(applications)
+    }
+
+    /// Approve or reject a pending application. Approving verifies the
+    /// vendor; rejecting needs a reason the vendor can act on.
+    pub async fn review_application(
+        &self,
+        application_id: Uuid,
+        reviewer_id: Uuid,
+        approve: bool,
+        reason: Option<String>,
+    ) -> Result<Model, ServiceError> {
+        let reason = reason
+            .map(|reason| reason.trim().to_string())
+            .filter(|reason| !reason.is_empty());
+        if !approve && reason.is_none() {
+            return Err(ServiceError::Validation(
+                "A reason is required to reject an application".to_string(),
+            ));
+        }
+
+        let txn = self.db.begin().await?;
+
+        let application = vendor_application::Entity::find_by_id(application_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Vendor application not found".to_string()))?;
+        if application.status != VendorApplicationStatus::Pending {
+            return Err(ServiceError::Validation(format!(
+                "Vendor application is already {}",
+                application.status
+            )));
+        }
+
+        let now = Utc::now();
+        let status = if approve {
+            user::Entity::update_many()
+                .col_expr(user::Column::VendorVerifiedAt, Expr::value(now))
+                .filter(user::Column::Id.eq(application.user_id))
+                .exec(&txn)
+                .await?;
+            VendorApplicationStatus::Approved
+        } else {
+            VendorApplicationStatus::Rejected
+        };
+
+        let mut active_model: vendor_application::ActiveModel = application.into();
+        active_model.status = Set(status);
+        active_model.rejection_reason = Set(if approve { None } else { reason });
+        active_model.reviewed_by = Set(Some(reviewer_id));
+        active_model.reviewed_at = Set(Some(now));
+        active_model.updated_at = Set(now);
+        let application = active_model.update(&txn).await?;
+
+        txn.commit().await?;
+
+        Ok(application)
+    }
+
+    /// Fail unless `user_id` is a vendor an admin has verified
+    pub async fn ensure_verified_vendor(&self, user_id: Uuid) -> Result<(), ServiceError> {
+        let user = user::Entity::find_by_id(user_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::UserNotFound("User not found".to_string()))?;
+        if user.role != UserRole::Vendor || user.vendor_verified_at.is_none() {
+            return Err(ServiceError::Forbidden(
+                "Only verified vendors can list products".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::Language;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn vendor(vendor_verified_at: Option<chrono::DateTime<Utc>>) -> user::Model {
+        user::Model {
+            id: Uuid::new_v4(),
+            full_name: "Test Vendor".to_string(),
+            email: None,
+            phone: "677777777".parse().unwrap(),
+            phone_verified_at: Some(Utc::now()),
+            failed_login_attempts: 0,
+            locked_until: None,
+            avatar_url: None,
+            preferred_language: Language::Fr,
+            pending_email: None,
+            pending_phone: None,
+            vendor_verified_at,
+            password_hash: "hashed_password".to_string(),
+            is_active: true,
+            role: UserRole::Vendor,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn application(user_id: Uuid, status: VendorApplicationStatus) -> Model {
+        Model {
+            id: Uuid::new_v4(),
+            user_id,
+            business_name: "Mboa Crafts".to_string(),
+            trade_registry_number: "RC/DLA/2024/B/1234".to_string(),
+            taxpayer_number: "M012345678901A".to_string(),
+            id_document: "id.jpg".to_string(),
+            status,
+            rejection_reason: None,
+            reviewed_by: None,
+            reviewed_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn new_application() -> NewVendorApplication {
+        NewVendorApplication {
+            business_name: " Mboa Crafts ".to_string(),
+            trade_registry_number: "RC/DLA/2024/B/1234".to_string(),
+            taxpayer_number: "M012345678901A".to_string(),
+            id_document: "id.jpg".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_submit_application() {
+        let user = vendor(None);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user.clone()]])
+            .append_query_results(vec![vec![application(
+                user.id,
+                VendorApplicationStatus::Rejected,
+            )]])
+            .append_query_results(vec![vec![application(
+                user.id,
+                VendorApplicationStatus::Pending,
+            )]])
+            .into_connection();
+        let service = VendorApplicationService::new(Arc::new(db));
+
+        let submitted = service.submit(user.id, new_application()).await.unwrap();
+        assert_eq!(submitted.status, VendorApplicationStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_submit_rejects_second_pending_application() {
+        let user = vendor(None);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![user.clone()]])
+            .append_query_results(vec![vec![application(
+                user.id,
+                VendorApplicationStatus::Pending,
+            )]])
+            .into_connection();
+        let service = VendorApplicationService::new(Arc::new(db));
+
+        let result = service.submit(user.id, new_application()).await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_approve_verifies_vendor() {
+        let pending = application(Uuid::new_v4(), VendorApplicationStatus::Pending);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![pending.clone()]])
+            .append_query_results(vec![vec![Model {
+                status: VendorApplicationStatus::Approved,
+                ..pending.clone()
+            }]])
+            .append_exec_results(vec![MockExecResult {
+                last_insert_id: 0,
+                rows_affected: 1,
+            }])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = VendorApplicationService::new(db.clone());
+
+        let reviewed = service
+            .review_application(pending.id, Uuid::new_v4(), true, None)
+            .await
+            .unwrap();
+        assert_eq!(reviewed.status, VendorApplicationStatus::Approved);
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let statements: Vec<String> = log[0].statements().iter().map(|s| s.to_string()).collect();
+        assert!(statements
+            .iter()
+            .any(|sql| sql.starts_with("UPDATE \"users\" SET \"vendor_verified_at\"")));
+    }
+
+    #[tokio::test]
+    async fn test_reject_requires_reason() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+        let service = VendorApplicationService::new(Arc::new(db));
+
+        let result = service
+            .review_application(Uuid::new_v4(), Uuid::new_v4(), false, Some(" ".to_string()))
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_unverified_vendor_cannot_list_products() {
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results(vec![vec![vendor(None)]])
+            .append_query_results(vec![vec![vendor(Some(Utc::now()))]])
+            .into_connection();
+        let service = VendorApplicationService::new(Arc::new(db));
+
+        let result = service.ensure_verified_vendor(Uuid::new_v4()).await;
+        assert!(matches!(result, Err(ServiceError::Forbidden(_))));
+        assert!(service.ensure_verified_vendor(Uuid::new_v4()).await.is_ok());
+    }
+}
@@ -0,0 +1,984 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    sync::Arc,
+};
+
+use chrono::Utc;
+use rand::RngCore;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, JoinType,
+    QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::models::{
+    cart_item::{self, CartItem},
+    money::Money,
+    product::{self, ModerationStatus},
+    product_variant, user,
+    wishlist::{self, Model},
+    wishlist_item,
+};
+
+use super::{
+    cart::{add_item, CartService},
+    errors::ServiceError,
+    sms::{Sms, SmsSender},
+};
+
+/// Wishlist items whose product, or variant, no longer has the price or the
+/// stock their user last heard of. In stock means approved with units left,
+/// as in [`current_offer`].
+const OFFER_CHANGED: &str = "(COALESCE(product_variants.price, products.price) \
+    <> wishlist_items.notified_price \
+    OR (products.moderation_status = 'approved' \
+    AND COALESCE(product_variants.quantity, products.quantity) > 0) \
+    <> wishlist_items.notified_in_stock)";
+
+/// Longest wishlist name, in characters
+const MAX_NAME_LENGTH: usize = 100;
+/// The list cart items are saved for later to, created on first use
+pub const SAVED_FOR_LATER: &str = "Saved for later";
+
+/// A product, or one of its variants, to put on a wishlist
+#[derive(Deserialize, Debug)]
+pub struct NewWishlistItem {
+    pub product_id: Uuid,
+    /// For products that have variants, the variant wanted
+    #[serde(default)]
+    pub variant_id: Option<Uuid>,
+    /// Quantity to put in the cart later, 1 when left out
+    #[serde(default = "one")]
+    pub quantity: i32,
+}
+
+fn one() -> i32 {
+    1
+}
+
+/// An item on a wishlist with the product and variant it refers to
+#[derive(Debug, Serialize)]
+pub struct WishlistEntry {
+    #[serde(flatten)]
+    pub item: wishlist_item::Model,
+    pub product: product::Model,
+    pub variant: Option<product_variant::Model>,
+}
+
+/// A wishlist with its items, the most recently added first
+#[derive(Debug, Serialize)]
+pub struct WishlistWithItems {
+    #[serde(flatten)]
+    pub wishlist: Model,
+    pub items: Vec<WishlistEntry>,
+}
+
+/// Outcome of one pass over wishlist items looking for price drops and
+/// restocks
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct AlertReport {
+    pub checked: usize,
+    pub price_drops: usize,
+    pub restocks: usize,
+    pub notified: usize,
+}
+
+impl fmt::Display for AlertReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "checked {}, price drops {}, restocks {}, users notified {}",
+            self.checked, self.price_drops, self.restocks, self.notified
+        )
+    }
+}
+
+/// Trimmed list name, or a validation error when it is blank or too long
+fn list_name(name: &str) -> Result<String, ServiceError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ServiceError::Validation(
+            "Wishlist name is required".to_string(),
+        ));
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(ServiceError::Validation(format!(
+            "Wishlist name must be at most {} characters",
+            MAX_NAME_LENGTH
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// Generate a random token for a share link
+fn new_share_token() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A change to text about a wishlist item, with the offer to record once
+/// the text went out
+struct Alert {
+    line: String,
+    item_id: Uuid,
+    price: Money,
+    in_stock: bool,
+}
+
+/// Price of an item and whether it can be bought now, from its variant when
+/// it names one and from the product otherwise
+fn current_offer(
+    product: &product::Model,
+    variant: Option<&product_variant::Model>,
+) -> (Money, bool) {
+    let (price, quantity) = match variant {
+        Some(variant) => (variant.price, variant.quantity),
+        None => (product.price, product.quantity),
+    };
+    let for_sale = product.moderation_status == ModerationStatus::Approved && quantity > 0;
+    (price, for_sale)
+}
+
+/// Product title, followed by the variant's option values if any
+fn item_label(product: &product::Model, variant: Option<&product_variant::Model>) -> String {
+    match variant {
+        Some(variant) if !variant.options.0.is_empty() => {
+            let values: Vec<&str> = variant.options.0.values().map(String::as_str).collect();
+            format!("{} ({})", product.title, values.join(", "))
+        }
+        _ => product.title.clone(),
+    }
+}
+
+/// Named lists of products users want, which they may share by link, move
+/// to their cart and hear about when prices drop or stock comes back
+pub struct WishlistService {
+    db: Arc<DatabaseConnection>,
+    cart_service: Arc<CartService>,
+    sms: Arc<dyn SmsSender>,
+}
+
+impl WishlistService {
+    pub fn new(
+        db: Arc<DatabaseConnection>,
+        cart_service: Arc<CartService>,
+        sms: Arc<dyn SmsSender>,
+    ) -> Self {
+        Self {
+            db,
+            cart_service,
+            sms,
+        }
+    }
+
+    /// One of the user's lists; other users' lists are not found
+    async fn owned_wishlist(
+        &self,
+        wishlist_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Model, ServiceError> {
+        wishlist::Entity::find_by_id(wishlist_id)
+            .filter(wishlist::Column::UserId.eq(user_id))
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Wishlist not found".to_string()))
+    }
+
+    async fn find_by_name(&self, user_id: Uuid, name: &str) -> Result<Option<Model>, ServiceError> {
+        Ok(wishlist::Entity::find()
+            .filter(wishlist::Column::UserId.eq(user_id))
+            .filter(wishlist::Column::Name.eq(name))
+            .one(self.db.as_ref())
+            .await?)
+    }
+
+    async fn insert_wishlist(&self, user_id: Uuid, name: String) -> Result<Model, ServiceError> {
+        let now = Utc::now();
+        let wishlist = wishlist::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(user_id),
+            name: Set(name),
+            share_token: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        Ok(wishlist)
+    }
+
+    /// The user's lists, oldest first
+    pub async fn list_wishlists(&self, user_id: Uuid) -> Result<Vec<Model>, ServiceError> {
+        Ok(wishlist::Entity::find()
+            .filter(wishlist::Column::UserId.eq(user_id))
+            .order_by_asc(wishlist::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?)
+    }
+
+    pub async fn create_wishlist(&self, user_id: Uuid, name: &str) -> Result<Model, ServiceError> {
+        let name = list_name(name)?;
+        if self.find_by_name(user_id, &name).await?.is_some() {
+            return Err(ServiceError::Validation(format!(
+                "You already have a wishlist named \"{}\"",
+                name
+            )));
+        }
+        self.insert_wishlist(user_id, name).await
+    }
+
+    pub async fn rename_wishlist(
+        &self,
+        wishlist_id: Uuid,
+        user_id: Uuid,
+        name: &str,
+    ) -> Result<Model, ServiceError> {
+        let name = list_name(name)?;
+        let wishlist = self.owned_wishlist(wishlist_id, user_id).await?;
+        if let Some(other) = self.find_by_name(user_id, &name).await? {
+            if other.id != wishlist.id {
+                return Err(ServiceError::Validation(format!(
+                    "You already have a wishlist named \"{}\"",
+                    name
+                )));
+            }
+        }
+
+        let mut active_model: wishlist::ActiveModel = wishlist.into();
+        active_model.name = Set(name);
+        active_model.updated_at = Set(Utc::now());
+        Ok(active_model.update(self.db.as_ref()).await?)
+    }
+
+    /// Delete one of the user's lists with its items
+    pub async fn delete_wishlist(
+        &self,
+        wishlist_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), ServiceError> {
+        let wishlist = self.owned_wishlist(wishlist_id, user_id).await?;
+        wishlist::Entity::delete_by_id(wishlist.id)
+            .exec(self.db.as_ref())
+            .await?;
+
+        Ok(())
+    }
+
+    /// Give a list a share link, keeping the one it already has
+    pub async fn share(&self, wishlist_id: Uuid, user_id: Uuid) -> Result<Model, ServiceError> {
+        let wishlist = self.owned_wishlist(wishlist_id, user_id).await?;
+        if wishlist.share_token.is_some() {
+            return Ok(wishlist);
+        }
+
+        let mut active_model: wishlist::ActiveModel = wishlist.into();
+        active_model.share_token = Set(Some(new_share_token()));
+        active_model.updated_at = Set(Utc::now());
+        Ok(active_model.update(self.db.as_ref()).await?)
+    }
+
+    /// Make a list private again; its old link stops working
+    pub async fn unshare(&self, wishlist_id: Uuid, user_id: Uuid) -> Result<Model, ServiceError> {
+        let wishlist = self.owned_wishlist(wishlist_id, user_id).await?;
+        if wishlist.share_token.is_none() {
+            return Ok(wishlist);
+        }
+
+        let mut active_model: wishlist::ActiveModel = wishlist.into();
+        active_model.share_token = Set(None);
+        active_model.updated_at = Set(Utc::now());
+        Ok(active_model.update(self.db.as_ref()).await?)
+    }
+
+    pub async fn get_wishlist(
+        &self,
+        wishlist_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<WishlistWithItems, ServiceError> {
+        let wishlist = self.owned_wishlist(wishlist_id, user_id).await?;
+        self.with_items(wishlist).await
+    }
+
+    /// A list shared by link, for anyone holding its token
+    pub async fn get_shared(&self, share_token: &str) -> Result<WishlistWithItems, ServiceError> {
+        let wishlist = wishlist::Entity::find()
+            .filter(wishlist::Column::ShareToken.eq(share_token))
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Wishlist not found".to_string()))?;
+        self.with_items(wishlist).await
+    }
+
+    async fn variants_by_id(
+        &self,
+        variant_ids: Vec<Uuid>,
+    ) -> Result<HashMap<Uuid, product_variant::Model>, ServiceError> {
+        if variant_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let variants = product_variant::Entity::find()
+            .filter(product_variant::Column::Id.is_in(variant_ids))
+            .all(self.db.as_ref())
+            .await?;
+        Ok(variants.into_iter().map(|v| (v.id, v)).collect())
+    }
+
+    async fn with_items(&self, wishlist: Model) -> Result<WishlistWithItems, ServiceError> {
+        let rows = wishlist_item::Entity::find()
+            .filter(wishlist_item::Column::WishlistId.eq(wishlist.id))
+            .find_also_related(product::Entity)
+            .order_by_desc(wishlist_item::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+        let mut variants = self
+            .variants_by_id(
+                rows.iter()
+                    .filter_map(|(item, _)| item.variant_id)
+                    .collect(),
+            )
+            .await?;
+        let items = rows
+            .into_iter()
+            .filter_map(|(item, product)| {
+                let variant = item.variant_id.and_then(|id| variants.remove(&id));
+                Some(WishlistEntry {
+                    item,
+                    product: product?,
+                    variant,
+                })
+            })
+            .collect();
+
+        Ok(WishlistWithItems { wishlist, items })
+    }
+
+    /// Put a product on a list, with its current price and stock as what the
+    /// owner last heard of. An item already on the list gets the new
+    /// quantity.
+    async fn insert_item(
+        &self,
+        wishlist: &Model,
+        data: NewWishlistItem,
+    ) -> Result<wishlist_item::Model, ServiceError> {
+        if data.quantity <= 0 {
+            return Err(ServiceError::Validation(
+                "Quantity must be at least 1".to_string(),
+            ));
+        }
+        let product = product::Entity::find_by_id(data.product_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Product not found".to_string()))?;
+        let variant = match data.variant_id {
+            Some(variant_id) => Some(
+                product_variant::Entity::find_by_id(variant_id)
+                    .filter(product_variant::Column::ProductId.eq(product.id))
+                    .one(self.db.as_ref())
+                    .await?
+                    .ok_or_else(|| {
+                        ServiceError::Validation("Variant not found for this product".to_string())
+                    })?,
+            ),
+            None => None,
+        };
+
+        let existing = wishlist_item::Entity::find()
+            .filter(wishlist_item::Column::WishlistId.eq(wishlist.id))
+            .filter(wishlist_item::Column::ProductId.eq(product.id))
+            .filter(match data.variant_id {
+                Some(variant_id) => wishlist_item::Column::VariantId.eq(variant_id),
+                None => wishlist_item::Column::VariantId.is_null(),
+            })
+            .one(self.db.as_ref())
+            .await?;
+        if let Some(item) = existing {
+            if item.quantity == data.quantity {
+                return Ok(item);
+            }
+            let mut active_model: wishlist_item::ActiveModel = item.into();
+            active_model.quantity = Set(data.quantity);
+            return Ok(active_model.update(self.db.as_ref()).await?);
+        }
+
+        let (price, in_stock) = current_offer(&product, variant.as_ref());
+        let item = wishlist_item::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            wishlist_id: Set(wishlist.id),
+            product_id: Set(product.id),
+            variant_id: Set(data.variant_id),
+            quantity: Set(data.quantity),
+            notified_price: Set(price),
+            notified_in_stock: Set(in_stock),
+            created_at: Set(Utc::now()),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        Ok(item)
+    }
+
+    pub async fn add_item(
+        &self,
+        wishlist_id: Uuid,
+        user_id: Uuid,
+        data: NewWishlistItem,
+    ) -> Result<wishlist_item::Model, ServiceError> {
+        let wishlist = self.owned_wishlist(wishlist_id, user_id).await?;
+        self.insert_item(&wishlist, data).await
+    }
+
+    pub async fn remove_item(
+        &self,
+        wishlist_id: Uuid,
+        item_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), ServiceError> {
+        let wishlist = self.owned_wishlist(wishlist_id, user_id).await?;
+        let result = wishlist_item::Entity::delete_many()
+            .filter(wishlist_item::Column::WishlistId.eq(wishlist.id))
+            .filter(wishlist_item::Column::Id.eq(item_id))
+            .exec(self.db.as_ref())
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(ServiceError::NotFound("Item not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Move an item from one of the user's lists to their cart, adding to
+    /// the quantity of the same cart line if there is one
+    pub async fn move_to_cart(
+        &self,
+        wishlist_id: Uuid,
+        item_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<cart_item::Model, ServiceError> {
+        let wishlist = self.owned_wishlist(wishlist_id, user_id).await?;
+        let item = wishlist_item::Entity::find_by_id(item_id)
+            .filter(wishlist_item::Column::WishlistId.eq(wishlist.id))
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Item not found".to_string()))?;
+
+        let cart = self
+            .cart_service
+            .get_or_create_cart(user_id.to_string())
+            .await?;
+        // Taking the item off the list first lets only one of two
+        // concurrent moves add it to the cart
+        let txn = self.db.begin().await?;
+        let removed = wishlist_item::Entity::delete_many()
+            .filter(wishlist_item::Column::Id.eq(item.id))
+            .filter(wishlist_item::Column::WishlistId.eq(wishlist.id))
+            .exec(&txn)
+            .await?;
+        if removed.rows_affected == 0 {
+            return Err(ServiceError::NotFound("Item not found".to_string()));
+        }
+        let line = add_item(
+            &txn,
+            cart.id,
+            CartItem {
+                cart_id: cart.id,
+                product_id: item.product_id,
+                variant_id: item.variant_id,
+                quantity: item.quantity,
+            },
+        )
+        .await?;
+        txn.commit().await?;
+
+        Ok(line)
+    }
+
+    /// Take a line out of the user's cart and keep it on their
+    /// [`SAVED_FOR_LATER`] list
+    pub async fn save_for_later(
+        &self,
+        user_id: Uuid,
+        cart_item_id: Uuid,
+    ) -> Result<wishlist_item::Model, ServiceError> {
+        let cart = self
+            .cart_service
+            .get_cart(user_id.to_string())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Cart not found".to_string()))?;
+        let line = cart_item::Entity::find_by_id(cart_item_id)
+            .filter(cart_item::Column::CartId.eq(cart.id))
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| ServiceError::NotFound("Item not found".to_string()))?;
+
+        let wishlist = match self.find_by_name(user_id, SAVED_FOR_LATER).await? {
+            Some(wishlist) => wishlist,
+            None => {
+                self.insert_wishlist(user_id, SAVED_FOR_LATER.to_string())
+                    .await?
+            }
+        };
+        let item = self
+            .insert_item(
+                &wishlist,
+                NewWishlistItem {
+                    product_id: line.product_id,
+                    variant_id: line.variant_id,
+                    quantity: line.quantity,
+                },
+            )
+            .await?;
+        self.cart_service
+            .remove_item_from_cart(cart.id, line.id)
+            .await?;
+
+        Ok(item)
+    }
+
+    /// Text users whose wishlisted items dropped in price or came back in
+    /// stock since they last heard of them, one message per user. Items are
+    /// brought up to date once their user was texted, so each change is
+    /// announced once and a failed text is retried on the next pass; a price
+    /// drop while out of stock waits for the restock.
+    pub async fn send_alerts(&self) -> Result<AlertReport, ServiceError> {
+        let rows = wishlist_item::Entity::find()
+            .find_also_related(product::Entity)
+            .join(JoinType::LeftJoin, wishlist_item::Relation::Variant.def())
+            .filter(Expr::cust(OFFER_CHANGED))
+            .all(self.db.as_ref())
+            .await?;
+        let variants = self
+            .variants_by_id(
+                rows.iter()
+                    .filter_map(|(item, _)| item.variant_id)
+                    .collect(),
+            )
+            .await?;
+
+        let mut report = AlertReport::default();
+        let mut news: BTreeMap<Uuid, Vec<Alert>> = BTreeMap::new();
+        for (item, product) in rows {
+            let Some(product) = product else {
+                continue;
+            };
+            let variant = item.variant_id.and_then(|id| variants.get(&id));
+            if item.variant_id.is_some() && variant.is_none() {
+                continue;
+            }
+            report.checked += 1;
+
+            let (price, in_stock) = current_offer(&product, variant);
+            if price == item.notified_price && in_stock == item.notified_in_stock {
+                continue;
+            }
+            let label = item_label(&product, variant);
+            let line = if in_stock && !item.notified_in_stock {
+                report.restocks += 1;
+                format!("{} is back in stock at {}", label, price)
+            } else if in_stock && price < item.notified_price {
+                report.price_drops += 1;
+                format!("{} is now {} (was {})", label, price, item.notified_price)
+            } else {
+                // Nothing to announce, only keep up with the change
+                self.mark_notified(item.id, price, in_stock).await?;
+                continue;
+            };
+            news.entry(item.wishlist_id).or_default().push(Alert {
+                line,
+                item_id: item.id,
+                price,
+                in_stock,
+            });
+        }
+        if news.is_empty() {
+            return Ok(report);
+        }
+
+        let owners: HashMap<Uuid, Uuid> = wishlist::Entity::find()
+            .filter(wishlist::Column::Id.is_in(news.keys().copied()))
+            .all(self.db.as_ref())
+            .await?
+            .into_iter()
+            .map(|wishlist| (wishlist.id, wishlist.user_id))
+            .collect();
+        let mut news_by_user: BTreeMap<Uuid, Vec<Alert>> = BTreeMap::new();
+        for (wishlist_id, alerts) in news {
+            if let Some(user_id) = owners.get(&wishlist_id) {
+                news_by_user.entry(*user_id).or_default().extend(alerts);
+            }
+        }
+        let users = user::Entity::find()
+            .filter(user::Column::Id.is_in(news_by_user.keys().copied()))
+            .all(self.db.as_ref())
+            .await?;
+        for user in users {
+            let Some(alerts) = news_by_user.get(&user.id) else {
+                continue;
+            };
+            let lines: BTreeSet<&str> = alerts.iter().map(|alert| alert.line.as_str()).collect();
+            let sms = Sms {
+                to: user.phone,
+                body: format!(
+                    "From your Cameroon Made Market wishlist: {}.",
+                    lines.into_iter().collect::<Vec<_>>().join("; ")
+                ),
+            };
+            if let Err(e) = self.sms.send(&sms).await {
+                error!("could not send wishlist alert to user {}: {}", user.id, e);
+                continue;
+            }
+            report.notified += 1;
+            for alert in alerts {
+                self.mark_notified(alert.item_id, alert.price, alert.in_stock)
+                    .await?;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Record the offer the user was last told about for a wishlist item
+    async fn mark_notified(
+        &self,
+        item_id: Uuid,
+        price: Money,
+        in_stock: bool,
+    ) -> Result<(), ServiceError> {
+        wishlist_item::Entity::update_many()
+            .col_expr(wishlist_item::Column::NotifiedPrice, Expr::value(price))
+            .col_expr(
+                wishlist_item::Column::NotifiedInStock,
+                Expr::value(in_stock),
+            )
+            .filter(wishlist_item::Column::Id.eq(item_id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{cart, user::UserRole};
+    use crate::services::sms::RecordingSmsSender;
+    use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+
+    fn service(db: Arc<DatabaseConnection>, sms: Arc<RecordingSmsSender>) -> WishlistService {
+        WishlistService::new(db.clone(), Arc::new(CartService::new(db)), sms)
+    }
+
+    fn wishlist(user_id: Uuid, name: &str) -> Model {
+        Model {
+            id: Uuid::new_v4(),
+            user_id,
+            name: name.to_string(),
+            share_token: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn product(price: i64, quantity: i32) -> product::Model {
+        product::Model {
+            id: Uuid::new_v4(),
+            seller_id: Uuid::new_v4(),
+            title: "Penja pepper".to_string(),
+            description: None,
+            price: Money::xaf(price),
+            category_id: None,
+            quantity,
+            image_urls: vec![],
+            moderation_status: ModerationStatus::Approved,
+            rejection_reason: None,
+            moderator_note: None,
+            return_policy: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn item(
+        wishlist_id: Uuid,
+        product_id: Uuid,
+        price: i64,
+        in_stock: bool,
+    ) -> wishlist_item::Model {
+        wishlist_item::Model {
+            id: Uuid::new_v4(),
+            wishlist_id,
+            product_id,
+            variant_id: None,
+            quantity: 2,
+            notified_price: Money::xaf(price),
+            notified_in_stock: in_stock,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn buyer(id: Uuid) -> user::Model {
+        user::Model {
+            id,
+            email: None,
+            password_hash: String::new(),
+            role: UserRole::Buyer,
+            full_name: "Test Buyer".to_string(),
+            is_active: true,
+            phone: "677777777".parse().unwrap(),
+            phone_verified_at: Some(Utc::now()),
+            failed_login_attempts: 0,
+            locked_until: None,
+            pending_phone: None,
+            vendor_verified_at: None,
+            pending_email: None,
+            avatar_url: None,
+            preferred_language: user::Language::Fr,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn exec(rows_affected: u64) -> MockExecResult {
+        MockExecResult {
+            last_insert_id: 0,
+            rows_affected,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wishlist_names_are_unique_per_user() {
+        let user_id = Uuid::new_v4();
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![wishlist(user_id, "Birthday")]])
+            .into_connection();
+        let service = service(Arc::new(db), Arc::new(RecordingSmsSender::default()));
+
+        let result = service.create_wishlist(user_id, " Birthday ").await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        let result = service.create_wishlist(user_id, "   ").await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn test_move_item_to_cart() {
+        let user_id = Uuid::new_v4();
+        let list = wishlist(user_id, "Birthday");
+        let wanted = item(list.id, Uuid::new_v4(), 2500, true);
+        let cart = cart::Model {
+            id: Uuid::new_v4(),
+            user_id,
+            created_at: Utc::now(),
+        };
+        let line = cart_item::Model {
+            id: Uuid::new_v4(),
+            cart_id: cart.id,
+            product_id: wanted.product_id,
+            variant_id: None,
+            quantity: 2,
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![list.clone()]])
+            .append_query_results([vec![wanted.clone()]])
+            .append_query_results([vec![cart.clone()]])
+            .append_query_results::<cart_item::Model, _, _>([vec![], vec![line.clone()]])
+            .append_exec_results([exec(1)])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = service(db.clone(), Arc::new(RecordingSmsSender::default()));
+
+        let result = service
+            .move_to_cart(list.id, wanted.id, user_id)
+            .await
+            .unwrap();
+        assert_eq!(result, line);
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        // The delete and the insert commit together
+        let statements = log[3].statements();
+        assert_eq!(statements[0].to_string(), "BEGIN");
+        let delete_sql = statements[1].to_string();
+        assert!(
+            delete_sql.starts_with("DELETE FROM \"wishlist_items\""),
+            "{}",
+            delete_sql
+        );
+        assert!(delete_sql.contains(&wanted.id.to_string()));
+        let insert_sql = statements[3].to_string();
+        assert!(
+            insert_sql.starts_with("INSERT INTO \"cart_items\""),
+            "{}",
+            insert_sql
+        );
+        assert_eq!(statements[4].to_string(), "COMMIT");
+    }
+
+    #[tokio::test]
+    async fn test_save_for_later_creates_the_list() {
+        let user_id = Uuid::new_v4();
+        let product = product(2500, 4);
+        let cart = cart::Model {
+            id: Uuid::new_v4(),
+            user_id,
+            created_at: Utc::now(),
+        };
+        let line = cart_item::Model {
+            id: Uuid::new_v4(),
+            cart_id: cart.id,
+            product_id: product.id,
+            variant_id: None,
+            quantity: 3,
+        };
+        let list = wishlist(user_id, SAVED_FOR_LATER);
+        let saved = wishlist_item::Model {
+            quantity: 3,
+            ..item(list.id, product.id, 2500, true)
+        };
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![cart.clone()]])
+            .append_query_results([vec![line.clone()]])
+            .append_query_results::<Model, _, _>([vec![], vec![list.clone()]])
+            .append_query_results([vec![product.clone()]])
+            .append_query_results::<wishlist_item::Model, _, _>([vec![], vec![saved.clone()]])
+            .append_exec_results([exec(1)])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = service(db.clone(), Arc::new(RecordingSmsSender::default()));
+
+        let result = service.save_for_later(user_id, line.id).await.unwrap();
+        assert_eq!(result, saved);
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let list_sql = log[3].statements()[0].to_string();
+        assert!(list_sql.contains("'Saved for later'"), "{}", list_sql);
+        let item_sql = log[6].statements()[0].to_string();
+        assert!(
+            item_sql.starts_with("INSERT INTO \"wishlist_items\""),
+            "{}",
+            item_sql
+        );
+        assert!(item_sql.contains("2500"), "{}", item_sql);
+        let remove_sql = log[7].statements()[0].to_string();
+        assert!(
+            remove_sql.starts_with("DELETE FROM \"cart_items\""),
+            "{}",
+            remove_sql
+        );
+    }
+
+    #[tokio::test]
+    async fn test_price_drops_and_restocks_are_texted_once() {
+        let user_id = Uuid::new_v4();
+        let list = wishlist(user_id, "Birthday");
+        let cheaper = product(2000, 5);
+        let restocked = product::Model {
+            title: "Ndop cloth".to_string(),
+            ..product(15000, 2)
+        };
+        let unchanged = product(3000, 1);
+        let sold_out = product(1000, 0);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![
+                (item(list.id, cheaper.id, 2500, true), cheaper.clone()),
+                (item(list.id, restocked.id, 15000, false), restocked.clone()),
+                (item(list.id, unchanged.id, 3000, true), unchanged.clone()),
+                (item(list.id, sold_out.id, 1500, false), sold_out.clone()),
+            ]])
+            .append_exec_results([exec(1), exec(1), exec(1)])
+            .append_query_results([vec![list.clone()]])
+            .append_query_results([vec![buyer(user_id)]])
+            .into_connection();
+        let db = Arc::new(db);
+        let sms = Arc::new(RecordingSmsSender::default());
+        let service = service(db.clone(), sms.clone());
+
+        let report = service.send_alerts().await.unwrap();
+        assert_eq!(
+            report,
+            AlertReport {
+                checked: 4,
+                price_drops: 1,
+                restocks: 1,
+                notified: 1,
+            }
+        );
+
+        let sent = sms.sent();
+        assert_eq!(sent.len(), 1);
+        assert!(
+            sent[0]
+                .body
+                .contains("Ndop cloth is back in stock at 15000 FCFA"),
+            "{}",
+            sent[0].body
+        );
+        assert!(
+            sent[0]
+                .body
+                .contains("Penja pepper is now 2000 FCFA (was 2500 FCFA)"),
+            "{}",
+            sent[0].body
+        );
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        let items_sql = log[0].statements()[0].to_string();
+        assert!(
+            items_sql.contains("<> wishlist_items.notified_price"),
+            "{}",
+            items_sql
+        );
+        // The sold out item is brought up to date silently, the others
+        // once the text went out
+        assert!(log[1].statements()[0].to_string().starts_with("UPDATE"));
+        assert!(log[3].statements()[0].to_string().contains("\"users\""));
+        assert!(log[4].statements()[0].to_string().starts_with("UPDATE"));
+        assert!(log[5].statements()[0].to_string().starts_with("UPDATE"));
+    }
+
+    #[derive(Debug)]
+    struct FailingSmsSender;
+
+    #[async_trait::async_trait]
+    impl SmsSender for FailingSmsSender {
+        async fn send(&self, _sms: &Sms) -> Result<(), ServiceError> {
+            Err(ServiceError::GenericError("SMS gateway down".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_alert_is_kept_when_text_fails() {
+        let user_id = Uuid::new_v4();
+        let list = wishlist(user_id, "Birthday");
+        let cheaper = product(2000, 5);
+        let db = MockDatabase::new(DatabaseBackend::Postgres)
+            .append_query_results([vec![(
+                item(list.id, cheaper.id, 2500, true),
+                cheaper.clone(),
+            )]])
+            .append_query_results([vec![list.clone()]])
+            .append_query_results([vec![buyer(user_id)]])
+            .into_connection();
+        let db = Arc::new(db);
+        let service = WishlistService::new(
+            db.clone(),
+            Arc::new(CartService::new(db.clone())),
+            Arc::new(FailingSmsSender),
+        );
+
+        let report = service.send_alerts().await.unwrap();
+        assert_eq!(report.price_drops, 1);
+        assert_eq!(report.notified, 0);
+
+        drop(service);
+        let log = Arc::try_unwrap(db).unwrap().into_transaction_log();
+        assert!(!log
+            .iter()
+            .any(|t| t.statements()[0].to_string().starts_with("UPDATE")));
+    }
+}
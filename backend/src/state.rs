@@ -1,7 +1,28 @@
 use crate::{
     config::{self, Config},
     migration::Migrator,
-    services::{cart::CartService, order::OrderService, product::ProductService},
+    services::{
+        audit::AuditService,
+        cart::CartService,
+        category::CategoryService,
+        ledger::LedgerService,
+        moderation::ModerationService,
+        order::OrderService,
+        otp::OtpService,
+        payment::PaymentService,
+        permission::PermissionService,
+        product::ProductService,
+        rate_limit::{
+            MemoryRateLimitStore, PostgresRateLimitStore, RateLimitBackend, RateLimitStore,
+        },
+        review::ReviewService,
+        search::SearchService,
+        session::SessionService,
+        store::StoreService,
+        variant::VariantService,
+        vendor_application::VendorApplicationService,
+        wishlist::WishlistService,
+    },
 };
 
 use sea_orm::{Database, DatabaseConnection};
@@ -14,23 +35,90 @@ pub struct AppState {
     pub db: Arc<DatabaseConnection>,
     pub config: Arc<Config>,
     pub product_service: Arc<ProductService>,
+    pub variant_service: Arc<VariantService>,
+    pub moderation_service: Arc<ModerationService>,
+    pub review_service: Arc<ReviewService>,
     pub cart_service: Arc<CartService>,
+    pub wishlist_service: Arc<WishlistService>,
 
     pub order_service: Arc<OrderService>,
+    pub payment_service: Arc<PaymentService>,
+    pub ledger_service: Arc<LedgerService>,
+    pub session_service: Arc<SessionService>,
+    pub otp_service: Arc<OtpService>,
+    pub rate_limiter: Arc<dyn RateLimitStore>,
+    pub vendor_application_service: Arc<VendorApplicationService>,
+    pub store_service: Arc<StoreService>,
+    pub search_service: Arc<SearchService>,
+    pub category_service: Arc<CategoryService>,
+    pub permission_service: Arc<PermissionService>,
+    pub audit_service: Arc<AuditService>,
 }
 
 impl AppState {
     pub fn new(db: DatabaseConnection, config: Config) -> Self {
         let db = Arc::new(db);
+        let config = Arc::new(config);
         let product_service = Arc::new(ProductService::new(db.clone()));
+        let variant_service = Arc::new(VariantService::new(db.clone()));
+        let moderation_service = Arc::new(ModerationService::new(
+            db.clone(),
+            config.sms_sender.clone(),
+        ));
+        let review_service = Arc::new(ReviewService::new(db.clone()));
         let cart_service = Arc::new(CartService::new(db.clone()));
+        let wishlist_service = Arc::new(WishlistService::new(
+            db.clone(),
+            cart_service.clone(),
+            config.sms_sender.clone(),
+        ));
         let order_service = Arc::new(OrderService::new(db.clone()));
+        let ledger_service = Arc::new(LedgerService::new(
+            db.clone(),
+            config.platform_commission_rate,
+        ));
+        let payment_service = Arc::new(PaymentService::new(
+            db.clone(),
+            config.payment_gateway.clone(),
+            ledger_service.clone(),
+        ));
+        let session_service = Arc::new(SessionService::new(db.clone(), config.clone()));
+        let otp_service = Arc::new(OtpService::new(
+            db.clone(),
+            config.sms_sender.clone(),
+            config.otp_secret.clone(),
+        ));
+        let vendor_application_service = Arc::new(VendorApplicationService::new(db.clone()));
+        let store_service = Arc::new(StoreService::new(db.clone()));
+        let search_service = Arc::new(SearchService::new(db.clone()));
+        let category_service = Arc::new(CategoryService::new(db.clone()));
+        let permission_service = Arc::new(PermissionService::new(db.clone()));
+        let audit_service = Arc::new(AuditService::new(db.clone()));
+        let rate_limiter: Arc<dyn RateLimitStore> = match config.rate_limit_backend {
+            RateLimitBackend::Memory => Arc::new(MemoryRateLimitStore::default()),
+            RateLimitBackend::Postgres => Arc::new(PostgresRateLimitStore::new(db.clone())),
+        };
         Self {
             db,
-            config: Arc::new(config),
+            config,
             cart_service,
+            wishlist_service,
             order_service,
+            payment_service,
+            ledger_service,
             product_service,
+            variant_service,
+            moderation_service,
+            review_service,
+            session_service,
+            otp_service,
+            rate_limiter,
+            vendor_application_service,
+            store_service,
+            search_service,
+            category_service,
+            permission_service,
+            audit_service,
         }
     }
 }
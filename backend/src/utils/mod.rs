@@ -1,3 +1,4 @@
 pub mod password;
+pub mod policy;
 pub mod rbac;
 pub mod shared;
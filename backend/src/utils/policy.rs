@@ -0,0 +1,111 @@
+use axum::http::StatusCode;
+use uuid::Uuid;
+
+use crate::middleware::auth::AuthUser;
+use crate::models::{role_permission::Permission, user::UserRole};
+use crate::utils::rbac::require_role;
+
+/// The orders a user is allowed to see and act on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderScope {
+    /// Admins and staff allowed to view orders see every order
+    All,
+    /// Buyers see the orders they placed
+    Buyer(Uuid),
+    /// Vendors see the orders that contain at least one of their products
+    Vendor(Uuid),
+}
+
+/// Resolve the order scope of an authenticated user
+pub fn order_scope(user: &AuthUser) -> Result<OrderScope, (StatusCode, &'static str)> {
+    let id = user_id(user)?;
+    if user.can(Permission::ViewOrders) {
+        return Ok(OrderScope::All);
+    }
+    match user.role {
+        UserRole::Vendor => Ok(OrderScope::Vendor(id)),
+        UserRole::Buyer => Ok(OrderScope::Buyer(id)),
+        UserRole::Admin | UserRole::Staff => Err((
+            StatusCode::FORBIDDEN,
+            "You do not have permission to view orders.",
+        )),
+    }
+}
+
+/// Only admins may delete orders
+pub fn can_delete_order(user: &AuthUser) -> Result<(), (StatusCode, &'static str)> {
+    require_role(user, &[UserRole::Admin])
+}
+
+/// Parse the id of an authenticated user
+pub fn user_id(user: &AuthUser) -> Result<Uuid, (StatusCode, &'static str)> {
+    Uuid::parse_str(&user.id).map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID in token."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::Iterable;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_order_scope_follows_role() {
+        let id = Uuid::new_v4();
+        let user = |role| AuthUser {
+            id: id.to_string(),
+            permissions: if role == UserRole::Admin {
+                Permission::iter().collect()
+            } else {
+                HashSet::new()
+            },
+            role,
+            session_id: Uuid::new_v4(),
+        };
+
+        assert_eq!(order_scope(&user(UserRole::Admin)), Ok(OrderScope::All));
+        assert_eq!(
+            order_scope(&user(UserRole::Vendor)),
+            Ok(OrderScope::Vendor(id))
+        );
+        assert_eq!(
+            order_scope(&user(UserRole::Buyer)),
+            Ok(OrderScope::Buyer(id))
+        );
+        assert!(can_delete_order(&user(UserRole::Buyer)).is_err());
+        assert!(can_delete_order(&user(UserRole::Admin)).is_ok());
+    }
+
+    #[test]
+    fn test_staff_order_scope_needs_permission() {
+        let staff = |permissions: &[Permission]| AuthUser {
+            id: Uuid::new_v4().to_string(),
+            role: UserRole::Staff,
+            session_id: Uuid::new_v4(),
+            permissions: permissions.iter().copied().collect(),
+        };
+
+        assert_eq!(
+            order_scope(&staff(&[Permission::ViewOrders])),
+            Ok(OrderScope::All)
+        );
+        assert!(matches!(
+            order_scope(&staff(&[Permission::ModerateProducts])),
+            Err((StatusCode::FORBIDDEN, _))
+        ));
+        assert!(can_delete_order(&staff(&[Permission::ViewOrders])).is_err());
+    }
+
+    #[test]
+    fn test_order_scope_rejects_malformed_id() {
+        let user = AuthUser {
+            id: "not-a-uuid".to_string(),
+            role: UserRole::Buyer,
+            session_id: Uuid::new_v4(),
+            permissions: HashSet::new(),
+        };
+        assert!(matches!(
+            order_scope(&user),
+            Err((StatusCode::UNAUTHORIZED, _))
+        ));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::middleware::auth::AuthUser;
-use crate::models::user::UserRole;
+use crate::models::{role_permission::Permission, user::UserRole};
 use axum::http::StatusCode;
 
 pub fn require_role(
@@ -15,3 +15,17 @@ pub fn require_role(
         ))
     }
 }
+
+pub fn require_permission(
+    user: &AuthUser,
+    permission: Permission,
+) -> Result<(), (StatusCode, &'static str)> {
+    if user.can(permission) {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::FORBIDDEN,
+            "You do not have permission to access this resource.",
+        ))
+    }
+}
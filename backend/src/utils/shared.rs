@@ -1,4 +1,12 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
 use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::services::errors::ServiceError;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
@@ -24,3 +32,23 @@ impl<T> ApiResponse<T> {
         }
     }
 }
+
+/// Map a service error to a response. Errors the client can act on keep
+/// their message; anything else is logged and answered with `context`.
+pub fn error_response(e: ServiceError, context: &str) -> Response {
+    let (status, msg) = match e {
+        ServiceError::Validation(msg) => (StatusCode::BAD_REQUEST, msg),
+        ServiceError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+        ServiceError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+        ServiceError::NotFound(msg) | ServiceError::UserNotFound(msg) => {
+            (StatusCode::NOT_FOUND, msg)
+        }
+        ServiceError::TooManyRequests(msg) => (StatusCode::TOO_MANY_REQUESTS, msg),
+        ServiceError::Conflict(msg) => (StatusCode::CONFLICT, msg),
+        e => {
+            error!("{}: {}", context, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, context.to_string())
+        }
+    };
+    (status, Json(ApiResponse::<()>::error(&msg))).into_response()
+}
@@ -21,7 +21,7 @@ interface Product {
   price: number;
   vendor_id: string;
   vendor_name: string;
-  is_approved: boolean;
+  moderation_status: 'draft' | 'pending_review' | 'approved' | 'rejected' | 'suspended';
   created_at: string;
   image_urls: string[];
   quantity: number;
@@ -87,6 +87,10 @@ export function ProductManagement() {
   };
 
   const handleReject = async (productId: string) => {
+    const reason = window.prompt('Why is this product rejected?');
+    if (!reason || !reason.trim()) {
+      return;
+    }
     try {
       const token = localStorage.getItem('token');
       const response = await fetch(`/api/admins/products/${productId}/reject`, {
@@ -95,6 +99,7 @@ export function ProductManagement() {
           'Authorization': `Bearer ${token}`,
           'Content-Type': 'application/json',
         },
+        body: JSON.stringify({ reason }),
       });
       if (response.ok) {
         setProducts(products.filter(product => product.id !== productId));
@@ -154,8 +159,8 @@ export function ProductManagement() {
                 <TableCell>${product.price.toFixed(2)}</TableCell>
                 <TableCell>{product.vendor_name}</TableCell>
                 <TableCell>
-                  <Badge variant={product.is_approved ? "default" : "destructive"}>
-                    {product.is_approved ? "Approved" : "Pending"}
+                  <Badge variant={product.moderation_status === 'approved' ? "default" : "destructive"}>
+                    {product.moderation_status === 'approved' ? "Approved" : "Pending"}
                   </Badge>
                 </TableCell>
                 <TableCell>{new Date(product.created_at).toLocaleDateString()}</TableCell>
@@ -165,7 +170,7 @@ export function ProductManagement() {
                       variant="green"
                       size="sm"
                       onClick={() => handleApproval(product.id)}
-                      disabled={product.is_approved}
+                      disabled={product.moderation_status === 'approved'}
                     >
                       Approve
                     </Button>
@@ -173,7 +178,7 @@ export function ProductManagement() {
                       variant="destructive"
                       size="sm"
                       onClick={() => handleReject(product.id)}
-                      disabled={product.is_approved}
+                      disabled={product.moderation_status === 'approved'}
                     >
                       Reject
                     </Button>